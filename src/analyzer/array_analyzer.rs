@@ -1,11 +1,75 @@
+use crate::analyzer::interval::Interval;
+use crate::analyzer::models::{BufferSize, Models};
+use crate::diagnostics::{DiagnosticSink, Reporter};
 use crate::parser::cpp_parser::{Expression, Statement};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 
+const MAX_WIDENING_ITERATIONS: usize = 32;
+const NARROWING_ITERATIONS: usize = 2;
+
+// Extent and current offset, in elements, of a pointer into a heap buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Buffer {
+    extent: Interval,
+    offset: Interval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AbstractValue {
+    Integer(Interval),
+    Buffer(Buffer),
+}
+
+impl AbstractValue {
+    fn interval(&self) -> Interval {
+        match self {
+            AbstractValue::Integer(interval) => *interval,
+            AbstractValue::Buffer(_) => Interval::top(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct State {
+    values: HashMap<String, Interval>,
+    buffers: HashMap<String, Buffer>,
+}
+
+impl State {
+    fn lookup(&self, id: &str) -> AbstractValue {
+        match self.buffers.get(id) {
+            Some(buffer) => AbstractValue::Buffer(*buffer),
+            None => {
+                AbstractValue::Integer(self.values.get(id).copied().unwrap_or_else(Interval::top))
+            }
+        }
+    }
+
+    fn bind(&mut self, id: &str, value: AbstractValue) {
+        match value {
+            AbstractValue::Integer(interval) => {
+                self.buffers.remove(id);
+                self.values.insert(id.to_string(), interval);
+            }
+            AbstractValue::Buffer(buffer) => {
+                self.values.remove(id);
+                self.buffers.insert(id.to_string(), buffer);
+            }
+        }
+    }
+}
+
+// Abstract state of every variable; None marks an unreachable program point
+type Environment = Option<State>;
+
 pub struct ArrayAnalyzer<'a> {
     program: &'a [Statement],
     array_sizes: HashMap<String, usize>,
+    // Size relationships between the parameters of modelled functions, e.g. `memcpy`
+    buffer_sizes: HashMap<String, Vec<BufferSize>>,
+    reporter: Reporter<ArrayError>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -19,71 +83,627 @@ impl fmt::Display for ArrayError {
     }
 }
 
-
 impl Error for ArrayError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         None
     }
 }
 
+fn combine_environments(
+    previous: &Environment,
+    next: &Environment,
+    combine: fn(&Interval, &Interval) -> Interval,
+) -> Environment {
+    match (previous, next) {
+        (None, env) | (env, None) => env.clone(),
+        (Some(previous), Some(next)) => {
+            let values = previous
+                .values
+                .iter()
+                .map(|(name, interval)| {
+                    let combined = match next.values.get(name) {
+                        Some(other) => combine(interval, other),
+                        None => Interval::top(),
+                    };
+                    (name.clone(), combined)
+                })
+                .collect();
+            // A pointer only keeps its buffer when both sides agree it has one
+            let buffers = previous
+                .buffers
+                .iter()
+                .filter_map(|(name, buffer)| {
+                    next.buffers.get(name).map(|other| {
+                        let combined = Buffer {
+                            extent: combine(&buffer.extent, &other.extent),
+                            offset: combine(&buffer.offset, &other.offset),
+                        };
+                        (name.clone(), combined)
+                    })
+                })
+                .collect();
+            Some(State { values, buffers })
+        }
+    }
+}
+
+fn join_environments(left: &Environment, right: &Environment) -> Environment {
+    combine_environments(left, right, Interval::join)
+}
+
+fn negate_comparison(op: &str) -> Option<&'static str> {
+    match op {
+        "<" => Some(">="),
+        "<=" => Some(">"),
+        ">" => Some("<="),
+        ">=" => Some("<"),
+        "==" => Some("!="),
+        "!=" => Some("=="),
+        _ => None,
+    }
+}
+
+fn mirror_comparison(op: &str) -> &str {
+    match op {
+        "<" => ">",
+        "<=" => ">=",
+        ">" => "<",
+        ">=" => "<=",
+        other => other,
+    }
+}
+
 impl<'a> ArrayAnalyzer<'a> {
     pub fn new(program: &'a [Statement]) -> Self {
         ArrayAnalyzer {
             program,
             array_sizes: HashMap::new(),
+            buffer_sizes: HashMap::new(),
+            reporter: Reporter::default(),
         }
     }
 
-    fn is_out_of_bounds_access(&self, expr: &Expression) -> bool {
-        if let Expression::ArrayAccess(id, index_expr) = expr {
-            if let Some(size) = self.array_sizes.get(id) {
-                if let Expression::Integer(index) = **index_expr {
-                    return index < 0 || index as usize >= *size;
-                }
+    pub fn add_models(&mut self, models: &Models) {
+        for (name, model) in models.functions() {
+            if !model.buffer_sizes.is_empty() {
+                self.buffer_sizes
+                    .insert(name.to_string(), model.buffer_sizes.clone());
             }
         }
+    }
+
+    fn is_out_of_bounds_access(&self, id: &str, index: &Interval) -> bool {
+        if let Some(size) = self.array_sizes.get(id) {
+            return is_outside(index, &Interval::constant(*size as i64));
+        }
         false
     }
-    
 
     fn handle_array_declaration(&mut self, id: &str, size: usize) {
         self.array_sizes.insert(id.to_string(), size);
     }
 
-    fn handle_array_access(&mut self, id: &str, index: &Expression) -> Result<(), ArrayError> {
-        if self.is_out_of_bounds_access(&Expression::ArrayAccess(id.to_string(), Box::new(index.clone()))) {
-            Err(ArrayError {
-                message: format!("Array access out of bounds for '{}'", id),
+    fn handle_array_access(&mut self, id: &str, index: &Interval) -> Result<(), ArrayError> {
+        if self.is_out_of_bounds_access(id, index) {
+            self.reporter.report(ArrayError {
+                message: format!(
+                    "Array access out of bounds for '{}': index in {}, size {}",
+                    id, index, self.array_sizes[id]
+                ),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_buffer_access(
+        &mut self,
+        target: &Expression,
+        buffer: &Buffer,
+        index: &Interval,
+    ) -> Result<(), ArrayError> {
+        let position = buffer.offset.add(index);
+        if is_outside(&position, &buffer.extent) {
+            let name = match target {
+                Expression::Identifier(id) | Expression::Variable(id) => format!("'{}'", id),
+                _ => String::from("pointer expression"),
+            };
+            self.reporter.report(ArrayError {
+                message: format!(
+                    "Heap buffer access out of bounds for {}: index in {}, size {}",
+                    name, position, buffer.extent
+                ),
             })
         } else {
             Ok(())
         }
     }
 
-    fn analyze_expression(&mut self, expr: &Expression) -> Result<(), ArrayError> {
+    // Checks that the sizes passed to a modelled function fit the buffers passed with them
+    fn handle_sized_call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        values: &[AbstractValue],
+    ) -> Result<(), ArrayError> {
+        for relation in self.buffer_sizes.get(name).into_iter().flatten() {
+            let (buffer, size) = match (values.get(relation.buffer), values.get(relation.size)) {
+                (Some(AbstractValue::Buffer(buffer)), Some(AbstractValue::Integer(size))) => {
+                    (buffer, size)
+                }
+                _ => continue,
+            };
+            // The last byte accessed, for sizes that access anything at all
+            let last = buffer
+                .offset
+                .add(size)
+                .subtract(&Interval::constant(1))
+                .meet(&Interval::new(buffer.offset.low, None));
+            if is_outside(&last, &buffer.extent) {
+                let buffer_name = match &args[relation.buffer] {
+                    Expression::Identifier(id) | Expression::Variable(id) => format!("'{}'", id),
+                    _ => String::from("pointer expression"),
+                };
+                self.reporter.report(ArrayError {
+                    message: format!(
+                        "Call to '{}' accesses {} out of bounds: size in {}, buffer size {}",
+                        name, buffer_name, size, buffer.extent
+                    ),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    // Number of elements of an allocation of `bytes`, recognizing `n * sizeof(T)`
+    fn element_count(
+        &mut self,
+        bytes: &Expression,
+        env: &mut State,
+        check: bool,
+    ) -> Result<Interval, ArrayError> {
+        if let Expression::BinaryOperation(op, left, right) = bytes {
+            if op == "*" {
+                if is_sizeof(right) {
+                    return Ok(self.evaluate(left, env, check)?.interval());
+                }
+                if is_sizeof(left) {
+                    return Ok(self.evaluate(right, env, check)?.interval());
+                }
+            }
+        }
+        if is_sizeof(bytes) {
+            return Ok(Interval::constant(1));
+        }
+        // Without a sizeof the buffer is counted in bytes
+        Ok(self.evaluate(bytes, env, check)?.interval())
+    }
+
+    fn allocation(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        env: &mut State,
+        check: bool,
+    ) -> Result<Option<Interval>, ArrayError> {
+        if let Some(size) = std_array_size(name) {
+            return Ok(Some(Interval::constant(size)));
+        }
+        let extent = match (name, args) {
+            ("malloc", [bytes]) | ("realloc", [_, bytes]) => {
+                self.element_count(bytes, env, check)?
+            }
+            ("calloc", [count, size]) => {
+                let count = self.evaluate(count, env, check)?.interval();
+                if is_sizeof(size) {
+                    count
+                } else {
+                    count.multiply(&self.evaluate(size, env, check)?.interval())
+                }
+            }
+            ("std::array", [_, size]) => self.evaluate(size, env, check)?.interval(),
+            _ => return Ok(None),
+        };
+        Ok(Some(extent))
+    }
+
+    fn evaluate(
+        &mut self,
+        expr: &Expression,
+        env: &mut State,
+        check: bool,
+    ) -> Result<AbstractValue, ArrayError> {
+        let top = AbstractValue::Integer(Interval::top());
         match expr {
-            Expression::ArrayAccess(id, index) => self.handle_array_access(id, index),
-            Expression::ArrayDeclaration(id, index) => {
-                if let Expression::Integer(size) = **index {
+            Expression::Integer(value) => Ok(AbstractValue::Integer(Interval::constant(*value))),
+            Expression::Identifier(id) | Expression::Variable(id) => Ok(env.lookup(id)),
+            Expression::ArrayAccess(id, index) => {
+                let index = self.evaluate(index, env, check)?.interval();
+                if check {
+                    self.handle_array_access(id, &index)?;
+                    if let Some(buffer) = env.buffers.get(id).copied() {
+                        self.handle_buffer_access(
+                            &Expression::Identifier(id.clone()),
+                            &buffer,
+                            &index,
+                        )?;
+                    }
+                }
+                Ok(top)
+            }
+            Expression::ArrayDeclaration(id, size) => {
+                if let Expression::Integer(size) = **size {
                     self.handle_array_declaration(id, size as usize);
                 }
-                Ok(())
+                Ok(top)
+            }
+            Expression::Assignment(target, value) => {
+                let value = self.evaluate(value, env, check)?;
+                match &**target {
+                    Expression::Identifier(id) | Expression::Variable(id) => env.bind(id, value),
+                    target => {
+                        self.evaluate(target, env, check)?;
+                    }
+                }
+                Ok(value)
+            }
+            // Compound assignments the parser did not desugar, e.g. in hand-built trees
+            Expression::BinaryOperation(op, target, value)
+                if op.len() > 1 && op.ends_with('=') && !is_comparison(op) =>
+            {
+                let op = op.trim_end_matches('=').to_string();
+                let desugared = Expression::Assignment(
+                    target.clone(),
+                    Box::new(Expression::BinaryOperation(
+                        op,
+                        target.clone(),
+                        value.clone(),
+                    )),
+                );
+                self.evaluate(&desugared, env, check)
+            }
+            Expression::BinaryOperation(op, left, right) => {
+                let left = self.evaluate(left, env, check)?;
+                let right = self.evaluate(right, env, check)?;
+                Ok(match (op.as_str(), left, right) {
+                    // Pointer arithmetic moves the offset within the same buffer
+                    ("+", AbstractValue::Buffer(buffer), AbstractValue::Integer(delta))
+                    | ("+", AbstractValue::Integer(delta), AbstractValue::Buffer(buffer)) => {
+                        AbstractValue::Buffer(Buffer {
+                            extent: buffer.extent,
+                            offset: buffer.offset.add(&delta),
+                        })
+                    }
+                    ("-", AbstractValue::Buffer(buffer), AbstractValue::Integer(delta)) => {
+                        AbstractValue::Buffer(Buffer {
+                            extent: buffer.extent,
+                            offset: buffer.offset.subtract(&delta),
+                        })
+                    }
+                    (op, AbstractValue::Integer(left), AbstractValue::Integer(right)) => {
+                        AbstractValue::Integer(match op {
+                            "+" => left.add(&right),
+                            "-" => left.subtract(&right),
+                            "*" => left.multiply(&right),
+                            "/" => left.divide(&right),
+                            _ => Interval::top(),
+                        })
+                    }
+                    _ => top,
+                })
+            }
+            // The AST does not tell `++i` from `i++`; the value before the update is
+            // returned, as in the common `a[i++]`
+            Expression::UnaryOperation(op, operand) if op == "++" || op == "--" => {
+                let value = self.evaluate(operand, env, check)?;
+                let step = Interval::constant(if op == "++" { 1 } else { -1 });
+                let updated = match value {
+                    AbstractValue::Integer(interval) => AbstractValue::Integer(interval.add(&step)),
+                    AbstractValue::Buffer(buffer) => AbstractValue::Buffer(Buffer {
+                        extent: buffer.extent,
+                        offset: buffer.offset.add(&step),
+                    }),
+                };
+                if let Expression::Identifier(id) | Expression::Variable(id) = &**operand {
+                    env.bind(id, updated);
+                }
+                Ok(value)
+            }
+            Expression::UnaryOperation(op, operand) => {
+                let operand = self.evaluate(operand, env, check)?.interval();
+                Ok(AbstractValue::Integer(if op == "-" {
+                    operand.negate()
+                } else {
+                    Interval::top()
+                }))
+            }
+            Expression::FunctionCall(name, args) => {
+                if let Some(extent) = self.allocation(name, args, env, check)? {
+                    return Ok(AbstractValue::Buffer(Buffer {
+                        extent,
+                        offset: Interval::constant(0),
+                    }));
+                }
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(self.evaluate(arg, env, check)?);
+                }
+                if check {
+                    self.handle_sized_call(name, args, &values)?;
+                }
+                Ok(top)
+            }
+            Expression::Dereference(inner) => {
+                let pointer = self.evaluate(inner, env, check)?;
+                if let (true, AbstractValue::Buffer(buffer)) = (check, pointer) {
+                    self.handle_buffer_access(inner, &buffer, &Interval::constant(0))?;
+                }
+                Ok(top)
+            }
+            Expression::Delete(inner, _)
+            | Expression::MemberAccess(inner, _)
+            | Expression::AddressOf(inner) => {
+                self.evaluate(inner, env, check)?;
+                Ok(top)
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.evaluate(object, env, check)?;
+                for arg in args {
+                    self.evaluate(arg, env, check)?;
+                }
+                Ok(top)
+            }
+            Expression::New(_, size, initializer) => {
+                for arg in initializer.iter().flatten() {
+                    self.evaluate(arg, env, check)?;
+                }
+                let extent = match size {
+                    Some(size) => self.evaluate(size, env, check)?.interval(),
+                    None => Interval::constant(1),
+                };
+                Ok(AbstractValue::Buffer(Buffer {
+                    extent,
+                    offset: Interval::constant(0),
+                }))
+            }
+            Expression::Uninitialized(type_name) => Ok(match std_array_size(type_name) {
+                Some(size) => AbstractValue::Buffer(Buffer {
+                    extent: Interval::constant(size),
+                    offset: Interval::constant(0),
+                }),
+                None => top,
+            }),
+            Expression::Null => Ok(top),
+        }
+    }
+
+    // Restricts the environment to the states where `condition` has the given outcome
+    fn refine(&mut self, condition: &Expression, env: &Environment, outcome: bool) -> Environment {
+        let mut env = env.clone()?;
+        match condition {
+            Expression::UnaryOperation(op, operand) if op == "!" => {
+                self.refine(operand, &Some(env), !outcome)
+            }
+            Expression::BinaryOperation(op, left, right)
+                if (op == "&&" && outcome) || (op == "||" && !outcome) =>
+            {
+                let env = self.refine(left, &Some(env), outcome);
+                self.refine(right, &env, outcome)
+            }
+            Expression::BinaryOperation(op, left, right) if op == "&&" || op == "||" => {
+                let left_env = self.refine(left, &Some(env.clone()), outcome);
+                let right_env = self.refine(right, &Some(env), outcome);
+                join_environments(&left_env, &right_env)
+            }
+            Expression::BinaryOperation(op, left, right) => {
+                let op = match (outcome, negate_comparison(op)) {
+                    (_, None) => return Some(env),
+                    (true, Some(_)) => op.as_str(),
+                    (false, Some(negated)) => negated,
+                };
+                let left_value = self.evaluate(left, &mut env, false).ok()?;
+                let right_value = self.evaluate(right, &mut env, false).ok()?;
+                if let (
+                    Expression::Identifier(id) | Expression::Variable(id),
+                    AbstractValue::Integer(left_interval),
+                ) = (&**left, left_value)
+                {
+                    let refined = constrain(&left_interval, op, &right_value.interval());
+                    if refined.is_empty() {
+                        return None;
+                    }
+                    env.values.insert(id.clone(), refined);
+                }
+                if let (
+                    Expression::Identifier(id) | Expression::Variable(id),
+                    AbstractValue::Integer(right_interval),
+                ) = (&**right, right_value)
+                {
+                    let refined = constrain(
+                        &right_interval,
+                        mirror_comparison(op),
+                        &left_value.interval(),
+                    );
+                    if refined.is_empty() {
+                        return None;
+                    }
+                    env.values.insert(id.clone(), refined);
+                }
+                Some(env)
+            }
+            Expression::Integer(value) => {
+                if (*value != 0) == outcome {
+                    Some(env)
+                } else {
+                    None
+                }
+            }
+            _ => Some(env),
+        }
+    }
+
+    // Range-based for loops have no condition and may run any number of times
+    fn refine_loop(
+        &mut self,
+        condition: Option<&Expression>,
+        env: &Environment,
+        outcome: bool,
+    ) -> Environment {
+        match condition {
+            Some(condition) => self.refine(condition, env, outcome),
+            None => env.clone(),
+        }
+    }
+
+    fn analyze_loop(
+        &mut self,
+        condition: Option<&Expression>,
+        body: &Statement,
+        env: Environment,
+        check: bool,
+    ) -> Result<Environment, ArrayError> {
+        let mut head = env.clone();
+        for _ in 0..MAX_WIDENING_ITERATIONS {
+            let entry = self.refine_loop(condition, &head, true);
+            let body_exit = self.analyze_statement(body, entry, false)?;
+            let next = join_environments(&env, &body_exit);
+            let widened = combine_environments(&head, &next, Interval::widen);
+            if widened == head {
+                break;
             }
-            _ => Ok(()),
+            head = widened;
+        }
+
+        for _ in 0..NARROWING_ITERATIONS {
+            let entry = self.refine_loop(condition, &head, true);
+            let body_exit = self.analyze_statement(body, entry, false)?;
+            let next = join_environments(&env, &body_exit);
+            head = combine_environments(&head, &next, Interval::narrow);
+        }
+
+        if check {
+            let entry = self.refine_loop(condition, &head, true);
+            self.analyze_statement(body, entry, true)?;
         }
+        Ok(self.refine_loop(condition, &head, false))
     }
 
-    fn analyze_statement(&mut self, stmt: &Statement) -> Result<(), ArrayError> {
+    fn analyze_statement(
+        &mut self,
+        stmt: &Statement,
+        env: Environment,
+        check: bool,
+    ) -> Result<Environment, ArrayError> {
+        let mut env = match env {
+            Some(env) => env,
+            None => return Ok(None),
+        };
         match stmt {
-            Statement::Expression(expr) => self.analyze_expression(expr),
-            _ => Ok(()),
+            Statement::Expression(expr) => {
+                self.evaluate(expr, &mut env, check)?;
+                Ok(Some(env))
+            }
+            Statement::Declaration(id, expr) | Statement::Reference(id, expr) => {
+                let value = self.evaluate(expr, &mut env, check)?;
+                env.bind(id, value);
+                Ok(Some(env))
+            }
+            Statement::Return(expr) | Statement::Throw(expr) => {
+                self.evaluate(expr, &mut env, check)?;
+                Ok(None)
+            }
+            Statement::Block(stmts) => {
+                let mut env = Some(env);
+                for stmt in stmts {
+                    env = self.analyze_statement(stmt, env, check)?;
+                }
+                Ok(env)
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                let env = Some(env);
+                let then_env = self.refine(condition, &env, true);
+                let else_env = self.refine(condition, &env, false);
+                let then_exit = self.analyze_statement(then_branch, then_env, check)?;
+                let else_exit = self.analyze_statement(else_branch, else_env, check)?;
+                Ok(join_environments(&then_exit, &else_exit))
+            }
+            Statement::While(condition, body) => {
+                self.analyze_loop(Some(condition), body, Some(env), check)
+            }
+            Statement::RangeFor(element, range, body) => {
+                self.evaluate(range, &mut env, check)?;
+                env.bind(element, AbstractValue::Integer(Interval::top()));
+                self.analyze_loop(None, body, Some(env), check)
+            }
+            Statement::Located(location, stmt) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let exit = self.analyze_statement(stmt, Some(env), check);
+                self.reporter.leave_statement(enclosing);
+                exit
+            }
         }
     }
 
     pub fn analyze(&mut self) -> Result<(), ArrayError> {
+        let mut env = Some(State::default());
         for stmt in self.program {
-            self.analyze_statement(stmt)?;
+            env = self.analyze_statement(stmt, env, true)?;
         }
         Ok(())
     }
+
+    // Analyzes the program without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&mut self, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze();
+        sink.extend(self.reporter.finish(result));
+    }
+}
+
+// Interval of `value` once `value <op> bound` is known to hold
+fn constrain(value: &Interval, op: &str, bound: &Interval) -> Interval {
+    let below = |offset: i64| Interval::new(None, bound.high.and_then(|h| h.checked_sub(offset)));
+    let above = |offset: i64| Interval::new(bound.low.and_then(|l| l.checked_add(offset)), None);
+    match op {
+        "<" => value.meet(&below(1)),
+        "<=" => value.meet(&below(0)),
+        ">" => value.meet(&above(1)),
+        ">=" => value.meet(&above(0)),
+        "==" => value.meet(bound),
+        _ => *value,
+    }
+}
+
+// Whether a known bound of `index` falls outside a buffer of `extent` elements. An
+// unbounded side says nothing, e.g. a loop counter bounded by a parameter
+fn is_outside(index: &Interval, extent: &Interval) -> bool {
+    // Nothing is known about the index, e.g. an unconstrained parameter
+    if index.is_top() || index.is_empty() {
+        return false;
+    }
+    let size = match extent.low {
+        Some(size) => size,
+        None => return false,
+    };
+    let below = matches!(index.low, Some(low) if low < 0);
+    let above = match size.checked_sub(1) {
+        Some(last) => matches!(index.high, Some(high) if high > last),
+        // Not even one element fits
+        None => true,
+    };
+    below || above
+}
+
+// N of a `std::array<T, N>` type, as the parser leaves it in declarations
+fn std_array_size(type_name: &str) -> Option<i64> {
+    let arguments = type_name.strip_prefix("std::array<")?.strip_suffix('>')?;
+    arguments.rsplit(',').next()?.trim().parse().ok()
+}
+
+fn is_comparison(op: &str) -> bool {
+    matches!(op, "==" | "!=" | "<=" | ">=")
+}
+
+fn is_sizeof(expr: &Expression) -> bool {
+    matches!(expr, Expression::FunctionCall(name, _) if name == "sizeof")
 }
@@ -0,0 +1,593 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::analyzer::pointer_analyzer::{PointerError, PointerErrorKind};
+use crate::diagnostics::Reporter;
+use crate::parser::cpp_parser::{new_operands, parameter_name, Declaration, Expression, Statement};
+
+// Lifetime named by `[[scpp::lifetime(a)]]`; `lifetime(mut a)` marks an exclusive borrow
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lifetime {
+    pub name: String,
+    pub mutable: bool,
+}
+
+impl Lifetime {
+    // Reads the lifetime out of an attribute or comment such as
+    // `[[scpp::lifetime(a)]]` or `/* scpp::lifetime(mut a) */`
+    pub fn parse(text: &str) -> Option<Lifetime> {
+        let start = text.find("scpp::lifetime(")? + "scpp::lifetime(".len();
+        let end = start + text[start..].find(')')?;
+        let mut words = text[start..end].split_whitespace();
+        let (mutable, name) = match (words.next()?, words.next()) {
+            ("mut", Some(name)) => (true, name),
+            (name, None) => (false, name),
+            _ => return None,
+        };
+        Some(Lifetime {
+            name: name.to_string(),
+            mutable,
+        })
+    }
+}
+
+// Lifetime annotations of one function signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionLifetimes {
+    pub name: String,
+    pub params: Vec<Option<Lifetime>>,
+    pub returns: Option<Lifetime>,
+}
+
+impl FunctionLifetimes {
+    // Parses a declaration such as
+    // `int* [[scpp::lifetime(a)]] pick(int* [[scpp::lifetime(a)]] x, int* y)`
+    pub fn parse(signature: &str) -> Option<FunctionLifetimes> {
+        let (text, annotations) = replace_annotations(signature)?;
+        let lifetime_in = |segment: &str| {
+            segment
+                .split_whitespace()
+                .filter_map(|word| word.strip_prefix('@')?.parse::<usize>().ok())
+                .find_map(|index| annotations[index].clone())
+        };
+
+        let open = text.find('(')?;
+        let close = text.rfind(')')?;
+        let head = text[..open].trim_end();
+        let name_start = head
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .map_or(0, |index| index + 1);
+        let params = text[open + 1..close]
+            .split(',')
+            .filter(|param| !param.trim().is_empty() && param.trim() != "void")
+            .map(lifetime_in)
+            .collect();
+
+        Some(FunctionLifetimes {
+            name: head[name_start..].to_string(),
+            params,
+            returns: lifetime_in(&head[..name_start]),
+        })
+    }
+
+    // The annotated function signatures of a source file, read from the text since the
+    // parser drops attributes and comments. A signature runs from the previous `;`, `{`
+    // or `}` to the `{` or `;` after its annotation
+    pub fn collect(source: &str) -> Vec<FunctionLifetimes> {
+        let text: String = source
+            .lines()
+            .map(|line| if line.trim_start().starts_with('#') { "" } else { line })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut signatures = Vec::new();
+        let mut searched = 0;
+        while let Some(offset) = text[searched..].find("scpp::lifetime(") {
+            let annotation = searched + offset;
+            let start = text[..annotation]
+                .rfind([';', '{', '}'])
+                .map_or(0, |index| index + 1);
+            let end = text[annotation..]
+                .find(['{', ';'])
+                .map_or(text.len(), |index| annotation + index);
+            searched = end.max(annotation + 1);
+            let signature = &text[start..end];
+            // Declarations and statements inside a body are not signatures
+            let bare = replace_annotations(signature).map(|(bare, _)| bare);
+            let head = bare
+                .as_deref()
+                .and_then(|bare| bare.split('(').next())
+                .unwrap_or("=");
+            if head.contains('=') || head.split_whitespace().any(|word| word == "return") {
+                continue;
+            }
+            signatures.extend(FunctionLifetimes::parse(signature));
+        }
+        signatures
+    }
+}
+
+// Replaces attributes and comments by placeholders `@0`, `@1`, ... so their parentheses
+// do not confuse the search for the parameter list, with the lifetime each one names
+fn replace_annotations(signature: &str) -> Option<(String, Vec<Option<Lifetime>>)> {
+    let mut annotations = Vec::new();
+    let mut text = String::new();
+    let mut rest = signature;
+    while let Some(start) = rest.find("[[").into_iter().chain(rest.find("/*")).min() {
+        let close = if rest[start..].starts_with("[[") {
+            "]]"
+        } else {
+            "*/"
+        };
+        let end = start + rest[start..].find(close)? + close.len();
+        text.push_str(&rest[..start]);
+        text.push_str(&format!(" @{} ", annotations.len()));
+        annotations.push(Lifetime::parse(&rest[start..end]));
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+    Some((text, annotations))
+}
+
+// Storage a pointer value may refer to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Source {
+    Param(usize),
+    Local(String),
+}
+
+// A pointer obtained through a `mut` lifetime, which excludes other uses of `object`
+struct MutableBorrow {
+    borrower: String,
+    object: String,
+}
+
+// Checks annotated lifetimes: returned pointers must come from parameters with the
+// return lifetime, results of annotated calls must not outlive their arguments, and
+// an object that is mutably borrowed may not be used while the borrow is live
+pub struct BorrowChecker<'a> {
+    declarations: &'a [Declaration],
+    signatures: &'a HashMap<String, FunctionLifetimes>,
+    function: String,
+    params: Vec<String>,
+    scopes: Vec<Vec<String>>,
+    sources: HashMap<String, BTreeSet<Source>>,
+    // Pointers whose borrowed local has gone out of scope
+    expired: HashMap<String, String>,
+    mutable_borrows: Vec<MutableBorrow>,
+    // Statements that follow the current one in each enclosing block
+    continuation: Vec<&'a [Statement]>,
+    // Shared with the pointer analyzer that runs the checker
+    pub(crate) reporter: Reporter<PointerError>,
+}
+
+impl<'a> BorrowChecker<'a> {
+    pub fn new(
+        declarations: &'a [Declaration],
+        signatures: &'a HashMap<String, FunctionLifetimes>,
+    ) -> Self {
+        BorrowChecker {
+            declarations,
+            signatures,
+            function: String::new(),
+            params: Vec::new(),
+            scopes: Vec::new(),
+            sources: HashMap::new(),
+            expired: HashMap::new(),
+            mutable_borrows: Vec::new(),
+            continuation: Vec::new(),
+            reporter: Reporter::default(),
+        }
+    }
+
+    pub fn analyze(&mut self) -> Result<(), PointerError> {
+        for declaration in self.declarations {
+            if let Declaration::Function(name, params, body) = declaration {
+                self.function = name.clone();
+                self.reporter.enter_function(name);
+                self.params = params
+                    .iter()
+                    .map(|param| parameter_name(param).to_string())
+                    .collect();
+                self.scopes = vec![Vec::new()];
+                self.sources.clear();
+                self.expired.clear();
+                self.mutable_borrows.clear();
+                self.analyze_statement(body)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().flatten().any(|local| local == name)
+            || self.params.contains(&name.to_string())
+    }
+
+    fn sources_of(&self, expr: &Expression) -> BTreeSet<Source> {
+        match expr {
+            Expression::Identifier(id) | Expression::Variable(id) => {
+                if let Some(sources) = self.sources.get(id) {
+                    sources.clone()
+                } else if let Some(index) = self.params.iter().position(|param| param == id) {
+                    BTreeSet::from([Source::Param(index)])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Expression::AddressOf(object) => match &**object {
+                Expression::Identifier(id) | Expression::ArrayAccess(id, _)
+                    if self.is_local(id) =>
+                {
+                    BTreeSet::from([Source::Local(id.clone())])
+                }
+                Expression::MemberAccess(object, _) => {
+                    self.sources_of(&Expression::AddressOf(object.clone()))
+                }
+                Expression::Dereference(pointer) => self.sources_of(pointer),
+                _ => BTreeSet::new(),
+            },
+            Expression::MemberAccess(object, _) => match &**object {
+                Expression::Dereference(pointer) => self.sources_of(pointer),
+                object => self.sources_of(object),
+            },
+            Expression::BinaryOperation(op, left, right) if op == "+" || op == "-" => {
+                let mut sources = self.sources_of(left);
+                sources.extend(self.sources_of(right));
+                sources
+            }
+            Expression::Assignment(_, value) => self.sources_of(value),
+            // The result of an annotated call borrows the arguments with the return lifetime
+            Expression::FunctionCall(name, args) => match self.signatures.get(name) {
+                Some(FunctionLifetimes {
+                    params,
+                    returns: Some(returns),
+                    ..
+                }) => params
+                    .iter()
+                    .zip(args)
+                    .filter(|(param, _)| matches!(param, Some(param) if param.name == returns.name))
+                    .flat_map(|(_, arg)| self.sources_of(arg))
+                    .collect(),
+                _ => BTreeSet::new(),
+            },
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn objects_of(&self, expr: &Expression) -> BTreeSet<String> {
+        self.sources_of(expr)
+            .into_iter()
+            .map(|source| match source {
+                Source::Param(index) => self.params[index].clone(),
+                Source::Local(name) => name,
+            })
+            .collect()
+    }
+
+    fn lifetime_mismatch(&self, details: String) -> PointerError {
+        PointerError::new(PointerErrorKind::LifetimeMismatch, details)
+    }
+
+    fn check_return(&mut self, expr: &Expression) -> Result<(), PointerError> {
+        let (returns, params) = match self.signatures.get(&self.function) {
+            Some(FunctionLifetimes {
+                returns: Some(returns),
+                params,
+                ..
+            }) => (returns, params),
+            _ => return Ok(()),
+        };
+        for source in self.sources_of(expr) {
+            match source {
+                Source::Local(local) => {
+                    let error = self.lifetime_mismatch(format!(
+                        "'{}' returns a reference to local '{}', which does not live for '{}",
+                        self.function, local, returns.name
+                    ));
+                    return self.reporter.report(error);
+                }
+                Source::Param(index) => {
+                    let lifetime = params.get(index).cloned().flatten();
+                    if lifetime.as_ref().map(|lifetime| &lifetime.name) != Some(&returns.name) {
+                        let annotation = match lifetime {
+                            Some(lifetime) => format!("lifetime '{}", lifetime.name),
+                            None => "no lifetime annotation".to_string(),
+                        };
+                        let error = self.lifetime_mismatch(format!(
+                            "'{}' returns a reference derived from parameter '{}' with {}, \
+                             but the return value has lifetime '{}",
+                            self.function, self.params[index], annotation, returns.name
+                        ));
+                        return self.reporter.report(error);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_used_later(&self, name: &str) -> bool {
+        self.continuation
+            .iter()
+            .flat_map(|statements| statements.iter())
+            .any(|statement| statement_mentions(statement, name))
+    }
+
+    // Rejects uses of a mutably borrowed object, or of another alias of it, while the
+    // borrowing pointer is still used later on
+    fn check_mutable_borrows(
+        &mut self,
+        mentions: &dyn Fn(&str) -> bool,
+    ) -> Result<(), PointerError> {
+        for borrow in &self.mutable_borrows {
+            if !mentions(&borrow.borrower) && !self.is_used_later(&borrow.borrower) {
+                continue;
+            }
+            let mut aliases = vec![borrow.object.clone()];
+            aliases.extend(
+                self.sources
+                    .iter()
+                    .filter(|(name, sources)| {
+                        **name != borrow.borrower
+                            && sources.contains(&Source::Local(borrow.object.clone()))
+                    })
+                    .map(|(name, _)| name.clone()),
+            );
+            if let Some(alias) = aliases.iter().find(|alias| mentions(alias)) {
+                return self.reporter.report(PointerError::new(
+                    PointerErrorKind::BorrowConflict,
+                    format!(
+                        "'{}' is used in '{}' while '{}' is mutably borrowed by '{}'",
+                        alias, self.function, borrow.object, borrow.borrower
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // An argument passed for a `mut` parameter may not alias any other argument
+    fn check_call(&mut self, name: &str, args: &[Expression]) -> Result<(), PointerError> {
+        let signature = match self.signatures.get(name) {
+            Some(signature) => signature,
+            None => return Ok(()),
+        };
+        for (index, (param, arg)) in signature.params.iter().zip(args).enumerate() {
+            if !matches!(param, Some(Lifetime { mutable: true, .. })) {
+                continue;
+            }
+            let borrowed = self.objects_of(arg);
+            for (other_index, other) in args.iter().enumerate() {
+                if other_index == index {
+                    continue;
+                }
+                if let Some(object) = self.objects_of(other).intersection(&borrowed).next() {
+                    return self.reporter.report(PointerError::new(
+                        PointerErrorKind::BorrowConflict,
+                        format!(
+                            "'{}' is mutably borrowed by argument {} of '{}' and also passed as \
+                             argument {}",
+                            object,
+                            index + 1,
+                            name,
+                            other_index + 1
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_expression(&mut self, expr: &Expression) -> Result<(), PointerError> {
+        match expr {
+            Expression::Identifier(id) | Expression::Variable(id) => {
+                if let Some(local) = self.expired.get(id) {
+                    let error = self.lifetime_mismatch(format!(
+                        "'{}' borrows '{}', which does not live long enough in '{}'",
+                        id, local, self.function
+                    ));
+                    return self.reporter.report(error);
+                }
+                Ok(())
+            }
+            Expression::FunctionCall(name, args) => {
+                self.check_call(name, args)?;
+                args.iter().try_for_each(|arg| self.check_expression(arg))
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.check_expression(object)?;
+                args.iter().try_for_each(|arg| self.check_expression(arg))
+            }
+            Expression::ArrayAccess(id, index) => {
+                self.check_expression(&Expression::Identifier(id.clone()))?;
+                self.check_expression(index)
+            }
+            Expression::BinaryOperation(_, left, right) | Expression::Assignment(left, right) => {
+                self.check_expression(left)?;
+                self.check_expression(right)
+            }
+            Expression::Dereference(inner)
+            | Expression::AddressOf(inner)
+            | Expression::UnaryOperation(_, inner)
+            | Expression::MemberAccess(inner, _)
+            | Expression::Delete(inner, _)
+            | Expression::ArrayDeclaration(_, inner) => self.check_expression(inner),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.check_expression(inner)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn bind(&mut self, name: &str, value: &Expression) {
+        self.expired.remove(name);
+        self.mutable_borrows
+            .retain(|borrow| borrow.borrower != name);
+        let sources = self.sources_of(value);
+
+        if let Expression::FunctionCall(callee, args) = value {
+            if let Some(FunctionLifetimes {
+                params,
+                returns: Some(returns),
+                ..
+            }) = self.signatures.get(callee)
+            {
+                for (param, arg) in params.iter().zip(args) {
+                    if matches!(param, Some(param) if param.mutable && param.name == returns.name) {
+                        for object in self.objects_of(arg) {
+                            self.mutable_borrows.push(MutableBorrow {
+                                borrower: name.to_string(),
+                                object,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        if sources.is_empty() {
+            self.sources.remove(name);
+        } else {
+            self.sources.insert(name.to_string(), sources);
+        }
+    }
+
+    fn exit_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for local in &scope {
+            self.sources.remove(local);
+            self.expired.remove(local);
+        }
+        self.mutable_borrows
+            .retain(|borrow| !scope.contains(&borrow.borrower) && !scope.contains(&borrow.object));
+        for (name, sources) in &self.sources {
+            let expired = sources.iter().find_map(|source| match source {
+                Source::Local(local) if scope.contains(local) => Some(local.clone()),
+                _ => None,
+            });
+            if let Some(local) = expired {
+                self.expired.insert(name.clone(), local);
+            }
+        }
+    }
+
+    fn analyze_statement(&mut self, statement: &'a Statement) -> Result<(), PointerError> {
+        match statement {
+            Statement::If(condition, ..)
+            | Statement::While(condition, _)
+            | Statement::RangeFor(_, condition, _) => {
+                self.check_mutable_borrows(&|name| expression_mentions(condition, name))?
+            }
+            Statement::Block(_) | Statement::Located(..) => {}
+            statement => self.check_mutable_borrows(&|name| statement_mentions(statement, name))?,
+        }
+        match statement {
+            Statement::Expression(expr) | Statement::Throw(expr) => {
+                self.check_expression(expr)?;
+                if let Expression::Assignment(target, value) = expr {
+                    if let Expression::Identifier(name) = &**target {
+                        self.bind(name, value);
+                    }
+                }
+                if let Expression::ArrayDeclaration(name, _) = expr {
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.push(name.clone());
+                    }
+                }
+            }
+            Statement::Declaration(name, value) | Statement::Reference(name, value) => {
+                self.check_expression(value)?;
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.push(name.clone());
+                }
+                self.bind(name, value);
+            }
+            Statement::Return(expr) => {
+                self.check_expression(expr)?;
+                self.check_return(expr)?;
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.check_expression(condition)?;
+                self.analyze_statement(then_branch)?;
+                self.analyze_statement(else_branch)?;
+            }
+            Statement::While(condition, body) => {
+                self.check_expression(condition)?;
+                self.analyze_statement(body)?;
+            }
+            Statement::RangeFor(element, range, body) => {
+                self.check_expression(range)?;
+                self.scopes.push(vec![element.clone()]);
+                self.analyze_statement(body)?;
+                self.exit_scope();
+            }
+            Statement::Block(statements) => {
+                self.scopes.push(Vec::new());
+                for (index, statement) in statements.iter().enumerate() {
+                    self.continuation.push(&statements[index + 1..]);
+                    let result = self.analyze_statement(statement);
+                    self.continuation.pop();
+                    result?;
+                }
+                self.exit_scope();
+            }
+            Statement::Located(location, statement) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(statement);
+                self.reporter.leave_statement(enclosing);
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn expression_mentions(expr: &Expression, name: &str) -> bool {
+    match expr {
+        Expression::Identifier(id) | Expression::Variable(id) => id == name,
+        Expression::ArrayAccess(id, index) => id == name || expression_mentions(index, name),
+        Expression::FunctionCall(_, args) => args.iter().any(|arg| expression_mentions(arg, name)),
+        Expression::MethodCall(object, _, args) => {
+            expression_mentions(object, name)
+                || args.iter().any(|arg| expression_mentions(arg, name))
+        }
+        Expression::BinaryOperation(_, left, right) | Expression::Assignment(left, right) => {
+            expression_mentions(left, name) || expression_mentions(right, name)
+        }
+        Expression::Dereference(inner)
+        | Expression::AddressOf(inner)
+        | Expression::UnaryOperation(_, inner)
+        | Expression::MemberAccess(inner, _)
+        | Expression::Delete(inner, _)
+        | Expression::ArrayDeclaration(_, inner) => expression_mentions(inner, name),
+        Expression::New(_, size, initializer) => {
+            new_operands(size, initializer).any(|inner| expression_mentions(inner, name))
+        }
+        _ => false,
+    }
+}
+
+fn statement_mentions(statement: &Statement, name: &str) -> bool {
+    match statement {
+        Statement::Expression(expr)
+        | Statement::Declaration(_, expr)
+        | Statement::Reference(_, expr)
+        | Statement::Return(expr)
+        | Statement::Throw(expr) => expression_mentions(expr, name),
+        Statement::If(condition, then_branch, else_branch) => {
+            expression_mentions(condition, name)
+                || statement_mentions(then_branch, name)
+                || statement_mentions(else_branch, name)
+        }
+        Statement::While(condition, body) | Statement::RangeFor(_, condition, body) => {
+            expression_mentions(condition, name) || statement_mentions(body, name)
+        }
+        Statement::Block(statements) => statements
+            .iter()
+            .any(|statement| statement_mentions(statement, name)),
+        Statement::Located(_, statement) => statement_mentions(statement, name),
+    }
+}
@@ -0,0 +1,566 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{
+    new_operands, parameter_name, ClassDeclaration, Declaration, Expression, MethodBody, Statement,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClassErrorKind {
+    RuleOfThree,
+    RuleOfFive,
+    NonVirtualDestructor,
+    SelfAssignment,
+}
+
+pub struct ClassError {
+    pub kind: ClassErrorKind,
+    pub class: String,
+    pub details: String,
+}
+
+impl ClassError {
+    pub fn new(kind: ClassErrorKind, class: &str, details: String) -> Self {
+        ClassError {
+            kind,
+            class: class.to_string(),
+            details,
+        }
+    }
+}
+
+impl fmt::Display for ClassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.details)
+    }
+}
+
+impl fmt::Debug for ClassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.details)
+    }
+}
+
+impl std::error::Error for ClassError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SpecialMember {
+    Destructor,
+    CopyConstructor,
+    CopyAssignment,
+    MoveConstructor,
+    MoveAssignment,
+}
+
+impl fmt::Display for SpecialMember {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecialMember::Destructor => write!(f, "destructor"),
+            SpecialMember::CopyConstructor => write!(f, "copy constructor"),
+            SpecialMember::CopyAssignment => write!(f, "copy assignment operator"),
+            SpecialMember::MoveConstructor => write!(f, "move constructor"),
+            SpecialMember::MoveAssignment => write!(f, "move assignment operator"),
+        }
+    }
+}
+
+const RULE_OF_FIVE: [SpecialMember; 5] = [
+    SpecialMember::Destructor,
+    SpecialMember::CopyConstructor,
+    SpecialMember::CopyAssignment,
+    SpecialMember::MoveConstructor,
+    SpecialMember::MoveAssignment,
+];
+
+// A member function declared in the class body or defined out of line as `Class::name`
+struct MemberFunction<'a> {
+    name: &'a str,
+    params: &'a [String],
+    // Out-of-line constructors have theirs turned into assignments by the parser
+    initializers: &'a [(String, Expression)],
+    body: Option<&'a Statement>,
+    // `= default` members are declared but not user-provided
+    defaulted: bool,
+}
+
+// Type of a parameter declaration without its name and cv-qualifiers, e.g. `Buffer&`
+fn parameter_type(param: &str) -> String {
+    let param = param.trim();
+    let prefix = param.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+    let named = !prefix.trim().is_empty()
+        && (prefix.ends_with(char::is_whitespace) || prefix.ends_with(['&', '*']));
+    let type_name = if named { prefix } else { param };
+    type_name
+        .split_whitespace()
+        .filter(|word| *word != "const")
+        .collect()
+}
+
+fn is_allocation(expr: &Expression) -> bool {
+    match expr {
+        Expression::New(..) => true,
+        Expression::FunctionCall(name, _) => {
+            matches!(name.as_str(), "malloc" | "calloc" | "realloc" | "strdup")
+        }
+        _ => false,
+    }
+}
+
+fn is_this(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(id) => id == "this",
+        Expression::Dereference(pointer) => is_this(pointer),
+        _ => false,
+    }
+}
+
+// Name of the field an expression refers to, either by name or through `this`
+fn field<'e>(expr: &'e Expression, fields: &HashSet<&str>) -> Option<&'e str> {
+    match expr {
+        Expression::Identifier(id) if fields.contains(id.as_str()) => Some(id),
+        Expression::MemberAccess(object, name) if is_this(object) => Some(name),
+        _ => None,
+    }
+}
+
+// What a member function body does to the class's fields and its parameter, in order
+enum Event<'e> {
+    Allocate(&'e str),
+    Release(&'e str),
+    ReadParameter,
+}
+
+struct EventCollector<'e, 'f> {
+    fields: &'f HashSet<&'f str>,
+    param: Option<&'f str>,
+    events: Vec<Event<'e>>,
+    // Set when the body compares `this` with the address of the parameter
+    self_check: bool,
+}
+
+impl<'e, 'f> EventCollector<'e, 'f> {
+    fn is_self_check(&self, condition: &Expression) -> bool {
+        let param = match self.param {
+            Some(param) => param,
+            None => return false,
+        };
+        let is_param_address = |expr: &Expression| {
+            matches!(expr, Expression::AddressOf(inner)
+                if matches!(&**inner, Expression::Identifier(id) if id == param))
+        };
+        match condition {
+            Expression::BinaryOperation(op, left, right) if op == "==" || op == "!=" => {
+                (is_this(left) && is_param_address(right))
+                    || (is_param_address(left) && is_this(right))
+            }
+            Expression::UnaryOperation(_, operand) => self.is_self_check(operand),
+            _ => false,
+        }
+    }
+
+    fn expression(&mut self, expr: &'e Expression) {
+        match expr {
+            Expression::Identifier(id) if Some(id.as_str()) == self.param => {
+                self.events.push(Event::ReadParameter);
+            }
+            Expression::Assignment(target, value) => {
+                self.expression(value);
+                match field(target, self.fields) {
+                    Some(name) if is_allocation(value) => self.events.push(Event::Allocate(name)),
+                    Some(_) => {}
+                    None => self.expression(target),
+                }
+            }
+            Expression::Delete(target, _) => match field(target, self.fields) {
+                Some(name) => self.events.push(Event::Release(name)),
+                None => self.expression(target),
+            },
+            Expression::FunctionCall(name, args) => {
+                match (
+                    name.as_str(),
+                    args.first().and_then(|arg| field(arg, self.fields)),
+                ) {
+                    ("free", Some(name)) => self.events.push(Event::Release(name)),
+                    _ => args.iter().for_each(|arg| self.expression(arg)),
+                }
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.expression(object);
+                args.iter().for_each(|arg| self.expression(arg));
+            }
+            Expression::BinaryOperation(_, left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::ArrayAccess(id, index) => {
+                if Some(id.as_str()) == self.param {
+                    self.events.push(Event::ReadParameter);
+                }
+                self.expression(index);
+            }
+            Expression::Dereference(inner)
+            | Expression::AddressOf(inner)
+            | Expression::UnaryOperation(_, inner)
+            | Expression::MemberAccess(inner, _)
+            | Expression::ArrayDeclaration(_, inner) => self.expression(inner),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.expression(inner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn statement(&mut self, statement: &'e Statement) {
+        match statement {
+            Statement::Expression(expr)
+            | Statement::Declaration(_, expr)
+            | Statement::Reference(_, expr)
+            | Statement::Return(expr)
+            | Statement::Throw(expr) => self.expression(expr),
+            Statement::If(condition, then_branch, else_branch) => {
+                self.self_check |= self.is_self_check(condition);
+                self.expression(condition);
+                self.statement(then_branch);
+                self.statement(else_branch);
+            }
+            Statement::While(condition, body) | Statement::RangeFor(_, condition, body) => {
+                self.expression(condition);
+                self.statement(body);
+            }
+            Statement::Block(statements) => statements
+                .iter()
+                .for_each(|statement| self.statement(statement)),
+            Statement::Located(_, statement) => self.statement(statement),
+        }
+    }
+}
+
+// Problems reported from now on belong to `function` and point at the class definition
+fn enter(reporter: &mut Reporter<ClassError>, function: &str, class: &ClassDeclaration) {
+    reporter.enter_function(function);
+    if let Some(location) = class.location {
+        reporter.enter_statement(location);
+    }
+}
+
+// Class-level ownership checks: classes whose raw pointer members are allocated in a
+// constructor or freed in the destructor must follow the rule of three (or five when
+// they declare move operations), polymorphic bases need a virtual destructor, and copy
+// assignment must survive `a = a`
+pub struct ClassAnalyzer<'a> {
+    classes: HashMap<&'a str, &'a ClassDeclaration>,
+    members: HashMap<&'a str, Vec<MemberFunction<'a>>>,
+    order: Vec<&'a str>,
+}
+
+impl<'a> ClassAnalyzer<'a> {
+    pub fn new(declarations: &'a [Declaration]) -> Self {
+        let mut analyzer = ClassAnalyzer {
+            classes: HashMap::new(),
+            members: HashMap::new(),
+            order: Vec::new(),
+        };
+        for declaration in declarations {
+            if let Declaration::Class(class) = declaration {
+                analyzer.classes.insert(&class.name, class);
+                analyzer.order.push(&class.name);
+                let members = analyzer.members.entry(&class.name).or_default();
+                for method in &class.methods {
+                    members.push(MemberFunction {
+                        name: &method.name,
+                        params: &method.params,
+                        initializers: &method.initializers,
+                        body: match &method.body {
+                            MethodBody::Defined(body) => Some(body),
+                            _ => None,
+                        },
+                        defaulted: method.body == MethodBody::Defaulted,
+                    });
+                }
+            }
+        }
+        for declaration in declarations {
+            if let Declaration::Function(name, params, body) = declaration {
+                if let Some((class, member)) = name.rsplit_once("::") {
+                    if let Some(members) = analyzer.members.get_mut(class) {
+                        members.push(MemberFunction {
+                            name: member,
+                            params,
+                            initializers: &[],
+                            body: Some(body),
+                            defaulted: false,
+                        });
+                    }
+                }
+            }
+        }
+        analyzer
+    }
+
+    pub fn analyze(&self) -> Result<(), ClassError> {
+        self.check_classes(&mut Reporter::default())
+    }
+
+    // Checks every class without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&self, sink: &mut DiagnosticSink) {
+        let mut reporter = Reporter::collecting();
+        let result = self.check_classes(&mut reporter);
+        sink.extend(reporter.finish(result));
+    }
+
+    fn check_classes(&self, reporter: &mut Reporter<ClassError>) -> Result<(), ClassError> {
+        for name in &self.order {
+            let class = self.classes[name];
+            // Class-wide problems belong to the class, those of one member to the member
+            enter(reporter, name, class);
+            self.check_ownership(class, reporter)?;
+            enter(reporter, name, class);
+            self.check_virtual_destructor(class, reporter)?;
+        }
+        Ok(())
+    }
+
+    fn special_member(&self, class: &str, member: &MemberFunction) -> Option<SpecialMember> {
+        if member.name == format!("~{}", class) {
+            return Some(SpecialMember::Destructor);
+        }
+        let param = match member.params {
+            [param] => parameter_type(param),
+            _ => return None,
+        };
+        let copy = param == format!("{}&", class);
+        let by_value = param == class;
+        let moved = param == format!("{}&&", class);
+        match member.name {
+            name if name == class && copy => Some(SpecialMember::CopyConstructor),
+            name if name == class && moved => Some(SpecialMember::MoveConstructor),
+            "operator=" if copy || by_value => Some(SpecialMember::CopyAssignment),
+            "operator=" if moved => Some(SpecialMember::MoveAssignment),
+            _ => None,
+        }
+    }
+
+    fn members(&self, class: &str) -> &[MemberFunction<'a>] {
+        self.members.get(class).map_or(&[], |members| members)
+    }
+
+    fn events<'f>(
+        &self,
+        fields: &'f HashSet<&'f str>,
+        param: Option<&'f str>,
+        body: &'a Statement,
+    ) -> EventCollector<'a, 'f> {
+        let mut collector = EventCollector {
+            fields,
+            param,
+            events: Vec::new(),
+            self_check: false,
+        };
+        collector.statement(body);
+        collector
+    }
+
+    fn check_ownership(
+        &self,
+        class: &ClassDeclaration,
+        reporter: &mut Reporter<ClassError>,
+    ) -> Result<(), ClassError> {
+        let name = class.name.as_str();
+        let pointers: HashSet<&str> = class
+            .fields
+            .iter()
+            .filter(|(type_name, _)| type_name.trim_end().ends_with('*'))
+            .map(|(_, field)| field.as_str())
+            .collect();
+
+        // Declared special members; `= default` copies are as shallow as implicit ones
+        let mut declared = HashSet::new();
+        let mut provided = HashSet::new();
+        let mut allocated = Vec::new();
+        let mut released = Vec::new();
+        for member in self.members(name) {
+            let special = self.special_member(name, member);
+            if let Some(special) = special {
+                declared.insert(special);
+                if !member.defaulted {
+                    provided.insert(special);
+                }
+            }
+            if member.name == name {
+                allocated.extend(
+                    member
+                        .initializers
+                        .iter()
+                        .filter(|(field, value)| {
+                            pointers.contains(field.as_str()) && is_allocation(value)
+                        })
+                        .map(|(field, _)| field.as_str()),
+                );
+            }
+            let body = match member.body {
+                Some(body) => body,
+                None => continue,
+            };
+            for event in self.events(&pointers, None, body).events {
+                match event {
+                    Event::Allocate(field) if member.name == name => allocated.push(field),
+                    Event::Release(field) if special == Some(SpecialMember::Destructor) => {
+                        released.push(field)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(field) = allocated.iter().find(|field| !released.contains(field)) {
+            if !provided.contains(&SpecialMember::Destructor) {
+                let details = format!(
+                    "'{}' allocates '{}' in its constructor but has no destructor to free it",
+                    name, field
+                );
+                reporter.report(ClassError::new(ClassErrorKind::RuleOfThree, name, details))?;
+            }
+        }
+        let owned = allocated.iter().chain(&released).next();
+        if let Some(field) = owned {
+            for copy in [
+                SpecialMember::CopyConstructor,
+                SpecialMember::CopyAssignment,
+            ] {
+                if !provided.contains(&copy) {
+                    let details = format!(
+                        "'{}' owns raw pointer '{}' but has no user-defined {}; copies share \
+                         '{}' and free it twice (define or delete it, or use std::unique_ptr)",
+                        name, field, copy, field
+                    );
+                    reporter.report(ClassError::new(ClassErrorKind::RuleOfThree, name, details))?;
+                }
+            }
+        }
+
+        let declares_move = declared.contains(&SpecialMember::MoveConstructor)
+            || declared.contains(&SpecialMember::MoveAssignment);
+        if declares_move {
+            if let Some(missing) = RULE_OF_FIVE
+                .iter()
+                .find(|member| !declared.contains(member))
+            {
+                let details = format!(
+                    "'{}' declares move operations but no {} (rule of five)",
+                    name, missing
+                );
+                reporter.report(ClassError::new(ClassErrorKind::RuleOfFive, name, details))?;
+            }
+        }
+
+        self.check_self_assignment(class, &pointers, reporter)
+    }
+
+    // Copy assignment that frees a member before reading the source frees the source too
+    // when both are the same object
+    fn check_self_assignment(
+        &self,
+        class: &ClassDeclaration,
+        pointers: &HashSet<&str>,
+        reporter: &mut Reporter<ClassError>,
+    ) -> Result<(), ClassError> {
+        let name = class.name.as_str();
+        for member in self.members(name) {
+            if self.special_member(name, member) != Some(SpecialMember::CopyAssignment) {
+                continue;
+            }
+            let (body, param) = match (member.body, member.params) {
+                (Some(body), [param]) if parameter_type(param).ends_with('&') => {
+                    (body, parameter_name(param))
+                }
+                _ => continue,
+            };
+            let collector = self.events(pointers, Some(param), body);
+            if collector.self_check {
+                continue;
+            }
+            let events = collector.events;
+            let first_read = events
+                .iter()
+                .position(|event| matches!(event, Event::ReadParameter));
+            let release = events
+                .iter()
+                .enumerate()
+                .find_map(|(index, event)| match event {
+                    Event::Release(field) => Some((index, *field)),
+                    _ => None,
+                });
+            if let (Some(read), Some((index, field))) = (first_read, release) {
+                if index < read {
+                    let details = format!(
+                        "copy assignment of '{}' frees '{}' before reading from '{}' and does \
+                         not check for self-assignment",
+                        name, field, param
+                    );
+                    enter(reporter, &format!("{}::{}", name, member.name), class);
+                    reporter.report(ClassError::new(
+                        ClassErrorKind::SelfAssignment,
+                        name,
+                        details,
+                    ))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn has_virtual_method(&self, class: &ClassDeclaration) -> bool {
+        class.methods.iter().any(|method| method.is_virtual)
+    }
+
+    fn has_virtual_destructor(&self, class: &ClassDeclaration, visited: &mut Vec<String>) -> bool {
+        if visited.contains(&class.name) {
+            return false;
+        }
+        visited.push(class.name.clone());
+        let destructor = format!("~{}", class.name);
+        class
+            .methods
+            .iter()
+            .any(|method| method.name == destructor && method.is_virtual)
+            || class
+                .bases
+                .iter()
+                .any(|base| match self.classes.get(base.as_str()) {
+                    Some(base) => self.has_virtual_destructor(base, visited),
+                    None => false,
+                })
+    }
+
+    fn check_virtual_destructor(
+        &self,
+        class: &ClassDeclaration,
+        reporter: &mut Reporter<ClassError>,
+    ) -> Result<(), ClassError> {
+        // Reported once, on the class that introduces the virtual functions
+        let inherits_virtual = class.bases.iter().any(|base| {
+            self.classes
+                .get(base.as_str())
+                .is_some_and(|base| self.has_virtual_method(base))
+        });
+        if !self.has_virtual_method(class)
+            || inherits_virtual
+            || self.has_virtual_destructor(class, &mut Vec::new())
+        {
+            return Ok(());
+        }
+        let details = format!(
+            "'{}' has virtual functions but a non-virtual destructor; deleting a derived object \
+             through a '{}*' does not run the derived destructor",
+            class.name, class.name
+        );
+        reporter.report(ClassError::new(
+            ClassErrorKind::NonVirtualDestructor,
+            &class.name,
+            details,
+        ))
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeMap;
+
+// Linear integer expression: sum(coefficient * variable) + constant
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearExpr {
+    coefficients: BTreeMap<String, i64>,
+    constant: i64,
+}
+
+impl LinearExpr {
+    pub fn constant(value: i64) -> Self {
+        LinearExpr {
+            coefficients: BTreeMap::new(),
+            constant: value,
+        }
+    }
+
+    pub fn variable(name: &str) -> Self {
+        let mut coefficients = BTreeMap::new();
+        coefficients.insert(name.to_string(), 1);
+        LinearExpr {
+            coefficients,
+            constant: 0,
+        }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        if self.coefficients.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    // Returns the variable name when the expression is exactly `1 * name`
+    pub fn as_variable(&self) -> Option<&str> {
+        match self.coefficients.iter().next() {
+            Some((name, 1)) if self.coefficients.len() == 1 && self.constant == 0 => Some(name),
+            _ => None,
+        }
+    }
+
+    // The arithmetic returns None when a coefficient or the constant overflows
+    pub fn add(&self, other: &LinearExpr) -> Option<LinearExpr> {
+        let mut result = self.clone();
+        for (name, coefficient) in &other.coefficients {
+            let sum = result.coefficients.entry(name.clone()).or_insert(0);
+            *sum = sum.checked_add(*coefficient)?;
+        }
+        result
+            .coefficients
+            .retain(|_, coefficient| *coefficient != 0);
+        result.constant = result.constant.checked_add(other.constant)?;
+        Some(result)
+    }
+
+    pub fn subtract(&self, other: &LinearExpr) -> Option<LinearExpr> {
+        self.add(&other.scale(-1)?)
+    }
+
+    pub fn scale(&self, factor: i64) -> Option<LinearExpr> {
+        if factor == 0 {
+            return Some(LinearExpr::constant(0));
+        }
+        let mut coefficients = BTreeMap::new();
+        for (name, coefficient) in &self.coefficients {
+            coefficients.insert(name.clone(), coefficient.checked_mul(factor)?);
+        }
+        Some(LinearExpr {
+            coefficients,
+            constant: self.constant.checked_mul(factor)?,
+        })
+    }
+}
+
+// Relation of a linear expression against zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    Eq,
+    Ne,
+    Le,
+    Lt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub expr: LinearExpr,
+    pub relation: Relation,
+}
+
+impl Constraint {
+    pub fn new(expr: LinearExpr, relation: Relation) -> Self {
+        Constraint { expr, relation }
+    }
+
+    // Builds `left <relation> right` as `left - right <relation> 0`; None on overflow
+    pub fn compare(left: &LinearExpr, relation: Relation, right: &LinearExpr) -> Option<Self> {
+        Some(Constraint::new(left.subtract(right)?, relation))
+    }
+
+    // None when negating a coefficient overflows
+    pub fn negate(&self) -> Option<Constraint> {
+        Some(match self.relation {
+            Relation::Eq => Constraint::new(self.expr.clone(), Relation::Ne),
+            Relation::Ne => Constraint::new(self.expr.clone(), Relation::Eq),
+            // !(e <= 0) <=> -e < 0
+            Relation::Le => Constraint::new(self.expr.scale(-1)?, Relation::Lt),
+            // !(e < 0) <=> -e <= 0
+            Relation::Lt => Constraint::new(self.expr.scale(-1)?, Relation::Le),
+        })
+    }
+}
+
+// Inequality `sum(coefficients) + constant <= 0` used during elimination
+#[derive(Clone)]
+struct Inequality {
+    coefficients: BTreeMap<String, i128>,
+    constant: i128,
+}
+
+impl Inequality {
+    // `sign * expr + offset <= 0`; in i128, where negating an i64 cannot overflow
+    fn from_expr(expr: &LinearExpr, sign: i128, offset: i64) -> Self {
+        Inequality {
+            coefficients: expr
+                .coefficients
+                .iter()
+                .map(|(name, coefficient)| (name.clone(), sign * *coefficient as i128))
+                .collect(),
+            constant: sign * expr.constant as i128 + offset as i128,
+        }
+        .normalized()
+    }
+
+    // Divides by the gcd of the coefficients, rounding the constant up so the
+    // integer solutions are preserved
+    fn normalized(mut self) -> Self {
+        self.coefficients.retain(|_, coefficient| *coefficient != 0);
+        let divisor = self
+            .coefficients
+            .values()
+            .fold(0, |acc, coefficient| gcd(acc, coefficient.abs()));
+        if divisor > 1 {
+            for coefficient in self.coefficients.values_mut() {
+                *coefficient /= divisor;
+            }
+            self.constant = div_ceil(self.constant, divisor);
+        }
+        self
+    }
+}
+
+pub struct ConstraintSolver {
+    max_disequality_splits: usize,
+    max_inequalities: usize,
+}
+
+impl ConstraintSolver {
+    pub fn new() -> Self {
+        ConstraintSolver {
+            max_disequality_splits: 8,
+            max_inequalities: 512,
+        }
+    }
+
+    // Returns false only when the constraints are proven to have no integer
+    // solution. When a budget is exceeded the result is conservatively true.
+    pub fn is_satisfiable(&self, constraints: &[Constraint]) -> bool {
+        let mut inequalities = Vec::new();
+        let mut disequalities = Vec::new();
+
+        for constraint in constraints {
+            match constraint.relation {
+                Relation::Le => inequalities.push(Inequality::from_expr(&constraint.expr, 1, 0)),
+                Relation::Lt => inequalities.push(Inequality::from_expr(&constraint.expr, 1, 1)),
+                Relation::Eq => {
+                    inequalities.push(Inequality::from_expr(&constraint.expr, 1, 0));
+                    inequalities.push(Inequality::from_expr(&constraint.expr, -1, 0));
+                }
+                Relation::Ne => disequalities.push(&constraint.expr),
+            }
+        }
+
+        if disequalities.len() > self.max_disequality_splits {
+            disequalities.truncate(self.max_disequality_splits);
+        }
+
+        self.satisfiable_with_disequalities(inequalities, &disequalities)
+    }
+
+    pub fn implies(&self, constraints: &[Constraint], constraint: &Constraint) -> bool {
+        let mut negated = constraints.to_vec();
+        match constraint.negate() {
+            Some(negation) => negated.push(negation),
+            None => return false,
+        }
+        !self.is_satisfiable(&negated)
+    }
+
+    // e != 0 is split into e < 0 or e > 0
+    fn satisfiable_with_disequalities(
+        &self,
+        inequalities: Vec<Inequality>,
+        disequalities: &[&LinearExpr],
+    ) -> bool {
+        match disequalities.split_first() {
+            None => self.eliminate(inequalities),
+            Some((expr, rest)) => {
+                let mut below = inequalities.clone();
+                below.push(Inequality::from_expr(expr, 1, 1));
+                if self.satisfiable_with_disequalities(below, rest) {
+                    return true;
+                }
+                let mut above = inequalities;
+                above.push(Inequality::from_expr(expr, -1, 1));
+                self.satisfiable_with_disequalities(above, rest)
+            }
+        }
+    }
+
+    // Fourier-Motzkin elimination with integer tightening
+    fn eliminate(&self, mut inequalities: Vec<Inequality>) -> bool {
+        loop {
+            if inequalities
+                .iter()
+                .any(|inequality| inequality.coefficients.is_empty() && inequality.constant > 0)
+            {
+                return false;
+            }
+            inequalities.retain(|inequality| !inequality.coefficients.is_empty());
+
+            let variable = match inequalities
+                .iter()
+                .flat_map(|inequality| inequality.coefficients.keys())
+                .next()
+            {
+                Some(variable) => variable.clone(),
+                None => return true,
+            };
+
+            let mut upper = Vec::new();
+            let mut lower = Vec::new();
+            let mut rest = Vec::new();
+            for inequality in inequalities {
+                match inequality.coefficients.get(&variable).copied() {
+                    Some(coefficient) if coefficient > 0 => upper.push(inequality),
+                    Some(_) => lower.push(inequality),
+                    None => rest.push(inequality),
+                }
+            }
+
+            if upper.len() * lower.len() + rest.len() > self.max_inequalities {
+                return true;
+            }
+
+            for up in &upper {
+                for low in &lower {
+                    let up_factor = -low.coefficients[&variable];
+                    let low_factor = up.coefficients[&variable];
+                    // Coefficients too large to combine exceed the budget as well
+                    match combine(up, up_factor, low, low_factor) {
+                        Some(mut inequality) => {
+                            inequality.coefficients.remove(&variable);
+                            rest.push(inequality.normalized());
+                        }
+                        None => return true,
+                    }
+                }
+            }
+
+            inequalities = rest;
+        }
+    }
+}
+
+impl Default for ConstraintSolver {
+    fn default() -> Self {
+        ConstraintSolver::new()
+    }
+}
+
+// `up * up_factor + low * low_factor`, or None on overflow
+fn combine(
+    up: &Inequality,
+    up_factor: i128,
+    low: &Inequality,
+    low_factor: i128,
+) -> Option<Inequality> {
+    let mut coefficients: BTreeMap<String, i128> = BTreeMap::new();
+    for (inequality, factor) in [(up, up_factor), (low, low_factor)] {
+        for (name, coefficient) in &inequality.coefficients {
+            let sum = coefficients.entry(name.clone()).or_insert(0);
+            *sum = sum.checked_add(coefficient.checked_mul(factor)?)?;
+        }
+    }
+    let constant = up
+        .constant
+        .checked_mul(up_factor)?
+        .checked_add(low.constant.checked_mul(low_factor)?)?;
+    Some(Inequality {
+        coefficients,
+        constant,
+    })
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn div_ceil(value: i128, divisor: i128) -> i128 {
+    let quotient = value / divisor;
+    if value % divisor > 0 {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use crate::analyzer::move_analyzer::path;
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{new_operands, parameter_name, Declaration, Expression, Statement};
+
+// How a standard container keeps its elements, which decides what invalidates them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerKind {
+    // std::vector, std::string: one buffer that may be reallocated
+    Contiguous,
+    Deque,
+    // std::list, std::map, std::set: one node per element
+    Node,
+    // std::unordered_map, std::unordered_set: nodes in buckets that may be rehashed
+    Hashed,
+}
+
+// Which views of a container an operation invalidates
+enum Invalidation {
+    All,
+    // Only the iterator passed as the first argument
+    Erased,
+    None,
+}
+
+impl ContainerKind {
+    pub fn from_type(name: &str) -> Option<ContainerKind> {
+        let name = name
+            .trim_start_matches("const ")
+            .trim_start_matches("std::");
+        let base = name.split('<').next().unwrap_or(name).trim();
+        match base {
+            "vector" | "string" | "wstring" | "basic_string" => Some(ContainerKind::Contiguous),
+            "deque" => Some(ContainerKind::Deque),
+            "list" | "forward_list" | "map" | "multimap" | "set" | "multiset" => {
+                Some(ContainerKind::Node)
+            }
+            "unordered_map" | "unordered_multimap" | "unordered_set" | "unordered_multiset" => {
+                Some(ContainerKind::Hashed)
+            }
+            _ => None,
+        }
+    }
+
+    fn invalidation(&self, method: &str) -> Invalidation {
+        let growing = matches!(
+            method,
+            "push_back" | "emplace_back" | "push_front" | "emplace_front" | "insert" | "emplace"
+        );
+        match (self, method) {
+            (_, "clear" | "assign" | "swap") => Invalidation::All,
+            (ContainerKind::Contiguous, "resize" | "reserve" | "shrink_to_fit")
+            | (ContainerKind::Contiguous, "erase" | "pop_back")
+            | (ContainerKind::Deque, "resize" | "shrink_to_fit")
+            | (ContainerKind::Deque, "erase" | "pop_back" | "pop_front") => Invalidation::All,
+            (ContainerKind::Contiguous | ContainerKind::Deque, _) if growing => Invalidation::All,
+            (ContainerKind::Hashed, "reserve" | "rehash") => Invalidation::All,
+            (ContainerKind::Hashed, _) if growing => Invalidation::All,
+            (ContainerKind::Node | ContainerKind::Hashed, "erase") => Invalidation::Erased,
+            _ => Invalidation::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ViewKind {
+    Iterator,
+    // The address of an element; references are tracked the same way
+    Element,
+    Data,
+}
+
+impl fmt::Display for ViewKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViewKind::Iterator => write!(f, "iterator"),
+            ViewKind::Element => write!(f, "element reference"),
+            ViewKind::Data => write!(f, "data() pointer"),
+        }
+    }
+}
+
+// A variable that refers into a container's storage
+#[derive(Debug, Clone, PartialEq)]
+struct View {
+    container: String,
+    kind: ViewKind,
+}
+
+#[derive(Clone, Default)]
+struct ContainerState {
+    views: HashMap<String, View>,
+    // Invalidated views and the operation that invalidated them
+    invalid: HashMap<String, String>,
+    // Invalidated on some paths only
+    maybe: HashSet<String>,
+    terminated: bool,
+}
+
+impl ContainerState {
+    fn merge(self, other: ContainerState) -> ContainerState {
+        if self.terminated {
+            return other;
+        }
+        if other.terminated {
+            return self;
+        }
+        let mut merged = self;
+        merged
+            .views
+            .retain(|name, view| other.views.get(name) == Some(view));
+        for name in merged.invalid.keys() {
+            if !other.invalid.contains_key(name) {
+                merged.maybe.insert(name.clone());
+            }
+        }
+        for (name, reason) in other.invalid {
+            if !merged.invalid.contains_key(&name) {
+                merged.maybe.insert(name.clone());
+                merged.invalid.insert(name, reason);
+            }
+        }
+        merged.maybe.extend(other.maybe);
+        merged
+    }
+
+    fn bind(&mut self, name: &str, view: Option<View>) {
+        self.invalid.remove(name);
+        self.maybe.remove(name);
+        match view {
+            Some(view) => self.views.insert(name.to_string(), view),
+            None => self.views.remove(name),
+        };
+    }
+}
+
+// Models iterators, element references and data() pointers into standard containers
+// and reports their use after an operation that may invalidate them, including
+// modifying a container while a range-based for loop iterates over it. Element
+// references are tracked through the element's address, e.g. `int& r = v[0]` as `&v[0]`
+pub struct ContainerAnalyzer<'a> {
+    declarations: &'a [Declaration],
+    kinds: HashMap<String, ContainerKind>,
+    // Locals declared as references; assigning to one writes the element it refers to
+    references: HashSet<String>,
+    // Containers iterated by the enclosing range-based for loops
+    iterating: Vec<String>,
+    state: ContainerState,
+    function: String,
+    reporter: Reporter<MemoryError>,
+}
+
+impl<'a> ContainerAnalyzer<'a> {
+    pub fn new(declarations: &'a [Declaration]) -> Self {
+        ContainerAnalyzer {
+            declarations,
+            kinds: HashMap::new(),
+            references: HashSet::new(),
+            iterating: Vec::new(),
+            state: ContainerState::default(),
+            function: String::new(),
+            reporter: Reporter::default(),
+        }
+    }
+
+    pub fn analyze(&mut self) -> Result<(), MemoryError> {
+        for declaration in self.declarations {
+            match declaration {
+                Declaration::Variable(name, value) => self.declare_container(name, value),
+                declaration => self.analyze_function(declaration)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Analyzes every function without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&mut self, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze();
+        sink.extend(self.reporter.finish(result));
+    }
+
+    // Analyzes only the function declared at `index`, with the global containers declared
+    // before it, adding its errors to `sink`, so that each function can be reported as
+    // soon as it is done
+    pub fn analyze_function_into(&mut self, index: usize, sink: &mut DiagnosticSink) {
+        for declaration in &self.declarations[..index] {
+            if let Declaration::Variable(name, value) = declaration {
+                self.declare_container(name, value);
+            }
+        }
+        self.reporter = Reporter::collecting();
+        let result = self.analyze_function(&self.declarations[index]);
+        sink.extend(self.reporter.finish(result));
+    }
+
+    fn analyze_function(&mut self, declaration: &Declaration) -> Result<(), MemoryError> {
+        if let Declaration::Function(name, params, body) = declaration {
+            self.function = name.clone();
+            self.reporter.enter_function(name);
+            self.state = ContainerState::default();
+            self.references.clear();
+            for param in params {
+                let name = parameter_name(param);
+                let type_name = param.trim_end().strip_suffix(name).unwrap_or(param);
+                self.declare_container(name, &Expression::Uninitialized(type_name.into()));
+            }
+            self.analyze_statement(body)?;
+        }
+        Ok(())
+    }
+
+    fn declare_container(&mut self, name: &str, value: &Expression) {
+        let kind = match value {
+            Expression::Uninitialized(type_name) | Expression::FunctionCall(type_name, _) => {
+                ContainerKind::from_type(type_name)
+            }
+            _ => None,
+        };
+        match kind {
+            Some(kind) => self.kinds.insert(name.to_string(), kind),
+            None => self.kinds.remove(name),
+        };
+    }
+
+    // Containers of unknown type, e.g. members, are treated like std::vector
+    fn kind(&self, container: &str) -> ContainerKind {
+        self.kinds
+            .get(container)
+            .copied()
+            .unwrap_or(ContainerKind::Contiguous)
+    }
+
+    // The container view an expression evaluates to, if any
+    fn view(&self, expr: &Expression) -> Option<View> {
+        let view = |container: &Expression, kind| {
+            path(container).map(|container| View { container, kind })
+        };
+        match expr {
+            Expression::Identifier(id) => self.state.views.get(id).cloned(),
+            Expression::MethodCall(container, method, _) => match method.as_str() {
+                "begin" | "end" | "cbegin" | "cend" | "rbegin" | "rend" | "find"
+                | "lower_bound" | "upper_bound" | "insert" | "emplace" | "erase" => {
+                    view(container, ViewKind::Iterator)
+                }
+                "data" | "c_str" => view(container, ViewKind::Data),
+                _ => None,
+            },
+            Expression::FunctionCall(name, args) => match (name.as_str(), args.first()) {
+                ("std::begin" | "std::end", Some(container)) => view(container, ViewKind::Iterator),
+                ("std::next" | "std::prev", Some(iterator)) => self.view(iterator),
+                _ => None,
+            },
+            Expression::AddressOf(element) => match &**element {
+                Expression::ArrayAccess(container, _) => Some(View {
+                    container: container.clone(),
+                    kind: ViewKind::Element,
+                }),
+                Expression::MethodCall(container, method, _)
+                    if matches!(method.as_str(), "front" | "back" | "at") =>
+                {
+                    view(container, ViewKind::Element)
+                }
+                Expression::Dereference(iterator) => self.view(iterator).map(|view| View {
+                    kind: ViewKind::Element,
+                    ..view
+                }),
+                _ => None,
+            },
+            // Iterator and pointer arithmetic stays within the same container
+            Expression::BinaryOperation(op, left, _) if op == "+" || op == "-" => self.view(left),
+            _ => None,
+        }
+    }
+
+    fn check_use(&mut self, name: &str) -> Result<(), MemoryError> {
+        let reason = match self.state.invalid.get(name) {
+            Some(reason) => reason,
+            None => return Ok(()),
+        };
+        let view = &self.state.views[name];
+        let certainty = if self.state.maybe.contains(name) {
+            "may have been"
+        } else {
+            "was"
+        };
+        let details = format!(
+            "{} '{}' into '{}' is used after it {} invalidated by {} in '{}'",
+            view.kind, name, view.container, certainty, reason, self.function
+        );
+        self.reporter.report(MemoryError::new(
+            MemoryErrorType::InvalidatedIterator,
+            details,
+        ))
+    }
+
+    fn invalidate(
+        &mut self,
+        container: &str,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<(), MemoryError> {
+        let invalidation = self.kind(container).invalidation(method);
+        if let Invalidation::None = invalidation {
+            return Ok(());
+        }
+        // Erasing by key, as in `m.erase(kv.first)`, may remove the element the loop is on
+        if self.iterating.iter().any(|iterated| iterated == container) {
+            let details = format!(
+                "'{}' is modified by '{}.{}()' while a range-based for loop iterates over it \
+                 in '{}'",
+                container, container, method, self.function
+            );
+            self.reporter.report(MemoryError::new(
+                MemoryErrorType::InvalidatedIterator,
+                details,
+            ))?;
+        }
+        let erased = match invalidation {
+            Invalidation::Erased => match args.first() {
+                Some(Expression::Identifier(iterator)) => Some(iterator.clone()),
+                _ => return Ok(()),
+            },
+            _ => None,
+        };
+        let reason = format!("'{}.{}()'", container, method);
+        let invalidated: Vec<String> = self
+            .state
+            .views
+            .iter()
+            .filter(|(name, view)| {
+                view.container == container
+                    && erased.as_ref().is_none_or(|erased| erased == *name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in invalidated {
+            if !self.state.invalid.contains_key(&name) {
+                self.state.maybe.remove(&name);
+                self.state.invalid.insert(name, reason.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression) -> Result<(), MemoryError> {
+        match expr {
+            Expression::Identifier(id) => self.check_use(id),
+            Expression::MethodCall(object, method, args) => {
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                self.analyze_expression(object)?;
+                match path(object) {
+                    Some(container) => self.invalidate(&container, method, args),
+                    None => Ok(()),
+                }
+            }
+            Expression::Assignment(target, value) => {
+                self.analyze_expression(value)?;
+                match &**target {
+                    Expression::Identifier(name) if self.references.contains(name) => {
+                        self.check_use(name)
+                    }
+                    Expression::Identifier(name) => {
+                        let view = self.view(value);
+                        self.state.bind(name, view);
+                        Ok(())
+                    }
+                    target => self.analyze_expression(target),
+                }
+            }
+            Expression::FunctionCall(_, args) => {
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                Ok(())
+            }
+            Expression::ArrayAccess(id, index) => {
+                self.check_use(id)?;
+                self.analyze_expression(index)
+            }
+            Expression::BinaryOperation(_, left, right) => {
+                self.analyze_expression(left)?;
+                self.analyze_expression(right)
+            }
+            Expression::Dereference(inner)
+            | Expression::AddressOf(inner)
+            | Expression::UnaryOperation(_, inner)
+            | Expression::MemberAccess(inner, _)
+            | Expression::Delete(inner, _)
+            | Expression::ArrayDeclaration(_, inner) => self.analyze_expression(inner),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.analyze_expression(inner)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn analyze_loop(
+        &mut self,
+        condition: Option<&Expression>,
+        body: &Statement,
+    ) -> Result<(), MemoryError> {
+        // A second pass catches views invalidated in one iteration and used in the next
+        let before = self.state.clone();
+        for _ in 0..2 {
+            if let Some(condition) = condition {
+                self.analyze_expression(condition)?;
+            }
+            let entry = self.state.clone();
+            self.analyze_statement(body)?;
+            let after_body = std::mem::take(&mut self.state);
+            self.state = entry.merge(after_body);
+        }
+        self.state = before.merge(std::mem::take(&mut self.state));
+        Ok(())
+    }
+
+    fn analyze_statement(&mut self, statement: &Statement) -> Result<(), MemoryError> {
+        if self.state.terminated {
+            return Ok(());
+        }
+        match statement {
+            Statement::Declaration(name, value) => {
+                self.analyze_expression(value)?;
+                self.declare_container(name, value);
+                self.references.remove(name);
+                let view = self.view(value);
+                self.state.bind(name, view);
+            }
+            // A reference to an element is tracked like the element's address
+            Statement::Reference(name, value) => {
+                self.analyze_expression(value)?;
+                self.references.insert(name.clone());
+                let view = self.view(&Expression::AddressOf(Box::new(value.clone())));
+                self.state.bind(name, view);
+            }
+            Statement::Expression(expr) => self.analyze_expression(expr)?,
+            Statement::Return(expr) | Statement::Throw(expr) => {
+                self.analyze_expression(expr)?;
+                self.state.terminated = true;
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.analyze_statement(then_branch)?;
+                let after_then = std::mem::replace(&mut self.state, before);
+                self.analyze_statement(else_branch)?;
+                let after_else = std::mem::take(&mut self.state);
+                self.state = after_then.merge(after_else);
+            }
+            Statement::While(condition, body) => self.analyze_loop(Some(condition), body)?,
+            Statement::RangeFor(element, range, body) => {
+                self.analyze_expression(range)?;
+                self.state.bind(element, None);
+                self.iterating.extend(path(range));
+                let result = self.analyze_loop(None, body);
+                if path(range).is_some() {
+                    self.iterating.pop();
+                }
+                result?;
+            }
+            Statement::Block(statements) => {
+                for statement in statements {
+                    self.analyze_statement(statement)?;
+                }
+            }
+            Statement::Located(location, statement) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(statement);
+                self.reporter.leave_statement(enclosing);
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
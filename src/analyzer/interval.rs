@@ -0,0 +1,161 @@
+use std::cmp::{max, min};
+use std::fmt;
+
+// Integer interval [low, high]; a missing bound is infinite
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub low: Option<i64>,
+    pub high: Option<i64>,
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.low {
+            Some(low) => write!(f, "[{}, ", low)?,
+            None => write!(f, "[-inf, ")?,
+        }
+        match self.high {
+            Some(high) => write!(f, "{}]", high),
+            None => write!(f, "+inf]"),
+        }
+    }
+}
+
+impl Interval {
+    pub fn new(low: Option<i64>, high: Option<i64>) -> Self {
+        Interval { low, high }
+    }
+
+    pub fn constant(value: i64) -> Self {
+        Interval::new(Some(value), Some(value))
+    }
+
+    pub fn top() -> Self {
+        Interval::new(None, None)
+    }
+
+    pub fn is_top(&self) -> bool {
+        self.low.is_none() && self.high.is_none()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!((self.low, self.high), (Some(low), Some(high)) if low > high)
+    }
+
+    pub fn is_within(&self, low: i64, high: i64) -> bool {
+        matches!(self.low, Some(l) if l >= low) && matches!(self.high, Some(h) if h <= high)
+    }
+
+    pub fn join(&self, other: &Interval) -> Interval {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        Interval::new(
+            self.low.zip(other.low).map(|(a, b)| min(a, b)),
+            self.high.zip(other.high).map(|(a, b)| max(a, b)),
+        )
+    }
+
+    pub fn meet(&self, other: &Interval) -> Interval {
+        Interval::new(
+            match (self.low, other.low) {
+                (Some(a), Some(b)) => Some(max(a, b)),
+                (a, b) => a.or(b),
+            },
+            match (self.high, other.high) {
+                (Some(a), Some(b)) => Some(min(a, b)),
+                (a, b) => a.or(b),
+            },
+        )
+    }
+
+    // Bounds that keep growing are pushed to infinity so loops terminate
+    pub fn widen(&self, next: &Interval) -> Interval {
+        if self.is_empty() {
+            return *next;
+        }
+        Interval::new(
+            match (self.low, next.low) {
+                (Some(a), Some(b)) if b >= a => Some(a),
+                _ => None,
+            },
+            match (self.high, next.high) {
+                (Some(a), Some(b)) if b <= a => Some(a),
+                _ => None,
+            },
+        )
+    }
+
+    // Recovers the infinite bounds introduced by widening
+    pub fn narrow(&self, next: &Interval) -> Interval {
+        Interval::new(self.low.or(next.low), self.high.or(next.high))
+    }
+
+    pub fn add(&self, other: &Interval) -> Interval {
+        Interval::new(
+            self.low.zip(other.low).and_then(|(a, b)| a.checked_add(b)),
+            self.high
+                .zip(other.high)
+                .and_then(|(a, b)| a.checked_add(b)),
+        )
+    }
+
+    pub fn negate(&self) -> Interval {
+        Interval::new(
+            self.high.and_then(|h| h.checked_neg()),
+            self.low.and_then(|l| l.checked_neg()),
+        )
+    }
+
+    pub fn subtract(&self, other: &Interval) -> Interval {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &Interval) -> Interval {
+        match (self.low, self.high, other.low, other.high) {
+            (Some(a), Some(b), Some(c), Some(d)) => {
+                let products = [
+                    a.checked_mul(c),
+                    a.checked_mul(d),
+                    b.checked_mul(c),
+                    b.checked_mul(d),
+                ];
+                if products.iter().any(|p| p.is_none()) {
+                    return Interval::top();
+                }
+                let products: Vec<i64> = products.iter().flatten().copied().collect();
+                Interval::new(
+                    products.iter().min().copied(),
+                    products.iter().max().copied(),
+                )
+            }
+            _ => Interval::top(),
+        }
+    }
+
+    pub fn divide(&self, other: &Interval) -> Interval {
+        match (self.low, self.high, other.low, other.high) {
+            (Some(a), Some(b), Some(c), Some(d)) if c > 0 || d < 0 => {
+                // `i64::MIN / -1` overflows
+                let quotients = [
+                    a.checked_div(c),
+                    a.checked_div(d),
+                    b.checked_div(c),
+                    b.checked_div(d),
+                ];
+                if quotients.iter().any(|q| q.is_none()) {
+                    return Interval::top();
+                }
+                let quotients: Vec<i64> = quotients.iter().flatten().copied().collect();
+                Interval::new(
+                    quotients.iter().min().copied(),
+                    quotients.iter().max().copied(),
+                )
+            }
+            _ => Interval::top(),
+        }
+    }
+}
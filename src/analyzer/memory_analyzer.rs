@@ -1,32 +1,292 @@
-use crate::parser::cpp_parser::{Declaration, Expression, Statement};
+use crate::analyzer::models::Models;
+use crate::analyzer::summaries::{FunctionSummaries, FunctionSummary};
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{
+    new_operands, parameter_name, Declaration, Expression, Location, Statement,
+};
 use std::{collections::{HashMap, HashSet}, fmt};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MemoryErrorType {
     MemoryLeak,
     DoubleFree,
     UninitializedMemoryAccess,
     NullPointerDereference,
+    UseAfterFree,
+    MismatchedDeallocation,
+    UseAfterMove,
+    InvalidatedIterator,
 }
 
-#[derive(Debug)]
+// How a block of memory was obtained, which decides how it must be released
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllocationFamily {
+    New,
+    NewArray,
+    Malloc,
+    Custom(String),
+}
+
+impl AllocationFamily {
+    // Family named in a models file; the built-in allocators keep their own families
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "new" => AllocationFamily::New,
+            "new[]" => AllocationFamily::NewArray,
+            "malloc" => AllocationFamily::Malloc,
+            _ => AllocationFamily::Custom(name.to_string()),
+        }
+    }
+
+    pub fn allocator(&self) -> &str {
+        match self {
+            AllocationFamily::New => "new",
+            AllocationFamily::NewArray => "new[]",
+            AllocationFamily::Malloc => "malloc",
+            AllocationFamily::Custom(allocator) => allocator,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MemoryError {
     error_type: MemoryErrorType,
     details: String,
 }
 
+impl MemoryError {
+    pub fn new(error_type: MemoryErrorType, details: String) -> Self {
+        MemoryError { error_type, details }
+    }
+
+    pub fn error_type(&self) -> MemoryErrorType {
+        self.error_type
+    }
+
+    pub fn details(&self) -> &str {
+        &self.details
+    }
+}
+
 impl fmt::Display for MemoryError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}: {}", self.error_type, self.details)
     }
 }
 
+// A heap allocation and whether it has been freed or handed to another owner
+#[derive(Clone)]
+struct Allocation {
+    site: String,
+    location: Option<Location>,
+    released: bool,
+}
+
+// Whether reading the whole object is a read of its value
+#[derive(Clone, Copy, PartialEq)]
+enum Storage {
+    Scalar,
+    Aggregate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Nullness {
+    Null,
+    NonNull,
+    MaybeNull,
+}
+
+// Facts that hold on the current execution path
+#[derive(Clone, Default)]
+struct PathState {
+    allocations: HashMap<usize, Allocation>,
+    owners: HashMap<String, usize>,
+    scopes: Vec<Vec<String>>,
+    terminated: bool,
+    // Objects that start out indeterminate, keyed by root: "x", "a", "*p" for heap memory
+    indeterminate: HashMap<String, Storage>,
+    // Paths written so far, e.g. "x", "s.f", "a[2]", "*p[0]"; "[]" stands for any index
+    written: HashSet<String>,
+    // Pointers whose nullness is known; anything else is assumed valid
+    nullness: HashMap<String, Nullness>,
+    // Pointers that were assigned memory, and those that point to freed memory with
+    // where it was freed
+    allocated_memory: HashSet<String>,
+    freed_memory: HashMap<String, Option<Location>>,
+    allocation_families: HashMap<String, AllocationFamily>,
+}
+
+impl PathState {
+    fn is_local(&self, id: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.iter().any(|name| name == id))
+    }
+
+    // Discards everything known about the contents of `root`
+    fn forget(&mut self, root: &str) {
+        self.indeterminate.remove(root);
+        self.written.retain(|path| segments(path)[0] != root);
+    }
+
+    fn is_initialized(&self, root: &str, path: &str) -> bool {
+        match self.indeterminate.get(root) {
+            None => true,
+            Some(Storage::Aggregate) if path == root => true,
+            Some(_) => self.written.iter().any(|written| covers(written, path)),
+        }
+    }
+
+    fn has_other_owner(&self, id: &str, allocation: usize) -> bool {
+        self.owners
+            .iter()
+            .any(|(owner, owned)| owner != id && *owned == allocation)
+    }
+
+    // State after an if/else: a path that returned does not reach the join point
+    fn merge(self, other: PathState) -> PathState {
+        if self.terminated {
+            return other;
+        }
+        if other.terminated {
+            return self;
+        }
+        let mut merged = self;
+        for (id, allocation) in other.allocations {
+            match merged.allocations.get_mut(&id) {
+                Some(existing) => existing.released &= allocation.released,
+                None => {
+                    merged.allocations.insert(id, allocation);
+                }
+            }
+        }
+        for (owner, allocation) in other.owners {
+            merged.owners.entry(owner).or_insert(allocation);
+        }
+        for (root, storage) in other.indeterminate {
+            merged.indeterminate.entry(root).or_insert(storage);
+        }
+        merged.written.retain(|path| other.written.contains(path));
+        // Memory freed on either branch may be freed after the join
+        merged.allocated_memory.extend(other.allocated_memory);
+        for (id, freed_at) in other.freed_memory {
+            merged.freed_memory.entry(id).or_insert(freed_at);
+        }
+        for (id, family) in other.allocation_families {
+            merged.allocation_families.entry(id).or_insert(family);
+        }
+        merged.nullness.retain(|id, nullness| match other.nullness.get(id) {
+            Some(other) => {
+                if other != nullness {
+                    *nullness = Nullness::MaybeNull;
+                }
+                true
+            }
+            None => false,
+        });
+        merged
+    }
+}
+
+fn is_null_constant(expr: &Expression) -> bool {
+    match expr {
+        Expression::Null | Expression::Integer(0) => true,
+        Expression::Identifier(id) => id == "NULL" || id == "nullptr",
+        _ => false,
+    }
+}
+
+// The pointer compared in `p == nullptr` or `0 != p`
+fn null_comparison<'e>(left: &'e Expression, right: &'e Expression) -> Option<&'e str> {
+    match (left, right) {
+        (Expression::Identifier(id), other) | (other, Expression::Identifier(id))
+            if is_null_constant(other) =>
+        {
+            Some(id)
+        }
+        _ => None,
+    }
+}
+
+// Splits "a[2].f" into "a", "[2]", ".f"
+fn segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (i, c) in path.char_indices() {
+        if i > 0 && (c == '.' || c == '[') {
+            segments.push(&path[start..i]);
+            start = i;
+        }
+    }
+    segments.push(&path[start..]);
+    segments
+}
+
+// Whether writing `written` initializes `path`; an unknown index matches any element
+fn covers(written: &str, path: &str) -> bool {
+    let written = segments(written);
+    let path = segments(path);
+    written.len() <= path.len()
+        && written.iter().zip(&path).all(|(w, p)| {
+            w == p || (w.starts_with('[') && p.starts_with('[') && (*w == "[]" || *p == "[]"))
+        })
+}
+
+fn is_scalar_type(type_name: &str) -> bool {
+    const SCALARS: [&str; 12] = [
+        "bool", "char", "short", "int", "long", "float", "double", "unsigned", "signed", "size_t",
+        "int32_t", "int64_t",
+    ];
+    type_name.ends_with('*')
+        || type_name
+            .split_whitespace()
+            .all(|word| SCALARS.contains(&word))
+}
+
+// `this` or `*this`
+fn is_this(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(id) => id == "this",
+        Expression::Dereference(pointer) => is_this(pointer),
+        _ => false,
+    }
+}
+
+// Fields a constructor body assigns, either by name or through `this`
+fn collect_assigned_fields(stmt: &Statement, locals: &mut Vec<String>, fields: &mut Vec<String>) {
+    match stmt {
+        Statement::Declaration(id, _) => locals.push(id.clone()),
+        Statement::Expression(Expression::Assignment(target, _)) => match &**target {
+            Expression::Identifier(field) if !locals.contains(field) => fields.push(field.clone()),
+            Expression::MemberAccess(object, field) if is_this(object) => {
+                fields.push(field.clone())
+            }
+            _ => {}
+        },
+        Statement::If(_, then_branch, else_branch) => {
+            collect_assigned_fields(then_branch, locals, fields);
+            collect_assigned_fields(else_branch, locals, fields);
+        }
+        Statement::While(_, body) | Statement::RangeFor(_, _, body) => {
+            collect_assigned_fields(body, locals, fields)
+        }
+        Statement::Block(stmts) => {
+            for stmt in stmts {
+                collect_assigned_fields(stmt, locals, fields);
+            }
+        }
+        Statement::Located(_, stmt) => collect_assigned_fields(stmt, locals, fields),
+        _ => {}
+    }
+}
+
 pub struct MemoryAnalyzer<'a> {
     declarations: &'a [Declaration],
-    allocated_memory: HashMap<String, Expression>,
-    freed_memory: HashSet<String>,
-    uninitialized_memory: HashSet<String>,
-    null_pointer_dereference: HashSet<String>,
+    custom_allocators: HashMap<String, AllocationFamily>,
+    // Family and released parameter of each user-defined deallocator
+    custom_deallocators: HashMap<String, (AllocationFamily, usize)>,
+    state: PathState,
+    next_allocation: usize,
+    summaries: FunctionSummaries,
+    reporter: Reporter<MemoryError>,
 }
 
 
@@ -34,150 +294,837 @@ impl<'a> MemoryAnalyzer<'a> {
     pub fn new(declarations: &'a [Declaration]) -> Self {
         MemoryAnalyzer {
             declarations,
-            allocated_memory: HashMap::new(),
-            freed_memory: HashSet::new(),
-            uninitialized_memory: HashSet::new(),
-            null_pointer_dereference: HashSet::new(),
+            custom_allocators: HashMap::new(),
+            custom_deallocators: HashMap::new(),
+            state: PathState::default(),
+            next_allocation: 0,
+            summaries: FunctionSummaries::compute(declarations),
+            reporter: Reporter::default(),
+        }
+    }
+
+    // Registers a user-defined pair such as fopen/fclose as its own family
+    pub fn register_allocation_pair(&mut self, allocator: &str, deallocator: &str) {
+        let family = AllocationFamily::Custom(allocator.to_string());
+        self.custom_allocators.insert(allocator.to_string(), family.clone());
+        self.custom_deallocators.insert(deallocator.to_string(), (family, 0));
+    }
+
+    // Describes calls to external functions, e.g. the bundled libc models
+    pub fn add_models(&mut self, models: &Models) {
+        for (name, model) in models.functions() {
+            if let Some(family) = &model.allocates {
+                self.custom_allocators
+                    .insert(name.to_string(), AllocationFamily::from_name(family));
+            }
+            if let Some(deallocation) = &model.deallocates {
+                let family = AllocationFamily::from_name(&deallocation.family);
+                self.custom_deallocators
+                    .insert(name.to_string(), (family, deallocation.param));
+            }
+        }
+        self.summaries = FunctionSummaries::with_models(self.declarations, models);
+    }
+
+    fn allocation_family(&self, expr: &Expression) -> Option<AllocationFamily> {
+        match expr {
+            Expression::New(_, None, _) => Some(AllocationFamily::New),
+            Expression::New(_, Some(_), _) => Some(AllocationFamily::NewArray),
+            Expression::FunctionCall(name, _) => match name.as_str() {
+                "malloc" | "calloc" | "realloc" | "strdup" => Some(AllocationFamily::Malloc),
+                _ => self.custom_allocators.get(name).cloned(),
+            },
+            _ => None,
+        }
+    }
+
+    // Family a deallocator releases and the parameter it takes the pointer in
+    fn deallocation(&self, name: &str) -> Option<(AllocationFamily, usize)> {
+        match name {
+            "free" => Some((AllocationFamily::Malloc, 0)),
+            _ => self.custom_deallocators.get(name).cloned(),
         }
     }
 
     fn is_memory_allocated(&self, expr: &Expression) -> bool {
         match expr {
-            Expression::Identifier(id) => self.allocated_memory.contains_key(id),
+            Expression::Identifier(id) => self.state.allocated_memory.contains(id),
             _ => false,
         }
     }
 
     fn is_memory_freed(&self, expr: &Expression) -> bool {
         match expr {
-            Expression::Identifier(id) => self.freed_memory.contains(id),
+            Expression::Identifier(id) => self.state.freed_memory.contains_key(id),
             _ => false,
         }
     }
 
-    fn is_memory_uninitialized(&self, expr: &Expression) -> bool {
+    fn report_error(&mut self, error: MemoryError) -> Result<(), MemoryError> {
+        self.reporter.report(error)
+    }
+
+    // Reports a problem with memory that was freed at `freed_at`
+    fn report_freed(
+        &mut self,
+        error: MemoryError,
+        freed_at: Option<Location>,
+        label: &str,
+    ) -> Result<(), MemoryError> {
+        self.reporter
+            .report_at(error, self.reporter.location(), &[(freed_at, label)])
+    }
+
+    fn handle_memory_allocation(&mut self, id: &str) {
+        self.state.freed_memory.remove(id);
+        self.state.allocated_memory.insert(id.to_string());
+    }
+
+    fn report_leak(&mut self, allocation: usize, reason: &str) -> Result<(), MemoryError> {
+        let Allocation { site, location, .. } = self.state.allocations[&allocation].clone();
+        let error = MemoryError {
+            error_type: MemoryErrorType::MemoryLeak,
+            details: format!("Memory leak: {} is never freed ({})", site, reason),
+        };
+        // Pointed at where the memory was allocated, which is what needs a free
+        self.reporter.report_at(error, location, &[])
+    }
+
+    // Drops `id` as an owner, reporting a leak if it held the last reference
+    fn release_owner(&mut self, id: &str, reason: &str) -> Result<(), MemoryError> {
+        if let Some(allocation) = self.state.owners.get(id).copied() {
+            // A failed allocation has nothing to free
+            let leaked = !self.state.allocations[&allocation].released
+                && !self.state.has_other_owner(id, allocation)
+                && self.state.nullness.get(id) != Some(&Nullness::Null);
+            self.state.owners.remove(id);
+            if leaked {
+                return self.report_leak(allocation, reason);
+            }
+        }
+        Ok(())
+    }
+
+    fn release_allocation_of(&mut self, expr: &Expression) {
+        if let Expression::Identifier(id) = expr {
+            if let Some(allocation) = self.state.owners.get(id) {
+                if let Some(allocation) = self.state.allocations.get_mut(allocation) {
+                    allocation.released = true;
+                }
+            }
+        }
+    }
+
+    fn handle_ownership_assignment(
+        &mut self,
+        id: &str,
+        expr: &Expression,
+    ) -> Result<(), MemoryError> {
+        let target = if let Some(family) = self.allocation_family(expr) {
+            self.next_allocation += 1;
+            self.state.allocations.insert(
+                self.next_allocation,
+                Allocation {
+                    site: format!("memory allocated with {} for '{}'", family.allocator(), id),
+                    location: self.reporter.location(),
+                    released: false,
+                },
+            );
+            Some(self.next_allocation)
+        } else if let Some(callee) = self.returns_fresh(expr) {
+            self.next_allocation += 1;
+            self.state.allocations.insert(
+                self.next_allocation,
+                Allocation {
+                    site: format!("memory returned by {}() for '{}'", callee, id),
+                    location: self.reporter.location(),
+                    released: false,
+                },
+            );
+            Some(self.next_allocation)
+        } else if let Some(Expression::Identifier(source)) = self.returned_argument(expr) {
+            self.state.owners.get(source).copied()
+        } else {
+            None
+        };
+
+        if !self.state.is_local(id) {
+            // Stored outside the function, so someone else is responsible for it
+            if let Some(allocation) = target.and_then(|t| self.state.allocations.get_mut(&t)) {
+                allocation.released = true;
+            }
+            return Ok(());
+        }
+        if self.state.owners.get(id) != target.as_ref() {
+            self.release_owner(id, &format!("'{}' is overwritten", id))?;
+        }
+        if let Some(allocation) = target {
+            self.state.owners.insert(id.to_string(), allocation);
+        }
+        Ok(())
+    }
+
+    // Callee of `f(...)` when it hands a new allocation to the caller
+    fn returns_fresh<'e>(&self, expr: &'e Expression) -> Option<&'e str> {
         match expr {
-            Expression::Identifier(id) => self.uninitialized_memory.contains(id),
-            _ => false,
+            Expression::FunctionCall(name, _) => self
+                .summaries
+                .get(name)
+                .filter(|summary| summary.returns_fresh)
+                .map(|_| name.as_str()),
+            _ => None,
+        }
+    }
+
+    // The expression itself, or the argument a call returns as its result
+    fn returned_argument<'e>(&self, expr: &'e Expression) -> Option<&'e Expression> {
+        match expr {
+            Expression::FunctionCall(name, args) => {
+                let summary = self.summaries.get(name)?;
+                match (summary.returns_fresh, summary.returned_params.len()) {
+                    (false, 1) => args.get(*summary.returned_params.iter().next()?),
+                    _ => None,
+                }
+            }
+            expr => Some(expr),
+        }
+    }
+
+    // Applies what a user-defined callee does to each of its arguments
+    fn apply_summary(
+        &mut self,
+        callee: &str,
+        summary: &FunctionSummary,
+        args: &[Expression],
+    ) -> Result<(), MemoryError> {
+        for (index, arg) in args.iter().enumerate() {
+            let freed = match arg {
+                Expression::Identifier(id) if summary.frees.contains(&index) => Some(id),
+                _ => None,
+            };
+            let freed_before = freed.and_then(|id| Some((id, *self.state.freed_memory.get(id)?)));
+            if let Some((id, freed_at)) = freed_before {
+                let error = MemoryError {
+                    error_type: MemoryErrorType::DoubleFree,
+                    details: format!(
+                        "Double free: '{}' is passed to '{}', which frees it again",
+                        id, callee
+                    ),
+                };
+                return self.report_freed(error, freed_at, "first freed here");
+            }
+            if summary.dereferences.contains(&index) {
+                self.check_argument_dereference(callee, arg)?;
+            }
+            if let Some(id) = freed {
+                self.handle_memory_free(id)?;
+            }
+            if summary.takes_ownership(index) {
+                self.release_allocation_of(arg);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_argument_dereference(
+        &mut self,
+        callee: &str,
+        arg: &Expression,
+    ) -> Result<(), MemoryError> {
+        let (error_type, details) = match arg {
+            arg if is_null_constant(arg) => (
+                MemoryErrorType::NullPointerDereference,
+                format!("Null pointer dereference: '{}' dereferences a null argument", callee),
+            ),
+            Expression::Identifier(id) if self.state.freed_memory.contains_key(id) => {
+                let error = MemoryError {
+                    error_type: MemoryErrorType::UseAfterFree,
+                    details: format!(
+                        "Use after free: '{}' is passed to '{}', which dereferences it",
+                        id, callee
+                    ),
+                };
+                return self.report_freed(error, self.state.freed_memory[id], "freed here");
+            }
+            Expression::Identifier(id) => match self.state.nullness.get(id) {
+                Some(Nullness::Null) | Some(Nullness::MaybeNull) => (
+                    MemoryErrorType::NullPointerDereference,
+                    format!(
+                        "Null pointer dereference: '{}' may be null when passed to '{}', which dereferences it",
+                        id, callee
+                    ),
+                ),
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        self.report_error(MemoryError { error_type, details })
+    }
+
+    // Leaves the innermost scope; `reason` says why its owners let go, given the owner
+    fn exit_scope(&mut self, reason: impl Fn(&str) -> String) -> Result<(), MemoryError> {
+        if let Some(scope) = self.state.scopes.pop() {
+            for id in scope {
+                self.release_owner(&id, &reason(&id))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Leaves every scope of the function, e.g. on return or throw
+    fn exit_function(&mut self, reason: &str) -> Result<(), MemoryError> {
+        let scopes = self.state.scopes.clone();
+        while !self.state.scopes.is_empty() {
+            self.exit_scope(|_| reason.to_string())?;
+        }
+        self.state.scopes = scopes;
+        self.state.terminated = true;
+        Ok(())
+    }
+
+    // Fields assigned by `T::T`, or None when the constructor is not available
+    fn constructor_fields(&self, type_name: &str) -> Option<Vec<String>> {
+        let constructor = format!("{0}::{0}", type_name);
+        self.declarations.iter().find_map(|decl| match decl {
+            Declaration::Function(name, params, body) if *name == constructor => {
+                let mut locals = params
+                    .iter()
+                    .map(|param| parameter_name(param).to_string())
+                    .collect();
+                let mut fields = Vec::new();
+                collect_assigned_fields(body, &mut locals, &mut fields);
+                Some(fields)
+            }
+            _ => None,
+        })
+    }
+
+    // Starts tracking `root` as indeterminate if C++ leaves an object of this type so;
+    // fields set by the constructor are written at `object`
+    fn track_object(
+        &mut self,
+        root: &str,
+        object: &str,
+        type_name: &str,
+        storage: Storage,
+    ) -> bool {
+        if let Some(fields) = self.constructor_fields(type_name) {
+            self.state.indeterminate.insert(root.to_string(), Storage::Aggregate);
+            for field in fields {
+                self.state.written.insert(format!("{}.{}", object, field));
+            }
+            true
+        } else if is_scalar_type(type_name) {
+            self.state.indeterminate.insert(root.to_string(), storage);
+            true
+        } else {
+            false
         }
     }
 
-    fn is_null_pointer_dereference(&self, expr: &Expression) -> bool {
+    // Tracks the memory `id` points to when it comes from an allocation that leaves
+    // it indeterminate (`new T`, `new T[n]`, `malloc`)
+    fn track_pointee(&mut self, id: &str, expr: &Expression) -> bool {
+        let root = format!("*{}", id);
+        self.state.forget(&root);
         match expr {
-            Expression::Identifier(id) => self.null_pointer_dereference.contains(id),
+            // `new int(5)` and `new int()` initialize the memory; a class still runs
+            // its constructor, which may leave fields out
+            Expression::New(type_name, _, Some(_))
+                if self.constructor_fields(type_name).is_none() =>
+            {
+                true
+            }
+            Expression::New(type_name, ..) => {
+                let object = format!("{}[]", root);
+                self.track_object(&root, &object, type_name, Storage::Aggregate)
+            }
+            Expression::FunctionCall(name, _) if name == "malloc" => {
+                self.state.indeterminate.insert(root, Storage::Aggregate);
+                true
+            }
             _ => false,
         }
     }
 
-    fn report_error(&mut self, error: MemoryError) -> Result<(), MemoryError> {
-        // ここでエラーを報告する方法を実装します。例えば、標準出力にエラーを表示することができます。
-        // エラーを表示し、Errを返す
-        eprintln!("{}", error);
-        Err(error)
+    fn handle_initialization(&mut self, id: &str, expr: &Expression) {
+        self.state.forget(id);
+        if let Expression::Uninitialized(type_name) = expr {
+            self.track_object(id, id, type_name, Storage::Scalar);
+        }
+        self.track_pointee(id, expr);
     }
 
-    fn handle_memory_allocation(&mut self, id: &str) {
-        if self.allocated_memory.contains_key(id) && !self.freed_memory.contains(id) {
-            let error = MemoryError {
-                error_type: MemoryErrorType::MemoryLeak,
-                details: format!("Memory leak detected for variable: {}", id),
-            };
-            self.report_error(error);
+    fn handle_write(&mut self, target: &Expression, value: &Expression) {
+        if let Expression::Identifier(id) = target {
+            self.set_nullness(id, value);
+            let pointee = format!("*{}", id);
+            let tracked = self.state.indeterminate.get(&pointee).copied();
+            // Pointing elsewhere: assume the new target is initialized
+            if !self.track_pointee(id, value) {
+                if let Some(storage) = tracked {
+                    self.state.indeterminate.insert(pointee.clone(), storage);
+                    self.state.written.insert(pointee);
+                }
+            }
+        }
+        if let Some((_, path)) = self.storage_path(target) {
+            self.state.written.insert(path);
+        }
+    }
+
+    // Root object and access path of an lvalue, e.g. ("s", "s.f") or ("*p", "*p[0]")
+    fn storage_path(&self, expr: &Expression) -> Option<(String, String)> {
+        match expr {
+            Expression::Identifier(id) | Expression::Variable(id) => Some((id.clone(), id.clone())),
+            Expression::Dereference(pointer) => match &**pointer {
+                Expression::Identifier(id) => {
+                    let root = format!("*{}", id);
+                    Some((root.clone(), format!("{}[0]", root)))
+                }
+                _ => None,
+            },
+            Expression::ArrayAccess(id, index) => {
+                let pointee = format!("*{}", id);
+                let root = if self.state.indeterminate.contains_key(&pointee) {
+                    pointee
+                } else {
+                    id.clone()
+                };
+                let path = match **index {
+                    Expression::Integer(index) => format!("{}[{}]", root, index),
+                    _ => format!("{}[]", root),
+                };
+                Some((root, path))
+            }
+            Expression::MemberAccess(object, field) => self
+                .storage_path(object)
+                .map(|(root, path)| (root, format!("{}.{}", path, field))),
+            _ => None,
+        }
+    }
+
+    fn check_initialized_read(&mut self, expr: &Expression) -> Result<(), MemoryError> {
+        if let Some((root, path)) = self.storage_path(expr) {
+            if !self.state.is_initialized(&root, &path) {
+                let details = if root.starts_with('*') {
+                    format!("Read of uninitialized heap memory '{}' before any write", path)
+                } else {
+                    format!("Read of uninitialized variable '{}' before any write", path)
+                };
+                let error = MemoryError {
+                    error_type: MemoryErrorType::UninitializedMemoryAccess,
+                    details,
+                };
+                return self.report_error(error);
+            }
+        }
+        Ok(())
+    }
+
+    fn value_nullness(&self, expr: &Expression) -> Option<Nullness> {
+        match expr {
+            expr if is_null_constant(expr) => Some(Nullness::Null),
+            // `new (std::nothrow) T` returns null instead of throwing
+            Expression::New(type_name, ..) if type_name.starts_with("(std::nothrow)") => {
+                Some(Nullness::MaybeNull)
+            }
+            Expression::New(..) | Expression::AddressOf(_) => Some(Nullness::NonNull),
+            Expression::FunctionCall(name, _)
+                if matches!(name.as_str(), "malloc" | "calloc" | "realloc") =>
+            {
+                Some(Nullness::MaybeNull)
+            }
+            Expression::FunctionCall(name, args) => {
+                let summary = self.summaries.get(name)?;
+                if summary.may_return_null {
+                    return Some(Nullness::MaybeNull);
+                }
+                match (summary.returns_fresh, summary.returned_params.len()) {
+                    (true, 0) => Some(Nullness::NonNull),
+                    (false, 1) => {
+                        let param = summary.returned_params.iter().next()?;
+                        self.value_nullness(args.get(*param)?)
+                    }
+                    _ => None,
+                }
+            }
+            Expression::Identifier(id) => self.state.nullness.get(id).copied(),
+            Expression::Assignment(_, value) => self.value_nullness(value),
+            _ => None,
+        }
+    }
+
+    fn set_nullness(&mut self, id: &str, value: &Expression) {
+        match self.value_nullness(value) {
+            Some(nullness) => self.state.nullness.insert(id.to_string(), nullness),
+            None => self.state.nullness.remove(id),
+        };
+    }
+
+    // Records that `id` has the given nullness; false if it is known not to
+    fn assume_nullness(&mut self, id: &str, nullness: Nullness) -> bool {
+        match self.state.nullness.get(id) {
+            Some(known) if *known != Nullness::MaybeNull && *known != nullness => false,
+            _ => {
+                self.state.nullness.insert(id.to_string(), nullness);
+                true
+            }
+        }
+    }
+
+    // Narrows the state to the paths where `condition` evaluates to `outcome`;
+    // returns false when no such path is feasible
+    fn refine(&mut self, condition: &Expression, outcome: bool) -> bool {
+        let (non_null, null) = if outcome {
+            (Nullness::NonNull, Nullness::Null)
         } else {
-            self.allocated_memory.insert(id.to_string(), Expression::Identifier(id.to_string()));
+            (Nullness::Null, Nullness::NonNull)
+        };
+        match condition {
+            Expression::Identifier(id) => self.assume_nullness(id, non_null),
+            Expression::UnaryOperation(op, operand) if op == "!" => self.refine(operand, !outcome),
+            Expression::BinaryOperation(op, left, right) => match op.as_str() {
+                "==" | "!=" => match null_comparison(left, right) {
+                    Some(id) if op == "==" => self.assume_nullness(id, null),
+                    Some(id) => self.assume_nullness(id, non_null),
+                    None => true,
+                },
+                "&&" if outcome => self.refine(left, true) && self.refine(right, true),
+                "||" if !outcome => self.refine(left, false) && self.refine(right, false),
+                _ => true,
+            },
+            _ => true,
         }
     }
 
-    fn handle_memory_free(&mut self, id: &str) {
-        if self.allocated_memory.contains_key(id) && !self.freed_memory.contains(id) {
-            self.freed_memory.insert(id.to_string());
-        } else if self.freed_memory.contains(id) {
+    fn refine_path(&mut self, condition: &Expression, outcome: bool) {
+        if !self.refine(condition, outcome) {
+            self.state.terminated = true;
+        }
+    }
+
+    fn check_dereference(&mut self, pointer: &Expression) -> Result<(), MemoryError> {
+        let id = match pointer {
+            Expression::Identifier(id) => id,
+            _ => return Ok(()),
+        };
+        let details = match self.state.nullness.get(id) {
+            Some(Nullness::Null) => format!("Null pointer dereference: '{}' is null here", id),
+            Some(Nullness::MaybeNull) => {
+                format!("Null pointer dereference: '{}' may be null here", id)
+            }
+            _ => return Ok(()),
+        };
+        let error = MemoryError {
+            error_type: MemoryErrorType::NullPointerDereference,
+            details,
+        };
+        self.report_error(error)
+    }
+
+    fn handle_memory_free(&mut self, id: &str) -> Result<(), MemoryError> {
+        if let Some(&freed_at) = self.state.freed_memory.get(id) {
             let error = MemoryError {
                 error_type: MemoryErrorType::DoubleFree,
                 details: format!("Double free attempt on variable: {}", id),
             };
-            self.report_error(error);
+            return self.report_freed(error, freed_at, "first freed here");
+        }
+        if self.state.allocated_memory.contains(id) {
+            let location = self.reporter.location();
+            self.state.freed_memory.insert(id.to_string(), location);
         }
+        Ok(())
+    }
+
+    fn handle_deallocation(
+        &mut self,
+        id: &str,
+        family: AllocationFamily,
+        deallocator: &str,
+    ) -> Result<(), MemoryError> {
+        if let Some(allocated) = self.state.allocation_families.get(id) {
+            if *allocated != family && !self.state.freed_memory.contains_key(id) {
+                let error = MemoryError {
+                    error_type: MemoryErrorType::MismatchedDeallocation,
+                    details: format!(
+                        "Memory for variable {} allocated with {} but released with {}",
+                        id,
+                        allocated.allocator(),
+                        deallocator
+                    ),
+                };
+                return self.report_error(error);
+            }
+        }
+        self.handle_memory_free(id)
     }
 
     fn handle_memory_assignment(&mut self, id: &Expression, expr: &Expression) {
         if let Expression::Identifier(id) = id {
-            if self.is_memory_allocated(expr) {
+            if let Some(family) = self.allocation_family(expr) {
+                self.handle_memory_allocation(id);
+                self.state.allocation_families.insert(id.to_string(), family);
+            } else if self.is_memory_allocated(expr) {
                 self.handle_memory_allocation(id);
-            } else if self.is_memory_freed(expr) {
-                self.handle_memory_free(id);
-            } else if self.is_memory_uninitialized(expr) {
-                self.uninitialized_memory.insert(id.to_string());
-            } else if self.is_null_pointer_dereference(expr) {
-                self.null_pointer_dereference.insert(id.to_string());
+            } else if self.is_memory_freed(expr) && self.state.allocated_memory.contains(id) {
+                // `id` now points to the freed memory as well
+                if let Expression::Identifier(source) = expr {
+                    let freed_at = self.state.freed_memory[source];
+                    self.state.freed_memory.insert(id.to_string(), freed_at);
+                }
             }
         }
     }
 
     fn analyze_expression(&mut self, expr: &Expression) -> Result<(), MemoryError> {
         match expr {
-            Expression::Identifier(id) => {
-                if self.is_null_pointer_dereference(expr) {
-                    let error = MemoryError {
-                        error_type: MemoryErrorType::NullPointerDereference,
-                        details: format!("Null pointer dereference detected for variable: {}", id),
-                    };
-                    return self.report_error(error);
-                }
-            }
+            Expression::Identifier(_) => self.check_initialized_read(expr)?,
             Expression::Assignment(id, assign_expr) => {
                 self.handle_memory_assignment(id, assign_expr);
+                self.analyze_lvalue(id)?;
                 self.analyze_expression(assign_expr)?;
+                // Whether the old value leaks depends on its nullness, not the new one's
+                match &**id {
+                    Expression::Identifier(id) => {
+                        self.handle_ownership_assignment(id, assign_expr)?
+                    }
+                    // Stored through a pointer or into a member: ownership is transferred
+                    _ => self.release_allocation_of(assign_expr),
+                }
+                self.handle_write(id, assign_expr);
+            }
+            Expression::FunctionCall(func_name, args) if func_name == "assert" => {
+                if let Some(condition) = args.first() {
+                    self.analyze_expression(condition)?;
+                    self.refine_path(condition, true);
+                }
             }
             Expression::FunctionCall(func_name, args) => {
-                if func_name == "free" {
-                    if let Some(arg) = args.first() {
-                        if let Expression::Identifier(id) = arg {
-                            self.handle_memory_free(id);
-                        }
+                if let Some((family, param)) = self.deallocation(func_name) {
+                    if let Some(Expression::Identifier(id)) = args.get(param) {
+                        self.handle_deallocation(id, family, func_name)?;
                     }
                 } else {
                     for arg in args {
-                        self.analyze_expression(arg)?;
+                        self.analyze_argument(arg)?;
                     }
+                    if let Some(summary) = self.summaries.get(func_name).cloned() {
+                        return self.apply_summary(func_name, &summary, args);
+                    }
+                }
+                // Freed, or passed to a callee that may take ownership
+                for arg in args {
+                    self.release_allocation_of(arg);
+                }
+            }
+            Expression::Delete(target, is_array) => {
+                if let Expression::Identifier(id) = &**target {
+                    let (family, deallocator) = if *is_array {
+                        (AllocationFamily::NewArray, "delete[]")
+                    } else {
+                        (AllocationFamily::New, "delete")
+                    };
+                    self.handle_deallocation(id, family, deallocator)?;
                 }
+                self.release_allocation_of(target);
             }
             Expression::BinaryOperation(_, left, right) => {
                 self.analyze_expression(left)?;
                 self.analyze_expression(right)?;
             }
+            Expression::UnaryOperation(op, operand) => {
+                self.analyze_expression(operand)?;
+                if op == "++" || op == "--" {
+                    self.handle_write(operand, expr);
+                }
+            }
+            Expression::Dereference(pointer) => {
+                self.analyze_expression(pointer)?;
+                self.check_dereference(pointer)?;
+                self.check_initialized_read(expr)?;
+            }
+            Expression::ArrayAccess(_, _) => {
+                self.analyze_lvalue(expr)?;
+                self.check_initialized_read(expr)?;
+            }
+            Expression::MemberAccess(object, _) => {
+                self.analyze_lvalue(object)?;
+                self.check_initialized_read(expr)?;
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.analyze_lvalue(object)?;
+                for arg in args {
+                    self.analyze_argument(arg)?;
+                }
+                // The method may initialize the object
+                if let Some((_, path)) = self.storage_path(object) {
+                    self.state.written.insert(path);
+                }
+            }
+            Expression::AddressOf(target) => {
+                // The object may be initialized through the pointer
+                self.analyze_lvalue(target)?;
+                if let Some((_, path)) = self.storage_path(target) {
+                    self.state.written.insert(path);
+                }
+                if let Expression::Identifier(id) = &**target {
+                    self.state.nullness.remove(id);
+                }
+            }
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.analyze_expression(inner)?;
+                }
+            }
+            Expression::ArrayDeclaration(id, size) => {
+                self.analyze_expression(size)?;
+                self.state.forget(id);
+                self.state.indeterminate.insert(id.clone(), Storage::Aggregate);
+            }
             _ => {}
         }
 
         Ok(())
     }
 
+    // Reads the parts of an lvalue that locate it, such as a pointer or an index
+    fn analyze_lvalue(&mut self, target: &Expression) -> Result<(), MemoryError> {
+        match target {
+            Expression::Dereference(pointer) => {
+                self.analyze_expression(pointer)?;
+                self.check_dereference(pointer)
+            }
+            Expression::ArrayAccess(id, index) => {
+                self.analyze_expression(index)?;
+                self.check_dereference(&Expression::Identifier(id.clone()))
+            }
+            Expression::MemberAccess(object, _) => self.analyze_lvalue(object),
+            _ => Ok(()),
+        }
+    }
+
+    // Arrays and heap memory passed to a callee may be filled in by it
+    fn analyze_argument(&mut self, arg: &Expression) -> Result<(), MemoryError> {
+        self.analyze_expression(arg)?;
+        if let Expression::Identifier(id) = arg {
+            for root in [id.clone(), format!("*{}", id)] {
+                if self.state.indeterminate.get(&root) == Some(&Storage::Aggregate) {
+                    self.state.written.insert(root);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn analyze_statement(&mut self, stmt: &Statement) -> Result<(), MemoryError> {
+        if self.state.terminated {
+            return Ok(());
+        }
         match stmt {
-            Statement::Declaration(id, expr) => {
-                if let expr @ Expression::FunctionCall(..) = expr {
+            Statement::Declaration(id, expr) | Statement::Reference(id, expr) => {
+                if let Some(family) = self.allocation_family(expr) {
+                    self.handle_memory_allocation(id);
+                    self.state.allocation_families.insert(id.to_string(), family);
+                    self.analyze_expression(expr)?;
+                } else if let expr @ Expression::FunctionCall(..) = expr {
                     self.handle_memory_allocation(id);
                     self.analyze_expression(expr)?;
                 } else {
-                    self.uninitialized_memory.insert(id.to_string());
+                    self.analyze_expression(expr)?;
+                }
+                if let Some(scope) = self.state.scopes.last_mut() {
+                    scope.push(id.clone());
                 }
+                self.handle_initialization(id, expr);
+                self.handle_ownership_assignment(id, expr)?;
+                self.set_nullness(id, expr);
             }
             Statement::Expression(expr) => {
                 self.analyze_expression(expr)?;
             }
-            Statement::If(_, _, _) => todo!(),
-            Statement::While(_, _) => todo!(),
-            Statement::Return(_) => todo!(),
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.refine_path(condition, true);
+                self.analyze_statement(then_branch)?;
+                let after_then = std::mem::replace(&mut self.state, before);
+                self.refine_path(condition, false);
+                self.analyze_statement(else_branch)?;
+                let after_else = std::mem::take(&mut self.state);
+                self.state = after_then.merge(after_else);
+            }
+            Statement::While(condition, body) => {
+                // The body may not run at all
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.refine_path(condition, true);
+                self.analyze_statement(body)?;
+                let after_body = std::mem::replace(&mut self.state, before.clone());
+                self.state = after_body.merge(before);
+                self.refine_path(condition, false);
+            }
+            Statement::RangeFor(element, range, body) => {
+                // Elements are initialized values of unknown nullness
+                self.analyze_expression(range)?;
+                self.state.forget(element);
+                self.state.nullness.remove(element);
+                let before = self.state.clone();
+                self.analyze_statement(body)?;
+                let after_body = std::mem::replace(&mut self.state, before.clone());
+                self.state = after_body.merge(before);
+            }
+            Statement::Return(expr) => {
+                self.analyze_expression(expr)?;
+                self.release_allocation_of(expr);
+                self.exit_function("the function returns without freeing it")?;
+            }
+            Statement::Throw(expr) => {
+                self.analyze_expression(expr)?;
+                self.release_allocation_of(expr);
+                self.exit_function("an exception is thrown before it is freed")?;
+            }
+            Statement::Located(location, stmt) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(stmt);
+                self.reporter.leave_statement(enclosing);
+                result?;
+            }
+            Statement::Block(stmts) => {
+                self.state.scopes.push(Vec::new());
+                for stmt in stmts {
+                    self.analyze_statement(stmt)?;
+                }
+                if !self.state.terminated {
+                    self.exit_scope(|id| format!("'{}' goes out of scope", id))?;
+                } else {
+                    self.state.scopes.pop();
+                }
+            }
         }
 
         Ok(())
     }
 
     pub fn analyze(&mut self, program: &'a [Statement]) -> Result<(), MemoryError> {
+        // One analyzer serves every function, so nothing of the previous body is kept
+        self.next_allocation = 0;
+        self.state = PathState::default();
+        self.state.scopes.push(Vec::new());
         for stmt in program {
             self.analyze_statement(stmt)?;
         }
+        if !self.state.terminated {
+            self.exit_function("the function returns without freeing it")?;
+        }
 
         Ok(())
     }
+    // Analyzes `program` without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&mut self, program: &'a [Statement], sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze(program);
+        sink.extend(self.reporter.finish(result));
+    }
 }
\ No newline at end of file
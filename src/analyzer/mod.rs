@@ -1,11 +1,30 @@
 pub mod array_analyzer;
+pub mod borrow_checker;
+pub mod class_analyzer;
+pub mod constraint_solver;
+pub mod container_analyzer;
+pub mod interval;
 pub mod memory_analyzer;
+pub mod models;
+pub mod move_analyzer;
 pub mod pointer_analyzer;
+pub mod points_to;
+pub mod smart_pointer_analyzer;
+pub mod summaries;
+pub mod symbolic_executor;
 
 pub use array_analyzer::ArrayError;
+pub use class_analyzer::ClassError;
 pub use memory_analyzer::MemoryError;
 pub use pointer_analyzer::PointerError;
 
 pub use array_analyzer::ArrayAnalyzer;
+pub use class_analyzer::ClassAnalyzer;
+pub use container_analyzer::ContainerAnalyzer;
 pub use memory_analyzer::MemoryAnalyzer;
+pub use models::Models;
+pub use move_analyzer::MoveAnalyzer;
 pub use pointer_analyzer::PointerAnalyzer;
+pub use smart_pointer_analyzer::SmartPointerAnalyzer;
+pub use summaries::FunctionSummaries;
+pub use symbolic_executor::SymbolicExecutor;
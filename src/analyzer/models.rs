@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::analyzer::summaries::FunctionSummary;
+use crate::json::Json;
+
+const LIBC_MODELS: &str = include_str!("../../models/libc.json");
+const CXX_MODELS: &str = include_str!("../../models/cxx.json");
+
+const FUNCTION_KEYS: [&str; 9] = [
+    "allocates",
+    "deallocates",
+    "takes_ownership",
+    "dereferences",
+    "nullable_return",
+    "buffer_sizes",
+    "taints_return",
+    "taints_params",
+    "taint_sinks",
+];
+
+#[derive(Debug, PartialEq)]
+pub struct ModelError {
+    pub details: String,
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid models file: {}", self.details)
+    }
+}
+
+impl Error for ModelError {}
+
+// The parameter a deallocator releases and the allocator family it belongs to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deallocation {
+    pub family: String,
+    pub param: usize,
+}
+
+// Parameter `size` is the number of bytes accessed through parameter `buffer`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferSize {
+    pub buffer: usize,
+    pub size: usize,
+}
+
+// What an external function, whose body is never parsed, does with memory and data
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionModel {
+    // Allocator family of the returned memory, e.g. "malloc" or "fopen"
+    pub allocates: Option<String>,
+    pub deallocates: Option<Deallocation>,
+    pub takes_ownership: Vec<usize>,
+    pub dereferences: Vec<usize>,
+    pub nullable_return: bool,
+    pub buffer_sizes: Vec<BufferSize>,
+    // The taint keys are reserved: they are parsed and checked so the bundled models
+    // can carry them, but no analyzer reads them yet
+    pub taints_return: bool,
+    // Parameters the function fills with untrusted data, e.g. the buffer of `fgets`
+    pub taints_params: Vec<usize>,
+    pub taint_sinks: Vec<usize>,
+}
+
+impl FunctionModel {
+    pub fn summary(&self) -> FunctionSummary {
+        FunctionSummary {
+            frees: self
+                .deallocates
+                .iter()
+                .map(|dealloc| dealloc.param)
+                .collect(),
+            dereferences: self.dereferences.iter().copied().collect(),
+            stores: self.takes_ownership.iter().copied().collect(),
+            returns_fresh: self.allocates.is_some(),
+            returned_params: Default::default(),
+            may_return_null: self.nullable_return,
+        }
+    }
+}
+
+fn field_error(function: &str, key: &str, expected: &str) -> ModelError {
+    ModelError {
+        details: format!("'{}' of '{}' must be {}", key, function, expected),
+    }
+}
+
+fn parse_indices(function: &str, key: &str, value: &Json) -> Result<Vec<usize>, ModelError> {
+    value
+        .as_array()
+        .and_then(|values| values.iter().map(Json::as_usize).collect())
+        .ok_or_else(|| field_error(function, key, "an array of parameter indices"))
+}
+
+fn parse_index(function: &str, key: &str, value: Option<&Json>) -> Result<usize, ModelError> {
+    value
+        .and_then(Json::as_usize)
+        .ok_or_else(|| field_error(function, key, "a parameter index"))
+}
+
+fn parse_function(name: &str, value: &Json) -> Result<FunctionModel, ModelError> {
+    let members = value.as_object().ok_or_else(|| ModelError {
+        details: format!("the model of '{}' must be an object", name),
+    })?;
+    let mut model = FunctionModel::default();
+    for (key, value) in members {
+        match key.as_str() {
+            "allocates" => {
+                let family = value
+                    .as_str()
+                    .ok_or_else(|| field_error(name, key, "an allocator family name"))?;
+                model.allocates = Some(family.to_string());
+            }
+            "deallocates" => {
+                let family = value
+                    .get("family")
+                    .and_then(Json::as_str)
+                    .ok_or_else(|| field_error(name, "deallocates.family", "a string"))?;
+                let param = match value.get("param") {
+                    Some(param) => parse_index(name, "deallocates.param", Some(param))?,
+                    None => 0,
+                };
+                model.deallocates = Some(Deallocation {
+                    family: family.to_string(),
+                    param,
+                });
+            }
+            "takes_ownership" => model.takes_ownership = parse_indices(name, key, value)?,
+            "dereferences" => model.dereferences = parse_indices(name, key, value)?,
+            "nullable_return" | "taints_return" => {
+                let flag = value
+                    .as_bool()
+                    .ok_or_else(|| field_error(name, key, "true or false"))?;
+                if key == "nullable_return" {
+                    model.nullable_return = flag;
+                } else {
+                    model.taints_return = flag;
+                }
+            }
+            "buffer_sizes" => {
+                let entries = value
+                    .as_array()
+                    .ok_or_else(|| field_error(name, key, "an array"))?;
+                for entry in entries {
+                    model.buffer_sizes.push(BufferSize {
+                        buffer: parse_index(name, "buffer_sizes.buffer", entry.get("buffer"))?,
+                        size: parse_index(name, "buffer_sizes.size", entry.get("size"))?,
+                    });
+                }
+            }
+            "taints_params" => model.taints_params = parse_indices(name, key, value)?,
+            "taint_sinks" => model.taint_sinks = parse_indices(name, key, value)?,
+            _ => {
+                return Err(ModelError {
+                    details: format!(
+                        "unknown key '{}' in the model of '{}', expected one of: {}",
+                        key,
+                        name,
+                        FUNCTION_KEYS.join(", ")
+                    ),
+                })
+            }
+        }
+    }
+    Ok(model)
+}
+
+// Models of external functions by name. Files loaded later override earlier ones
+#[derive(Debug, Clone, Default)]
+pub struct Models {
+    functions: BTreeMap<String, FunctionModel>,
+}
+
+impl Models {
+    pub fn new() -> Self {
+        Models::default()
+    }
+
+    // The models shipped with the analyzer for libc and the C++ standard library
+    pub fn bundled() -> Self {
+        let mut models = Models::new();
+        for source in [LIBC_MODELS, CXX_MODELS] {
+            models.extend(Models::parse(source).expect("bundled models are valid"));
+        }
+        models
+    }
+
+    // Reads a document of the form `{"functions": {"my_alloc": {"allocates": "pool"}}}`
+    pub fn parse(source: &str) -> Result<Self, ModelError> {
+        let document = Json::parse(source).map_err(|error| ModelError {
+            details: error.to_string(),
+        })?;
+        let functions = document
+            .get("functions")
+            .and_then(Json::as_object)
+            .ok_or_else(|| ModelError {
+                details: "expected a top-level \"functions\" object".to_string(),
+            })?;
+        let mut models = Models::new();
+        for (name, value) in functions {
+            models
+                .functions
+                .insert(name.clone(), parse_function(name, value)?);
+        }
+        Ok(models)
+    }
+
+    pub fn load(path: &str) -> Result<Self, ModelError> {
+        let source = std::fs::read_to_string(path).map_err(|error| ModelError {
+            details: format!("{}: {}", path, error),
+        })?;
+        Models::parse(&source).map_err(|error| ModelError {
+            details: format!("{}: {}", path, error.details),
+        })
+    }
+
+    pub fn extend(&mut self, other: Models) {
+        self.functions.extend(other.functions);
+    }
+
+    pub fn insert(&mut self, name: &str, model: FunctionModel) {
+        self.functions.insert(name.to_string(), model);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FunctionModel> {
+        self.functions.get(name)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &FunctionModel)> {
+        self.functions
+            .iter()
+            .map(|(name, model)| (name.as_str(), model))
+    }
+}
@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use crate::analyzer::smart_pointer_analyzer::SmartPointerKind;
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{new_operands, Declaration, Expression, Location, Statement};
+
+// Methods that put a moved-from object back into a known state
+const RESETTING_METHODS: [&str; 3] = ["clear", "reset", "assign"];
+
+fn is_rvalue_reference(param: &str) -> bool {
+    param.contains("&&")
+}
+
+// `std::forward` is usually spelled with its template argument, `std::forward<T>(t)`
+fn is_move(callee: &str) -> bool {
+    callee == "std::move" || callee.starts_with("std::forward")
+}
+
+// Variable or member path an expression names, e.g. `s.name`
+pub(crate) fn path(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(id) => Some(id.clone()),
+        Expression::MemberAccess(object, field) => match &**object {
+            Expression::Dereference(pointer) => Some(format!("{}->{}", path(pointer)?, field)),
+            object => Some(format!("{}.{}", path(object)?, field)),
+        },
+        _ => None,
+    }
+}
+
+// How and where an object was moved from
+#[derive(Clone)]
+struct Move {
+    reason: String,
+    location: Option<Location>,
+}
+
+#[derive(Clone, Default)]
+struct MoveState {
+    // Moved-from paths and how they were moved
+    moved: HashMap<String, Move>,
+    // Moved on some paths only
+    maybe: HashSet<String>,
+    terminated: bool,
+}
+
+impl MoveState {
+    fn merge(self, other: MoveState) -> MoveState {
+        if self.terminated {
+            return other;
+        }
+        if other.terminated {
+            return self;
+        }
+        let mut merged = self;
+        for name in merged.moved.keys() {
+            if !other.moved.contains_key(name) {
+                merged.maybe.insert(name.clone());
+            }
+        }
+        for (name, moved) in other.moved {
+            if !merged.moved.contains_key(&name) {
+                merged.maybe.insert(name.clone());
+                merged.moved.insert(name, moved);
+            }
+        }
+        merged.maybe.extend(other.maybe);
+        merged
+    }
+
+    fn restore(&mut self, name: &str) {
+        let prefix = format!("{}.", name);
+        let arrow = format!("{}->", name);
+        self.moved.retain(|moved, _| {
+            moved != name && !moved.starts_with(&prefix) && !moved.starts_with(&arrow)
+        });
+        self.maybe.retain(|moved| self.moved.contains_key(moved));
+    }
+}
+
+// Flags uses of objects after std::move / std::forward or after they were passed to an
+// rvalue-reference parameter. Assignment, clear(), reset() and destruction are allowed.
+// Smart pointers are null after a move, so testing them is fine but dereferencing is not
+pub struct MoveAnalyzer<'a> {
+    declarations: &'a [Declaration],
+    // Parameter positions taking an rvalue reference, per function
+    rvalue_params: HashMap<String, Vec<usize>>,
+    smart_pointers: HashSet<String>,
+    state: MoveState,
+    function: String,
+    reporter: Reporter<MemoryError>,
+}
+
+impl<'a> MoveAnalyzer<'a> {
+    pub fn new(declarations: &'a [Declaration]) -> Self {
+        let mut rvalue_params = HashMap::new();
+        for declaration in declarations {
+            if let Declaration::Function(name, params, _) = declaration {
+                let positions: Vec<usize> = params
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, param)| is_rvalue_reference(param))
+                    .map(|(index, _)| index)
+                    .collect();
+                if !positions.is_empty() {
+                    rvalue_params.insert(name.clone(), positions);
+                }
+            }
+        }
+        MoveAnalyzer {
+            declarations,
+            rvalue_params,
+            smart_pointers: HashSet::new(),
+            state: MoveState::default(),
+            function: String::new(),
+            reporter: Reporter::default(),
+        }
+    }
+
+    pub fn analyze(&mut self) -> Result<(), MemoryError> {
+        for declaration in self.declarations {
+            self.analyze_function(declaration)?;
+        }
+        Ok(())
+    }
+
+    // Analyzes every function without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&mut self, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze();
+        sink.extend(self.reporter.finish(result));
+    }
+
+    // Analyzes only the function declared at `index`, adding its errors to `sink`, so
+    // that each function can be reported as soon as it is done
+    pub fn analyze_function_into(&mut self, index: usize, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze_function(&self.declarations[index]);
+        sink.extend(self.reporter.finish(result));
+    }
+
+    fn analyze_function(&mut self, declaration: &Declaration) -> Result<(), MemoryError> {
+        if let Declaration::Function(name, _, body) = declaration {
+            self.function = name.clone();
+            self.reporter.enter_function(name);
+            self.state = MoveState::default();
+            self.smart_pointers.clear();
+            self.analyze_statement(body)?;
+        }
+        Ok(())
+    }
+
+    fn mark_moved(&mut self, expr: &Expression, reason: String) -> Result<(), MemoryError> {
+        match path(expr) {
+            Some(name) => {
+                self.check_use(&name)?;
+                self.state.maybe.remove(&name);
+                let location = self.reporter.location();
+                self.state.moved.insert(name, Move { reason, location });
+                Ok(())
+            }
+            None => self.analyze_expression(expr),
+        }
+    }
+
+    fn check_use(&mut self, name: &str) -> Result<(), MemoryError> {
+        // Using `s.name` is a use of `s` when `s` was moved as a whole
+        let moved = self.state.moved.iter().find(|(moved, _)| {
+            name == moved.as_str()
+                || name.starts_with(&format!("{}.", moved))
+                || name.starts_with(&format!("{}->", moved))
+        });
+        let (moved, Move { reason, location }) = match moved {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        if self.smart_pointers.contains(moved) && name == moved {
+            return Ok(());
+        }
+        let certainty = if self.state.maybe.contains(moved) {
+            "may have been"
+        } else {
+            "was"
+        };
+        let details = format!(
+            "'{}' is used after it {} moved from ({}) in '{}'",
+            name, certainty, reason, self.function
+        );
+        let moved_at = [(*location, "moved here")];
+        self.reporter.report_at(
+            MemoryError::new(MemoryErrorType::UseAfterMove, details),
+            self.reporter.location(),
+            &moved_at,
+        )
+    }
+
+    // Smart pointers are null after a move: dereferencing one is the only invalid use
+    fn check_dereference(&mut self, pointer: &Expression) -> Result<(), MemoryError> {
+        let name = match path(pointer) {
+            Some(name) if self.smart_pointers.contains(&name) => name,
+            _ => return Ok(()),
+        };
+        match self.state.moved.get(&name) {
+            Some(Move { reason, location }) => {
+                let details = format!(
+                    "'{}' is dereferenced after it was moved from ({}) in '{}'; it is null",
+                    name, reason, self.function
+                );
+                let moved_at = [(*location, "moved here")];
+                self.reporter.report_at(
+                    MemoryError::new(MemoryErrorType::UseAfterMove, details),
+                    self.reporter.location(),
+                    &moved_at,
+                )
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn analyze_call(&mut self, callee: &str, args: &[Expression]) -> Result<(), MemoryError> {
+        if is_move(callee) {
+            return match args.first() {
+                Some(arg) => self.mark_moved(arg, format!("passed to {}", callee)),
+                None => Ok(()),
+            };
+        }
+        let rvalue_params = self.rvalue_params.get(callee).cloned().unwrap_or_default();
+        for (index, arg) in args.iter().enumerate() {
+            if rvalue_params.contains(&index) {
+                let reason = format!("passed to rvalue-reference parameter of '{}'", callee);
+                match arg {
+                    Expression::FunctionCall(name, inner) if is_move(name) => {
+                        if let Some(inner) = inner.first() {
+                            self.mark_moved(inner, reason)?;
+                        }
+                    }
+                    arg => self.mark_moved(arg, reason)?,
+                }
+            } else {
+                self.analyze_expression(arg)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression) -> Result<(), MemoryError> {
+        match expr {
+            Expression::Identifier(_) | Expression::MemberAccess(..) if path(expr).is_some() => {
+                if let Expression::MemberAccess(object, _) = expr {
+                    if let Expression::Dereference(pointer) = &**object {
+                        self.check_dereference(pointer)?;
+                    }
+                }
+                self.check_use(&path(expr).unwrap_or_default())
+            }
+            Expression::MemberAccess(object, _) => self.analyze_expression(object),
+            Expression::FunctionCall(callee, args) => self.analyze_call(callee, args),
+            Expression::MethodCall(object, method, args) => {
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                match path(object) {
+                    Some(name) if RESETTING_METHODS.contains(&method.as_str()) => {
+                        self.state.restore(&name);
+                        Ok(())
+                    }
+                    _ => {
+                        if let Expression::Dereference(pointer) = &**object {
+                            self.check_dereference(pointer)?;
+                        }
+                        self.analyze_expression(object)
+                    }
+                }
+            }
+            Expression::Assignment(target, value) => {
+                self.analyze_expression(value)?;
+                match path(target) {
+                    Some(name) => {
+                        self.state.restore(&name);
+                        if let Expression::MemberAccess(object, _) = &**target {
+                            self.analyze_expression(object)?;
+                        }
+                        Ok(())
+                    }
+                    None => self.analyze_expression(target),
+                }
+            }
+            Expression::Dereference(pointer) => {
+                self.check_dereference(pointer)?;
+                self.analyze_expression(pointer)
+            }
+            Expression::ArrayAccess(array, index) => {
+                self.check_use(array)?;
+                self.analyze_expression(index)
+            }
+            Expression::BinaryOperation(_, left, right) => {
+                self.analyze_expression(left)?;
+                self.analyze_expression(right)
+            }
+            Expression::UnaryOperation(_, operand)
+            | Expression::AddressOf(operand)
+            | Expression::Delete(operand, _) => self.analyze_expression(operand),
+            Expression::ArrayDeclaration(_, size) => self.analyze_expression(size),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.analyze_expression(inner)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn is_smart_pointer(&self, value: &Expression) -> bool {
+        match value {
+            Expression::Uninitialized(type_name) => {
+                SmartPointerKind::from_type(type_name).is_some()
+            }
+            Expression::FunctionCall(callee, args) => {
+                SmartPointerKind::from_type(callee).is_some()
+                    || (is_move(callee)
+                        && args.first().is_some_and(|arg| self.is_smart_pointer(arg)))
+            }
+            Expression::Identifier(id) => self.smart_pointers.contains(id),
+            _ => false,
+        }
+    }
+
+    fn analyze_loop(
+        &mut self,
+        condition: Option<&Expression>,
+        body: &Statement,
+    ) -> Result<(), MemoryError> {
+        // A second pass catches objects moved in one iteration and used in the next
+        let before = self.state.clone();
+        for _ in 0..2 {
+            if let Some(condition) = condition {
+                self.analyze_expression(condition)?;
+            }
+            let entry = self.state.clone();
+            self.analyze_statement(body)?;
+            let after_body = std::mem::take(&mut self.state);
+            self.state = entry.merge(after_body);
+        }
+        self.state = before.merge(std::mem::take(&mut self.state));
+        Ok(())
+    }
+
+    fn analyze_statement(&mut self, statement: &Statement) -> Result<(), MemoryError> {
+        if self.state.terminated {
+            return Ok(());
+        }
+        match statement {
+            Statement::Declaration(name, value) | Statement::Reference(name, value) => {
+                if let Expression::FunctionCall(callee, args) = value {
+                    if is_move(callee) {
+                        if let Some(arg) = args.first() {
+                            self.mark_moved(arg, format!("moved into '{}'", name))?;
+                        }
+                    } else {
+                        self.analyze_expression(value)?;
+                    }
+                } else {
+                    self.analyze_expression(value)?;
+                }
+                if self.is_smart_pointer(value) {
+                    self.smart_pointers.insert(name.clone());
+                } else {
+                    self.smart_pointers.remove(name);
+                }
+                self.state.restore(name);
+            }
+            Statement::Expression(expr) => self.analyze_expression(expr)?,
+            Statement::Return(expr) | Statement::Throw(expr) => {
+                self.analyze_expression(expr)?;
+                self.state.terminated = true;
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.analyze_statement(then_branch)?;
+                let after_then = std::mem::replace(&mut self.state, before);
+                self.analyze_statement(else_branch)?;
+                let after_else = std::mem::take(&mut self.state);
+                self.state = after_then.merge(after_else);
+            }
+            Statement::While(condition, body) => self.analyze_loop(Some(condition), body)?,
+            Statement::RangeFor(element, range, body) => {
+                self.analyze_expression(range)?;
+                // Each iteration binds a fresh element
+                self.smart_pointers.remove(element);
+                self.state.restore(element);
+                self.analyze_loop(None, body)?;
+            }
+            Statement::Block(statements) => {
+                for statement in statements {
+                    self.analyze_statement(statement)?;
+                }
+            }
+            Statement::Located(location, statement) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(statement);
+                self.reporter.leave_statement(enclosing);
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
-use std::{collections::HashMap, rc::Rc, cell::RefCell};
-use crate::parser::cpp_parser::{Declaration, Expression, Statement};
+use std::{collections::{BTreeMap, HashMap, HashSet}, rc::Rc, cell::RefCell};
+use crate::analyzer::borrow_checker::{BorrowChecker, FunctionLifetimes};
+use crate::analyzer::points_to::{AbstractLocation, PointsToAnalysis};
+use crate::analyzer::models::Models;
+use crate::analyzer::summaries::FunctionSummaries;
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{parameter_name, Declaration, Expression, Location, Statement};
 
 pub struct Function {
     pub name: String,
@@ -14,6 +19,10 @@ pub enum PointerErrorKind {
     IncorrectNumberOfArguments,
     NonPointerArgumentForPointerParameter,
     NullDereference,
+    UseAfterFree,
+    DanglingStackReference,
+    LifetimeMismatch,
+    BorrowConflict,
 }
 
 pub struct PointerError {
@@ -45,6 +54,16 @@ pub struct PointerAnalyzer {
     declarations: Vec<Declaration>,
     pointer_states: Rc<RefCell<HashMap<String, PointerState>>>,
     functions: HashMap<String, Function>,
+    globals: HashSet<String>,
+    lifetimes: HashMap<String, FunctionLifetimes>,
+    alias_state: AliasState,
+    points_to: PointsToAnalysis,
+    summaries: FunctionSummaries,
+    models: Models,
+    reporter: Reporter<PointerError>,
+    current_function: String,
+    // Whether the whole-file facts are known, for analyzing one function at a time
+    prepared: bool,
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -53,37 +72,431 @@ enum PointerState {
     Deallocated,
 }
 
+// Where an allocation was released: the function, the pointer used and the statement
+#[derive(Clone, PartialEq)]
+struct FreeSite {
+    function: String,
+    pointer: String,
+    location: Option<Location>,
+}
+
+// Maps access paths ("q", "s.f", "p->next", "v[]") to the allocation they refer to
+#[derive(Clone, Default)]
+struct AliasState {
+    freed: Vec<Option<FreeSite>>,
+    // Where each allocation was made, by the same index as `freed`
+    allocated: Vec<Option<Location>>,
+    aliases: HashMap<String, usize>,
+    freed_locations: BTreeMap<AbstractLocation, FreeSite>,
+    stack: StackState,
+    // The path returned or threw, so nothing after it runs
+    terminated: bool,
+}
+
+// Locals of the current function by scope, and the access paths that point to them
+#[derive(Clone, Default)]
+struct StackState {
+    scopes: Vec<Vec<String>>,
+    arrays: HashSet<String>,
+    references: HashMap<String, String>,
+    // Paths that still point to a local whose scope has ended
+    dangling: HashMap<String, String>,
+}
+
+impl StackState {
+    fn is_local(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.iter().any(|local| local == name))
+    }
+
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(name.to_string());
+        }
+        self.forget(name);
+    }
+
+    fn forget(&mut self, path: &str) {
+        self.references.remove(path);
+        self.dangling.remove(path);
+    }
+
+    // Paths that outlive the scope and still point into it become dangling
+    fn exit_scope(&mut self) {
+        let scope = match self.scopes.pop() {
+            Some(scope) => scope,
+            None => return,
+        };
+        let in_scope = |path: &str| scope.iter().any(|local| local == path_root(path));
+        self.references.retain(|path, _| !in_scope(path));
+        self.dangling.retain(|path, _| !in_scope(path));
+        for local in &scope {
+            self.arrays.remove(local);
+        }
+        let expired: Vec<String> = self
+            .references
+            .iter()
+            .filter(|(_, local)| scope.contains(local))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in expired {
+            if let Some(local) = self.references.remove(&path) {
+                self.dangling.insert(path, local);
+            }
+        }
+    }
+
+    fn merge(&mut self, other: StackState) {
+        for (path, local) in other.references {
+            self.references.entry(path).or_insert(local);
+        }
+        for (path, local) in other.dangling {
+            self.dangling.entry(path).or_insert(local);
+        }
+    }
+}
+
+// Storage a pointer to a local is written into
+enum Destination {
+    Local(String),
+    Global(String),
+    Member(String),
+    Heap(String),
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Destination::Local(name) => write!(f, "local '{}'", name),
+            Destination::Global(name) => write!(f, "global '{}'", name),
+            Destination::Member(name) => write!(f, "member '{}'", name),
+            Destination::Heap(pointer) => write!(f, "memory pointed to by '{}'", pointer),
+        }
+    }
+}
+
+impl AliasState {
+    fn allocate(&mut self, location: Option<Location>) -> usize {
+        self.freed.push(None);
+        self.allocated.push(location);
+        self.freed.len() - 1
+    }
+
+    // An allocation is considered freed if it was freed on either branch that reaches
+    // the join point
+    fn merge(&mut self, other: AliasState) {
+        if other.terminated {
+            return;
+        }
+        if self.terminated {
+            *self = other;
+            return;
+        }
+        let allocations = other.freed.into_iter().zip(other.allocated);
+        for (id, (site, allocated)) in allocations.enumerate() {
+            if id >= self.freed.len() {
+                self.freed.push(site);
+                self.allocated.push(allocated);
+            } else if self.freed[id].is_none() {
+                self.freed[id] = site;
+            }
+        }
+        for (path, id) in other.aliases {
+            self.aliases.entry(path).or_insert(id);
+        }
+        for (location, site) in other.freed_locations {
+            self.freed_locations.entry(location).or_insert(site);
+        }
+        self.stack.merge(other.stack);
+    }
+}
+
+// Variable an access path starts from: "s" for "s.f", "p" for "p->next"
+fn path_root(path: &str) -> &str {
+    path.split(['.', '-', '[']).next().unwrap_or(path)
+}
+
+fn is_allocation(expr: &Expression) -> bool {
+    match expr {
+        Expression::New(..) => true,
+        Expression::FunctionCall(name, _) => {
+            matches!(name.as_str(), "malloc" | "calloc" | "realloc" | "strdup")
+        }
+        _ => false,
+    }
+}
+
+// Access path naming the storage an expression refers to
+fn access_path(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(id) | Expression::Variable(id) => Some(id.clone()),
+        Expression::MemberAccess(object, field) => match &**object {
+            Expression::Dereference(pointer) => {
+                access_path(pointer).map(|path| format!("{}->{}", path, field))
+            }
+            object => access_path(object).map(|path| format!("{}.{}", path, field)),
+        },
+        Expression::ArrayAccess(id, _) => Some(format!("{}[]", id)),
+        Expression::MethodCall(object, method, _)
+            if matches!(method.as_str(), "front" | "back" | "at") =>
+        {
+            access_path(object).map(|path| format!("{}[]", path))
+        }
+        _ => None,
+    }
+}
+
 impl PointerAnalyzer {
     pub fn new(declarations: Vec<Declaration>) -> Self {
         PointerAnalyzer {
             declarations,
             pointer_states: Rc::new(RefCell::new(HashMap::new())),
             functions: HashMap::new(),
+            globals: HashSet::new(),
+            lifetimes: HashMap::new(),
+            alias_state: AliasState::default(),
+            points_to: PointsToAnalysis::new(),
+            summaries: FunctionSummaries::default(),
+            models: Models::new(),
+            reporter: Reporter::default(),
+            current_function: String::new(),
+            prepared: false,
         }
     }
 
-    fn analyze_variable_declaration(&mut self, decl: &Declaration) -> Result<(), PointerError> {
-        if let Declaration::Variable(name, expr) = decl {
-            if self.is_pointer_expression(expr) {
-                self.pointer_states.borrow_mut().insert(name.clone(), PointerState::Allocated);
+    // Describes calls to external functions, e.g. the bundled libc models
+    pub fn add_models(&mut self, models: &Models) {
+        self.models.extend(models.clone());
+    }
+
+    // Opts a function into lifetime checking, e.g. with
+    // `FunctionLifetimes::parse("int* [[scpp::lifetime(a)]] f(int* [[scpp::lifetime(a)]] p)")`
+    pub fn add_lifetime_annotations(&mut self, lifetimes: FunctionLifetimes) {
+        self.lifetimes.insert(lifetimes.name.clone(), lifetimes);
+    }
+
+    fn lookup_alias(&self, expr: &Expression) -> Option<usize> {
+        match expr {
+            // Pointer arithmetic stays within the same allocation
+            Expression::BinaryOperation(op, left, right) if op == "+" || op == "-" => {
+                self.lookup_alias(left).or_else(|| self.lookup_alias(right))
             }
+            Expression::Assignment(_, value) => self.lookup_alias(value),
+            expr => access_path(expr).and_then(|path| self.alias_state.aliases.get(&path).copied()),
         }
-        Ok(())
     }
 
-    fn analyze_function_call(&mut self, expr: &Expression) -> Result<(), PointerError> {
-        if let Expression::FunctionCall(name, args) = expr {
-            self.check_function_call_arguments(name, args)?;
-            for arg in args {
-                self.analyze_expression(arg)?;
+    fn bind_alias(&mut self, path: String, value: &Expression) {
+        // Modelled allocators and user functions returning fresh memory allocate too
+        let returns_fresh = match value {
+            Expression::FunctionCall(name, _) => self
+                .summaries
+                .get(name)
+                .is_some_and(|summary| summary.returns_fresh),
+            _ => false,
+        };
+        let target = if is_allocation(value) || returns_fresh {
+            Some(self.alias_state.allocate(self.reporter.location()))
+        } else {
+            self.lookup_alias(value)
+        };
+        match target {
+            Some(id) => {
+                self.alias_state.aliases.insert(path, id);
+            }
+            None => {
+                self.alias_state.aliases.remove(&path);
+            }
+        }
+    }
+
+    fn handle_deallocation(&mut self, expr: &Expression) {
+        let site = FreeSite {
+            function: self.current_function.clone(),
+            pointer: access_path(expr).unwrap_or_default(),
+            location: self.reporter.location(),
+        };
+        // Only a pointer with a single heap target definitely frees that object
+        let targets = self.points_to.points_to(&self.current_function, expr);
+        if let [location @ AbstractLocation::Heap { .. }] = Vec::from_iter(targets).as_slice() {
+            self.alias_state
+                .freed_locations
+                .entry(location.clone())
+                .or_insert_with(|| site.clone());
+        }
+        if let Some(id) = self.lookup_alias(expr) {
+            if self.alias_state.freed[id].is_none() {
+                self.alias_state.freed[id] = Some(site);
+            }
+        }
+    }
+
+    // Free site of the memory `expr` refers to, if it has been released
+    fn freed_site(&self, expr: &Expression) -> Option<&FreeSite> {
+        if let Some(id) = self.lookup_alias(expr) {
+            return self.alias_state.freed[id].as_ref();
+        }
+        // Aliases created through pointers to pointers are only visible to points-to
+        let targets = self.points_to.points_to(&self.current_function, expr);
+        let mut sites = targets
+            .iter()
+            .map(|location| self.alias_state.freed_locations.get(location));
+        match sites.next() {
+            Some(Some(site)) if sites.all(|other| other.is_some()) => Some(site),
+            _ => None,
+        }
+    }
+
+    // Local variable whose storage `expr` points to
+    fn stack_target(&self, expr: &Expression) -> Option<String> {
+        let stack = &self.alias_state.stack;
+        match expr {
+            Expression::AddressOf(object) => {
+                let path = access_path(object)?;
+                let root = path_root(&path);
+                if stack.is_local(root) && !path.contains("->") {
+                    Some(root.to_string())
+                } else {
+                    None
+                }
+            }
+            // Arrays decay to a pointer to their first element
+            Expression::Identifier(id) if stack.arrays.contains(id) => Some(id.clone()),
+            Expression::BinaryOperation(op, left, right) if op == "+" || op == "-" => {
+                self.stack_target(left).or_else(|| self.stack_target(right))
+            }
+            Expression::Assignment(_, value) => self.stack_target(value),
+            expr => access_path(expr).and_then(|path| stack.references.get(&path).cloned()),
+        }
+    }
+
+    // Where a store to `target` ends up, judged by the variable that owns the storage
+    fn destination(&self, target: &Expression) -> Option<Destination> {
+        let stack = &self.alias_state.stack;
+        let pointee = |pointer: &Expression| match access_path(pointer) {
+            Some(path) => match stack.references.get(&path) {
+                Some(local) => Destination::Local(local.clone()),
+                None => Destination::Heap(path),
+            },
+            None => Destination::Heap(String::new()),
+        };
+        Some(match target {
+            Expression::Identifier(id) | Expression::Variable(id) => {
+                if stack.is_local(id) {
+                    Destination::Local(id.clone())
+                } else if self.globals.contains(id) {
+                    Destination::Global(id.clone())
+                } else {
+                    Destination::Member(id.clone())
+                }
+            }
+            Expression::ArrayAccess(id, _) if stack.arrays.contains(id) => {
+                Destination::Local(id.clone())
+            }
+            Expression::ArrayAccess(id, _) => pointee(&Expression::Identifier(id.clone())),
+            Expression::Dereference(pointer) => pointee(pointer),
+            Expression::MemberAccess(object, _) => match &**object {
+                Expression::Dereference(pointer) => pointee(pointer),
+                object => self.destination(object)?,
+            },
+            _ => return None,
+        })
+    }
+
+    // Records that `target` now holds `value`, reporting addresses of locals that
+    // escape into storage outliving the function
+    fn bind_stack_reference(
+        &mut self,
+        target: &Expression,
+        value: &Expression,
+    ) -> Result<(), PointerError> {
+        let path = access_path(target);
+        let local = match self.stack_target(value) {
+            Some(local) => local,
+            None => {
+                if let Some(path) = path {
+                    self.alias_state.stack.forget(&path);
+                }
+                return Ok(());
             }
+        };
+        match self.destination(target) {
+            Some(Destination::Local(_)) | None => {
+                if let Some(path) = path {
+                    self.alias_state.stack.dangling.remove(&path);
+                    self.alias_state.stack.references.insert(path, local);
+                }
+                Ok(())
+            }
+            Some(destination) => {
+                let error = self.escaping_reference(&local, destination);
+                self.reporter.report(error)
+            }
+        }
+    }
+
+    fn escaping_reference(&self, local: &str, destination: Destination) -> PointerError {
+        PointerError::new(
+            PointerErrorKind::DanglingStackReference,
+            format!(
+                "Address of local '{}' in '{}' is stored in {}, which outlives it",
+                local, self.current_function, destination
+            ),
+        )
+    }
+
+    fn check_returned_reference(&mut self, expr: &Expression) -> Result<(), PointerError> {
+        if let Some(local) = self.stack_target(expr) {
+            return self.reporter.report(PointerError::new(
+                PointerErrorKind::DanglingStackReference,
+                format!(
+                    "'{}' returns the address of its local '{}'",
+                    self.current_function, local
+                ),
+            ));
+        }
+        self.check_dangling_use(expr, "returned")
+    }
+
+    fn check_dangling_use(&mut self, expr: &Expression, usage: &str) -> Result<(), PointerError> {
+        let dangling = access_path(expr)
+            .and_then(|path| self.alias_state.stack.dangling.get(&path).map(|local| (path, local)));
+        if let Some((path, local)) = dangling {
+            return self.reporter.report(PointerError::new(
+                PointerErrorKind::DanglingStackReference,
+                format!(
+                    "'{}' {} in '{}', but it points to '{}', which is out of scope",
+                    path, usage, self.current_function, local
+                ),
+            ));
+        }
+        if let Some(site) = self.freed_site(expr) {
+            let details = format!(
+                "Use after free: '{}' {} in '{}', but the memory was freed through '{}' in '{}'",
+                access_path(expr).unwrap_or_default(),
+                usage,
+                self.current_function,
+                site.pointer,
+                site.function
+            );
+            let freed_at = site.location;
+            let allocated_at = self
+                .lookup_alias(expr)
+                .and_then(|id| self.alias_state.allocated[id]);
+            return self.reporter.report_at(
+                PointerError::new(PointerErrorKind::UseAfterFree, details),
+                self.reporter.location(),
+                &[(freed_at, "freed here"), (allocated_at, "allocated here")],
+            );
         }
         Ok(())
     }
 
-    fn analyze_function_call_args(&mut self, args: &Vec<Expression>) -> Result<(), PointerError> {
-        for arg in args {
-            self.analyze_expression(arg)?;
+    fn analyze_variable_declaration(&mut self, decl: &Declaration) -> Result<(), PointerError> {
+        if let Declaration::Variable(name, expr) = decl {
+            if self.is_pointer_expression(expr) {
+                self.pointer_states.borrow_mut().insert(name.clone(), PointerState::Allocated);
+            }
+            self.bind_alias(name.clone(), expr);
         }
         Ok(())
     }
@@ -97,13 +510,28 @@ impl PointerAnalyzer {
     }
 
     fn check_function_call_arguments(
-        &self,
+        &mut self,
         name: &str,
-        args: &Vec<Expression>,
+        args: &[Expression],
     ) -> Result<(), PointerError> {
+        if !self.functions.contains_key(name) {
+            // Calls through a function pointer are checked against every possible target
+            for target in self.points_to.resolve_call(&self.current_function, name) {
+                let expected = self.points_to.parameters(&target).map_or(0, |params| params.len());
+                if expected != args.len() {
+                    return self.reporter.report(PointerError::new(
+                        PointerErrorKind::IncorrectNumberOfArguments,
+                        format!(
+                            "Function '{}' called through '{}' with incorrect number of arguments",
+                            target, name
+                        ),
+                    ));
+                }
+            }
+        }
         if let Some(func) = self.functions.get(name) {
             if func.params.len() != args.len() {
-                return Err(PointerError::new(
+                return self.reporter.report(PointerError::new(
                     PointerErrorKind::IncorrectNumberOfArguments,
                     format!(
                         "Function '{}' called with incorrect number of arguments",
@@ -113,7 +541,7 @@ impl PointerAnalyzer {
             }
             for (arg, param) in args.iter().zip(func.params.iter()) {
                 if param.is_pointer && !self.is_pointer_expression(arg) {
-                    return Err(PointerError::new(
+                    return self.reporter.report(PointerError::new(
                         PointerErrorKind::NonPointerArgumentForPointerParameter,
                         format!(
                             "Function '{}' called with non-pointer argument for a pointer parameter",
@@ -134,26 +562,85 @@ impl PointerAnalyzer {
                 for arg in args {
                     self.analyze_expression(arg)?;
                 }
+                if name == "free" {
+                    if let Some(arg) = args.first() {
+                        self.handle_deallocation(arg);
+                    }
+                } else {
+                    for arg in args {
+                        self.check_dangling_use(arg, &format!("passed to '{}'", name))?;
+                    }
+                    // The callee releases the memory behind some of its arguments
+                    let freed = self.summaries.get(name).map(|summary| summary.frees.clone());
+                    for index in freed.unwrap_or_default() {
+                        if let Some(arg) = args.get(index) {
+                            self.handle_deallocation(arg);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Expression::Delete(target, _) => {
+                self.analyze_expression(target)?;
+                self.handle_deallocation(target);
                 Ok(())
             }
             Expression::Dereference(expr) => {
-                let is_pointer = self.is_pointer_expression(&**expr);
+                let is_pointer = self.is_pointer_expression(expr);
                 if let Expression::Variable(id) = &**expr {
                     let pointer_states = self.pointer_states.borrow();
                     if let Some(state) = pointer_states.get(id) {
                         if *state == PointerState::Deallocated && is_pointer {
-                            return Err(PointerError::new(
+                            return self.reporter.report(PointerError::new(
                                 PointerErrorKind::NullDereference,
                                 format!("Null dereference of variable '{}'", id),
                             ));
                         }
                     }
                 }
-                self.analyze_expression(&**expr)
+                self.check_dangling_use(expr, "dereferenced")?;
+                self.analyze_expression(expr)
+            }
+            Expression::ArrayAccess(id, index) => {
+                self.check_dangling_use(&Expression::Identifier(id.clone()), "indexed")?;
+                self.analyze_expression(index)
+            }
+            Expression::MemberAccess(object, _) => self.analyze_expression(object),
+            Expression::MethodCall(object, method, args) => {
+                self.analyze_expression(object)?;
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                // Storing a pointer into a container aliases its elements
+                let stores_element = matches!(
+                    method.as_str(),
+                    "push_back" | "push_front" | "insert" | "emplace_back"
+                );
+                if stores_element {
+                    if let (Some(path), Some(value)) = (access_path(object), args.last()) {
+                        if let Some(id) = self.lookup_alias(value) {
+                            self.alias_state.aliases.insert(format!("{}[]", path), id);
+                        }
+                        // Elements live as long as the container
+                        if let Some(local) = self.stack_target(value) {
+                            match self.destination(object) {
+                                Some(Destination::Local(_)) | None => {
+                                    let element = format!("{}[]", path);
+                                    self.alias_state.stack.references.insert(element, local);
+                                }
+                                Some(destination) => {
+                                    let error = self.escaping_reference(&local, destination);
+                                    return self.reporter.report(error);
+                                }
+                            }
+                        }
+                    }
+                }
+                Ok(())
             }
             Expression::BinaryOperation(_, left, right) => {
-                self.analyze_expression(&**left)?;
-                self.analyze_expression(&**right)
+                self.analyze_expression(left)?;
+                self.analyze_expression(right)
             }
             Expression::Assignment(left, right) => {
                 let left_id = if let Expression::Variable(id) = &**left {
@@ -161,19 +648,29 @@ impl PointerAnalyzer {
                 } else {
                     None
                 };
-                self.analyze_expression(&**left)?;
-                self.analyze_expression(&**right)?;
+                self.analyze_expression(left)?;
+                self.analyze_expression(right)?;
 
                 if let Some(id) = left_id {
+                    let is_pointer = self.is_pointer_expression(right);
                     let mut pointer_states = self.pointer_states.borrow_mut();
                     if let Some(state) = pointer_states.get_mut(&id) {
-                        if self.is_pointer_expression(&**right) {
+                        if is_pointer {
                             *state = PointerState::Allocated;
                         } else {
                             *state = PointerState::Deallocated;
                         }
                     }
                 }
+                if let Some(path) = access_path(left) {
+                    self.bind_alias(path, right);
+                }
+                self.bind_stack_reference(left, right)
+            }
+            Expression::ArrayDeclaration(name, size) => {
+                self.analyze_expression(size)?;
+                self.alias_state.stack.declare(name);
+                self.alias_state.stack.arrays.insert(name.clone());
                 Ok(())
             }
             _ => Ok(()),
@@ -181,41 +678,188 @@ impl PointerAnalyzer {
     }
 
     fn analyze_statement(&mut self, stmt: &Statement) -> Result<(), PointerError> {
+        if self.alias_state.terminated {
+            return Ok(());
+        }
         match stmt {
-            Statement::Return(expr) => self.analyze_expression(expr),
-            _ => Ok(()),
+            Statement::Expression(expr) => self.analyze_expression(expr),
+            Statement::Throw(expr) => {
+                self.analyze_expression(expr)?;
+                self.alias_state.terminated = true;
+                Ok(())
+            }
+            Statement::Return(expr) => {
+                self.analyze_expression(expr)?;
+                self.check_returned_reference(expr)?;
+                self.alias_state.terminated = true;
+                Ok(())
+            }
+            Statement::Declaration(name, expr) | Statement::Reference(name, expr) => {
+                self.analyze_expression(expr)?;
+                self.bind_alias(name.clone(), expr);
+                self.alias_state.stack.declare(name);
+                self.bind_stack_reference(&Expression::Identifier(name.clone()), expr)
+            }
+            Statement::Located(location, stmt) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(stmt);
+                self.reporter.leave_statement(enclosing);
+                result
+            }
+            Statement::Block(stmts) => {
+                self.alias_state.stack.scopes.push(Vec::new());
+                for stmt in stmts {
+                    self.analyze_statement(stmt)?;
+                }
+                self.alias_state.stack.exit_scope();
+                Ok(())
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition)?;
+                let before = self.alias_state.clone();
+                self.analyze_statement(then_branch)?;
+                let after_then = std::mem::replace(&mut self.alias_state, before);
+                self.analyze_statement(else_branch)?;
+                self.alias_state.merge(after_then);
+                Ok(())
+            }
+            // The body may not run at all, so the path after the loop joins the one
+            // that skips it with the body's exit, if the body does not return
+            Statement::While(condition, body) => {
+                self.analyze_expression(condition)?;
+                let before = self.alias_state.clone();
+                self.analyze_statement(body)?;
+                let after_body = std::mem::replace(&mut self.alias_state, before);
+                self.alias_state.merge(after_body);
+                Ok(())
+            }
+            Statement::RangeFor(element, range, body) => {
+                self.analyze_expression(range)?;
+                let before = self.alias_state.clone();
+                self.alias_state.stack.scopes.push(Vec::new());
+                self.alias_state.stack.declare(element);
+                self.alias_state.aliases.remove(element);
+                self.analyze_statement(body)?;
+                self.alias_state.stack.exit_scope();
+                let after_body = std::mem::replace(&mut self.alias_state, before);
+                self.alias_state.merge(after_body);
+                Ok(())
+            }
         }
     }
 
     fn analyze_declaration(&mut self, declaration: &Declaration) -> Result<(), PointerError> {
         match declaration {
-            Declaration::Function(_, _, stmt) => self.analyze_statement(stmt),
+            Declaration::Function(name, params, stmt) => {
+                self.current_function = name.clone();
+                self.reporter.enter_function(name);
+                let globals = self.alias_state.clone();
+                self.alias_state.stack = StackState {
+                    scopes: vec![params
+                        .iter()
+                        .map(|param| parameter_name(param).to_string())
+                        .collect()],
+                    ..StackState::default()
+                };
+                let result = self.analyze_statement(stmt);
+                self.alias_state = globals;
+                self.reporter.leave_function();
+                result
+            }
             Declaration::Variable(_, expr) => self.analyze_expression(expr),
+            Declaration::Class(_) => Ok(()),
         }
     }
 
     pub fn analyze(&mut self) -> Result<(), PointerError> {
-        for decl in &self.declarations {
+        let declarations = std::mem::take(&mut self.declarations);
+        let result = self.analyze_declarations(&declarations);
+        self.declarations = declarations;
+        result
+    }
+
+    // Analyzes every declaration without stopping at the first error, adding every error
+    // to `sink`
+    pub fn analyze_into(&mut self, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze();
+        sink.extend(self.reporter.finish(result));
+    }
+
+    // Analyzes only the function declared at `index`, adding its errors to `sink`, so
+    // that each function can be reported as soon as it is done. The first call also
+    // analyzes the global variables
+    pub fn analyze_function_into(&mut self, index: usize, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let declarations = std::mem::take(&mut self.declarations);
+        let result = self.analyze_function(&declarations, index);
+        self.declarations = declarations;
+        sink.extend(self.reporter.finish(result));
+    }
+
+    fn analyze_function(
+        &mut self,
+        declarations: &[Declaration],
+        index: usize,
+    ) -> Result<(), PointerError> {
+        if !self.prepared {
+            self.prepare(declarations)?;
+            for decl in declarations {
+                if let Declaration::Variable(..) = decl {
+                    self.analyze_declaration(decl)?;
+                }
+            }
+        }
+        let decl = &declarations[index];
+        self.analyze_declaration(decl)?;
+        self.check_lifetimes(std::slice::from_ref(decl))
+    }
+
+    // The facts about the whole file that every function is analyzed with
+    fn prepare(&mut self, declarations: &[Declaration]) -> Result<(), PointerError> {
+        self.prepared = true;
+        for decl in declarations {
             if let Declaration::Function(name, params, _) = decl {
                 self.functions.insert(
                     name.clone(),
                     Function {
-                        //params is bool
                         name: name.clone(),
-                        params: params.clone(),
+                        // Parameter types are not parsed, so none is known to be a pointer
+                        params: params.iter().map(|_| Param { is_pointer: false }).collect(),
                     },
                 );
             }
         }
 
-        for decl in &self.declarations {
+        self.points_to = PointsToAnalysis::new();
+        self.points_to.analyze(declarations);
+        self.summaries = FunctionSummaries::with_models(declarations, &self.models);
+
+        for decl in declarations {
+            if let Declaration::Variable(name, _) = decl {
+                self.globals.insert(name.clone());
+            }
             self.analyze_variable_declaration(decl)?;
         }
+        Ok(())
+    }
 
-        for decl in &self.declarations {
+    fn analyze_declarations(&mut self, declarations: &[Declaration]) -> Result<(), PointerError> {
+        self.prepare(declarations)?;
+        for decl in declarations {
             self.analyze_declaration(decl)?;
         }
+        self.check_lifetimes(declarations)
+    }
 
+    fn check_lifetimes(&mut self, declarations: &[Declaration]) -> Result<(), PointerError> {
+        if !self.lifetimes.is_empty() {
+            let mut checker = BorrowChecker::new(declarations, &self.lifetimes);
+            checker.reporter = std::mem::take(&mut self.reporter);
+            let result = checker.analyze();
+            self.reporter = checker.reporter;
+            result?;
+        }
         Ok(())
     }
 
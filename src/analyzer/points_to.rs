@@ -0,0 +1,621 @@
+use crate::parser::cpp_parser::{parameter_name, Declaration, Expression, Statement};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+// A memory location a pointer may refer to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AbstractLocation {
+    // Local variable of a function, or a global when `function` is None
+    Variable {
+        function: Option<String>,
+        name: String,
+    },
+    // Every object created by one allocation expression
+    Heap {
+        function: String,
+        site: usize,
+    },
+    Field(Box<AbstractLocation>, String),
+    Function(String),
+}
+
+impl fmt::Display for AbstractLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbstractLocation::Variable {
+                function: Some(function),
+                name,
+            } => write!(f, "{}::{}", function, name),
+            AbstractLocation::Variable {
+                function: None,
+                name,
+            } => write!(f, "{}", name),
+            AbstractLocation::Heap { function, site } => {
+                write!(f, "heap allocation #{} in '{}'", site, function)
+            }
+            AbstractLocation::Field(base, field) => write!(f, "{}.{}", base, field),
+            AbstractLocation::Function(name) => write!(f, "function '{}'", name),
+        }
+    }
+}
+
+// Constraint graph node: the points-to set of a location, a temporary or a return value
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Node {
+    Location(AbstractLocation),
+    Temporary(usize),
+    Return(String),
+}
+
+enum PointsToConstraint {
+    // location ∈ pts(node)
+    AddressOf(Node, AbstractLocation),
+    // pts(source) ⊆ pts(target)
+    Copy(Node, Node),
+    // ∀o ∈ pts(pointer): pts(o.field) ⊆ pts(target)
+    Load(Node, Node, Option<String>),
+    // ∀o ∈ pts(pointer): pts(source) ⊆ pts(o.field)
+    Store(Node, Option<String>, Node),
+    // ∀o ∈ pts(pointer): o.field ∈ pts(target)
+    FieldAddress(Node, Node, String),
+    // Call through a function pointer from the given caller, resolved while solving
+    IndirectCall(String, Node, Node, Vec<Node>),
+}
+
+// Storage named by an lvalue expression
+enum Lvalue {
+    Direct(AbstractLocation),
+    Indirect(Node, Option<String>),
+}
+
+fn field_of(location: &AbstractLocation, field: &Option<String>) -> AbstractLocation {
+    match field {
+        Some(field) => AbstractLocation::Field(Box::new(location.clone()), field.clone()),
+        None => location.clone(),
+    }
+}
+
+// Flow-insensitive, field-sensitive inclusion-based (Andersen) points-to analysis
+pub struct PointsToAnalysis {
+    functions: BTreeMap<String, Vec<String>>,
+    globals: HashSet<String>,
+    locals: HashSet<(String, String)>,
+    constraints: Vec<PointsToConstraint>,
+    points_to: BTreeMap<Node, BTreeSet<AbstractLocation>>,
+    call_targets: BTreeMap<String, BTreeSet<String>>,
+    bound_calls: HashSet<(Node, String)>,
+    current_function: Option<String>,
+    next_temporary: usize,
+    next_site: usize,
+}
+
+impl PointsToAnalysis {
+    pub fn new() -> Self {
+        PointsToAnalysis {
+            functions: BTreeMap::new(),
+            globals: HashSet::new(),
+            locals: HashSet::new(),
+            constraints: Vec::new(),
+            points_to: BTreeMap::new(),
+            call_targets: BTreeMap::new(),
+            bound_calls: HashSet::new(),
+            current_function: None,
+            next_temporary: 0,
+            next_site: 0,
+        }
+    }
+
+    fn temporary(&mut self) -> Node {
+        self.next_temporary += 1;
+        Node::Temporary(self.next_temporary)
+    }
+
+    fn variable(&self, function: Option<&str>, name: &str) -> AbstractLocation {
+        let local = function.filter(|function| {
+            self.locals
+                .contains(&(function.to_string(), name.to_string()))
+        });
+        AbstractLocation::Variable {
+            function: local.map(|function| function.to_string()),
+            name: name.to_string(),
+        }
+    }
+
+    fn is_function_name(&self, function: Option<&str>, name: &str) -> bool {
+        self.functions.contains_key(name)
+            && !self.globals.contains(name)
+            && !function.is_some_and(|function| {
+                self.locals
+                    .contains(&(function.to_string(), name.to_string()))
+            })
+    }
+
+    fn collect_locals(&mut self, function: &str, stmt: &Statement) {
+        match stmt {
+            Statement::Declaration(name, _) | Statement::Reference(name, _) => {
+                self.locals.insert((function.to_string(), name.clone()));
+            }
+            Statement::Expression(Expression::ArrayDeclaration(name, _)) => {
+                self.locals.insert((function.to_string(), name.clone()));
+            }
+            Statement::Block(stmts) => {
+                for stmt in stmts {
+                    self.collect_locals(function, stmt);
+                }
+            }
+            Statement::If(_, then_branch, else_branch) => {
+                self.collect_locals(function, then_branch);
+                self.collect_locals(function, else_branch);
+            }
+            Statement::While(_, body) | Statement::Located(_, body) => {
+                self.collect_locals(function, body)
+            }
+            Statement::RangeFor(element, _, body) => {
+                self.locals.insert((function.to_string(), element.clone()));
+                self.collect_locals(function, body);
+            }
+            Statement::Expression(_) | Statement::Return(_) | Statement::Throw(_) => {}
+        }
+    }
+
+    fn lvalue(&mut self, expr: &Expression) -> Option<Lvalue> {
+        let function = self.current_function.clone();
+        match expr {
+            Expression::Identifier(name) | Expression::Variable(name) => {
+                Some(Lvalue::Direct(self.variable(function.as_deref(), name)))
+            }
+            Expression::Dereference(pointer) => {
+                let pointer = self.generate(pointer);
+                Some(Lvalue::Indirect(pointer, None))
+            }
+            Expression::MemberAccess(object, field) => match &**object {
+                Expression::Dereference(pointer) => {
+                    let pointer = self.generate(pointer);
+                    Some(Lvalue::Indirect(pointer, Some(field.clone())))
+                }
+                object => match self.lvalue(object)? {
+                    Lvalue::Direct(location) => Some(Lvalue::Direct(AbstractLocation::Field(
+                        Box::new(location),
+                        field.clone(),
+                    ))),
+                    Lvalue::Indirect(pointer, None) => {
+                        Some(Lvalue::Indirect(pointer, Some(field.clone())))
+                    }
+                    // Nested fields behind a pointer are merged into the outer field
+                    Lvalue::Indirect(pointer, outer) => Some(Lvalue::Indirect(pointer, outer)),
+                },
+            },
+            // Array elements are not distinguished from each other
+            Expression::ArrayAccess(name, index) => {
+                self.generate(index);
+                let pointer = Node::Location(self.variable(function.as_deref(), name));
+                Some(Lvalue::Indirect(pointer, None))
+            }
+            _ => None,
+        }
+    }
+
+    fn assign(&mut self, target: Lvalue, source: Node) {
+        let constraint = match target {
+            Lvalue::Direct(location) => PointsToConstraint::Copy(Node::Location(location), source),
+            Lvalue::Indirect(pointer, field) => PointsToConstraint::Store(pointer, field, source),
+        };
+        self.constraints.push(constraint);
+    }
+
+    fn allocation_site(&mut self) -> Node {
+        let function = self.current_function.clone().unwrap_or_default();
+        self.next_site += 1;
+        let node = self.temporary();
+        let site = AbstractLocation::Heap {
+            function,
+            site: self.next_site,
+        };
+        self.constraints
+            .push(PointsToConstraint::AddressOf(node.clone(), site));
+        node
+    }
+
+    // Emits constraints for `expr` and returns the node holding its points-to set
+    fn generate(&mut self, expr: &Expression) -> Node {
+        let function = self.current_function.clone();
+        match expr {
+            Expression::Identifier(name) | Expression::Variable(name) => {
+                if self.is_function_name(function.as_deref(), name) {
+                    let node = self.temporary();
+                    self.constraints.push(PointsToConstraint::AddressOf(
+                        node.clone(),
+                        AbstractLocation::Function(name.clone()),
+                    ));
+                    node
+                } else {
+                    Node::Location(self.variable(function.as_deref(), name))
+                }
+            }
+            Expression::AddressOf(target) => match &**target {
+                Expression::Identifier(name) | Expression::Variable(name)
+                    if self.is_function_name(function.as_deref(), name) =>
+                {
+                    self.generate(target)
+                }
+                target => {
+                    let node = self.temporary();
+                    match self.lvalue(target) {
+                        Some(Lvalue::Direct(location)) => self
+                            .constraints
+                            .push(PointsToConstraint::AddressOf(node.clone(), location)),
+                        Some(Lvalue::Indirect(pointer, None)) => {
+                            self.constraints
+                                .push(PointsToConstraint::Copy(node.clone(), pointer));
+                        }
+                        Some(Lvalue::Indirect(pointer, Some(field))) => self.constraints.push(
+                            PointsToConstraint::FieldAddress(node.clone(), pointer, field),
+                        ),
+                        None => {}
+                    }
+                    node
+                }
+            },
+            Expression::Dereference(_)
+            | Expression::MemberAccess(..)
+            | Expression::ArrayAccess(..) => {
+                let node = self.temporary();
+                match self.lvalue(expr) {
+                    Some(Lvalue::Direct(location)) => self.constraints.push(
+                        PointsToConstraint::Copy(node.clone(), Node::Location(location)),
+                    ),
+                    Some(Lvalue::Indirect(pointer, field)) => self
+                        .constraints
+                        .push(PointsToConstraint::Load(node.clone(), pointer, field)),
+                    None => {}
+                }
+                node
+            }
+            Expression::New(_, size, initializer) => {
+                if let Some(size) = size {
+                    self.generate(size);
+                }
+                for arg in initializer.iter().flatten() {
+                    self.generate(arg);
+                }
+                self.allocation_site()
+            }
+            Expression::FunctionCall(name, args) => {
+                let args: Vec<Node> = args.iter().map(|arg| self.generate(arg)).collect();
+                if matches!(name.as_str(), "malloc" | "calloc" | "strdup") {
+                    return self.allocation_site();
+                }
+                if name == "realloc" {
+                    let node = self.allocation_site();
+                    if let Some(pointer) = args.first() {
+                        self.constraints
+                            .push(PointsToConstraint::Copy(node.clone(), pointer.clone()));
+                    }
+                    return node;
+                }
+                let result = self.temporary();
+                if self.is_function_name(function.as_deref(), name) {
+                    self.bind_call(name, &args, &result);
+                    if let Some(function) = &function {
+                        self.call_targets
+                            .entry(function.clone())
+                            .or_default()
+                            .insert(name.clone());
+                    }
+                } else {
+                    let callee = Node::Location(self.variable(function.as_deref(), name));
+                    self.constraints.push(PointsToConstraint::IndirectCall(
+                        function.clone().unwrap_or_default(),
+                        result.clone(),
+                        callee,
+                        args,
+                    ));
+                }
+                result
+            }
+            Expression::Assignment(target, value) => {
+                let source = self.generate(value);
+                if let Some(target) = self.lvalue(target) {
+                    self.assign(target, source.clone());
+                }
+                source
+            }
+            Expression::BinaryOperation(op, left, right) => {
+                let left = self.generate(left);
+                let right = self.generate(right);
+                let node = self.temporary();
+                // Pointer arithmetic keeps pointing into the same object
+                if op == "+" || op == "-" {
+                    self.constraints
+                        .push(PointsToConstraint::Copy(node.clone(), left));
+                    self.constraints
+                        .push(PointsToConstraint::Copy(node.clone(), right));
+                }
+                node
+            }
+            Expression::UnaryOperation(_, operand) | Expression::Delete(operand, _) => {
+                self.generate(operand);
+                self.temporary()
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.generate(object);
+                for arg in args {
+                    self.generate(arg);
+                }
+                self.temporary()
+            }
+            Expression::ArrayDeclaration(_, size) => {
+                self.generate(size);
+                self.temporary()
+            }
+            Expression::Integer(_) | Expression::Null | Expression::Uninitialized(_) => {
+                self.temporary()
+            }
+        }
+    }
+
+    fn bind_call(&mut self, callee: &str, args: &[Node], result: &Node) {
+        let params = self.functions.get(callee).cloned().unwrap_or_default();
+        for (param, arg) in params.iter().zip(args) {
+            let param = AbstractLocation::Variable {
+                function: Some(callee.to_string()),
+                name: param.clone(),
+            };
+            self.constraints
+                .push(PointsToConstraint::Copy(Node::Location(param), arg.clone()));
+        }
+        self.constraints.push(PointsToConstraint::Copy(
+            result.clone(),
+            Node::Return(callee.to_string()),
+        ));
+    }
+
+    fn generate_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Expression(expr) => {
+                self.generate(expr);
+            }
+            Statement::Declaration(name, expr) | Statement::Reference(name, expr) => {
+                let source = self.generate(expr);
+                let location = self.variable(self.current_function.as_deref(), name);
+                self.assign(Lvalue::Direct(location), source);
+            }
+            Statement::Throw(expr) => {
+                self.generate(expr);
+            }
+            Statement::Return(expr) => {
+                let source = self.generate(expr);
+                if let Some(function) = self.current_function.clone() {
+                    self.constraints
+                        .push(PointsToConstraint::Copy(Node::Return(function), source));
+                }
+            }
+            Statement::Block(stmts) => {
+                for stmt in stmts {
+                    self.generate_statement(stmt);
+                }
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.generate(condition);
+                self.generate_statement(then_branch);
+                self.generate_statement(else_branch);
+            }
+            Statement::While(condition, body) => {
+                self.generate(condition);
+                self.generate_statement(body);
+            }
+            Statement::Located(_, stmt) => self.generate_statement(stmt),
+            // Container elements are not modeled, so the element points nowhere known
+            Statement::RangeFor(_, range, body) => {
+                self.generate(range);
+                self.generate_statement(body);
+            }
+        }
+    }
+
+    fn set(&self, node: &Node) -> BTreeSet<AbstractLocation> {
+        self.points_to.get(node).cloned().unwrap_or_default()
+    }
+
+    fn include(&mut self, node: &Node, locations: BTreeSet<AbstractLocation>) -> bool {
+        let set = self.points_to.entry(node.clone()).or_default();
+        let before = set.len();
+        set.extend(locations);
+        set.len() != before
+    }
+
+    fn solve(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut resolved_calls = Vec::new();
+            let constraints = std::mem::take(&mut self.constraints);
+            for constraint in &constraints {
+                changed |= match constraint {
+                    PointsToConstraint::AddressOf(node, location) => {
+                        self.include(node, BTreeSet::from([location.clone()]))
+                    }
+                    PointsToConstraint::Copy(target, source) => {
+                        let source = self.set(source);
+                        self.include(target, source)
+                    }
+                    PointsToConstraint::Load(target, pointer, field) => {
+                        let mut changed = false;
+                        for object in self.set(pointer) {
+                            let source = self.set(&Node::Location(field_of(&object, field)));
+                            changed |= self.include(target, source);
+                        }
+                        changed
+                    }
+                    PointsToConstraint::Store(pointer, field, source) => {
+                        let mut changed = false;
+                        let source = self.set(source);
+                        for object in self.set(pointer) {
+                            let target = Node::Location(field_of(&object, field));
+                            changed |= self.include(&target, source.clone());
+                        }
+                        changed
+                    }
+                    PointsToConstraint::FieldAddress(target, pointer, field) => {
+                        let fields = self
+                            .set(pointer)
+                            .iter()
+                            .map(|object| field_of(object, &Some(field.clone())))
+                            .collect();
+                        self.include(target, fields)
+                    }
+                    PointsToConstraint::IndirectCall(caller, result, callee, args) => {
+                        for location in self.set(callee) {
+                            if let AbstractLocation::Function(name) = location {
+                                if self.bound_calls.insert((result.clone(), name.clone())) {
+                                    resolved_calls.push((
+                                        caller.clone(),
+                                        name,
+                                        args.clone(),
+                                        result.clone(),
+                                    ));
+                                }
+                            }
+                        }
+                        false
+                    }
+                }
+            }
+            self.constraints = constraints;
+
+            for (caller, callee, args, result) in resolved_calls {
+                self.call_targets
+                    .entry(caller)
+                    .or_default()
+                    .insert(callee.clone());
+                self.bind_call(&callee, &args, &result);
+                changed = true;
+            }
+        }
+    }
+
+    pub fn analyze(&mut self, declarations: &[Declaration]) {
+        for decl in declarations {
+            match decl {
+                Declaration::Function(name, params, body) => {
+                    let params: Vec<String> = params
+                        .iter()
+                        .map(|param| parameter_name(param).to_string())
+                        .collect();
+                    for param in &params {
+                        self.locals.insert((name.clone(), param.clone()));
+                    }
+                    self.functions.insert(name.clone(), params);
+                    self.collect_locals(name, body);
+                }
+                Declaration::Variable(name, _) => {
+                    self.globals.insert(name.clone());
+                }
+                Declaration::Class(_) => {}
+            }
+        }
+
+        for decl in declarations {
+            match decl {
+                Declaration::Function(name, _, body) => {
+                    self.current_function = Some(name.clone());
+                    self.generate_statement(body);
+                }
+                Declaration::Variable(name, expr) => {
+                    self.current_function = None;
+                    let source = self.generate(expr);
+                    let location = self.variable(None, name);
+                    self.assign(Lvalue::Direct(location), source);
+                }
+                Declaration::Class(_) => {}
+            }
+        }
+        self.current_function = None;
+
+        self.solve();
+    }
+
+    // Locations `expr`, evaluated inside `function`, may point to
+    pub fn points_to(&self, function: &str, expr: &Expression) -> BTreeSet<AbstractLocation> {
+        match expr {
+            Expression::Identifier(name) | Expression::Variable(name) => {
+                if self.is_function_name(Some(function), name) {
+                    BTreeSet::from([AbstractLocation::Function(name.clone())])
+                } else {
+                    self.set(&Node::Location(self.variable(Some(function), name)))
+                }
+            }
+            Expression::AddressOf(target) => self.locations_of(function, target),
+            Expression::Dereference(_)
+            | Expression::MemberAccess(..)
+            | Expression::ArrayAccess(..) => self
+                .locations_of(function, expr)
+                .iter()
+                .flat_map(|location| self.set(&Node::Location(location.clone())))
+                .collect(),
+            Expression::BinaryOperation(op, left, right) if op == "+" || op == "-" => {
+                let mut locations = self.points_to(function, left);
+                locations.extend(self.points_to(function, right));
+                locations
+            }
+            Expression::Assignment(_, value) => self.points_to(function, value),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    // Locations denoted by the lvalue `expr`
+    fn locations_of(&self, function: &str, expr: &Expression) -> BTreeSet<AbstractLocation> {
+        match expr {
+            Expression::Identifier(name) | Expression::Variable(name) => {
+                BTreeSet::from([self.variable(Some(function), name)])
+            }
+            Expression::Dereference(pointer) => self.points_to(function, pointer),
+            Expression::ArrayAccess(name, _) => {
+                self.points_to(function, &Expression::Identifier(name.clone()))
+            }
+            Expression::MemberAccess(object, field) => {
+                let bases = match &**object {
+                    Expression::Dereference(pointer) => self.points_to(function, pointer),
+                    object => self.locations_of(function, object),
+                };
+                bases
+                    .iter()
+                    .map(|base| field_of(base, &Some(field.clone())))
+                    .collect()
+            }
+            _ => BTreeSet::new(),
+        }
+    }
+
+    pub fn may_alias(&self, function: &str, left: &Expression, right: &Expression) -> bool {
+        let left = self.points_to(function, left);
+        let right = self.points_to(function, right);
+        !left.is_disjoint(&right)
+    }
+
+    // Functions a call through `callee` inside `function` may reach
+    pub fn resolve_call(&self, function: &str, callee: &str) -> BTreeSet<String> {
+        self.points_to(function, &Expression::Identifier(callee.to_string()))
+            .into_iter()
+            .filter_map(|location| match location {
+                AbstractLocation::Function(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Functions called from `function`, directly or through resolved function pointers
+    pub fn callees(&self, function: &str) -> BTreeSet<String> {
+        self.call_targets.get(function).cloned().unwrap_or_default()
+    }
+
+    pub fn parameters(&self, function: &str) -> Option<&[String]> {
+        self.functions.get(function).map(|params| params.as_slice())
+    }
+}
+
+impl Default for PointsToAnalysis {
+    fn default() -> Self {
+        PointsToAnalysis::new()
+    }
+}
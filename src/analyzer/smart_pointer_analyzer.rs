@@ -0,0 +1,805 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use crate::diagnostics::{DiagnosticSink, Reporter};
+use crate::parser::cpp_parser::{Declaration, Expression, Statement};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmartPointerKind {
+    Unique,
+    Shared,
+    Weak,
+}
+
+impl SmartPointerKind {
+    // Kind of smart pointer a type or factory produces, e.g. `std::make_shared<Node>`
+    pub fn from_type(name: &str) -> Option<SmartPointerKind> {
+        let name = name.trim_start_matches("std::");
+        let base = name.split('<').next().unwrap_or(name).trim();
+        match base {
+            "unique_ptr" | "make_unique" => Some(SmartPointerKind::Unique),
+            "shared_ptr" | "make_shared" => Some(SmartPointerKind::Shared),
+            "weak_ptr" => Some(SmartPointerKind::Weak),
+            _ => None,
+        }
+    }
+}
+
+// What a pointer refers to on the current path
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Object(usize),
+    // Non-null, but the object is not tracked
+    Valid,
+    Null(String),
+    MaybeNull(String),
+}
+
+impl Target {
+    fn join(&self, other: &Target) -> Target {
+        match (self, other) {
+            (a, b) if a == b => a.clone(),
+            (Target::Null(reason) | Target::MaybeNull(reason), _)
+            | (_, Target::Null(reason) | Target::MaybeNull(reason)) => {
+                Target::MaybeNull(reason.clone())
+            }
+            _ => Target::Valid,
+        }
+    }
+
+    fn object(&self) -> Option<usize> {
+        match self {
+            Target::Object(object) => Some(*object),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SmartPointer {
+    kind: SmartPointerKind,
+    target: Target,
+}
+
+// A raw pointer into memory that smart pointers manage
+#[derive(Debug, Clone)]
+struct RawPointer {
+    object: usize,
+    // Set for `p.get()`: the raw pointer must not be deleted
+    borrowed_from: Option<String>,
+}
+
+#[derive(Clone, Default)]
+struct State {
+    smart: HashMap<String, SmartPointer>,
+    raw: HashMap<String, RawPointer>,
+    // Smart pointer that first took ownership of each object
+    owners: HashMap<usize, String>,
+    // Objects a smart pointer has owned; plain new and delete are the pointer
+    // analyzer's to check
+    managed: HashSet<usize>,
+    freed: HashMap<usize, String>,
+    // Objects kept alive by a shared_ptr stored inside another object
+    strong_edges: HashMap<usize, HashSet<usize>>,
+    scopes: Vec<Vec<String>>,
+    terminated: bool,
+}
+
+impl State {
+    fn merge(self, other: State) -> State {
+        if self.terminated {
+            return other;
+        }
+        if other.terminated {
+            return self;
+        }
+        let mut merged = self;
+        merged
+            .smart
+            .retain(|name, pointer| match other.smart.get(name) {
+                Some(other) => {
+                    pointer.target = pointer.target.join(&other.target);
+                    true
+                }
+                None => false,
+            });
+        merged.raw.retain(|name, pointer| {
+            matches!(other.raw.get(name), Some(other) if other.object == pointer.object)
+        });
+        for (object, owner) in other.owners {
+            merged.owners.entry(object).or_insert(owner);
+        }
+        merged.managed.extend(other.managed);
+        // Memory freed on either path may be freed
+        for (object, reason) in other.freed {
+            merged.freed.entry(object).or_insert(reason);
+        }
+        for (object, targets) in other.strong_edges {
+            merged
+                .strong_edges
+                .entry(object)
+                .or_default()
+                .extend(targets);
+        }
+        merged
+    }
+}
+
+// Models std::unique_ptr, std::shared_ptr and std::weak_ptr: ownership transfer,
+// null after move or release(), non-owning aliases from get(), memory adopted by two
+// owners, shared_ptr cycles and unchecked weak_ptr::lock() results
+pub struct SmartPointerAnalyzer<'a> {
+    declarations: &'a [Declaration],
+    state: State,
+    next_object: usize,
+    function: String,
+    reporter: Reporter<MemoryError>,
+}
+
+impl<'a> SmartPointerAnalyzer<'a> {
+    pub fn new(declarations: &'a [Declaration]) -> Self {
+        SmartPointerAnalyzer {
+            declarations,
+            state: State::default(),
+            next_object: 0,
+            function: String::new(),
+            reporter: Reporter::default(),
+        }
+    }
+
+    pub fn analyze(&mut self) -> Result<(), MemoryError> {
+        for declaration in self.declarations {
+            self.analyze_function(declaration)?;
+        }
+        Ok(())
+    }
+
+    // Analyzes every function without stopping at the first error, adding every error to `sink`
+    pub fn analyze_into(&mut self, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze();
+        sink.extend(self.reporter.finish(result));
+    }
+
+    // Analyzes only the function declared at `index`, adding its errors to `sink`, so
+    // that each function can be reported as soon as it is done
+    pub fn analyze_function_into(&mut self, index: usize, sink: &mut DiagnosticSink) {
+        self.reporter = Reporter::collecting();
+        let result = self.analyze_function(&self.declarations[index]);
+        sink.extend(self.reporter.finish(result));
+    }
+
+    fn analyze_function(&mut self, declaration: &Declaration) -> Result<(), MemoryError> {
+        if let Declaration::Function(name, _, body) = declaration {
+            self.function = name.clone();
+            self.reporter.enter_function(name);
+            self.state = State {
+                scopes: vec![Vec::new()],
+                ..State::default()
+            };
+            self.analyze_statement(body)?;
+        }
+        Ok(())
+    }
+
+    fn error(&mut self, error_type: MemoryErrorType, details: String) -> Result<(), MemoryError> {
+        let details = format!("{} in '{}'", details, self.function);
+        self.reporter.report(MemoryError::new(error_type, details))
+    }
+
+    fn allocate(&mut self) -> usize {
+        self.next_object += 1;
+        self.next_object
+    }
+
+    fn smart_target(&self, name: &str) -> Option<&Target> {
+        self.state.smart.get(name).map(|pointer| &pointer.target)
+    }
+
+    // Object a raw pointer expression refers to
+    fn raw_object(&self, expr: &Expression) -> Option<usize> {
+        match expr {
+            Expression::Identifier(id) => self.state.raw.get(id).map(|raw| raw.object),
+            Expression::MethodCall(object, method, _) if method == "get" => match &**object {
+                Expression::Identifier(owner) => self.smart_target(owner)?.object(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Takes `name` out of ownership of its object, freeing the object when no other
+    // owner is left
+    fn drop_ownership(&mut self, name: &str, reason: &str) {
+        let (kind, object) = match self.state.smart.get(name) {
+            Some(SmartPointer {
+                kind,
+                target: Target::Object(object),
+            }) => (*kind, *object),
+            _ => return,
+        };
+        if kind == SmartPointerKind::Weak {
+            return;
+        }
+        let shared_elsewhere = self.state.smart.iter().any(|(other, pointer)| {
+            other != name
+                && pointer.kind != SmartPointerKind::Weak
+                && pointer.target == Target::Object(object)
+        }) || self
+            .state
+            .strong_edges
+            .values()
+            .any(|targets| targets.contains(&object));
+        if !shared_elsewhere {
+            self.state.freed.insert(object, reason.to_string());
+            self.state.strong_edges.remove(&object);
+        }
+    }
+
+    // Records that `name` now owns `object`, reporting memory adopted twice
+    fn adopt(&mut self, name: &str, object: usize) -> Result<(), MemoryError> {
+        if let Some(owner) = self.state.owners.get(&object) {
+            if owner != name && !self.state.freed.contains_key(&object) {
+                let details = format!(
+                    "'{}' takes ownership of memory already owned by '{}'; it would be freed twice",
+                    name, owner
+                );
+                return self.error(MemoryErrorType::DoubleFree, details);
+            }
+        }
+        self.state.owners.insert(object, name.to_string());
+        self.state.managed.insert(object);
+        Ok(())
+    }
+
+    // Value a smart pointer gets from an initializer, transferring ownership as needed
+    fn smart_value(
+        &mut self,
+        name: &str,
+        kind: SmartPointerKind,
+        value: &Expression,
+    ) -> Result<Target, MemoryError> {
+        match value {
+            Expression::Null | Expression::Uninitialized(_) => Ok(Target::Null(format!(
+                "'{}' was never assigned an object",
+                name
+            ))),
+            Expression::Identifier(id) if id == "nullptr" || id == "NULL" => {
+                Ok(Target::Null(format!("'{}' was assigned nullptr", name)))
+            }
+            Expression::FunctionCall(callee, args)
+                if callee == "std::move" || callee == "std::forward" =>
+            {
+                match args.first() {
+                    Some(Expression::Identifier(source))
+                        if self.state.smart.contains_key(source) =>
+                    {
+                        let target = self.state.smart[source].target.clone();
+                        if let Some(pointer) = self.state.smart.get_mut(source) {
+                            pointer.target =
+                                Target::Null(format!("'{}' was moved into '{}'", source, name));
+                        }
+                        if let Some(object) = target.object() {
+                            if kind != SmartPointerKind::Weak {
+                                self.state.owners.insert(object, name.to_string());
+                            }
+                        }
+                        Ok(target)
+                    }
+                    Some(arg) => self.smart_value(name, kind, arg),
+                    None => Ok(Target::Valid),
+                }
+            }
+            Expression::FunctionCall(callee, args) => match SmartPointerKind::from_type(callee) {
+                Some(_) if callee.contains("make_") => {
+                    let object = self.allocate();
+                    self.state.owners.insert(object, name.to_string());
+                    self.state.managed.insert(object);
+                    Ok(Target::Object(object))
+                }
+                Some(_) => match args.first() {
+                    None => Ok(Target::Null(format!("'{}' was default-constructed", name))),
+                    Some(Expression::New(..)) => {
+                        let object = self.allocate();
+                        self.adopt(name, object)?;
+                        Ok(Target::Object(object))
+                    }
+                    Some(arg) => {
+                        if let Some(object) = self.raw_object(arg) {
+                            if kind != SmartPointerKind::Weak {
+                                self.adopt(name, object)?;
+                            }
+                            return Ok(Target::Object(object));
+                        }
+                        self.smart_value(name, kind, arg)
+                    }
+                },
+                None => Ok(Target::Valid),
+            },
+            Expression::Identifier(source) => Ok(match self.smart_target(source) {
+                Some(target) => target.clone(),
+                None => match self.raw_object(value) {
+                    Some(object) => Target::Object(object),
+                    None => Target::Valid,
+                },
+            }),
+            Expression::MethodCall(object, method, _) if method == "lock" => {
+                let weak = weak_name(object);
+                let expired = match &**object {
+                    Expression::Identifier(weak) => self
+                        .smart_target(weak)
+                        .and_then(Target::object)
+                        .and_then(|object| self.state.freed.get(&object)),
+                    _ => None,
+                };
+                // Known expired locks are null; otherwise the owner may be gone by now
+                Ok(match expired {
+                    Some(reason) => Target::Null(format!(
+                        "'{}' locked '{}' after it expired ({})",
+                        name, weak, reason
+                    )),
+                    None => Target::MaybeNull(format!(
+                        "'{}' holds the result of '{}.lock()'",
+                        name, weak
+                    )),
+                })
+            }
+            _ => Ok(Target::Valid),
+        }
+    }
+
+    fn assign_smart(
+        &mut self,
+        name: &str,
+        kind: SmartPointerKind,
+        value: &Expression,
+    ) -> Result<(), MemoryError> {
+        let target = self.smart_value(name, kind, value)?;
+        self.drop_ownership(name, &format!("'{}' was reassigned", name));
+        self.state
+            .smart
+            .insert(name.to_string(), SmartPointer { kind, target });
+        Ok(())
+    }
+
+    fn assign_raw(&mut self, name: &str, value: &Expression) {
+        self.state.raw.remove(name);
+        let raw = match value {
+            Expression::New(..) => {
+                let object = self.allocate();
+                Some(RawPointer {
+                    object,
+                    borrowed_from: None,
+                })
+            }
+            Expression::MethodCall(owner, method, _) => match (&**owner, method.as_str()) {
+                (Expression::Identifier(owner), "get") => self
+                    .smart_target(owner)
+                    .and_then(Target::object)
+                    .map(|object| RawPointer {
+                        object,
+                        borrowed_from: Some(owner.clone()),
+                    }),
+                (Expression::Identifier(owner), "release") => {
+                    let object = self.smart_target(owner).and_then(Target::object);
+                    if let Some(object) = object {
+                        self.state.owners.remove(&object);
+                    }
+                    object.map(|object| RawPointer {
+                        object,
+                        borrowed_from: None,
+                    })
+                }
+                _ => None,
+            },
+            Expression::Identifier(source) => self.state.raw.get(source).cloned(),
+            _ => None,
+        };
+        if let Some(raw) = raw {
+            self.state.raw.insert(name.to_string(), raw);
+        }
+    }
+
+    fn handle_assignment(&mut self, name: &str, value: &Expression) -> Result<(), MemoryError> {
+        let kind = match self.state.smart.get(name) {
+            Some(pointer) => Some(pointer.kind),
+            None => self.value_kind(value),
+        };
+        match kind {
+            Some(kind) => self.assign_smart(name, kind, value),
+            None => {
+                self.assign_raw(name, value);
+                Ok(())
+            }
+        }
+    }
+
+    // Kind of smart pointer an initializer evaluates to, if any
+    fn value_kind(&self, value: &Expression) -> Option<SmartPointerKind> {
+        match value {
+            Expression::Uninitialized(type_name) => SmartPointerKind::from_type(type_name),
+            Expression::FunctionCall(callee, args) => match SmartPointerKind::from_type(callee) {
+                Some(kind) => Some(kind),
+                None if callee == "std::move" || callee == "std::forward" => {
+                    args.first().and_then(|arg| self.value_kind(arg))
+                }
+                None => None,
+            },
+            Expression::Identifier(source) => self.state.smart.get(source).map(|p| p.kind),
+            Expression::MethodCall(_, method, _) if method == "lock" => {
+                Some(SmartPointerKind::Shared)
+            }
+            _ => None,
+        }
+    }
+
+    fn check_dereference(&mut self, pointer: &Expression) -> Result<(), MemoryError> {
+        match pointer {
+            Expression::Identifier(id) => {
+                if let Some(pointer) = self.state.smart.get(id) {
+                    return match &pointer.target {
+                        Target::Null(reason) => self.error(
+                            MemoryErrorType::NullPointerDereference,
+                            format!("'{}' is dereferenced while null ({})", id, reason),
+                        ),
+                        Target::MaybeNull(reason) => self.error(
+                            MemoryErrorType::NullPointerDereference,
+                            format!(
+                                "'{}' is dereferenced without checking for null ({})",
+                                id, reason
+                            ),
+                        ),
+                        Target::Object(object) => self.check_freed(id, *object),
+                        Target::Valid => Ok(()),
+                    };
+                }
+                if let Some(raw) = self.state.raw.get(id) {
+                    return self.check_freed(id, raw.object);
+                }
+                Ok(())
+            }
+            Expression::MethodCall(weak, method, _) if method == "lock" => self.error(
+                MemoryErrorType::NullPointerDereference,
+                format!(
+                    "the result of '{}.lock()' is dereferenced without checking for null",
+                    weak_name(weak)
+                ),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_freed(&mut self, id: &str, object: usize) -> Result<(), MemoryError> {
+        if !self.state.managed.contains(&object) {
+            return Ok(());
+        }
+        match self.state.freed.get(&object) {
+            Some(reason) => self.error(
+                MemoryErrorType::UseAfterFree,
+                format!("'{}' is used after its memory was freed ({})", id, reason),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_delete(&mut self, target: &Expression) -> Result<(), MemoryError> {
+        let id = match target {
+            Expression::Identifier(id) => id,
+            _ => return Ok(()),
+        };
+        if let Some(raw) = self.state.raw.get(id).cloned() {
+            self.check_freed(id, raw.object)?;
+            if let Some(owner) = &raw.borrowed_from {
+                if !self.state.freed.contains_key(&raw.object) {
+                    let details = format!(
+                        "'{}' came from '{}.get()' and is deleted while '{}' still owns it",
+                        id, owner, owner
+                    );
+                    return self.error(MemoryErrorType::DoubleFree, details);
+                }
+            }
+            self.state
+                .freed
+                .insert(raw.object, format!("'{}' was deleted", id));
+        }
+        Ok(())
+    }
+
+    fn handle_method_call(
+        &mut self,
+        object: &Expression,
+        method: &str,
+        args: &[Expression],
+    ) -> Result<(), MemoryError> {
+        let name = match object {
+            Expression::Identifier(name) if self.state.smart.contains_key(name) => name.clone(),
+            Expression::Dereference(pointer) | Expression::MemberAccess(pointer, _) => {
+                return self.check_dereference(pointer)
+            }
+            _ => return Ok(()),
+        };
+        match method {
+            "reset" => {
+                let kind = self.state.smart[&name].kind;
+                let value = match args.first() {
+                    Some(arg) => {
+                        Expression::FunctionCall("std::unique_ptr".to_string(), vec![arg.clone()])
+                    }
+                    None => Expression::Null,
+                };
+                let target = self.smart_value(&name, kind, &value)?;
+                self.drop_ownership(&name, &format!("'{}' was reset", name));
+                self.state
+                    .smart
+                    .insert(name.clone(), SmartPointer { kind, target });
+            }
+            "release" => {
+                if let Some(pointer) = self.state.smart.get_mut(&name) {
+                    pointer.target = Target::Null(format!("'{}' was released", name));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // `a->next = b` with both shared: a keeps b alive
+    fn handle_member_store(
+        &mut self,
+        target: &Expression,
+        value: &Expression,
+    ) -> Result<(), MemoryError> {
+        let owner = match target {
+            Expression::MemberAccess(object, _) => match &**object {
+                Expression::Dereference(pointer) => match &**pointer {
+                    Expression::Identifier(owner) => owner,
+                    _ => return Ok(()),
+                },
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        let stored = match value {
+            Expression::Identifier(stored) => stored,
+            Expression::FunctionCall(callee, args) if callee == "std::move" => match args.first() {
+                Some(Expression::Identifier(stored)) => stored,
+                _ => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        let shared_object = |name: &str| match self.state.smart.get(name) {
+            Some(SmartPointer {
+                kind: SmartPointerKind::Shared,
+                target: Target::Object(object),
+            }) => Some(*object),
+            _ => None,
+        };
+        let (from, to) = match (shared_object(owner), shared_object(stored)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(()),
+        };
+        self.state.strong_edges.entry(from).or_default().insert(to);
+        if self.reaches(to, from) {
+            let details = format!(
+                "shared_ptr cycle: the objects owned by '{}' and '{}' keep each other alive and \
+                 are never freed; make one of the links a weak_ptr",
+                owner, stored
+            );
+            return self.error(MemoryErrorType::MemoryLeak, details);
+        }
+        Ok(())
+    }
+
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![from];
+        while let Some(object) = pending.pop() {
+            if object == to {
+                return true;
+            }
+            if visited.insert(object) {
+                if let Some(targets) = self.state.strong_edges.get(&object) {
+                    pending.extend(targets.iter().copied());
+                }
+            }
+        }
+        false
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression) -> Result<(), MemoryError> {
+        match expr {
+            Expression::Dereference(pointer) => {
+                self.analyze_expression(pointer)?;
+                self.check_dereference(pointer)
+            }
+            Expression::MemberAccess(object, _) => self.analyze_expression(object),
+            Expression::ArrayAccess(id, index) => {
+                self.analyze_expression(index)?;
+                self.check_dereference(&Expression::Identifier(id.clone()))
+            }
+            Expression::MethodCall(object, method, args) => {
+                self.analyze_expression(object)?;
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                self.handle_method_call(object, method, args)
+            }
+            Expression::FunctionCall(_, args) => {
+                for arg in args {
+                    self.analyze_expression(arg)?;
+                }
+                Ok(())
+            }
+            Expression::Delete(target, _) => {
+                self.analyze_expression(target)?;
+                self.handle_delete(target)
+            }
+            Expression::Assignment(target, value) => {
+                self.analyze_expression(value)?;
+                match &**target {
+                    Expression::Identifier(name) => self.handle_assignment(name, value),
+                    target => {
+                        self.analyze_expression(target)?;
+                        self.handle_member_store(target, value)
+                    }
+                }
+            }
+            Expression::BinaryOperation(_, left, right) => {
+                self.analyze_expression(left)?;
+                self.analyze_expression(right)
+            }
+            Expression::UnaryOperation(_, operand) | Expression::AddressOf(operand) => {
+                self.analyze_expression(operand)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Narrows smart pointer nullness on a branch; false if the branch is infeasible
+    fn refine(&mut self, condition: &Expression, outcome: bool) -> bool {
+        match condition {
+            Expression::Identifier(id) => {
+                let target = match self.state.smart.get_mut(id) {
+                    Some(pointer) if pointer.kind != SmartPointerKind::Weak => &mut pointer.target,
+                    _ => return true,
+                };
+                match (&*target, outcome) {
+                    (Target::Null(_), true) | (Target::Object(_) | Target::Valid, false) => false,
+                    (Target::MaybeNull(_), true) => {
+                        *target = Target::Valid;
+                        true
+                    }
+                    (Target::MaybeNull(reason), false) => {
+                        *target = Target::Null(reason.clone());
+                        true
+                    }
+                    _ => true,
+                }
+            }
+            Expression::UnaryOperation(op, operand) if op == "!" => self.refine(operand, !outcome),
+            Expression::BinaryOperation(op, left, right) if op == "==" || op == "!=" => {
+                let is_null = |expr: &Expression| match expr {
+                    Expression::Null | Expression::Integer(0) => true,
+                    Expression::Identifier(id) => id == "nullptr" || id == "NULL",
+                    _ => false,
+                };
+                let pointer = if is_null(right) {
+                    left
+                } else if is_null(left) {
+                    right
+                } else {
+                    return true;
+                };
+                self.refine(pointer, (op == "!=") == outcome)
+            }
+            Expression::BinaryOperation(op, left, right) if op == "&&" && outcome => {
+                self.refine(left, true) && self.refine(right, true)
+            }
+            Expression::BinaryOperation(op, left, right) if op == "||" && !outcome => {
+                self.refine(left, false) && self.refine(right, false)
+            }
+            _ => true,
+        }
+    }
+
+    fn refine_path(&mut self, condition: &Expression, outcome: bool) {
+        if !self.refine(condition, outcome) {
+            self.state.terminated = true;
+        }
+    }
+
+    fn exit_scope(&mut self) {
+        let scope = self.state.scopes.pop().unwrap_or_default();
+        for name in &scope {
+            self.drop_ownership(name, &format!("'{}' went out of scope", name));
+        }
+        for name in &scope {
+            self.state.smart.remove(name);
+            self.state.raw.remove(name);
+        }
+    }
+
+    fn analyze_statement(&mut self, statement: &Statement) -> Result<(), MemoryError> {
+        if self.state.terminated {
+            return Ok(());
+        }
+        match statement {
+            Statement::Declaration(name, value) | Statement::Reference(name, value) => {
+                self.analyze_expression(value)?;
+                if let Some(scope) = self.state.scopes.last_mut() {
+                    scope.push(name.clone());
+                }
+                self.state.smart.remove(name);
+                self.handle_assignment(name, value)?;
+            }
+            Statement::Expression(expr) => {
+                self.analyze_expression(expr)?;
+                if let Expression::FunctionCall(name, args) = expr {
+                    if name == "assert" {
+                        if let Some(condition) = args.first() {
+                            self.refine_path(condition, true);
+                        }
+                    }
+                }
+            }
+            Statement::Return(expr) | Statement::Throw(expr) => {
+                self.analyze_expression(expr)?;
+                self.state.terminated = true;
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.refine_path(condition, true);
+                self.analyze_statement(then_branch)?;
+                let after_then = std::mem::replace(&mut self.state, before);
+                self.refine_path(condition, false);
+                self.analyze_statement(else_branch)?;
+                let after_else = std::mem::take(&mut self.state);
+                self.state = after_then.merge(after_else);
+            }
+            Statement::While(condition, body) => {
+                self.analyze_expression(condition)?;
+                let before = self.state.clone();
+                self.refine_path(condition, true);
+                self.analyze_statement(body)?;
+                let after_body = std::mem::replace(&mut self.state, before.clone());
+                self.state = after_body.merge(before);
+                self.refine_path(condition, false);
+            }
+            Statement::RangeFor(element, range, body) => {
+                self.analyze_expression(range)?;
+                self.state.smart.remove(element);
+                self.state.raw.remove(element);
+                let before = self.state.clone();
+                self.analyze_statement(body)?;
+                let after_body = std::mem::replace(&mut self.state, before.clone());
+                self.state = after_body.merge(before);
+            }
+            Statement::Block(statements) => {
+                self.state.scopes.push(Vec::new());
+                for statement in statements {
+                    self.analyze_statement(statement)?;
+                }
+                if self.state.terminated {
+                    self.state.scopes.pop();
+                } else {
+                    self.exit_scope();
+                }
+            }
+            Statement::Located(location, statement) => {
+                let enclosing = self.reporter.enter_statement(*location);
+                let result = self.analyze_statement(statement);
+                self.reporter.leave_statement(enclosing);
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn weak_name(expr: &Expression) -> String {
+    match expr {
+        Expression::Identifier(id) => id.clone(),
+        _ => "weak_ptr".to_string(),
+    }
+}
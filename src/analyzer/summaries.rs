@@ -0,0 +1,411 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::analyzer::models::Models;
+use crate::parser::cpp_parser::{new_operands, parameter_name, Declaration, Expression, Statement};
+
+const MAX_ROUNDS: usize = 64;
+
+// Memory effects of a function on its parameters and result, by parameter index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionSummary {
+    pub frees: BTreeSet<usize>,
+    pub dereferences: BTreeSet<usize>,
+    // Parameters stored where they outlive the call, e.g. in a global or a heap object
+    pub stores: BTreeSet<usize>,
+    // The result is an allocation the caller has to free
+    pub returns_fresh: bool,
+    pub returned_params: BTreeSet<usize>,
+    pub may_return_null: bool,
+}
+
+impl FunctionSummary {
+    // Whether the callee takes over responsibility for freeing the argument
+    pub fn takes_ownership(&self, param: usize) -> bool {
+        self.frees.contains(&param) || self.stores.contains(&param)
+    }
+}
+
+// What a pointer in the summarized function may hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Value {
+    Param(usize),
+    Fresh,
+    Null,
+}
+
+fn is_null_constant(expr: &Expression) -> bool {
+    match expr {
+        Expression::Null | Expression::Integer(0) => true,
+        Expression::Identifier(id) => id == "NULL" || id == "nullptr",
+        _ => false,
+    }
+}
+
+fn collect_calls(stmt: &Statement, calls: &mut Vec<String>) {
+    fn visit(expr: &Expression, calls: &mut Vec<String>) {
+        match expr {
+            Expression::FunctionCall(name, args) => {
+                calls.push(name.clone());
+                args.iter().for_each(|arg| visit(arg, calls));
+            }
+            Expression::MethodCall(object, _, args) => {
+                visit(object, calls);
+                args.iter().for_each(|arg| visit(arg, calls));
+            }
+            Expression::BinaryOperation(_, left, right) | Expression::Assignment(left, right) => {
+                visit(left, calls);
+                visit(right, calls);
+            }
+            Expression::Dereference(inner)
+            | Expression::UnaryOperation(_, inner)
+            | Expression::Delete(inner, _)
+            | Expression::MemberAccess(inner, _)
+            | Expression::AddressOf(inner)
+            | Expression::ArrayAccess(_, inner)
+            | Expression::ArrayDeclaration(_, inner) => visit(inner, calls),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    visit(inner, calls);
+                }
+            }
+            _ => {}
+        }
+    }
+    match stmt {
+        Statement::Expression(expr)
+        | Statement::Declaration(_, expr)
+        | Statement::Reference(_, expr)
+        | Statement::Return(expr)
+        | Statement::Throw(expr) => visit(expr, calls),
+        Statement::If(condition, then_branch, else_branch) => {
+            visit(condition, calls);
+            collect_calls(then_branch, calls);
+            collect_calls(else_branch, calls);
+        }
+        Statement::While(condition, body) | Statement::RangeFor(_, condition, body) => {
+            visit(condition, calls);
+            collect_calls(body, calls);
+        }
+        Statement::Block(stmts) => stmts.iter().for_each(|stmt| collect_calls(stmt, calls)),
+        Statement::Located(_, stmt) => collect_calls(stmt, calls),
+    }
+}
+
+// Builds the summary of one function from the current summaries of its callees.
+// The analysis is flow-insensitive: a local holds everything ever assigned to it
+struct SummaryBuilder<'s> {
+    summaries: &'s HashMap<String, FunctionSummary>,
+    locals: HashMap<String, BTreeSet<Value>>,
+    summary: FunctionSummary,
+    // A fresh allocation was also stored elsewhere, so returning it does not hand it over
+    fresh_escapes: bool,
+    returns_fresh: bool,
+}
+
+impl<'s> SummaryBuilder<'s> {
+    fn values(&self, expr: &Expression) -> BTreeSet<Value> {
+        match expr {
+            expr if is_null_constant(expr) => BTreeSet::from([Value::Null]),
+            Expression::Identifier(id) | Expression::Variable(id) => {
+                self.locals.get(id).cloned().unwrap_or_default()
+            }
+            Expression::New(type_name, ..) if type_name.starts_with("(std::nothrow)") => {
+                BTreeSet::from([Value::Fresh, Value::Null])
+            }
+            Expression::New(..) => BTreeSet::from([Value::Fresh]),
+            Expression::FunctionCall(name, _)
+                if matches!(name.as_str(), "malloc" | "calloc" | "realloc" | "strdup") =>
+            {
+                BTreeSet::from([Value::Fresh, Value::Null])
+            }
+            Expression::FunctionCall(name, args) => {
+                let mut values = BTreeSet::new();
+                if let Some(callee) = self.summaries.get(name) {
+                    if callee.returns_fresh {
+                        values.insert(Value::Fresh);
+                    }
+                    if callee.may_return_null {
+                        values.insert(Value::Null);
+                    }
+                    for param in &callee.returned_params {
+                        if let Some(arg) = args.get(*param) {
+                            values.extend(self.values(arg));
+                        }
+                    }
+                }
+                values
+            }
+            // Pointer arithmetic stays within the same object
+            Expression::BinaryOperation(op, left, _) if op == "+" || op == "-" => self.values(left),
+            Expression::Assignment(_, value) => self.values(value),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    fn params(values: &BTreeSet<Value>) -> impl Iterator<Item = usize> + '_ {
+        values.iter().filter_map(|value| match value {
+            Value::Param(index) => Some(*index),
+            _ => None,
+        })
+    }
+
+    fn add_local(&mut self, id: &str, values: BTreeSet<Value>) {
+        self.locals
+            .entry(id.to_string())
+            .or_default()
+            .extend(values);
+    }
+
+    fn dereference(&mut self, pointer: &Expression) {
+        let params: Vec<usize> = Self::params(&self.values(pointer)).collect();
+        self.summary.dereferences.extend(params);
+    }
+
+    fn free(&mut self, pointer: &Expression) {
+        let params: Vec<usize> = Self::params(&self.values(pointer)).collect();
+        self.summary.frees.extend(params);
+    }
+
+    fn store(&mut self, value: &Expression) {
+        let values = self.values(value);
+        self.fresh_escapes |= values.contains(&Value::Fresh);
+        self.summary.stores.extend(Self::params(&values));
+    }
+
+    // Local object an lvalue such as `s.f` is part of
+    fn local_root(&self, target: &Expression) -> Option<String> {
+        match target {
+            Expression::Identifier(id) | Expression::Variable(id) => {
+                self.locals.contains_key(id).then(|| id.clone())
+            }
+            Expression::MemberAccess(object, _) => self.local_root(object),
+            _ => None,
+        }
+    }
+
+    fn analyze_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Assignment(target, value) => {
+                self.analyze_expression(target);
+                self.analyze_expression(value);
+                match self.local_root(target) {
+                    Some(local) => {
+                        let values = self.values(value);
+                        self.add_local(&local, values);
+                    }
+                    // A global, or memory reached through a pointer
+                    None => self.store(value),
+                }
+            }
+            Expression::FunctionCall(name, args) => {
+                args.iter().for_each(|arg| self.analyze_expression(arg));
+                if name == "free" {
+                    if let Some(arg) = args.first() {
+                        self.free(arg);
+                    }
+                } else if let Some(callee) = self.summaries.get(name) {
+                    for (index, arg) in args.iter().enumerate() {
+                        if callee.frees.contains(&index) {
+                            self.free(arg);
+                        }
+                        if callee.dereferences.contains(&index) {
+                            self.dereference(arg);
+                        }
+                        if callee.stores.contains(&index) {
+                            self.store(arg);
+                        }
+                    }
+                }
+            }
+            Expression::MethodCall(object, method, args) => {
+                self.analyze_expression(object);
+                args.iter().for_each(|arg| self.analyze_expression(arg));
+                let stores_element = matches!(
+                    method.as_str(),
+                    "push_back" | "push_front" | "insert" | "emplace_back"
+                );
+                if let (true, Some(value)) = (stores_element, args.last()) {
+                    match self.local_root(object) {
+                        Some(local) => {
+                            let values = self.values(value);
+                            self.add_local(&local, values);
+                        }
+                        None => self.store(value),
+                    }
+                }
+            }
+            Expression::Delete(target, _) => {
+                self.analyze_expression(target);
+                self.free(target);
+            }
+            Expression::Dereference(pointer) => {
+                self.analyze_expression(pointer);
+                self.dereference(pointer);
+            }
+            Expression::ArrayAccess(id, index) => {
+                self.analyze_expression(index);
+                self.dereference(&Expression::Identifier(id.clone()));
+            }
+            Expression::BinaryOperation(_, left, right) => {
+                self.analyze_expression(left);
+                self.analyze_expression(right);
+            }
+            Expression::UnaryOperation(_, inner)
+            | Expression::MemberAccess(inner, _)
+            | Expression::AddressOf(inner)
+            | Expression::ArrayDeclaration(_, inner) => self.analyze_expression(inner),
+            Expression::New(_, size, initializer) => {
+                for inner in new_operands(size, initializer) {
+                    self.analyze_expression(inner);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn analyze_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Expression(expr) | Statement::Throw(expr) => self.analyze_expression(expr),
+            Statement::Declaration(id, expr) | Statement::Reference(id, expr) => {
+                self.analyze_expression(expr);
+                let values = self.values(expr);
+                self.add_local(id, values);
+            }
+            Statement::Return(expr) => {
+                self.analyze_expression(expr);
+                for value in self.values(expr) {
+                    match value {
+                        Value::Param(index) => {
+                            self.summary.returned_params.insert(index);
+                        }
+                        Value::Fresh => self.returns_fresh = true,
+                        Value::Null => self.summary.may_return_null = true,
+                    }
+                }
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                self.analyze_expression(condition);
+                self.analyze_statement(then_branch);
+                self.analyze_statement(else_branch);
+            }
+            Statement::While(condition, body) => {
+                self.analyze_expression(condition);
+                self.analyze_statement(body);
+            }
+            Statement::RangeFor(element, range, body) => {
+                self.analyze_expression(range);
+                self.add_local(element, BTreeSet::new());
+                self.analyze_statement(body);
+            }
+            Statement::Block(stmts) => stmts.iter().for_each(|stmt| self.analyze_statement(stmt)),
+            Statement::Located(_, stmt) => self.analyze_statement(stmt),
+        }
+    }
+
+    fn summarize(
+        summaries: &'s HashMap<String, FunctionSummary>,
+        params: &[String],
+        body: &Statement,
+    ) -> FunctionSummary {
+        let mut builder = SummaryBuilder {
+            summaries,
+            locals: HashMap::new(),
+            summary: FunctionSummary::default(),
+            fresh_escapes: false,
+            returns_fresh: false,
+        };
+        for (index, param) in params.iter().enumerate() {
+            builder.add_local(parameter_name(param), BTreeSet::from([Value::Param(index)]));
+        }
+        // Values only grow, so repeat until assignments in loops have propagated
+        loop {
+            let locals = builder.locals.clone();
+            builder.analyze_statement(body);
+            if builder.locals == locals {
+                break;
+            }
+        }
+        builder.summary.returns_fresh = builder.returns_fresh && !builder.fresh_escapes;
+        builder.summary
+    }
+}
+
+// Summaries of every function in a translation unit, computed bottom-up over the call graph
+#[derive(Debug, Clone, Default)]
+pub struct FunctionSummaries {
+    summaries: HashMap<String, FunctionSummary>,
+}
+
+impl FunctionSummaries {
+    pub fn compute(declarations: &[Declaration]) -> Self {
+        Self::with_models(declarations, &Models::new())
+    }
+
+    // Like `compute`, with calls to undefined functions described by `models`
+    pub fn with_models(declarations: &[Declaration], models: &Models) -> Self {
+        let functions: HashMap<&str, (&[String], &Statement)> = declarations
+            .iter()
+            .filter_map(|decl| match decl {
+                Declaration::Function(name, params, body) => {
+                    Some((name.as_str(), (params.as_slice(), &**body)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Callees before callers, so most functions are final after one pass
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for decl in declarations {
+            if let Declaration::Function(name, _, _) = decl {
+                Self::visit(name, &functions, &mut visited, &mut order);
+            }
+        }
+
+        // Recursive functions depend on their own summaries, so iterate to a fixpoint.
+        // Only `returns_fresh` can shrink, so the bound is just a guard against it flipping
+        let mut summaries: HashMap<String, FunctionSummary> = models
+            .functions()
+            .filter(|(name, _)| !functions.contains_key(name))
+            .map(|(name, model)| (name.to_string(), model.summary()))
+            .collect();
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for name in &order {
+                let (params, body) = functions[name.as_str()];
+                let summary = SummaryBuilder::summarize(&summaries, params, body);
+                if summaries.get(name) != Some(&summary) {
+                    summaries.insert(name.clone(), summary);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        FunctionSummaries { summaries }
+    }
+
+    fn visit(
+        name: &str,
+        functions: &HashMap<&str, (&[String], &Statement)>,
+        visited: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        let mut calls = Vec::new();
+        collect_calls(functions[name].1, &mut calls);
+        for callee in calls {
+            if functions.contains_key(callee.as_str()) {
+                Self::visit(&callee, functions, visited, order);
+            }
+        }
+        order.push(name.to_string());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FunctionSummary> {
+        self.summaries.get(name)
+    }
+}
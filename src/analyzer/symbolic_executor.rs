@@ -0,0 +1,714 @@
+use crate::analyzer::constraint_solver::{Constraint, ConstraintSolver, LinearExpr, Relation};
+use crate::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use crate::parser::cpp_parser::{parameter_name, Declaration, Expression, Location, Statement};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+const DEFAULT_LOOP_BOUND: usize = 4;
+const DEFAULT_MAX_PATHS: usize = 256;
+
+// One branch decision along an execution path
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchDecision {
+    pub condition: Expression,
+    pub taken: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolicFinding {
+    pub function: String,
+    pub error: MemoryError,
+    pub path: Vec<BranchDecision>,
+    // The statement the problem is reported at; for a leak, where the memory was allocated
+    pub location: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SymbolicValue {
+    Integer(LinearExpr),
+    Null,
+    Address(usize),
+}
+
+#[derive(Clone)]
+struct HeapObject {
+    site: String,
+    location: Option<Location>,
+    freed: bool,
+    escaped: bool,
+}
+
+#[derive(Clone)]
+struct PathState {
+    variables: HashMap<String, SymbolicValue>,
+    locals: HashSet<String>,
+    objects: Vec<HeapObject>,
+    freed_inputs: BTreeSet<String>,
+    constraints: Vec<Constraint>,
+    path: Vec<BranchDecision>,
+    return_value: Option<SymbolicValue>,
+}
+
+// Boolean formula over linear constraints, produced from branch conditions
+#[derive(Clone)]
+enum Formula {
+    Constant(bool),
+    Atom(Constraint),
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Unknown,
+}
+
+impl Formula {
+    fn negate(&self) -> Formula {
+        match self {
+            Formula::Constant(value) => Formula::Constant(!value),
+            Formula::Atom(constraint) => match constraint.negate() {
+                Some(negation) => Formula::Atom(negation),
+                None => Formula::Unknown,
+            },
+            Formula::And(left, right) => {
+                Formula::Or(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            Formula::Or(left, right) => {
+                Formula::And(Box::new(left.negate()), Box::new(right.negate()))
+            }
+            Formula::Unknown => Formula::Unknown,
+        }
+    }
+
+    // Disjunctive normal form: each inner vector is one conjunction
+    fn to_dnf(&self) -> Vec<Vec<Constraint>> {
+        match self {
+            Formula::Constant(true) | Formula::Unknown => vec![Vec::new()],
+            Formula::Constant(false) => Vec::new(),
+            Formula::Atom(constraint) => vec![vec![constraint.clone()]],
+            Formula::And(left, right) => {
+                let mut result = Vec::new();
+                for left_conjunction in left.to_dnf() {
+                    for right_conjunction in right.to_dnf() {
+                        let mut conjunction = left_conjunction.clone();
+                        conjunction.extend(right_conjunction);
+                        result.push(conjunction);
+                    }
+                }
+                result
+            }
+            Formula::Or(left, right) => {
+                let mut result = left.to_dnf();
+                result.extend(right.to_dnf());
+                result
+            }
+        }
+    }
+}
+
+pub struct SymbolicExecutor<'a> {
+    declarations: &'a [Declaration],
+    solver: ConstraintSolver,
+    loop_bound: usize,
+    max_paths: usize,
+    next_symbol: usize,
+    current_function: String,
+    location: Option<Location>,
+    findings: Vec<SymbolicFinding>,
+}
+
+impl<'a> SymbolicExecutor<'a> {
+    pub fn new(declarations: &'a [Declaration]) -> Self {
+        SymbolicExecutor::with_limits(declarations, DEFAULT_LOOP_BOUND, DEFAULT_MAX_PATHS)
+    }
+
+    pub fn with_limits(
+        declarations: &'a [Declaration],
+        loop_bound: usize,
+        max_paths: usize,
+    ) -> Self {
+        SymbolicExecutor {
+            declarations,
+            solver: ConstraintSolver::new(),
+            loop_bound,
+            max_paths,
+            next_symbol: 0,
+            current_function: String::new(),
+            location: None,
+            findings: Vec::new(),
+        }
+    }
+
+    fn fresh_value(&mut self) -> SymbolicValue {
+        self.next_symbol += 1;
+        SymbolicValue::Integer(LinearExpr::variable(&format!("${}", self.next_symbol)))
+    }
+
+    fn report(&mut self, state: &PathState, error_type: MemoryErrorType, details: String) {
+        self.report_at(state, error_type, details, self.location);
+    }
+
+    fn report_at(
+        &mut self,
+        state: &PathState,
+        error_type: MemoryErrorType,
+        details: String,
+        location: Option<Location>,
+    ) {
+        // Two objects allocated alike have the same message, so a finding is told
+        // apart by where it is; only findings without a location fall back to the text
+        let duplicate = self.findings.iter().any(|finding| {
+            finding.function == self.current_function
+                && finding.error.error_type() == error_type
+                && finding.location == location
+                && (location.is_some() || finding.error.details() == details)
+        });
+        if !duplicate {
+            self.findings.push(SymbolicFinding {
+                function: self.current_function.clone(),
+                error: MemoryError::new(error_type, details),
+                path: state.path.clone(),
+                location,
+            });
+        }
+    }
+
+    fn is_definitely_null(&self, state: &PathState, value: &SymbolicValue) -> bool {
+        match value {
+            SymbolicValue::Null => true,
+            SymbolicValue::Address(_) => false,
+            SymbolicValue::Integer(expr) => self.solver.implies(
+                &state.constraints,
+                &Constraint::new(expr.clone(), Relation::Eq),
+            ),
+        }
+    }
+
+    fn may_be_non_null(&self, state: &PathState, expr: &LinearExpr) -> bool {
+        let mut constraints = state.constraints.clone();
+        constraints.push(Constraint::new(expr.clone(), Relation::Ne));
+        self.solver.is_satisfiable(&constraints)
+    }
+
+    fn check_access(&mut self, state: &PathState, value: &SymbolicValue, target: &Expression) {
+        if self.is_definitely_null(state, value) {
+            self.report(
+                state,
+                MemoryErrorType::NullPointerDereference,
+                format!("Null pointer dereference of {}", describe(target)),
+            );
+            return;
+        }
+        match value {
+            SymbolicValue::Address(id) if state.objects[*id].freed => {
+                let details = format!(
+                    "Use of {} after free, memory allocated by {}",
+                    describe(target),
+                    state.objects[*id].site
+                );
+                self.report(state, MemoryErrorType::UseAfterFree, details);
+            }
+            SymbolicValue::Integer(expr) => {
+                if let Some(input) = input_symbol(expr) {
+                    if state.freed_inputs.contains(&input) {
+                        self.report(
+                            state,
+                            MemoryErrorType::UseAfterFree,
+                            format!("Use of {} after free", describe(target)),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn deallocate(&mut self, state: &mut PathState, value: SymbolicValue, target: &Expression) {
+        if self.is_definitely_null(state, &value) {
+            return;
+        }
+        match value {
+            SymbolicValue::Address(id) => {
+                if state.objects[id].freed {
+                    let details = format!(
+                        "Double free of {}, memory allocated by {}",
+                        describe(target),
+                        state.objects[id].site
+                    );
+                    self.report(state, MemoryErrorType::DoubleFree, details);
+                } else {
+                    state.objects[id].freed = true;
+                }
+            }
+            SymbolicValue::Integer(expr) => {
+                if let Some(input) = input_symbol(&expr) {
+                    if state.freed_inputs.contains(&input) {
+                        if self.may_be_non_null(state, &expr) {
+                            self.report(
+                                state,
+                                MemoryErrorType::DoubleFree,
+                                format!("Double free of {}", describe(target)),
+                            );
+                        }
+                    } else {
+                        state.freed_inputs.insert(input);
+                    }
+                }
+            }
+            SymbolicValue::Null => {}
+        }
+    }
+
+    fn allocate(&mut self, state: &mut PathState, site: String) -> SymbolicValue {
+        state.objects.push(HeapObject {
+            site,
+            location: self.location,
+            freed: false,
+            escaped: false,
+        });
+        SymbolicValue::Address(state.objects.len() - 1)
+    }
+
+    fn escape(&mut self, state: &mut PathState, value: &SymbolicValue) {
+        if let SymbolicValue::Address(id) = value {
+            state.objects[*id].escaped = true;
+        }
+    }
+
+    fn lookup(&mut self, state: &PathState, name: &str) -> SymbolicValue {
+        match state.variables.get(name) {
+            Some(value) => value.clone(),
+            // Globals and unknown names are unconstrained inputs
+            None => SymbolicValue::Integer(LinearExpr::variable(name)),
+        }
+    }
+
+    fn evaluate(&mut self, state: &mut PathState, expr: &Expression) -> SymbolicValue {
+        match expr {
+            Expression::Integer(value) => SymbolicValue::Integer(LinearExpr::constant(*value)),
+            Expression::Null => SymbolicValue::Null,
+            Expression::Uninitialized(_) => self.fresh_value(),
+            Expression::Identifier(name) | Expression::Variable(name) => self.lookup(state, name),
+            Expression::New(type_name, size, initializer) => {
+                for arg in initializer.iter().flatten() {
+                    self.evaluate(state, arg);
+                }
+                let site = match size {
+                    Some(size) => {
+                        self.evaluate(state, size);
+                        format!("new {}[]", type_name)
+                    }
+                    None => format!("new {}", type_name),
+                };
+                self.allocate(state, site)
+            }
+            Expression::Delete(target, _) => {
+                let value = self.evaluate(state, target);
+                self.deallocate(state, value, target);
+                self.fresh_value()
+            }
+            Expression::FunctionCall(name, args) => {
+                let values: Vec<SymbolicValue> =
+                    args.iter().map(|arg| self.evaluate(state, arg)).collect();
+                match name.as_str() {
+                    "malloc" | "calloc" => self.allocate(state, format!("{}()", name)),
+                    "free" => {
+                        if let (Some(value), Some(arg)) = (values.into_iter().next(), args.first())
+                        {
+                            self.deallocate(state, value, arg);
+                        }
+                        self.fresh_value()
+                    }
+                    _ => {
+                        for value in &values {
+                            self.escape(state, value);
+                        }
+                        self.fresh_value()
+                    }
+                }
+            }
+            Expression::Dereference(target) => {
+                let value = self.evaluate(state, target);
+                self.check_access(state, &value, target);
+                self.fresh_value()
+            }
+            Expression::ArrayAccess(name, index) => {
+                self.evaluate(state, index);
+                let value = self.lookup(state, name);
+                self.check_access(state, &value, &Expression::Identifier(name.clone()));
+                self.fresh_value()
+            }
+            Expression::ArrayDeclaration(name, size) => {
+                self.evaluate(state, size);
+                let value = self.fresh_value();
+                state.locals.insert(name.clone());
+                state.variables.insert(name.clone(), value.clone());
+                value
+            }
+            Expression::Assignment(target, value) => {
+                let value = self.evaluate(state, value);
+                self.assign(state, target, value.clone());
+                value
+            }
+            Expression::BinaryOperation(op, left, right) => {
+                let left = self.evaluate(state, left);
+                let right = self.evaluate(state, right);
+                // A result that overflows is unknown, like a non-linear one
+                let result = match (op.as_str(), left, right) {
+                    ("+", SymbolicValue::Integer(left), SymbolicValue::Integer(right)) => {
+                        left.add(&right)
+                    }
+                    ("-", SymbolicValue::Integer(left), SymbolicValue::Integer(right)) => {
+                        left.subtract(&right)
+                    }
+                    ("*", SymbolicValue::Integer(left), SymbolicValue::Integer(right)) => {
+                        match (left.as_constant(), right.as_constant()) {
+                            (Some(factor), _) => right.scale(factor),
+                            (_, Some(factor)) => left.scale(factor),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                match result {
+                    Some(expr) => SymbolicValue::Integer(expr),
+                    None => self.fresh_value(),
+                }
+            }
+            Expression::UnaryOperation(op, operand) => {
+                let value = self.evaluate(state, operand);
+                match (op.as_str(), value) {
+                    ("-", SymbolicValue::Integer(expr)) => match expr.scale(-1) {
+                        Some(negation) => SymbolicValue::Integer(negation),
+                        None => self.fresh_value(),
+                    },
+                    _ => self.fresh_value(),
+                }
+            }
+            Expression::MemberAccess(object, _) | Expression::AddressOf(object) => {
+                self.evaluate(state, object);
+                self.fresh_value()
+            }
+            Expression::MethodCall(object, _, args) => {
+                self.evaluate(state, object);
+                for arg in args {
+                    let value = self.evaluate(state, arg);
+                    self.escape(state, &value);
+                }
+                self.fresh_value()
+            }
+        }
+    }
+
+    fn assign(&mut self, state: &mut PathState, target: &Expression, value: SymbolicValue) {
+        match target {
+            Expression::Identifier(name) | Expression::Variable(name) => {
+                if !state.locals.contains(name) {
+                    self.escape(state, &value);
+                }
+                state.variables.insert(name.clone(), value);
+            }
+            Expression::Dereference(pointer) => {
+                let pointer_value = self.evaluate(state, pointer);
+                self.check_access(state, &pointer_value, pointer);
+                self.escape(state, &value);
+            }
+            Expression::ArrayAccess(name, index) => {
+                self.evaluate(state, index);
+                let pointer_value = self.lookup(state, name);
+                self.check_access(state, &pointer_value, &Expression::Identifier(name.clone()));
+                self.escape(state, &value);
+            }
+            _ => {
+                self.evaluate(state, target);
+            }
+        }
+    }
+
+    fn truthiness(&self, value: SymbolicValue) -> Formula {
+        match value {
+            SymbolicValue::Null => Formula::Constant(false),
+            SymbolicValue::Address(_) => Formula::Constant(true),
+            SymbolicValue::Integer(expr) => Formula::Atom(Constraint::new(expr, Relation::Ne)),
+        }
+    }
+
+    fn compare(&self, op: &str, left: SymbolicValue, right: SymbolicValue) -> Formula {
+        let as_integer = |value: &SymbolicValue| match value {
+            SymbolicValue::Integer(expr) => Some(expr.clone()),
+            SymbolicValue::Null => Some(LinearExpr::constant(0)),
+            SymbolicValue::Address(_) => None,
+        };
+
+        if let (Some(left), Some(right)) = (as_integer(&left), as_integer(&right)) {
+            let constraint = match op {
+                "==" => Constraint::compare(&left, Relation::Eq, &right),
+                "!=" => Constraint::compare(&left, Relation::Ne, &right),
+                "<" => Constraint::compare(&left, Relation::Lt, &right),
+                "<=" => Constraint::compare(&left, Relation::Le, &right),
+                ">" => Constraint::compare(&right, Relation::Lt, &left),
+                ">=" => Constraint::compare(&right, Relation::Le, &left),
+                _ => None,
+            };
+            return constraint.map_or(Formula::Unknown, Formula::Atom);
+        }
+
+        let equal = match (&left, &right) {
+            (SymbolicValue::Address(a), SymbolicValue::Address(b)) => Some(a == b),
+            (SymbolicValue::Address(_), SymbolicValue::Null)
+            | (SymbolicValue::Null, SymbolicValue::Address(_)) => Some(false),
+            _ => None,
+        };
+        match (op, equal) {
+            ("==", Some(equal)) => Formula::Constant(equal),
+            ("!=", Some(equal)) => Formula::Constant(!equal),
+            _ => Formula::Unknown,
+        }
+    }
+
+    fn condition(&mut self, state: &mut PathState, expr: &Expression) -> Formula {
+        match expr {
+            Expression::BinaryOperation(op, left, right) if op == "&&" || op == "||" => {
+                let left = self.condition(state, left);
+                let right = self.condition(state, right);
+                if op == "&&" {
+                    Formula::And(Box::new(left), Box::new(right))
+                } else {
+                    Formula::Or(Box::new(left), Box::new(right))
+                }
+            }
+            Expression::BinaryOperation(op, left, right)
+                if matches!(op.as_str(), "==" | "!=" | "<" | "<=" | ">" | ">=") =>
+            {
+                let left = self.evaluate(state, left);
+                let right = self.evaluate(state, right);
+                self.compare(op, left, right)
+            }
+            Expression::UnaryOperation(op, operand) if op == "!" => {
+                self.condition(state, operand).negate()
+            }
+            _ => {
+                let value = self.evaluate(state, expr);
+                self.truthiness(value)
+            }
+        }
+    }
+
+    // Forks every state on `condition`, keeping only feasible successors
+    fn branch(
+        &mut self,
+        states: Vec<PathState>,
+        condition: &Expression,
+    ) -> (Vec<PathState>, Vec<PathState>) {
+        let mut taken = Vec::new();
+        let mut not_taken = Vec::new();
+
+        for mut state in states {
+            let formula = self.condition(&mut state, condition);
+            for (outcome, target) in [(true, &mut taken), (false, &mut not_taken)] {
+                let formula = if outcome {
+                    formula.clone()
+                } else {
+                    formula.negate()
+                };
+                for conjunction in formula.to_dnf() {
+                    let mut successor = state.clone();
+                    successor.constraints.extend(conjunction);
+                    if !self.solver.is_satisfiable(&successor.constraints) {
+                        continue;
+                    }
+                    successor.path.push(BranchDecision {
+                        condition: condition.clone(),
+                        taken: outcome,
+                    });
+                    target.push(successor);
+                }
+            }
+        }
+
+        (self.limit(taken), self.limit(not_taken))
+    }
+
+    fn limit(&self, mut states: Vec<PathState>) -> Vec<PathState> {
+        states.truncate(self.max_paths);
+        states
+    }
+
+    fn execute(&mut self, stmt: &Statement, states: Vec<PathState>) -> Vec<PathState> {
+        let (mut live, finished): (Vec<PathState>, Vec<PathState>) = states
+            .into_iter()
+            .partition(|state| state.return_value.is_none());
+
+        live = match stmt {
+            Statement::Expression(expr) => live
+                .into_iter()
+                .map(|mut state| {
+                    self.evaluate(&mut state, expr);
+                    state
+                })
+                .collect(),
+            Statement::Declaration(name, expr) | Statement::Reference(name, expr) => live
+                .into_iter()
+                .map(|mut state| {
+                    let value = self.evaluate(&mut state, expr);
+                    state.locals.insert(name.clone());
+                    state.variables.insert(name.clone(), value);
+                    state
+                })
+                .collect(),
+            Statement::Return(expr) => live
+                .into_iter()
+                .map(|mut state| {
+                    let value = self.evaluate(&mut state, expr);
+                    state.return_value = Some(value);
+                    state
+                })
+                .collect(),
+            // The thrown object escapes; locals are left behind like on a return
+            Statement::Throw(expr) => live
+                .into_iter()
+                .map(|mut state| {
+                    let value = self.evaluate(&mut state, expr);
+                    self.escape(&mut state, &value);
+                    state.return_value = Some(self.fresh_value());
+                    state
+                })
+                .collect(),
+            Statement::Located(location, inner) => {
+                let enclosing = self.location.replace(*location);
+                let states = self.execute(inner, live);
+                self.location = enclosing;
+                states
+            }
+            Statement::Block(stmts) => {
+                let mut states = live;
+                for stmt in stmts {
+                    states = self.execute(stmt, states);
+                }
+                states
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                let (taken, not_taken) = self.branch(live, condition);
+                let mut states = self.execute(then_branch, taken);
+                states.extend(self.execute(else_branch, not_taken));
+                self.limit(states)
+            }
+            Statement::While(condition, body) => {
+                let mut exits = Vec::new();
+                let mut iterating = live;
+                for iteration in 0..=self.loop_bound {
+                    let (taken, not_taken) = self.branch(iterating, condition);
+                    exits.extend(not_taken);
+                    if iteration == self.loop_bound || taken.is_empty() {
+                        break;
+                    }
+                    let (still_iterating, returned): (Vec<PathState>, Vec<PathState>) = self
+                        .execute(body, taken)
+                        .into_iter()
+                        .partition(|state| state.return_value.is_none());
+                    exits.extend(returned);
+                    iterating = still_iterating;
+                }
+                self.limit(exits)
+            }
+            // The number of elements is unknown, so the loop may exit after any iteration
+            Statement::RangeFor(element, range, body) => {
+                let mut iterating: Vec<PathState> = live
+                    .into_iter()
+                    .map(|mut state| {
+                        self.evaluate(&mut state, range);
+                        state
+                    })
+                    .collect();
+                let mut exits = Vec::new();
+                for iteration in 0..=self.loop_bound {
+                    exits.extend(iterating.iter().cloned());
+                    if iteration == self.loop_bound || iterating.is_empty() {
+                        break;
+                    }
+                    for state in &mut iterating {
+                        let value = self.fresh_value();
+                        state.locals.insert(element.clone());
+                        state.variables.insert(element.clone(), value);
+                    }
+                    let (still_iterating, returned): (Vec<PathState>, Vec<PathState>) = self
+                        .execute(body, iterating)
+                        .into_iter()
+                        .partition(|state| state.return_value.is_none());
+                    exits.extend(returned);
+                    iterating = still_iterating;
+                }
+                self.limit(exits)
+            }
+        };
+
+        live.extend(finished);
+        live
+    }
+
+    fn check_leaks(&mut self, state: &PathState) {
+        for (id, object) in state.objects.iter().enumerate() {
+            let returned = state.return_value == Some(SymbolicValue::Address(id));
+            if !object.freed && !object.escaped && !returned {
+                let details = format!("Memory allocated by {} is never freed", object.site);
+                self.report_at(state, MemoryErrorType::MemoryLeak, details, object.location);
+            }
+        }
+    }
+
+    fn analyze_function(&mut self, name: &str, params: &[String], body: &Statement) {
+        self.current_function = name.to_string();
+        self.location = None;
+        let mut initial = PathState {
+            variables: HashMap::new(),
+            locals: HashSet::new(),
+            objects: Vec::new(),
+            freed_inputs: BTreeSet::new(),
+            constraints: Vec::new(),
+            path: Vec::new(),
+            return_value: None,
+        };
+        for param in params {
+            let param = parameter_name(param);
+            initial.locals.insert(param.to_string());
+            initial.variables.insert(
+                param.to_string(),
+                SymbolicValue::Integer(LinearExpr::variable(param)),
+            );
+        }
+
+        for state in self.execute(body, vec![initial]) {
+            self.check_leaks(&state);
+        }
+    }
+
+    pub fn analyze(&mut self) -> &[SymbolicFinding] {
+        for decl in self.declarations {
+            if let Declaration::Function(name, params, body) = decl {
+                self.analyze_function(name, params, body);
+            }
+        }
+        &self.findings
+    }
+
+    // The findings in the function declared at `index` only, so that each function can
+    // be reported as soon as it is done
+    pub fn analyze_function_at(&mut self, index: usize) -> &[SymbolicFinding] {
+        let start = self.findings.len();
+        if let Declaration::Function(name, params, body) = &self.declarations[index] {
+            self.analyze_function(name, params, body);
+        }
+        &self.findings[start..]
+    }
+}
+
+// Name of the input a pointer value was read from, e.g. a parameter
+fn input_symbol(expr: &LinearExpr) -> Option<String> {
+    match expr.as_variable() {
+        Some(name) if !name.starts_with('$') => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+fn describe(expr: &Expression) -> String {
+    match expr {
+        Expression::Identifier(name) | Expression::Variable(name) => format!("'{}'", name),
+        _ => String::from("expression"),
+    }
+}
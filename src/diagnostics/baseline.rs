@@ -0,0 +1,255 @@
+// A baseline records the findings a codebase already has, so that only new ones are
+// reported. Findings are identified by their fingerprint, which survives code moving
+// around but not changes to the code the finding points at
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::diagnostics::fingerprint::fingerprint;
+use crate::diagnostics::{Diagnostic, SourceFile};
+use crate::json::Json;
+
+pub const BASELINE_VERSION: usize = 1;
+
+#[derive(Debug, PartialEq)]
+pub struct BaselineError {
+    pub details: String,
+}
+
+impl fmt::Display for BaselineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid baseline file: {}", self.details)
+    }
+}
+
+impl Error for BaselineError {}
+
+// One recorded finding. Only the file and fingerprint are matched; the rest is there
+// so the baseline can be reviewed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineEntry {
+    pub file: String,
+    pub fingerprint: String,
+    pub rule: String,
+    pub function: Option<String>,
+    pub message: String,
+}
+
+impl BaselineEntry {
+    fn parse(value: &Json) -> Result<Self, BaselineError> {
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(Json::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| BaselineError {
+                    details: format!("every finding needs a string \"{}\"", key),
+                })
+        };
+        Ok(BaselineEntry {
+            file: field("file")?,
+            fingerprint: field("fingerprint")?,
+            rule: field("rule")?,
+            function: value
+                .get("function")
+                .and_then(Json::as_str)
+                .map(str::to_string),
+            message: field("message").unwrap_or_default(),
+        })
+    }
+
+    fn to_json(&self) -> Json {
+        let function = self.function.as_deref().map_or(Json::Null, Json::from);
+        Json::object([
+            ("file", Json::from(self.file.as_str())),
+            ("fingerprint", Json::from(self.fingerprint.as_str())),
+            ("rule", Json::from(self.rule.as_str())),
+            ("function", function),
+            ("message", Json::from(self.message.as_str())),
+        ])
+    }
+}
+
+// The findings of any number of files, written by `--write-baseline` and read by
+// `--baseline`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Baseline {
+    entries: Vec<BaselineEntry>,
+    // Directory of the baseline file, which recorded files are relative to
+    directory: Option<PathBuf>,
+}
+
+// `path` with `/` separators and without `.` components, so `./a.cpp` is `a.cpp`
+fn normalize(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .map(|component| match component {
+            Component::RootDir => String::new(),
+            component => component.as_os_str().to_string_lossy().into_owned(),
+        })
+        .collect();
+    match parts.as_slice() {
+        [root] if root.is_empty() => "/".to_string(),
+        parts => parts.join("/"),
+    }
+}
+
+impl Baseline {
+    pub fn new() -> Self {
+        Baseline::default()
+    }
+
+    // An empty baseline to be saved as `path`
+    pub fn at(path: &str) -> Self {
+        Baseline {
+            entries: Vec::new(),
+            directory: Some(directory_of(path)),
+        }
+    }
+
+    // Reads a document of the form `{"version": 1, "findings": [...]}`
+    pub fn parse(source: &str) -> Result<Self, BaselineError> {
+        let document = Json::parse(source).map_err(|error| BaselineError {
+            details: error.to_string(),
+        })?;
+        match document.get("version").and_then(Json::as_usize) {
+            Some(BASELINE_VERSION) => {}
+            Some(version) => {
+                return Err(BaselineError {
+                    details: format!(
+                        "version {} is not supported, expected {}",
+                        version, BASELINE_VERSION
+                    ),
+                })
+            }
+            None => {
+                return Err(BaselineError {
+                    details: "expected a top-level \"version\" number".to_string(),
+                })
+            }
+        }
+        let findings = document
+            .get("findings")
+            .and_then(Json::as_array)
+            .ok_or_else(|| BaselineError {
+                details: "expected a top-level \"findings\" array".to_string(),
+            })?;
+        let entries = findings
+            .iter()
+            .map(BaselineEntry::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Baseline {
+            entries,
+            directory: None,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Self, BaselineError> {
+        let source = std::fs::read_to_string(path).map_err(|error| BaselineError {
+            details: format!("{}: {}", path, error),
+        })?;
+        let baseline = Baseline::parse(&source).map_err(|error| BaselineError {
+            details: format!("{}: {}", path, error.details),
+        })?;
+        Ok(Baseline {
+            directory: Some(directory_of(path)),
+            ..baseline
+        })
+    }
+
+    // How `file` is recorded: relative to the baseline's directory when both exist, so
+    // the same file matches however it is named on the command line and from wherever
+    // the tool runs
+    fn file_key(&self, file: &str) -> String {
+        let path = Path::new(file);
+        let resolved = self.directory.as_ref().and_then(|directory| {
+            let directory = directory.canonicalize().ok()?;
+            let path = path.canonicalize().ok()?;
+            Some(match path.strip_prefix(&directory) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            })
+        });
+        normalize(resolved.as_deref().unwrap_or(path))
+    }
+
+    pub fn entries(&self) -> &[BaselineEntry] {
+        &self.entries
+    }
+
+    // Replaces what is recorded for `source` with `diagnostics`, keeping other files.
+    // Entries are kept sorted so the file diffs well under version control
+    pub fn record(&mut self, source: &SourceFile, diagnostics: &[Diagnostic]) {
+        let file = self.file_key(&source.name);
+        self.entries
+            .retain(|entry| normalize(Path::new(&entry.file)) != file);
+        self.entries
+            .extend(diagnostics.iter().map(|diagnostic| BaselineEntry {
+                file: file.clone(),
+                fingerprint: fingerprint(diagnostic, source),
+                rule: diagnostic.rule.clone(),
+                function: diagnostic.function.clone(),
+                message: diagnostic.message.clone(),
+            }));
+        self.entries.sort_by(|a, b| {
+            (&a.file, &a.rule, &a.fingerprint).cmp(&(&b.file, &b.rule, &b.fingerprint))
+        });
+    }
+
+    // Splits `diagnostics` of `source` into those not in the baseline, which are
+    // returned, and a count of those that are. Each recorded fingerprint matches one
+    // diagnostic, so a new copy of a known problem is still reported
+    pub fn filter(
+        &self,
+        source: &SourceFile,
+        diagnostics: Vec<Diagnostic>,
+    ) -> (Vec<Diagnostic>, usize) {
+        let file = self.file_key(&source.name);
+        let mut known: HashMap<&str, usize> = HashMap::new();
+        for entry in self
+            .entries
+            .iter()
+            .filter(|entry| normalize(Path::new(&entry.file)) == file)
+        {
+            *known.entry(entry.fingerprint.as_str()).or_default() += 1;
+        }
+        let mut matched = 0;
+        let new = diagnostics
+            .into_iter()
+            .filter(
+                |diagnostic| match known.get_mut(fingerprint(diagnostic, source).as_str()) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        matched += 1;
+                        false
+                    }
+                    _ => true,
+                },
+            )
+            .collect();
+        (new, matched)
+    }
+
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("version", Json::from(BASELINE_VERSION)),
+            (
+                "findings",
+                Json::Array(self.entries.iter().map(BaselineEntry::to_json).collect()),
+            ),
+        ])
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, format!("{}\n", self.to_json().pretty()))
+    }
+}
+
+fn directory_of(path: &str) -> PathBuf {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use crate::analyzer::array_analyzer::ArrayError;
+use crate::analyzer::class_analyzer::{ClassError, ClassErrorKind};
+use crate::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use crate::analyzer::pointer_analyzer::{PointerError, PointerErrorKind};
+use crate::analyzer::symbolic_executor::{BranchDecision, SymbolicFinding};
+use crate::diagnostics::rules;
+use crate::error::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// A range of source text; line and column are 1-based, the length is in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, length: usize) -> Self {
+        Span {
+            line,
+            column,
+            length,
+        }
+    }
+}
+
+// A span with an explanation, e.g. "allocated here"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// Replacing the text at `span` with `replacement` fixes the problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+// A step on the way to the problem, e.g. a branch taken; `code` is the source text
+// involved, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub code: String,
+    pub message: String,
+}
+
+// A finding of any analyzer. Analyzers only know where statements start, so spans
+// of length 0 are narrowed later by whoever knows the source, if at all
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    // Function the problem was found in, when the analyzer knows it
+    pub function: Option<String>,
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+    pub fixes: Vec<Fix>,
+    // Set by path-sensitive analyses
+    pub path: Vec<Step>,
+}
+
+impl Diagnostic {
+    // A diagnostic for a known rule, with the rule's default severity and help
+    pub fn new(rule: &str, message: String) -> Self {
+        let known = rules::find(rule);
+        Diagnostic {
+            rule: rule.to_string(),
+            severity: known.map_or(Severity::Error, |rule| rule.severity),
+            message,
+            function: None,
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: known
+                .filter(|rule| !rule.help.is_empty())
+                .map(|rule| rule.help.to_string()),
+            fixes: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn in_function(mut self, function: &str) -> Self {
+        self.function = Some(function.to_string());
+        self
+    }
+
+    pub fn with_primary(mut self, span: Span, message: &str) -> Self {
+        self.primary = Some(Label {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: &str) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: message.to_string(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
+    pub fn with_step(mut self, code: String, message: String) -> Self {
+        self.path.push(Step { code, message });
+        self
+    }
+
+    // Each branch becomes a step of the path and, for plain text, a note
+    pub fn with_branches(mut self, branches: &[BranchDecision]) -> Self {
+        for decision in branches {
+            let code = decision.condition.to_string();
+            let message = format!("condition `{}` is {}", code, decision.taken);
+            self = self.with_note(message.clone()).with_step(code, message);
+        }
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)?;
+        if let Some(primary) = &self.primary {
+            write!(f, " at {}:{}", primary.span.line, primary.span.column)?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  = note: {}", note)?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\n  = help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl From<MemoryError> for Diagnostic {
+    fn from(error: MemoryError) -> Self {
+        let rule = match error.error_type() {
+            MemoryErrorType::MemoryLeak => "memory-leak",
+            MemoryErrorType::DoubleFree => "double-free",
+            MemoryErrorType::UninitializedMemoryAccess => "uninitialized-read",
+            MemoryErrorType::NullPointerDereference => "null-dereference",
+            MemoryErrorType::UseAfterFree => "use-after-free",
+            MemoryErrorType::MismatchedDeallocation => "mismatched-deallocation",
+            MemoryErrorType::UseAfterMove => "use-after-move",
+            MemoryErrorType::InvalidatedIterator => "invalidated-iterator",
+        };
+        Diagnostic::new(rule, error.details().to_string())
+    }
+}
+
+impl From<PointerError> for Diagnostic {
+    fn from(error: PointerError) -> Self {
+        let rule = match error.kind {
+            PointerErrorKind::IncorrectNumberOfArguments => "argument-count",
+            PointerErrorKind::NonPointerArgumentForPointerParameter => "non-pointer-argument",
+            PointerErrorKind::NullDereference => "null-dereference",
+            PointerErrorKind::UseAfterFree => "use-after-free",
+            PointerErrorKind::DanglingStackReference => "dangling-reference",
+            PointerErrorKind::LifetimeMismatch => "lifetime-mismatch",
+            PointerErrorKind::BorrowConflict => "borrow-conflict",
+        };
+        Diagnostic::new(rule, error.details)
+    }
+}
+
+impl From<ArrayError> for Diagnostic {
+    fn from(error: ArrayError) -> Self {
+        Diagnostic::new("out-of-bounds", error.to_string())
+    }
+}
+
+impl From<ClassError> for Diagnostic {
+    fn from(error: ClassError) -> Self {
+        let rule = match error.kind {
+            ClassErrorKind::RuleOfThree => "rule-of-three",
+            ClassErrorKind::RuleOfFive => "rule-of-five",
+            ClassErrorKind::NonVirtualDestructor => "non-virtual-destructor",
+            ClassErrorKind::SelfAssignment => "self-assignment",
+        };
+        Diagnostic::new(rule, error.details)
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Array(error) => error.into(),
+            Error::Class(error) => error.into(),
+            Error::Memory(error) => error.into(),
+            Error::Pointer(error) => error.into(),
+        }
+    }
+}
+
+// The branches that lead to the problem become the path and, for plain text, notes
+impl From<SymbolicFinding> for Diagnostic {
+    fn from(finding: SymbolicFinding) -> Self {
+        let diagnostic = Diagnostic::from(finding.error)
+            .in_function(&finding.function)
+            .with_branches(&finding.path);
+        match finding.location {
+            Some(location) => {
+                diagnostic.with_primary(Span::new(location.line, location.column, 0), "")
+            }
+            None => diagnostic,
+        }
+    }
+}
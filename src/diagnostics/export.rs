@@ -0,0 +1,131 @@
+// The `--format json` and `--format jsonl` outputs; docs/json-output.md describes the
+// schema. Bump SCHEMA_VERSION on any change that could break a consumer
+use crate::diagnostics::fingerprint::fingerprint;
+use crate::diagnostics::{Diagnostic, Severity, SourceFile, Span};
+use crate::json::Json;
+
+pub const SCHEMA_VERSION: usize = 1;
+
+fn optional<T, F: FnOnce(T) -> Json>(value: Option<T>, convert: F) -> Json {
+    value.map_or(Json::Null, convert)
+}
+
+fn span(span: Span, label: &str) -> Json {
+    let mut members = vec![
+        ("line", Json::from(span.line)),
+        ("column", Json::from(span.column)),
+        ("length", Json::from(span.length)),
+    ];
+    if !label.is_empty() {
+        members.push(("label", Json::from(label)));
+    }
+    Json::object(members)
+}
+
+fn strings(values: &[String]) -> Json {
+    Json::Array(
+        values
+            .iter()
+            .map(|value| Json::from(value.as_str()))
+            .collect(),
+    )
+}
+
+pub fn diagnostic_json(source: &SourceFile, diagnostic: &Diagnostic) -> Json {
+    let function = diagnostic.function.as_deref();
+    let fixes = diagnostic.fixes.iter().map(|fix| {
+        Json::object([
+            ("message", Json::from(fix.message.as_str())),
+            ("span", span(fix.span, "")),
+            ("replacement", Json::from(fix.replacement.as_str())),
+        ])
+    });
+    let path = diagnostic.path.iter().map(|step| {
+        Json::object([
+            ("code", Json::from(step.code.as_str())),
+            ("message", Json::from(step.message.as_str())),
+            (
+                "span",
+                optional(source.find(function, &step.code), |found| span(found, "")),
+            ),
+        ])
+    });
+    Json::object([
+        ("rule", Json::from(diagnostic.rule.as_str())),
+        ("severity", Json::from(diagnostic.severity.as_str())),
+        ("message", Json::from(diagnostic.message.as_str())),
+        ("file", Json::from(source.name.as_str())),
+        ("function", optional(function, Json::from)),
+        (
+            "span",
+            optional(diagnostic.primary.as_ref(), |label| {
+                span(label.span, &label.message)
+            }),
+        ),
+        (
+            "related",
+            Json::Array(
+                diagnostic
+                    .secondary
+                    .iter()
+                    .map(|label| span(label.span, &label.message))
+                    .collect(),
+            ),
+        ),
+        ("notes", strings(&diagnostic.notes)),
+        ("help", optional(diagnostic.help.as_deref(), Json::from)),
+        ("fixes", Json::Array(fixes.collect())),
+        ("path", Json::Array(path.collect())),
+        ("fingerprint", Json::from(fingerprint(diagnostic, source))),
+    ])
+}
+
+// One line of `--format jsonl`: a diagnostic that carries the schema version itself
+pub fn json_line(source: &SourceFile, diagnostic: &Diagnostic) -> String {
+    let mut line = diagnostic_json(source, diagnostic);
+    if let Json::Object(members) = &mut line {
+        members.insert(
+            0,
+            ("schema_version".to_string(), Json::from(SCHEMA_VERSION)),
+        );
+    }
+    line.to_string()
+}
+
+// The document of `--format json`. `dropped` counts diagnostics left out by limits
+pub fn json_report(source: &SourceFile, diagnostics: &[Diagnostic], dropped: usize) -> Json {
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    Json::object([
+        ("schema_version", Json::from(SCHEMA_VERSION)),
+        (
+            "tool",
+            Json::object([
+                ("name", Json::from("safecpp")),
+                ("version", Json::from(env!("CARGO_PKG_VERSION"))),
+            ]),
+        ),
+        (
+            "diagnostics",
+            Json::Array(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic_json(source, diagnostic))
+                    .collect(),
+            ),
+        ),
+        (
+            "summary",
+            Json::object([
+                ("errors", Json::from(count(Severity::Error))),
+                ("warnings", Json::from(count(Severity::Warning))),
+                ("notes", Json::from(count(Severity::Note))),
+                ("dropped", Json::from(dropped)),
+            ]),
+        ),
+    ])
+}
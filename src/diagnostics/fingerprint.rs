@@ -0,0 +1,31 @@
+use crate::diagnostics::{Diagnostic, SourceFile};
+
+// FNV-1a, which unlike std's hasher gives the same value on every run and platform
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Source text with runs of whitespace collapsed, so reindenting keeps fingerprints
+pub fn normalize(code: &str) -> String {
+    code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Identifies a finding across edits that move it around: the rule, the enclosing
+// function and the normalized line of code it points at, or its message when there
+// is no location. Line numbers are deliberately left out
+pub fn fingerprint(diagnostic: &Diagnostic, source: &SourceFile) -> String {
+    let code = diagnostic
+        .primary
+        .as_ref()
+        .and_then(|primary| source.line(primary.span.line))
+        .map_or_else(|| diagnostic.message.clone(), normalize);
+    let key = format!(
+        "{}\0{}\0{}",
+        diagnostic.rule,
+        diagnostic.function.as_deref().unwrap_or_default(),
+        code
+    );
+    format!("{:016x}", fnv1a(key.as_bytes()))
+}
@@ -0,0 +1,293 @@
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::diagnostics::rules::{self, RULES};
+use crate::diagnostics::{Diagnostic, Severity, SourceFile, Span};
+
+// Everything is inline so the report works offline and can be archived as is
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+h2 { font-size: 1.15em; margin-top: 1.5em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+.error { color: #b00; font-weight: bold; }
+.warning { color: #a60; font-weight: bold; }
+.note { color: #070; font-weight: bold; }
+.source { font-family: monospace; white-space: pre; border: 1px solid #ccc; }
+.source td { border: none; padding: 0 0.6em; }
+.number { color: #888; text-align: right; user-select: none; }
+.marked { background: #fff3c4; }
+.primary { background: #ffd6d6; }
+.step { font-family: sans-serif; white-space: normal; }
+.step span { display: inline-block; margin: 0.2em 0; padding: 0.1em 0.5em; border-radius: 0.3em;
+  background: #e8eefc; border: 1px solid #9ab; }
+.step.primary span { background: #fbe3e3; border-color: #c99; }
+.badge { display: inline-block; min-width: 1.2em; text-align: center; border-radius: 0.6em;
+  background: #456; color: #fff; font-size: 0.85em; margin-right: 0.4em; }
+";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn finding_page_name(index: usize) -> String {
+    format!("finding-{}.html", index + 1)
+}
+
+// One event on the way to the problem, in source order
+struct Event {
+    span: Span,
+    message: String,
+    primary: bool,
+}
+
+// Branches taken, allocation, free and use, ordered by where they appear in the source
+fn events(source: &SourceFile, diagnostic: &Diagnostic) -> Vec<Event> {
+    let function = diagnostic.function.as_deref();
+    let mut events: Vec<Event> = diagnostic
+        .secondary
+        .iter()
+        .map(|label| Event {
+            span: label.span,
+            message: label.message.clone(),
+            primary: false,
+        })
+        .chain(diagnostic.path.iter().filter_map(|step| {
+            source.find(function, &step.code).map(|span| Event {
+                span,
+                message: step.message.clone(),
+                primary: false,
+            })
+        }))
+        .collect();
+    if let Some(primary) = &diagnostic.primary {
+        let message = if primary.message.is_empty() {
+            diagnostic.message.clone()
+        } else {
+            primary.message.clone()
+        };
+        events.push(Event {
+            span: primary.span,
+            message,
+            primary: true,
+        });
+    }
+    events.sort_by_key(|event| (event.span.line, event.span.column));
+    events
+}
+
+fn location(diagnostic: &Diagnostic) -> String {
+    match &diagnostic.primary {
+        Some(primary) => format!("{}:{}", primary.span.line, primary.span.column),
+        None => String::new(),
+    }
+}
+
+fn write_finding(out: &mut String, source: &SourceFile, diagnostic: &Diagnostic) -> fmt::Result {
+    let severity = diagnostic.severity.as_str();
+    writeln!(out, "<p><a href=\"index.html\">&larr; All findings</a></p>")?;
+    writeln!(
+        out,
+        "<h1><span class=\"{}\">{}[{}]</span> {}</h1>",
+        severity,
+        severity,
+        escape(&diagnostic.rule),
+        escape(&diagnostic.message)
+    )?;
+    write!(out, "<p>{}", escape(&source.name))?;
+    if !location(diagnostic).is_empty() {
+        write!(out, ":{}", location(diagnostic))?;
+    }
+    if let Some(function) = &diagnostic.function {
+        write!(out, ", in <code>{}</code>", escape(function))?;
+    }
+    writeln!(out, "</p>")?;
+    if let Some(rule) = rules::find(&diagnostic.rule) {
+        writeln!(out, "<p>{}</p>", escape(rule.description))?;
+    }
+    for note in &diagnostic.notes {
+        writeln!(out, "<p><b>note:</b> {}</p>", escape(note))?;
+    }
+    if let Some(help) = &diagnostic.help {
+        writeln!(out, "<p><b>help:</b> {}</p>", escape(help))?;
+    }
+
+    let events = events(source, diagnostic);
+    if events.is_empty() {
+        writeln!(
+            out,
+            "<p>The finding could not be located in the source.</p>"
+        )?;
+        return Ok(());
+    }
+    // The whole function when it can be found, otherwise a few lines around the events
+    let first = events
+        .iter()
+        .map(|event| event.span.line)
+        .min()
+        .unwrap_or(1);
+    let last = events
+        .iter()
+        .map(|event| event.span.line)
+        .max()
+        .unwrap_or(1);
+    let (start, end) = match diagnostic
+        .function
+        .as_deref()
+        .and_then(|function| source.function_lines(function))
+    {
+        Some((start, end)) => (start.min(first), end.max(last)),
+        None => (first.saturating_sub(3).max(1), last + 3),
+    };
+    let end = end.min(source.line_count());
+
+    writeln!(out, "<table class=\"source\">")?;
+    for line in start..=end {
+        let on_line: Vec<(usize, &Event)> = events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| event.span.line == line)
+            .collect();
+        let class = match on_line.iter().any(|(_, event)| event.primary) {
+            true => " class=\"primary\"",
+            false if !on_line.is_empty() => " class=\"marked\"",
+            false => "",
+        };
+        writeln!(
+            out,
+            "<tr{}><td class=\"number\" id=\"L{}\">{}</td><td>{}</td></tr>",
+            class,
+            line,
+            line,
+            escape(source.line(line).unwrap_or_default())
+        )?;
+        for (index, event) in on_line {
+            writeln!(
+                out,
+                "<tr class=\"step{}\"><td></td><td>{}<span><span class=\"badge\">{}</span>{}</span></td></tr>",
+                if event.primary { " primary" } else { "" },
+                " ".repeat(event.span.column - 1),
+                index + 1,
+                escape(&event.message)
+            )?;
+        }
+    }
+    writeln!(out, "</table>")
+}
+
+fn write_index(out: &mut String, source: &SourceFile, diagnostics: &[Diagnostic]) -> fmt::Result {
+    let count = |severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    writeln!(out, "<h1>safecpp report</h1>")?;
+    writeln!(
+        out,
+        "<p>{} errors, {} warnings in {}</p>",
+        count(Severity::Error),
+        count(Severity::Warning),
+        escape(&source.name)
+    )?;
+    if diagnostics.is_empty() {
+        return writeln!(out, "<p>No issues detected.</p>");
+    }
+
+    // Known rules in their usual order, then any others
+    let mut rule_ids: Vec<&str> = RULES.iter().map(|rule| rule.id).collect();
+    for diagnostic in diagnostics {
+        if !rule_ids.contains(&diagnostic.rule.as_str()) {
+            rule_ids.push(&diagnostic.rule);
+        }
+    }
+    for rule in rule_ids {
+        let findings: Vec<(usize, &Diagnostic)> = diagnostics
+            .iter()
+            .enumerate()
+            .filter(|(_, diagnostic)| diagnostic.rule == rule)
+            .collect();
+        if findings.is_empty() {
+            continue;
+        }
+        write!(
+            out,
+            "<h2><code>{}</code> ({})",
+            escape(rule),
+            findings.len()
+        )?;
+        if let Some(rule) = rules::find(rule) {
+            write!(out, " &mdash; {}", escape(rule.description))?;
+        }
+        writeln!(out, "</h2>")?;
+        writeln!(
+            out,
+            "<table>\n<tr><th>File</th><th>Function</th><th>Line</th><th>Severity</th><th>Message</th></tr>"
+        )?;
+        for (index, diagnostic) in findings {
+            let severity = diagnostic.severity.as_str();
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td><a href=\"{}\">{}</a></td></tr>",
+                escape(&source.name),
+                escape(diagnostic.function.as_deref().unwrap_or_default()),
+                location(diagnostic),
+                severity,
+                severity,
+                finding_page_name(index),
+                escape(&diagnostic.message)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+    Ok(())
+}
+
+// Writes `index.html`, listing the findings by rule, and one page per finding that
+// shows the source with the events leading to it. Spans should already be located
+pub fn write_html_report(
+    directory: &Path,
+    source: &SourceFile,
+    diagnostics: &[Diagnostic],
+) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+    let mut index = String::new();
+    write_index(&mut index, source, diagnostics).expect("writing to a String cannot fail");
+    fs::write(directory.join("index.html"), page("safecpp report", &index))?;
+
+    for (number, diagnostic) in diagnostics.iter().enumerate() {
+        let mut body = String::new();
+        write_finding(&mut body, source, diagnostic).expect("writing to a String cannot fail");
+        let title = format!("{}: {}", diagnostic.rule, diagnostic.message);
+        fs::write(
+            directory.join(finding_page_name(number)),
+            page(&title, &body),
+        )?;
+    }
+    Ok(())
+}
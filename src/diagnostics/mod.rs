@@ -0,0 +1,18 @@
+pub mod baseline;
+pub mod diagnostic;
+pub mod export;
+pub mod fingerprint;
+pub mod html;
+pub mod render;
+pub mod rules;
+pub mod sarif;
+pub mod sink;
+pub mod source;
+pub mod suppression;
+
+pub use diagnostic::{Diagnostic, Fix, Label, Severity, Span, Step};
+pub use render::{ColorChoice, Renderer};
+pub use rules::Rule;
+pub use sink::{DiagnosticSink, Limits, Reporter};
+pub use source::SourceFile;
+pub use suppression::Suppressions;
@@ -0,0 +1,181 @@
+use std::fmt::{self, Write};
+use std::io::IsTerminal;
+
+use crate::diagnostics::source::SourceFile;
+use crate::diagnostics::{Diagnostic, Label, Severity};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+// The `--color` option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    // Auto colours only a terminal, and respects the NO_COLOR convention
+    pub fn enabled(&self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+// Renders diagnostics in the style of rustc: a header, the source lines the
+// diagnostic points at with the spans underlined, then its notes and help
+pub struct Renderer {
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(color: bool) -> Self {
+        Renderer { color }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity_style(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+        }
+    }
+
+    pub fn render(&self, source: &SourceFile, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        self.write(&mut out, source, diagnostic)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    fn write(&self, out: &mut String, source: &SourceFile, diagnostic: &Diagnostic) -> fmt::Result {
+        let style = Renderer::severity_style(diagnostic.severity);
+        writeln!(
+            out,
+            "{}{}",
+            self.paint(
+                style,
+                &format!("{}[{}]", diagnostic.severity, diagnostic.rule)
+            ),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        )?;
+
+        // Primary label first; the snippet is shown in line order
+        let mut labels: Vec<(&Label, bool)> = diagnostic
+            .primary
+            .iter()
+            .map(|label| (label, true))
+            .chain(diagnostic.secondary.iter().map(|label| (label, false)))
+            .filter(|(label, _)| label.span.line <= source.line_count())
+            .collect();
+        let width = labels
+            .iter()
+            .map(|(label, _)| label.span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(width);
+        let bar = self.paint(BLUE, "|");
+
+        let location = match labels.first() {
+            Some((label, _)) => {
+                format!("{}:{}:{}", source.name, label.span.line, label.span.column)
+            }
+            None => source.name.clone(),
+        };
+        write!(out, "{}{} {}", gutter, self.paint(BLUE, "-->"), location)?;
+        if let (None, Some(function)) = (labels.first(), &diagnostic.function) {
+            write!(out, " (in '{}')", function)?;
+        }
+        out.push('\n');
+
+        if !labels.is_empty() {
+            writeln!(out, "{} {}", gutter, bar)?;
+            labels.sort_by_key(|(label, _)| (label.span.line, label.span.column));
+            let mut previous: Option<usize> = None;
+            for (label, primary) in &labels {
+                let line = label.span.line;
+                if previous != Some(line) {
+                    if previous.is_some_and(|previous| line > previous + 1) {
+                        writeln!(out, "{}", self.paint(BLUE, "..."))?;
+                    }
+                    let text = source.line(line).unwrap_or_default();
+                    let number = format!("{:>width$}", line, width = width);
+                    writeln!(out, "{} {} {}", self.paint(BLUE, &number), bar, text)?;
+                    previous = Some(line);
+                }
+                let (marker, marker_style) = if *primary { ('^', style) } else { ('-', BLUE) };
+                let underline = format!(
+                    "{}{} {}",
+                    " ".repeat(label.span.column - 1),
+                    marker.to_string().repeat(label.span.length.max(1)),
+                    label.message
+                );
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    gutter,
+                    bar,
+                    self.paint(marker_style, underline.trim_end())
+                )?;
+            }
+        }
+
+        if !diagnostic.notes.is_empty() || diagnostic.help.is_some() {
+            writeln!(out, "{} {}", gutter, bar)?;
+        }
+        for note in &diagnostic.notes {
+            writeln!(out, "{} {} note: {}", gutter, self.paint(BLUE, "="), note)?;
+        }
+        if let Some(help) = &diagnostic.help {
+            writeln!(out, "{} {} help: {}", gutter, self.paint(BLUE, "="), help)?;
+        }
+        Ok(())
+    }
+
+    // The closing line, e.g. "2 errors and 1 warning emitted"
+    pub fn summary(&self, errors: usize, warnings: usize) -> String {
+        let count = |count: usize, noun: &str| {
+            format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+        };
+        let (style, text) = match (errors, warnings) {
+            (0, warnings) => (YELLOW, count(warnings, "warning")),
+            (errors, 0) => (RED, count(errors, "error")),
+            (errors, warnings) => (
+                RED,
+                format!(
+                    "{} and {}",
+                    count(errors, "error"),
+                    count(warnings, "warning")
+                ),
+            ),
+        };
+        format!("{} emitted", self.paint(style, &text))
+    }
+}
@@ -0,0 +1,151 @@
+use crate::diagnostics::Severity;
+
+// A check the analyzer reports; the id is stable and used in suppressions and reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    pub help: &'static str,
+}
+
+const fn rule(
+    id: &'static str,
+    severity: Severity,
+    description: &'static str,
+    help: &'static str,
+) -> Rule {
+    Rule {
+        id,
+        severity,
+        description,
+        help,
+    }
+}
+
+pub const RULES: [Rule; 20] = [
+    rule(
+        "memory-leak",
+        Severity::Error,
+        "Heap memory is never freed",
+        "free the memory on every path, or give it an owner such as std::unique_ptr",
+    ),
+    rule(
+        "double-free",
+        Severity::Error,
+        "Memory is freed more than once",
+        "set the pointer to nullptr after freeing it, or use a smart pointer",
+    ),
+    rule(
+        "use-after-free",
+        Severity::Error,
+        "Memory is used after it has been freed",
+        "make sure no pointer to the memory is used after it is freed",
+    ),
+    rule(
+        "null-dereference",
+        Severity::Error,
+        "A pointer that is or may be null is dereferenced",
+        "compare the pointer against nullptr before using it",
+    ),
+    rule(
+        "uninitialized-read",
+        Severity::Error,
+        "A value is read before it has been initialized",
+        "initialize the object where it is declared",
+    ),
+    rule(
+        "mismatched-deallocation",
+        Severity::Error,
+        "Memory is released with a deallocator of another allocator family",
+        "release memory from new with delete, new[] with delete[] and malloc with free",
+    ),
+    rule(
+        "use-after-move",
+        Severity::Warning,
+        "An object is used after it has been moved from",
+        "assign a new value to the object or reset it before using it again",
+    ),
+    rule(
+        "invalidated-iterator",
+        Severity::Error,
+        "An iterator or reference into a container is used after the container invalidated it",
+        "obtain the iterator or reference again after modifying the container",
+    ),
+    rule(
+        "out-of-bounds",
+        Severity::Error,
+        "An array or buffer is accessed outside its bounds",
+        "check the index against the size of the array before the access",
+    ),
+    rule(
+        "dangling-reference",
+        Severity::Error,
+        "A pointer or reference to a local variable outlives the variable",
+        "return or store a copy, or allocate the object with a longer lifetime",
+    ),
+    rule(
+        "lifetime-mismatch",
+        Severity::Error,
+        "A value does not live as long as its lifetime annotation requires",
+        "",
+    ),
+    rule(
+        "borrow-conflict",
+        Severity::Error,
+        "An object is modified or moved while it is borrowed",
+        "",
+    ),
+    rule(
+        "argument-count",
+        Severity::Error,
+        "A function is called with the wrong number of arguments",
+        "",
+    ),
+    rule(
+        "non-pointer-argument",
+        Severity::Error,
+        "A non-pointer value is passed for a pointer parameter",
+        "",
+    ),
+    rule(
+        "rule-of-three",
+        Severity::Warning,
+        "An owning class lacks a destructor, copy constructor or copy assignment operator",
+        "define or delete the missing special member functions",
+    ),
+    rule(
+        "rule-of-five",
+        Severity::Warning,
+        "A class with move operations does not define all five special member functions",
+        "define or delete the missing special member functions",
+    ),
+    rule(
+        "non-virtual-destructor",
+        Severity::Warning,
+        "A polymorphic base class has a non-virtual destructor",
+        "declare the destructor virtual",
+    ),
+    rule(
+        "self-assignment",
+        Severity::Warning,
+        "An assignment operator frees members before checking for self-assignment",
+        "return early when `this == &other`, or use copy-and-swap",
+    ),
+    rule(
+        "unused-suppression",
+        Severity::Warning,
+        "A suppression comment silences no diagnostic",
+        "remove the comment, or correct its rule ids",
+    ),
+    rule(
+        "invalid-suppression",
+        Severity::Warning,
+        "A suppression comment is malformed, names an unknown rule or lacks a required reason",
+        "write it as `// scpp-ignore[rule-id]: reason`",
+    ),
+];
+
+pub fn find(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
@@ -0,0 +1,180 @@
+use crate::diagnostics::fingerprint::fingerprint;
+use crate::diagnostics::rules::{self, RULES};
+use crate::diagnostics::{Diagnostic, Severity, SourceFile, Span};
+use crate::json::Json;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const TOOL_NAME: &str = "safecpp";
+// Key of the fingerprint in `partialFingerprints`; bump it when the fingerprint changes
+const FINGERPRINT_KEY: &str = "safecpp/v1";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    }
+}
+
+fn message(text: &str) -> Json {
+    Json::object([("text", Json::from(text))])
+}
+
+// "use-after-free" becomes "UseAfterFree"
+fn rule_name(id: &str) -> String {
+    id.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn rule_descriptor(rule: &rules::Rule) -> Json {
+    let mut members = vec![
+        ("id", Json::from(rule.id)),
+        ("name", Json::from(rule_name(rule.id))),
+        ("shortDescription", message(rule.description)),
+        (
+            "defaultConfiguration",
+            Json::object([("level", Json::from(level(rule.severity)))]),
+        ),
+    ];
+    if !rule.help.is_empty() {
+        members.push(("help", message(rule.help)));
+    }
+    Json::object(members)
+}
+
+// The file, and the region in it when the span is known. Consumers such as code
+// scanning drop results whose location does not name a file
+fn physical_location(source: &SourceFile, span: Option<Span>) -> Json {
+    let mut members = vec![(
+        "artifactLocation",
+        Json::object([
+            ("uri", Json::from(source.name.replace('\\', "/"))),
+            ("index", Json::from(0)),
+        ]),
+    )];
+    if let Some(span) = span {
+        members.push((
+            "region",
+            Json::object([
+                ("startLine", Json::from(span.line)),
+                ("startColumn", Json::from(span.column)),
+                ("endColumn", Json::from(span.column + span.length)),
+            ]),
+        ));
+    }
+    Json::object(members)
+}
+
+fn location(source: &SourceFile, diagnostic: &Diagnostic, span: Option<Span>) -> Json {
+    let mut members = vec![("physicalLocation", physical_location(source, span))];
+    if let Some(function) = &diagnostic.function {
+        members.push((
+            "logicalLocations",
+            Json::Array(vec![Json::object([
+                ("name", Json::from(function.as_str())),
+                ("kind", Json::from("function")),
+            ])]),
+        ));
+    }
+    Json::object(members)
+}
+
+// The branches leading to a path-sensitive finding, in order
+fn code_flow(source: &SourceFile, diagnostic: &Diagnostic) -> Json {
+    let function = diagnostic.function.as_deref();
+    let steps = diagnostic.path.iter().map(|step| {
+        let span = source.find(function, &step.code);
+        let mut location = location(source, diagnostic, span);
+        if let Json::Object(members) = &mut location {
+            members.push(("message".to_string(), message(&step.message)));
+        }
+        Json::object([("location", location)])
+    });
+    let thread_flow = Json::object([("locations", Json::Array(steps.collect()))]);
+    Json::object([("threadFlows", Json::Array(vec![thread_flow]))])
+}
+
+fn result(source: &SourceFile, diagnostic: &Diagnostic) -> Json {
+    let mut members = vec![("ruleId", Json::from(diagnostic.rule.as_str()))];
+    if let Some(index) = RULES.iter().position(|rule| rule.id == diagnostic.rule) {
+        members.push(("ruleIndex", Json::from(index)));
+    }
+    let mut text = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        text.push_str(&format!("\nnote: {}", note));
+    }
+    members.push(("level", Json::from(level(diagnostic.severity))));
+    members.push(("message", message(&text)));
+    let primary = diagnostic.primary.as_ref().map(|label| label.span);
+    members.push((
+        "locations",
+        Json::Array(vec![location(source, diagnostic, primary)]),
+    ));
+    if !diagnostic.secondary.is_empty() {
+        let related = diagnostic.secondary.iter().enumerate().map(|(id, label)| {
+            Json::object([
+                ("id", Json::from(id)),
+                (
+                    "physicalLocation",
+                    physical_location(source, Some(label.span)),
+                ),
+                ("message", message(&label.message)),
+            ])
+        });
+        members.push(("relatedLocations", Json::Array(related.collect())));
+    }
+    if !diagnostic.path.is_empty() {
+        members.push((
+            "codeFlows",
+            Json::Array(vec![code_flow(source, diagnostic)]),
+        ));
+    }
+    members.push((
+        "partialFingerprints",
+        Json::object([(FINGERPRINT_KEY, Json::from(fingerprint(diagnostic, source)))]),
+    ));
+    Json::object(members)
+}
+
+// A SARIF log with one run over `source`. Spans should already be located
+pub fn sarif_log(source: &SourceFile, diagnostics: &[Diagnostic]) -> Json {
+    let driver = Json::object([
+        ("name", Json::from(TOOL_NAME)),
+        ("version", Json::from(env!("CARGO_PKG_VERSION"))),
+        (
+            "rules",
+            Json::Array(RULES.iter().map(rule_descriptor).collect()),
+        ),
+    ]);
+    let artifact = Json::object([(
+        "location",
+        Json::object([("uri", Json::from(source.name.replace('\\', "/")))]),
+    )]);
+    let run = Json::object([
+        ("tool", Json::object([("driver", driver)])),
+        ("artifacts", Json::Array(vec![artifact])),
+        ("columnKind", Json::from("unicodeCodePoints")),
+        (
+            "results",
+            Json::Array(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| result(source, diagnostic))
+                    .collect(),
+            ),
+        ),
+    ]);
+    Json::object([
+        ("$schema", Json::from(SARIF_SCHEMA)),
+        ("version", Json::from(SARIF_VERSION)),
+        ("runs", Json::Array(vec![run])),
+    ])
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use crate::diagnostics::source::subject;
+use crate::diagnostics::{Diagnostic, Label, Severity, Span};
+use crate::parser::cpp_parser::Location;
+
+// How many diagnostics a sink keeps; None means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    pub max_per_file: Option<usize>,
+    pub max_per_rule: Option<usize>,
+}
+
+// Rules that name one problem from two sides, e.g. a dereference after a move is also
+// a dereference of a null unique_ptr
+const SAME_PROBLEM: &[(&str, &str)] = &[("use-after-move", "null-dereference")];
+
+// Whether two diagnostics of the rules above are about the same variable at the same
+// statement, so only the first one is worth reporting
+fn same_problem(kept: &Diagnostic, new: &Diagnostic) -> bool {
+    let rules = (kept.rule.as_str(), new.rule.as_str());
+    let related = SAME_PROBLEM
+        .iter()
+        .any(|&(a, b)| rules == (a, b) || rules == (b, a));
+    let span = |diagnostic: &Diagnostic| diagnostic.primary.as_ref().map(|label| label.span);
+    related
+        && kept.function == new.function
+        && span(kept).is_some()
+        && span(kept) == span(new)
+        && subject(&kept.message).is_some()
+        && subject(&kept.message) == subject(&new.message)
+}
+
+// Collects the diagnostics of one file. Identical diagnostics, e.g. from a loop body
+// analyzed twice, are kept once, and so is a problem two analyzers report under
+// different rules
+#[derive(Debug, Default)]
+pub struct DiagnosticSink {
+    limits: Limits,
+    diagnostics: Vec<Diagnostic>,
+    per_rule: HashMap<String, usize>,
+    dropped: usize,
+}
+
+impl DiagnosticSink {
+    pub fn new() -> Self {
+        DiagnosticSink::default()
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        DiagnosticSink {
+            limits,
+            ..DiagnosticSink::default()
+        }
+    }
+
+    // Returns false if the diagnostic is a duplicate or over a limit
+    pub fn push(&mut self, diagnostic: Diagnostic) -> bool {
+        if self.diagnostics.contains(&diagnostic) {
+            return false;
+        }
+        // The kept diagnostic is as severe as the worse of the two
+        let same = self
+            .diagnostics
+            .iter_mut()
+            .find(|kept| same_problem(kept, &diagnostic));
+        if let Some(kept) = same {
+            kept.severity = kept.severity.max(diagnostic.severity);
+            return false;
+        }
+        let for_rule = self.per_rule.get(&diagnostic.rule).copied().unwrap_or(0);
+        let over_file = self
+            .limits
+            .max_per_file
+            .is_some_and(|max| self.diagnostics.len() >= max);
+        let over_rule = self.limits.max_per_rule.is_some_and(|max| for_rule >= max);
+        if over_file || over_rule {
+            self.dropped += 1;
+            return false;
+        }
+        self.per_rule.insert(diagnostic.rule.clone(), for_rule + 1);
+        self.diagnostics.push(diagnostic);
+        true
+    }
+
+    pub fn extend<I>(&mut self, diagnostics: I)
+    where
+        I: IntoIterator,
+        I::Item: Into<Diagnostic>,
+    {
+        for diagnostic in diagnostics {
+            self.push(diagnostic.into());
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    // Diagnostics left out because a limit was reached
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.limits
+            .max_per_file
+            .is_some_and(|max| self.diagnostics.len() >= max)
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+// Where a problem was reported: the statement it is about and the statements that
+// led to it, e.g. where the memory was freed
+#[derive(Debug, Clone, Default)]
+struct Sites {
+    primary: Option<Location>,
+    related: Vec<(Location, String)>,
+}
+
+impl Sites {
+    // Spans of length 0 only say which statement is meant; `SourceFile::locate`
+    // narrows them down to the name the message is about
+    fn apply(self, mut diagnostic: Diagnostic) -> Diagnostic {
+        let label = |location: Location, message: String| Label {
+            span: Span::new(location.line, location.column, 0),
+            message,
+        };
+        if let Some(primary) = self.primary {
+            diagnostic.primary = Some(label(primary, String::new()));
+        }
+        diagnostic.secondary.extend(
+            self.related
+                .into_iter()
+                .map(|(location, message)| label(location, message)),
+        );
+        diagnostic
+    }
+}
+
+// How an analyzer reports a problem: by default the first one aborts the analysis
+// with `Err`; while collecting, each is recorded and the analysis carries on.
+// Each problem is kept with the function and statement the analyzer was in when it
+// was reported
+#[derive(Debug)]
+pub struct Reporter<E> {
+    collected: Option<Vec<(E, Option<String>, Sites)>>,
+    function: Option<String>,
+    location: Option<Location>,
+    // Sites of the problem that ended the analysis, when not collecting
+    stopped_at: Option<Sites>,
+}
+
+impl<E> Default for Reporter<E> {
+    fn default() -> Self {
+        Reporter {
+            collected: None,
+            function: None,
+            location: None,
+            stopped_at: None,
+        }
+    }
+}
+
+impl<E> Reporter<E> {
+    pub fn collecting() -> Self {
+        Reporter {
+            collected: Some(Vec::new()),
+            ..Reporter::default()
+        }
+    }
+
+    // Problems reported from now on belong to `function`
+    pub fn enter_function(&mut self, function: &str) {
+        self.function = Some(function.to_string());
+        self.location = None;
+    }
+
+    // Problems reported from now on belong to no function, e.g. a global initializer
+    pub fn leave_function(&mut self) {
+        self.function = None;
+        self.location = None;
+    }
+
+    // Problems reported from now on are about the statement at `location`. Returns the
+    // enclosing statement, for `leave_statement`
+    pub fn enter_statement(&mut self, location: Location) -> Option<Location> {
+        self.location.replace(location)
+    }
+
+    pub fn leave_statement(&mut self, enclosing: Option<Location>) {
+        self.location = enclosing;
+    }
+
+    // The statement being analyzed, if the AST says where it is
+    pub fn location(&self) -> Option<Location> {
+        self.location
+    }
+
+    // Reports a problem with the statement being analyzed
+    pub fn report(&mut self, error: E) -> Result<(), E> {
+        self.report_at(error, self.location, &[])
+    }
+
+    // Reports a problem with the statement at `primary`, e.g. the allocation that
+    // leaks, labelling the `related` statements that led to it
+    pub fn report_at(
+        &mut self,
+        error: E,
+        primary: Option<Location>,
+        related: &[(Option<Location>, &str)],
+    ) -> Result<(), E> {
+        let sites = Sites {
+            primary,
+            related: related
+                .iter()
+                .filter_map(|(location, message)| Some(((*location)?, message.to_string())))
+                .collect(),
+        };
+        match &mut self.collected {
+            Some(errors) => {
+                errors.push((error, self.function.clone(), sites));
+                Ok(())
+            }
+            None => {
+                self.stopped_at = Some(sites);
+                Err(error)
+            }
+        }
+    }
+
+    // Everything recorded, plus the error the analysis ended with, if any.
+    // The reporter stops at the first error again afterwards
+    pub fn finish(&mut self, result: Result<(), E>) -> Vec<Diagnostic>
+    where
+        E: Into<Diagnostic>,
+    {
+        let mut errors = self.collected.take().unwrap_or_default();
+        if let Err(error) = result {
+            let sites = self.stopped_at.take().unwrap_or(Sites {
+                primary: self.location,
+                related: Vec::new(),
+            });
+            errors.push((error, self.function.clone(), sites));
+        }
+        errors
+            .into_iter()
+            .map(|(error, function, sites)| {
+                let diagnostic = match function {
+                    Some(function) => error.into().in_function(&function),
+                    None => error.into(),
+                };
+                sites.apply(diagnostic)
+            })
+            .collect()
+    }
+}
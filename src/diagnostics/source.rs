@@ -0,0 +1,321 @@
+use std::ops::Range;
+
+use crate::diagnostics::{Diagnostic, Label, Span};
+
+// How a mention of a variable in the source relates to the problem
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mention {
+    Allocated,
+    Freed,
+    Moved,
+    Used,
+}
+
+// A mention of a variable, as a byte range into the source
+struct Occurrence {
+    range: Range<usize>,
+    mention: Mention,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// The first quoted name in a message, e.g. `p` in "Double free: 'p' is passed to 'f'";
+// messages without quotes name the variable last, as in "... on variable: p"
+pub(crate) fn subject(message: &str) -> Option<&str> {
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| is_identifier_char(c) || matches!(c, '.' | ':' | '-' | '>'))
+    };
+    let quoted = message
+        .split('\'')
+        .skip(1)
+        .step_by(2)
+        .find(|name| is_name(name));
+    quoted.or_else(|| {
+        let last = message.rsplit(' ').next()?;
+        is_name(last).then_some(last)
+    })
+}
+
+// The source text of one file, used to find where a diagnostic points. The analyzers
+// only know names, so spans are recovered from where those names are mentioned
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: &str, text: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        SourceFile {
+            name: name.to_string(),
+            text: text.to_string(),
+            line_starts,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    // Text of a 1-based line, without its line break
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |next| next - 1);
+        Some(self.text[start..end].trim_end_matches('\r'))
+    }
+
+    pub fn span(&self, range: Range<usize>) -> Span {
+        let line = self
+            .line_starts
+            .partition_point(|start| *start <= range.start);
+        let start = self.line_starts[line - 1];
+        let column = self.text[start..range.start].chars().count() + 1;
+        let length = self.text[range].chars().count();
+        Span::new(line, column, length)
+    }
+
+    // Byte range of the body of `function`, from its opening to its closing brace
+    fn function_body(&self, function: &str) -> Option<Range<usize>> {
+        let text = self.text.as_str();
+        for (start, _) in text.match_indices(function) {
+            let before = text[..start].chars().next_back();
+            let rest = text[start + function.len()..].trim_start();
+            if before.is_some_and(|c| is_identifier_char(c) || c == ':') || !rest.starts_with('(') {
+                continue;
+            }
+            // A definition has its body before the next ';'
+            let open = match text[start..].find(['{', ';']) {
+                Some(offset) if text[start + offset..].starts_with('{') => start + offset,
+                _ => continue,
+            };
+            let mut depth = 0;
+            for (offset, c) in text[open..].char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(open..open + offset + 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return Some(open..text.len());
+        }
+        None
+    }
+
+    fn occurrences(&self, name: &str, region: Range<usize>) -> Vec<Occurrence> {
+        let text = &self.text[region.clone()];
+        let mut occurrences = Vec::new();
+        for (offset, _) in text.match_indices(name) {
+            let start = region.start + offset;
+            let end = start + name.len();
+            let before = &self.text[..start];
+            let after = &self.text[end..];
+            let bounded =
+                !before.ends_with(is_identifier_char) && !after.starts_with(is_identifier_char);
+            let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+            if !bounded || before[line_start..].contains("//") {
+                continue;
+            }
+            let before = before.trim_end();
+            let after = after.trim_start();
+            let mention = if ["delete", "delete[]", "free("]
+                .iter()
+                .any(|deallocator| before.ends_with(deallocator))
+            {
+                Mention::Freed
+            } else if before.ends_with("std::move(") {
+                Mention::Moved
+            } else if after.starts_with('=') && !after.starts_with("==") {
+                let value = after[1..].trim_start();
+                if value.starts_with("new ")
+                    || ["malloc(", "calloc(", "realloc(", "strdup("]
+                        .iter()
+                        .any(|allocator| value.starts_with(allocator))
+                {
+                    Mention::Allocated
+                } else {
+                    Mention::Used
+                }
+            } else {
+                Mention::Used
+            };
+            occurrences.push(Occurrence {
+                range: start..end,
+                mention,
+            });
+        }
+        occurrences
+    }
+
+    // Where `code` first appears in `function`, or anywhere when the function is unknown
+    pub fn find(&self, function: Option<&str>, code: &str) -> Option<Span> {
+        let region = self.region(function);
+        let offset = self.text[region.clone()].find(code)?;
+        let start = region.start + offset;
+        Some(self.span(start..start + code.len()))
+    }
+
+    // First and last line of the body of `function`
+    pub fn function_lines(&self, function: &str) -> Option<(usize, usize)> {
+        let body = self.function_body(function)?;
+        let last = self.span(body.end.saturating_sub(1)..body.end).line;
+        Some((self.span(body.start..body.start).line, last))
+    }
+
+    fn region(&self, function: Option<&str>) -> Range<usize> {
+        function
+            .and_then(|function| self.function_body(function))
+            .unwrap_or(0..self.text.len())
+    }
+
+    // Byte offset of a 1-based line and character column
+    fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let text = self.line(line)?;
+        let within = match text.char_indices().nth(column.checked_sub(1)?) {
+            Some((index, _)) => index,
+            None => return None,
+        };
+        Some(start + within)
+    }
+
+    // Narrows a label that only knows where its statement starts to the mention of
+    // `name` in that statement, preferring the kind of mention the label describes,
+    // or else to the statement itself
+    fn narrow(&self, label: Label, name: Option<&str>) -> Label {
+        if label.span.length > 0 {
+            return label;
+        }
+        let start = match self.offset(label.span.line, label.span.column) {
+            Some(start) => start,
+            None => return label,
+        };
+        let rest = &self.text[start..];
+        let line_end = rest.find('\n').unwrap_or(rest.len());
+        let end = start + rest[..line_end].find(';').unwrap_or(line_end);
+        let wanted = match label.message.as_str() {
+            "allocated here" => Some(Mention::Allocated),
+            "freed here" | "first freed here" | "freed again here" => Some(Mention::Freed),
+            "moved here" => Some(Mention::Moved),
+            _ => None,
+        };
+        let occurrences = name.map_or_else(Vec::new, |name| self.occurrences(name, start..end));
+        let mentioned = occurrences
+            .iter()
+            .find(|o| Some(o.mention) == wanted)
+            .or(occurrences.first());
+        let range = match mentioned {
+            Some(occurrence) => occurrence.range.clone(),
+            None => start..start + self.text[start..end].trim_end().len(),
+        };
+        Label {
+            span: self.span(range),
+            message: label.message,
+        }
+    }
+
+    // Fills in the spans of a diagnostic. Analyzers that know the statement a problem
+    // is at give its start; the span is narrowed to the variable the message names.
+    // Otherwise the spans are guessed from the mentions of that variable inside the
+    // function the problem was found in
+    pub fn locate(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        if let Some(mut primary) = diagnostic.primary.take() {
+            let name = subject(&diagnostic.message);
+            if primary.message.is_empty() {
+                let message = match diagnostic.rule.as_str() {
+                    "memory-leak" => "allocated here",
+                    "double-free" => "freed again here",
+                    "use-after-free" | "use-after-move" => "used here",
+                    _ => "",
+                };
+                primary.message = message.to_string();
+            }
+            diagnostic.primary = Some(self.narrow(primary, name));
+            diagnostic.secondary = std::mem::take(&mut diagnostic.secondary)
+                .into_iter()
+                .map(|label| self.narrow(label, name))
+                .collect();
+            return diagnostic;
+        }
+        let region = self.region(diagnostic.function.as_deref());
+        let name = match subject(&diagnostic.message) {
+            Some(name) => name.to_string(),
+            None => return diagnostic,
+        };
+        let occurrences = self.occurrences(&name, region);
+        let first = |mention: Mention| occurrences.iter().find(|o| o.mention == mention);
+        let label = |occurrence: &Occurrence, message: &str| Label {
+            span: self.span(occurrence.range.clone()),
+            message: message.to_string(),
+        };
+
+        let freed = first(Mention::Freed);
+        let moved = first(Mention::Moved);
+        let (primary, secondary) = match diagnostic.rule.as_str() {
+            "memory-leak" => (
+                first(Mention::Allocated).map(|o| label(o, "allocated here")),
+                None,
+            ),
+            "double-free" => {
+                let again = occurrences
+                    .iter()
+                    .filter(|o| o.mention == Mention::Freed)
+                    .nth(1);
+                (
+                    again.map(|o| label(o, "freed again here")),
+                    freed.map(|o| label(o, "first freed here")),
+                )
+            }
+            "use-after-free" => {
+                let used = freed.and_then(|freed| {
+                    occurrences
+                        .iter()
+                        .find(|o| o.range.start > freed.range.start && o.mention == Mention::Used)
+                });
+                (
+                    used.map(|o| label(o, "used here")),
+                    freed.map(|o| label(o, "freed here")),
+                )
+            }
+            "use-after-move" => {
+                let used = moved.and_then(|moved| {
+                    occurrences
+                        .iter()
+                        .find(|o| o.range.start > moved.range.start)
+                });
+                (
+                    used.map(|o| label(o, "used here")),
+                    moved.map(|o| label(o, "moved here")),
+                )
+            }
+            _ => (occurrences.first().map(|o| label(o, "")), None),
+        };
+        // Without the use itself, the secondary location is the best there is
+        let (primary, secondary) = match (primary, secondary) {
+            (None, Some(secondary)) => (Some(secondary), None),
+            pair => pair,
+        };
+        diagnostic.primary = primary;
+        diagnostic.secondary.extend(secondary);
+        if diagnostic.rule == "use-after-free" {
+            let allocated = first(Mention::Allocated).map(|o| label(o, "allocated here"));
+            diagnostic.secondary.extend(allocated);
+        }
+        diagnostic
+    }
+}
@@ -0,0 +1,255 @@
+// Comments that silence diagnostics:
+//
+//     // scpp-ignore[rule-id, ...]: reason      this line and the next
+//     // scpp-ignore-file[rule-id, ...]: reason the whole file
+//     // scpp-ignore-begin[rule-id, ...]: reason up to the matching scpp-ignore-end
+//     // scpp-ignore-end
+//
+// Without a rule list every rule is silenced. docs/suppressions.md has the details
+use crate::diagnostics::{rules, Diagnostic, Span};
+use crate::parser::cpp_lexer::Comment;
+
+const MARKER: &str = "scpp-ignore";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    // The line of the comment and the one after it
+    Line(usize),
+    File,
+    // First and last line, inclusive
+    Region(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Directive {
+    Ignore,
+    File,
+    Begin,
+    End,
+}
+
+// A suppression comment as written
+#[derive(Debug, Clone, PartialEq)]
+struct Parsed {
+    directive: Directive,
+    // Empty means every rule
+    rules: Vec<String>,
+    reason: Option<String>,
+}
+
+// The text between the comment delimiters
+fn body(comment: &str) -> &str {
+    let body = match comment.strip_prefix("/*") {
+        Some(block) => block.strip_suffix("*/").unwrap_or(block),
+        None => comment.trim_start_matches('/'),
+    };
+    body.trim()
+}
+
+// None when the comment is not a suppression at all; Err when it is a malformed one
+fn parse(comment: &str) -> Option<Result<Parsed, String>> {
+    let rest = body(comment).strip_prefix(MARKER)?;
+    let (directive, rest) = [
+        ("-file", Directive::File),
+        ("-begin", Directive::Begin),
+        ("-end", Directive::End),
+        ("", Directive::Ignore),
+    ]
+    .into_iter()
+    .find_map(|(suffix, directive)| Some((directive, rest.strip_prefix(suffix)?)))?;
+    // "scpp-ignored" and the like are ordinary words
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+
+    let mut rest = rest.trim_start();
+    let mut rules = Vec::new();
+    if let Some(list) = rest.strip_prefix('[') {
+        let Some(close) = list.find(']') else {
+            return Some(Err("the rule list is missing its closing ']'".to_string()));
+        };
+        rules = list[..close]
+            .split(',')
+            .map(|rule| rule.trim().to_string())
+            .collect();
+        if rules.iter().any(String::is_empty) {
+            return Some(Err("the rule list has an empty entry".to_string()));
+        }
+        rest = list[close + 1..].trim_start();
+    }
+    let reason = match rest.strip_prefix(':') {
+        Some(reason) => Some(reason.trim()).filter(|reason| !reason.is_empty()),
+        None if rest.is_empty() => None,
+        None => {
+            return Some(Err(format!(
+                "expected ':' and a reason after '{}', found '{}'",
+                MARKER, rest
+            )))
+        }
+    };
+    Some(Ok(Parsed {
+        directive,
+        rules,
+        reason: reason.map(str::to_string),
+    }))
+}
+
+fn comment_span(comment: &Comment) -> Span {
+    let first_line = comment.text.lines().next().unwrap_or_default();
+    Span::new(comment.line, comment.column, first_line.chars().count())
+}
+
+fn problem(rule: &str, comment: &Comment, message: String) -> Diagnostic {
+    Diagnostic::new(rule, message).with_primary(comment_span(comment), "")
+}
+
+#[derive(Debug)]
+struct Suppression {
+    comment: Comment,
+    rules: Vec<String>,
+    scope: Scope,
+    used: bool,
+}
+
+impl Suppression {
+    fn covers(&self, diagnostic: &Diagnostic) -> bool {
+        if !self.rules.is_empty() && !self.rules.contains(&diagnostic.rule) {
+            return false;
+        }
+        let line = diagnostic.primary.as_ref().map(|primary| primary.span.line);
+        match (self.scope, line) {
+            (Scope::File, _) => true,
+            (Scope::Line(comment), Some(line)) => line == comment || line == comment + 1,
+            (Scope::Region(first, last), Some(line)) => (first..=last).contains(&line),
+            // Only a file-wide suppression can silence a diagnostic without a location
+            (_, None) => false,
+        }
+    }
+}
+
+// The suppressions of one file, and the problems found with them
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    suppressions: Vec<Suppression>,
+    problems: Vec<Diagnostic>,
+}
+
+impl Suppressions {
+    // With `require_reason`, a suppression without a reason is reported and has no effect
+    pub fn new(comments: &[Comment], require_reason: bool) -> Self {
+        let mut suppressions = Suppressions::default();
+        // Begin markers waiting for their end marker
+        let mut open: Vec<(Comment, Vec<String>)> = Vec::new();
+        for comment in comments {
+            let parsed = match parse(&comment.text) {
+                None => continue,
+                Some(Ok(parsed)) => parsed,
+                Some(Err(problem)) => {
+                    suppressions.report(
+                        comment,
+                        format!("Malformed suppression comment: {}", problem),
+                    );
+                    continue;
+                }
+            };
+            for rule in &parsed.rules {
+                if rules::find(rule).is_none() {
+                    suppressions.report(
+                        comment,
+                        format!("Suppression names an unknown rule '{}'", rule),
+                    );
+                }
+            }
+            if parsed.directive != Directive::End && require_reason && parsed.reason.is_none() {
+                suppressions.report(
+                    comment,
+                    "Suppression has no reason; write it after a ':'".to_string(),
+                );
+                continue;
+            }
+            let scope = match parsed.directive {
+                // After code, the comment is about that code only
+                Directive::Ignore if comment.trailing => Scope::Region(comment.line, comment.line),
+                Directive::Ignore => Scope::Line(comment.line),
+                Directive::File => Scope::File,
+                Directive::Begin => {
+                    open.push((comment.clone(), parsed.rules));
+                    continue;
+                }
+                Directive::End => {
+                    match open.pop() {
+                        Some((begin, rules)) => suppressions.add(
+                            begin.clone(),
+                            rules,
+                            Scope::Region(begin.line, comment.line),
+                        ),
+                        None => suppressions.report(
+                            comment,
+                            format!("'{}-end' without a matching '{}-begin'", MARKER, MARKER),
+                        ),
+                    }
+                    continue;
+                }
+            };
+            suppressions.add(comment.clone(), parsed.rules, scope);
+        }
+        // An unclosed region runs to the end of the file
+        for (begin, rules) in open {
+            suppressions.report(
+                &begin,
+                format!("'{}-begin' without a matching '{}-end'", MARKER, MARKER),
+            );
+            suppressions.add(begin.clone(), rules, Scope::Region(begin.line, usize::MAX));
+        }
+        suppressions
+    }
+
+    fn add(&mut self, comment: Comment, rules: Vec<String>, scope: Scope) {
+        self.suppressions.push(Suppression {
+            comment,
+            rules,
+            scope,
+            used: false,
+        });
+    }
+
+    fn report(&mut self, comment: &Comment, message: String) {
+        self.problems
+            .push(problem("invalid-suppression", comment, message));
+    }
+
+    // Whether a suppression silences `diagnostic`; every suppression that does is
+    // marked as used. Spans should already be located
+    pub fn suppresses(&mut self, diagnostic: &Diagnostic) -> bool {
+        let mut suppressed = false;
+        for suppression in &mut self.suppressions {
+            if suppression.covers(diagnostic) {
+                suppression.used = true;
+                suppressed = true;
+            }
+        }
+        suppressed
+    }
+
+    // The problems with the suppressions, including those that silenced nothing
+    pub fn finish(self) -> Vec<Diagnostic> {
+        let mut problems = self.problems;
+        for suppression in self.suppressions.iter().filter(|s| !s.used) {
+            let message = match suppression.rules.as_slice() {
+                [] => "Unused suppression: no diagnostic is silenced by it".to_string(),
+                rules => format!(
+                    "Unused suppression: no '{}' diagnostic is silenced by it",
+                    rules.join("', '")
+                ),
+            };
+            problems.push(problem("unused-suppression", &suppression.comment, message));
+        }
+        problems.sort_by_key(|diagnostic| {
+            diagnostic
+                .primary
+                .as_ref()
+                .map(|primary| (primary.span.line, primary.span.column))
+        });
+        problems
+    }
+}
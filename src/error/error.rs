@@ -1,9 +1,11 @@
 use crate::analyzer::array_analyzer::ArrayError;
+use crate::analyzer::class_analyzer::ClassError;
 use crate::analyzer::memory_analyzer::MemoryError;
 use crate::analyzer::pointer_analyzer::PointerError;
 
 pub enum Error {
     Array(ArrayError),
+    Class(ClassError),
     Memory(MemoryError),
     Pointer(PointerError),
 }
@@ -14,6 +16,7 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Error::Array(array_error) => write!(f, "{}", array_error),
+            Error::Class(class_error) => write!(f, "{}", class_error),
             Error::Memory(memory_error) => write!(f, "{}", memory_error),
             Error::Pointer(pointer_error) => write!(f, "{}", pointer_error),
         }
@@ -24,6 +27,7 @@ impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Error::Array(array_error) => write!(f, "{:?}", array_error),
+            Error::Class(class_error) => write!(f, "{:?}", class_error),
             Error::Memory(memory_error) => write!(f, "{:?}", memory_error),
             Error::Pointer(pointer_error) => write!(f, "{:?}", pointer_error),
         }
@@ -36,6 +40,12 @@ impl From<ArrayError> for Error {
     }
 }
 
+impl From<ClassError> for Error {
+    fn from(error: ClassError) -> Self {
+        Error::Class(error)
+    }
+}
+
 impl From<MemoryError> for Error {
     fn from(error: MemoryError) -> Self {
         Error::Memory(error)
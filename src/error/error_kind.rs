@@ -1,5 +1,8 @@
+use std::str::FromStr;
+
 pub enum ErrorKind {
     ArrayErrorKind,
+    ClassErrorKind,
     MemoryErrorKind,
     PointerErrorKind,
 }
@@ -9,15 +12,22 @@ impl ErrorKind {
     pub fn description(&self) -> &str {
         match self {
             ErrorKind::ArrayErrorKind => "Array error",
+            ErrorKind::ClassErrorKind => "Class error",
             ErrorKind::MemoryErrorKind => "Memory error",
             ErrorKind::PointerErrorKind => "Pointer error",
         }
     }
 
+}
+
+impl FromStr for ErrorKind {
+    type Err = &'static str;
+
     // Converts a string to an ErrorKind, returning an error message if the string is not a valid error kind
-    pub fn from_str(s: &str) -> Result<Self, &str> {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "ArrayErrorKind" => Ok(ErrorKind::ArrayErrorKind),
+            "ClassErrorKind" => Ok(ErrorKind::ClassErrorKind),
             "MemoryErrorKind" => Ok(ErrorKind::MemoryErrorKind),
             "PointerErrorKind" => Ok(ErrorKind::PointerErrorKind),
             _ => Err("Invalid error kind"),
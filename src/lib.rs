@@ -1,3 +1,5 @@
 pub mod parser;
 pub mod analyzer;
-pub mod error;
\ No newline at end of file
+pub mod diagnostics;
+pub mod error;
+pub mod json;
\ No newline at end of file
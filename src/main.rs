@@ -1,30 +1,333 @@
+use safecpp::analyzer::borrow_checker::FunctionLifetimes;
+use safecpp::analyzer::{
+    ArrayAnalyzer, ClassAnalyzer, ContainerAnalyzer, MemoryAnalyzer, Models, MoveAnalyzer,
+    PointerAnalyzer, SmartPointerAnalyzer, SymbolicExecutor,
+};
+use safecpp::diagnostics::baseline::Baseline;
+use safecpp::diagnostics::export::{json_line, json_report};
+use safecpp::diagnostics::html::write_html_report;
+use safecpp::diagnostics::sarif::sarif_log;
+use safecpp::diagnostics::{
+    ColorChoice, Diagnostic, DiagnosticSink, Limits, Renderer, Severity, SourceFile, Suppressions,
+};
+use safecpp::parser::cpp_lexer::collect_comments;
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration};
 use std::env;
+use std::io::Write;
+use std::path::Path;
 use std::process;
-use safecpp::analyzer::Analyzer;
-use safecpp::parser::Parser;
-use safecpp::error::Result;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Sarif,
+    Json,
+    JsonLines,
+}
+
+impl Format {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Format::Text),
+            "sarif" => Some(Format::Sarif),
+            "json" => Some(Format::Json),
+            "jsonl" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+struct Options {
+    input: String,
+    // Models files, applied in order on top of the bundled ones
+    models: Vec<String>,
+    bundled_models: bool,
+    limits: Limits,
+    color: ColorChoice,
+    format: Format,
+    // Directory to write an HTML report to, alongside the chosen format
+    html_report: Option<String>,
+    // Reject suppression comments that do not say why
+    require_suppression_reason: bool,
+    // Findings recorded here are not reported
+    baseline: Option<String>,
+    // Record the findings here instead of reporting them
+    write_baseline: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Option<Options> {
+    let mut options = Options {
+        input: String::new(),
+        models: Vec::new(),
+        bundled_models: true,
+        limits: Limits::default(),
+        color: ColorChoice::Auto,
+        format: Format::Text,
+        html_report: None,
+        require_suppression_reason: false,
+        baseline: None,
+        write_baseline: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--models" => options.models.push(args.next()?.clone()),
+            "--no-bundled-models" => options.bundled_models = false,
+            "--max-errors" => options.limits.max_per_file = Some(args.next()?.parse().ok()?),
+            "--max-errors-per-rule" => {
+                options.limits.max_per_rule = Some(args.next()?.parse().ok()?)
+            }
+            "--color" => options.color = ColorChoice::parse(args.next()?)?,
+            arg if arg.starts_with("--color=") => {
+                options.color = ColorChoice::parse(&arg["--color=".len()..])?
+            }
+            "--format" => options.format = Format::parse(args.next()?)?,
+            arg if arg.starts_with("--format=") => {
+                options.format = Format::parse(&arg["--format=".len()..])?
+            }
+            "--html-report" => options.html_report = Some(args.next()?.clone()),
+            "--require-suppression-reason" => options.require_suppression_reason = true,
+            "--baseline" => options.baseline = Some(args.next()?.clone()),
+            "--write-baseline" => options.write_baseline = Some(args.next()?.clone()),
+            arg if arg.starts_with("--") => return None,
+            arg if options.input.is_empty() => options.input = arg.to_string(),
+            _ => return None,
+        }
+    }
+    (!options.input.is_empty()).then_some(options)
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} <input_file>", args[0]);
+    let options = match parse_args(&args[1..]) {
+        Some(options) => options,
+        None => {
+            eprintln!(
+                "Usage: {} [--models <file>]... [--no-bundled-models] [--max-errors <n>] \
+                 [--max-errors-per-rule <n>] [--color=auto|always|never] \
+                 [--format=text|sarif|json|jsonl] [--html-report <dir>] \
+                 [--require-suppression-reason] [--baseline <file>] \
+                 [--write-baseline <file>] <input_file>",
+                args[0]
+            );
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = run(&options) {
+        eprintln!("Error: {}", e);
         process::exit(1);
     }
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let mut models = if options.bundled_models {
+        Models::bundled()
+    } else {
+        Models::new()
+    };
+    for path in &options.models {
+        models.extend(Models::load(path)?);
+    }
 
-    if let Err(e) = run(&args[1]) {
-        eprintln!("Error: {}", e);
+    let input = std::fs::read_to_string(&options.input)?;
+    let ast = parse_cpp_code(&input)?;
+    // The analyzers know where statements start; the exact spans come from the source
+    // text. Suppressions and the baseline need those spans, and go before the limits so
+    // that only diagnostics that will be reported count towards them
+    let source = SourceFile::new(&options.input, &input);
+    let mut suppressions = Suppressions::new(
+        &collect_comments(&input),
+        options.require_suppression_reason,
+    );
+    let baseline = match &options.baseline {
+        Some(path) => Some(Baseline::load(path)?),
+        None => None,
+    };
+    // jsonl lines are written as each function is done, unless the findings are only
+    // recorded in a baseline
+    let streaming = options.format == Format::JsonLines && options.write_baseline.is_none();
+    let mut recorded = Vec::new();
+    let mut known = 0;
+    let mut sink = DiagnosticSink::with_limits(options.limits);
+    let mut stdout = std::io::stdout().lock();
+    let mut deliver = |mut findings: Vec<Diagnostic>| -> std::io::Result<()> {
+        if options.write_baseline.is_some() {
+            recorded.extend(findings);
+            return Ok(());
+        }
+        if let Some(baseline) = &baseline {
+            let (new, matched) = baseline.filter(&source, findings);
+            known += matched;
+            findings = new;
+        }
+        for diagnostic in findings {
+            let line = streaming.then(|| json_line(&source, &diagnostic));
+            let added = sink.push(diagnostic);
+            if let Some(line) = line.filter(|_| added) {
+                writeln!(stdout, "{}", line)?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    };
+    analyze(
+        ast,
+        &models,
+        FunctionLifetimes::collect(&input),
+        &mut |found| {
+            let findings = found
+                .into_iter()
+                .map(|diagnostic| source.locate(diagnostic))
+                .filter(|diagnostic| !suppressions.suppresses(diagnostic))
+                .collect();
+            deliver(findings)
+        },
+    )?;
+    deliver(suppressions.finish())?;
+    drop(stdout);
+
+    if let Some(path) = &options.write_baseline {
+        // Other files recorded in an existing baseline are kept
+        let mut baseline = if Path::new(path).exists() {
+            Baseline::load(path)?
+        } else {
+            Baseline::at(path)
+        };
+        baseline.record(&source, &recorded);
+        baseline.save(path)?;
+        eprintln!("Recorded {} findings in {}", recorded.len(), path);
+        return Ok(());
+    }
+    if let Some(path) = &options.baseline {
+        if known > 0 {
+            eprintln!(
+                "{} findings in the baseline {} were not reported",
+                known, path
+            );
+        }
+    }
+
+    let diagnostics = sink.diagnostics().to_vec();
+    if let Some(directory) = &options.html_report {
+        write_html_report(Path::new(directory), &source, &diagnostics)?;
+    }
+    match options.format {
+        Format::Text => print_text(options, &source, &diagnostics, sink.dropped()),
+        Format::Sarif => println!("{}", sarif_log(&source, &diagnostics).pretty()),
+        Format::Json => println!(
+            "{}",
+            json_report(&source, &diagnostics, sink.dropped()).pretty()
+        ),
+        // Already written
+        Format::JsonLines => {}
+    }
+    if sink.has_errors() {
         process::exit(1);
     }
+    Ok(())
 }
 
-fn run(file_path: &str) -> Result<()> {
-    let input = std::fs::read_to_string(file_path)?;
-    let mut parser = Parser::new(&input);
-    let ast = parser.parse()?;
+fn print_text(options: &Options, source: &SourceFile, diagnostics: &[Diagnostic], dropped: usize) {
+    let renderer = Renderer::new(options.color.enabled());
+    for diagnostic in diagnostics {
+        println!("{}", renderer.render(source, diagnostic));
+    }
+    if dropped > 0 {
+        println!("{} more diagnostics not shown\n", dropped);
+    }
+    if diagnostics.is_empty() {
+        println!("No memory issues detected.");
+        return;
+    }
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    println!(
+        "{}",
+        renderer.summary(count(Severity::Error), count(Severity::Warning))
+    );
+}
+
+// Runs the analyzers one function at a time and hands the findings of each function
+// to `report` as soon as it is done, so output can be streamed; findings about classes
+// come first
+fn analyze(
+    declarations: Vec<Declaration>,
+    models: &Models,
+    lifetimes: Vec<FunctionLifetimes>,
+    report: &mut dyn FnMut(Vec<Diagnostic>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut classes = DiagnosticSink::new();
+    ClassAnalyzer::new(&declarations).analyze_into(&mut classes);
+    report(classes.into_diagnostics())?;
+
+    // Summarizing every function is costly, so it is done once for the whole file
+    let mut memory_analyzer = MemoryAnalyzer::new(&declarations);
+    memory_analyzer.add_models(models);
+    let mut move_analyzer = MoveAnalyzer::new(&declarations);
+    let mut container_analyzer = ContainerAnalyzer::new(&declarations);
+    let mut smart_pointer_analyzer = SmartPointerAnalyzer::new(&declarations);
+    let mut symbolic_executor = SymbolicExecutor::new(&declarations);
+    let mut pointer_analyzer = PointerAnalyzer::new(declarations.clone());
+    pointer_analyzer.add_models(models);
+    for lifetimes in lifetimes {
+        pointer_analyzer.add_lifetime_annotations(lifetimes);
+    }
 
-    let mut analyzer = Analyzer::new();
-    analyzer.analyze(&ast)?;
+    for (index, declaration) in declarations.iter().enumerate() {
+        let (name, body) = match declaration {
+            Declaration::Function(name, _, body) => (name, body),
+            _ => continue,
+        };
+        let mut found = DiagnosticSink::new();
+        // These analyzers see only the body, so the function is added here
+        let mut in_body = DiagnosticSink::new();
+        let body = std::slice::from_ref(&**body);
+        memory_analyzer.analyze_into(body, &mut in_body);
 
-    println!("No memory issues detected.");
+        let mut array_analyzer = ArrayAnalyzer::new(body);
+        array_analyzer.add_models(models);
+        array_analyzer.analyze_into(&mut in_body);
+
+        for diagnostic in in_body.into_diagnostics() {
+            found.push(diagnostic.in_function(name));
+        }
+        move_analyzer.analyze_function_into(index, &mut found);
+        container_analyzer.analyze_function_into(index, &mut found);
+        smart_pointer_analyzer.analyze_function_into(index, &mut found);
+        pointer_analyzer.analyze_function_into(index, &mut found);
+
+        // The symbolic executor confirms findings along one feasible path. Its path goes
+        // on the diagnostic of the other analyzers with the same rule at the same
+        // statement, or on its own if there is none
+        let mut paths: Vec<Diagnostic> = symbolic_executor
+            .analyze_function_at(index)
+            .iter()
+            .cloned()
+            .map(Diagnostic::from)
+            .collect();
+
+        let mut sink = DiagnosticSink::new();
+        for mut diagnostic in found.into_diagnostics() {
+            let matching = paths.iter().position(|path| {
+                path.rule == diagnostic.rule
+                    && path.function == diagnostic.function
+                    && path.primary.as_ref().map(|label| label.span)
+                        == diagnostic.primary.as_ref().map(|label| label.span)
+            });
+            if let Some(index) = matching.filter(|_| diagnostic.path.is_empty()) {
+                let path = paths.remove(index);
+                diagnostic.notes.extend(path.notes);
+                diagnostic.path = path.path;
+            }
+            sink.push(diagnostic);
+        }
+        sink.extend(paths);
+        report(sink.into_diagnostics())?;
+    }
     Ok(())
 }
@@ -1,14 +1,15 @@
-use std::iter::Peekable;
-use std::str::Chars;
-use std::fmt;
-use std::error::Error;
 use crate::parser::cpp_parser::ParserError;
+use std::error::Error;
+use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Keyword(String),
     Identifier(String),
     Symbol(char),
+    // Operators of more than one character, e.g. `->`, `::` or `+=`
+    Operator(String),
+    // Character literals are integers, as in C++
     Integer(i64),
     Float(f64),
     StringLiteral(String),
@@ -16,6 +17,22 @@ pub enum Token {
     Newline,
 }
 
+// Longest first, so that `<<=` is not read as `<<` and `=`
+const OPERATORS: [&str; 23] = [
+    "<<=", ">>=", "...", "::", "->", "++", "--", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+];
+
+// A comment as written, delimiters included, and the 1-based position it starts at
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    // Whether something other than whitespace comes before it on its line
+    pub trailing: bool,
+}
+
 #[derive(Debug)]
 pub struct LexerError {
     details: String,
@@ -42,67 +59,205 @@ impl From<LexerError> for ParserError {
 }
 
 pub struct Lexer<'a> {
-    input: Peekable<Chars<'a>>,
+    input: &'a str,
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+    comments: Vec<Comment>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Lexer {
-            input: input.chars().peekable(),
+            input,
+            chars: input.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    // 1-based line of the next character
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // 1-based column of the next character, counted in characters
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    // The comments skipped so far
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
         }
+        Some(c)
     }
 
-    fn read_identifier(&mut self) -> Result<String, LexerError> {
-        let mut identifier = String::new();
-        while let Some(&c) = self.input.peek() {
+    fn read_identifier(&mut self, first: char) -> String {
+        let mut identifier = first.to_string();
+        while let Some(c) = self.peek() {
             if c.is_alphanumeric() || c == '_' {
                 identifier.push(c);
-                self.input.next();
+                self.advance();
             } else {
                 break;
             }
         }
-        Ok(identifier)
+        identifier
     }
 
-    fn read_number(&mut self) -> Result<String, LexerError> {
-        let mut number = String::new();
-        while let Some(&c) = self.input.peek() {
-            if c.is_digit(10) || c == '.' {
+    // Digits, digit separators, suffixes and exponents, e.g. `1'000`, `0x1Fu` or `1.5e-3f`
+    fn read_number(&mut self, first: char) -> String {
+        let mut number = first.to_string();
+        while let Some(c) = self.peek() {
+            let exponent_sign =
+                matches!(c, '+' | '-') && !number.starts_with("0x") && number.ends_with(['e', 'E']);
+            if c.is_alphanumeric() || c == '.' || c == '\'' || exponent_sign {
                 number.push(c);
-                self.input.next();
+                self.advance();
             } else {
                 break;
             }
         }
-        Ok(number)
+        number
+    }
+
+    fn number_token(number: &str) -> Result<Token, ParserError> {
+        let number = number.replace('\'', "").to_ascii_lowercase();
+        let invalid = |kind: &str| ParserError {
+            details: format!("Invalid {} '{}'", kind, number),
+        };
+        let integer = |digits: &str, radix: u32| {
+            let digits = digits.trim_end_matches(['u', 'l', 'z']);
+            i64::from_str_radix(digits, radix)
+                .or_else(|_| u64::from_str_radix(digits, radix).map(|value| value as i64))
+                .map(Token::Integer)
+                .map_err(|_| invalid("integer"))
+        };
+        if let Some(digits) = number.strip_prefix("0x") {
+            integer(digits, 16)
+        } else if let Some(digits) = number.strip_prefix("0b") {
+            integer(digits, 2)
+        } else if number.contains(['.', 'e']) {
+            number
+                .trim_end_matches(['f', 'l'])
+                .parse()
+                .map(Token::Float)
+                .map_err(|_| invalid("float"))
+        } else if number.len() > 1 && number.starts_with('0') {
+            integer(&number[1..], 8)
+        } else {
+            integer(&number, 10)
+        }
     }
 
+    fn read_escape(&mut self) -> Result<char, LexerError> {
+        let escaped = self.advance().ok_or_else(|| LexerError {
+            details: String::from("Incomplete escape sequence"),
+        })?;
+        Ok(match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c => c,
+        })
+    }
+
+    // The opening quote has been read already
     fn read_string_literal(&mut self) -> Result<String, LexerError> {
         let mut string_literal = String::new();
-        self.input.next(); // Skip opening quote
+        while let Some(c) = self.advance() {
+            match c {
+                '\\' => string_literal.push(self.read_escape()?),
+                '"' => return Ok(string_literal),
+                // Only raw strings span lines; the next line is lexed on its own
+                '\n' => break,
+                c => string_literal.push(c),
+            }
+        }
+        Err(LexerError {
+            details: String::from("Unterminated string literal"),
+        })
+    }
 
-        while let Some(c) = self.input.next() {
-            if c == '\\' {
-                if let Some(escaped_char) = self.input.next() {
-                    string_literal.push(escaped_char);
-                } else {
-                    return Err(LexerError {
-                        details: String::from("Incomplete escape sequence"),
-                    });
-                }
-            } else if c == '"' {
+    // `R"delimiter(...)delimiter"`, from the opening quote on
+    fn read_raw_string_literal(&mut self) -> Result<String, LexerError> {
+        self.advance();
+        let mut delimiter = String::new();
+        while let Some(c) = self.advance() {
+            if c == '(' {
                 break;
-            } else {
-                string_literal.push(c);
+            }
+            delimiter.push(c);
+        }
+        let end = format!("){}\"", delimiter);
+        let mut string_literal = String::new();
+        while let Some(c) = self.advance() {
+            string_literal.push(c);
+            if string_literal.ends_with(&end) {
+                string_literal.truncate(string_literal.len() - end.len());
+                return Ok(string_literal);
             }
         }
+        Err(LexerError {
+            details: String::from("Unterminated raw string literal"),
+        })
+    }
 
-        Ok(string_literal)
+    // The opening quote has been read already
+    fn read_char_literal(&mut self) -> Result<i64, LexerError> {
+        let value = match self.advance() {
+            Some('\\') => self.read_escape()?,
+            Some('\'') => {
+                return Err(LexerError {
+                    details: String::from("Empty character literal"),
+                })
+            }
+            Some(c) => c,
+            None => '\0',
+        };
+        // Multi-character literals such as 'ab' are rare; only the first is kept
+        while let Some(c) = self.advance() {
+            match c {
+                '\'' => return Ok(value as i64),
+                '\n' => break,
+                _ => {}
+            }
+        }
+        Err(LexerError {
+            details: String::from("Unterminated character literal"),
+        })
     }
 
     pub fn next_token(&mut self) -> Result<Option<Token>, ParserError> {
-        let next_char = match self.input.next() {
+        let (line, column) = (self.line, self.column);
+        let next_char = match self.advance() {
             Some(c) => c,
             None => return Ok(None),
         };
@@ -116,100 +271,205 @@ impl<'a> Lexer<'a> {
                 }
             }
             c if c.is_alphabetic() || c == '_' => {
-                let identifier = self.read_identifier()?;
-                if is_keyword(&identifier) {
-                    Token::Keyword(identifier)
-                } else {
-                    Token::Identifier(identifier)
-                }
-            }
-                c if c.is_digit(10) => {
-                    let number = self.read_number()?;
-                    if number.contains('.') {
-                        Token::Float(number.parse().map_err(|_| ParserError {
-                            details: String::from("Invalid float"),
-                        })?)
-                    } else {
-                        Token::Integer(number.parse().map_err(|_| ParserError {
-                            details: String::from("Invalid integer"),
-                        })?)
+                let identifier = self.read_identifier(c);
+                let prefix = identifier.strip_suffix('R').unwrap_or(&identifier);
+                let raw =
+                    identifier.ends_with('R') && matches!(prefix, "" | "L" | "u" | "U" | "u8");
+                match self.peek() {
+                    Some('"') if raw => Token::StringLiteral(self.read_raw_string_literal()?),
+                    Some('"') if matches!(identifier.as_str(), "L" | "u" | "U" | "u8") => {
+                        self.advance();
+                        Token::StringLiteral(self.read_string_literal()?)
                     }
+                    Some('\'') if matches!(identifier.as_str(), "L" | "u" | "U" | "u8") => {
+                        self.advance();
+                        Token::Integer(self.read_char_literal()?)
+                    }
+                    _ if is_keyword(&identifier) => Token::Keyword(identifier),
+                    _ => Token::Identifier(identifier),
                 }
-                '"' => {
-                    let string_literal = self.read_string_literal()?;
-                    Token::StringLiteral(string_literal)
-                }
-                '/' => {
-                    if let Some(&next_char) = self.input.peek() {
-                        if next_char == '/' || next_char == '*' {
-                            self.read_comment()?;
-                            return self.next_token(); // Skip the comment and get the next token
-                        } else {
-                            Token::Symbol(next_char)
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && self.peek().is_some_and(|next| next.is_ascii_digit())) =>
+            {
+                let number = self.read_number(c);
+                Lexer::number_token(&number)?
+            }
+            '"' => Token::StringLiteral(self.read_string_literal()?),
+            '\'' => Token::Integer(self.read_char_literal()?),
+            '/' if matches!(self.peek(), Some('/' | '*')) => {
+                self.read_comment(line, column)?;
+                return self.next_token(); // Skip the comment and get the next token
+            }
+            c => {
+                let operator = OPERATORS.iter().find(|operator| {
+                    operator
+                        .chars()
+                        .enumerate()
+                        .all(|(offset, expected)| match offset {
+                            0 => expected == c,
+                            offset => self.peek_at(offset - 1) == Some(expected),
+                        })
+                });
+                match operator {
+                    Some(operator) => {
+                        for _ in 1..operator.len() {
+                            self.advance();
                         }
-                    } else {
-                        Token::Symbol('/')
+                        Token::Operator(operator.to_string())
                     }
+                    None => Token::Symbol(c),
                 }
-                c => Token::Symbol(c),
-            };
-    
-            Ok(Some(token))
-        }
-    
-        fn read_comment(&mut self) -> Result<(), LexerError> {
-            let comment_start = self.input.next().unwrap(); // Skip the first '/'
-    
-            if let Some(comment_type) = self.input.next() {
-                match comment_type {
-                    '/' => {
-                        while let Some(c) = self.input.next() {
-                            if c == '\n' {
-                                break;
-                            }
-                        }
+            }
+        };
+
+        Ok(Some(token))
+    }
+
+    // The opening '/' has been read already
+    fn read_comment(&mut self, line: usize, column: usize) -> Result<(), LexerError> {
+        let trailing = self.chars[..self.position - 1]
+            .iter()
+            .rev()
+            .take_while(|c| **c != '\n')
+            .any(|c| !c.is_whitespace());
+        let mut text = String::from("/");
+        match self.advance() {
+            Some('/') => {
+                text.push('/');
+                while let Some(c) = self.peek() {
+                    if c == '\n' {
+                        break;
                     }
-                    '*' => {
-                        let mut last_char = '\0';
-                        while let Some(c) = self.input.next() {
-                            if last_char == '*' && c == '/' {
-                                break;
-                            }
-                            last_char = c;
-                        }
+                    text.push(c);
+                    self.advance();
+                }
+            }
+            Some('*') => {
+                text.push('*');
+                let mut last_char = '\0';
+                while let Some(c) = self.advance() {
+                    text.push(c);
+                    if last_char == '*' && c == '/' {
+                        break;
                     }
-                    _ => return Err(LexerError {
-                        details: String::from("Invalid comment start sequence"),
-                    }),
+                    last_char = c;
                 }
-            } else {
+            }
+            Some(_) => {
+                return Err(LexerError {
+                    details: String::from("Invalid comment start sequence"),
+                })
+            }
+            None => {
                 return Err(LexerError {
                     details: String::from("Unexpected end of input"),
-                });
+                })
             }
-    
-            Ok(())
         }
+
+        let text = text.trim_end_matches('\r').to_string();
+        self.comments.push(Comment {
+            line,
+            column,
+            text,
+            trailing,
+        });
+        Ok(())
     }
-    
-    fn is_keyword(s: &str) -> bool {
-        matches!(
-            s,
-            "if" | "else" | "for" | "while" | "do" | "int" | "float" | "double" | "char" | "bool"
-                | "void" | "true" | "false" | "const" | "static" | "class" | "struct" | "public"
-                | "private" | "protected" | "return" | "break" | "continue" | "switch" | "case"
-                | "default" | "enum" | "typedef" | "sizeof" | "unsigned" | "signed" | "short"
-                | "long" | "namespace" | "using" | "try" | "catch" | "throw" | "new" | "delete"
-                | "template" | "explicit" | "virtual" | "friend" | "inline" | "operator"
-                | "typeid" | "constexpr" | "decltype" | "alignas" | "alignof" | "char8_t"
-                | "char16_t" | "char32_t" | "concept" | "consteval" | "constinit" | "co_await"
-                | "co_return" | "co_yield" | "requires" | "noexcept" | "static_assert"
-                | "static_cast" | "reinterpret_cast" | "dynamic_cast" | "const_cast" | "nullptr"
-                | "override" | "final" | "import" | "module" | "transaction_safe"
-                | "transaction_safe_dynamic" | "auto" | "register" | "goto" | "asm" | "volatile"
-                | "restrict" | "thread_local" | "mutable"
-        )
-    }
-
-
-    
+}
+
+// Every comment in `source`. A lexer error does not end the scan: each call consumes
+// input, errors included, so the comments after a bad token are found too
+pub fn collect_comments(source: &str) -> Vec<Comment> {
+    let mut lexer = Lexer::new(source);
+    while !matches!(lexer.next_token(), Ok(None)) {}
+    lexer.comments
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(
+        s,
+        "if" | "else"
+            | "for"
+            | "while"
+            | "do"
+            | "int"
+            | "float"
+            | "double"
+            | "char"
+            | "bool"
+            | "void"
+            | "true"
+            | "false"
+            | "const"
+            | "static"
+            | "class"
+            | "struct"
+            | "public"
+            | "private"
+            | "protected"
+            | "return"
+            | "break"
+            | "continue"
+            | "switch"
+            | "case"
+            | "default"
+            | "enum"
+            | "typedef"
+            | "sizeof"
+            | "unsigned"
+            | "signed"
+            | "short"
+            | "long"
+            | "namespace"
+            | "using"
+            | "try"
+            | "catch"
+            | "throw"
+            | "new"
+            | "delete"
+            | "template"
+            | "explicit"
+            | "virtual"
+            | "friend"
+            | "inline"
+            | "operator"
+            | "typeid"
+            | "constexpr"
+            | "decltype"
+            | "alignas"
+            | "alignof"
+            | "char8_t"
+            | "char16_t"
+            | "char32_t"
+            | "concept"
+            | "consteval"
+            | "constinit"
+            | "co_await"
+            | "co_return"
+            | "co_yield"
+            | "requires"
+            | "noexcept"
+            | "static_assert"
+            | "static_cast"
+            | "reinterpret_cast"
+            | "dynamic_cast"
+            | "const_cast"
+            | "nullptr"
+            | "override"
+            | "final"
+            | "import"
+            | "module"
+            | "transaction_safe"
+            | "transaction_safe_dynamic"
+            | "auto"
+            | "register"
+            | "goto"
+            | "asm"
+            | "volatile"
+            | "restrict"
+            | "thread_local"
+            | "mutable"
+    )
+}
@@ -14,6 +14,17 @@ pub enum Expression {
     Assignment(Box<Expression>, Box<Expression>),
     ArrayAccess(String, Box<Expression>),
     ArrayDeclaration(String, Box<Expression>),
+    UnaryOperation(String, Box<Expression>),
+    Null,
+    // Type, array size and initializer; a placement such as `(std::nothrow)` is kept in
+    // front of the type. `new int` has no initializer, `new int()` an empty one
+    New(String, Option<Box<Expression>>, Option<Vec<Expression>>),
+    Delete(Box<Expression>, bool),
+    MemberAccess(Box<Expression>, String),
+    MethodCall(Box<Expression>, String, Vec<Expression>),
+    AddressOf(Box<Expression>),
+    // Initializer of a declaration that has none, e.g. `int x;`; holds the type
+    Uninitialized(String),
 }
 
 impl Borrow<String> for Expression {
@@ -27,6 +38,87 @@ impl Borrow<String> for Expression {
     }
 }
 
+fn write_list(f: &mut fmt::Formatter, items: &[Expression]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+// Renders the expression as C++ source, e.g. for diagnostics
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Nested operations are parenthesized so the rendering keeps their grouping
+        let operand = |f: &mut fmt::Formatter, expr: &Expression| match expr {
+            Expression::BinaryOperation(..) | Expression::Assignment(..) => {
+                write!(f, "({})", expr)
+            }
+            expr => write!(f, "{}", expr),
+        };
+        match self {
+            Expression::Identifier(name) | Expression::Variable(name) => write!(f, "{}", name),
+            Expression::Integer(value) => write!(f, "{}", value),
+            Expression::FunctionCall(name, args) => {
+                write!(f, "{}(", name)?;
+                write_list(f, args)?;
+                write!(f, ")")
+            }
+            Expression::Dereference(pointer) => {
+                write!(f, "*")?;
+                operand(f, pointer)
+            }
+            Expression::BinaryOperation(op, left, right) => {
+                operand(f, left)?;
+                write!(f, " {} ", op)?;
+                operand(f, right)
+            }
+            Expression::Assignment(target, value) => write!(f, "{} = {}", target, value),
+            Expression::ArrayAccess(array, index) | Expression::ArrayDeclaration(array, index) => {
+                write!(f, "{}[{}]", array, index)
+            }
+            Expression::UnaryOperation(op, operand_expr) => {
+                write!(f, "{}", op)?;
+                operand(f, operand_expr)
+            }
+            Expression::Null => write!(f, "nullptr"),
+            Expression::New(type_name, size, initializer) => {
+                write!(f, "new {}", type_name)?;
+                if let Some(size) = size {
+                    write!(f, "[{}]", size)?;
+                }
+                if let Some(args) = initializer {
+                    write!(f, "(")?;
+                    write_list(f, args)?;
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Expression::Delete(target, true) => write!(f, "delete[] {}", target),
+            Expression::Delete(target, false) => write!(f, "delete {}", target),
+            Expression::MemberAccess(object, field) => match &**object {
+                Expression::Dereference(pointer) => write!(f, "{}->{}", pointer, field),
+                object => write!(f, "{}.{}", object, field),
+            },
+            Expression::MethodCall(object, method, args) => {
+                match &**object {
+                    Expression::Dereference(pointer) => write!(f, "{}->{}(", pointer, method)?,
+                    object => write!(f, "{}.{}(", object, method)?,
+                }
+                write_list(f, args)?;
+                write!(f, ")")
+            }
+            Expression::AddressOf(target) => {
+                write!(f, "&")?;
+                operand(f, target)
+            }
+            Expression::Uninitialized(type_name) => write!(f, "<uninitialized {}>", type_name),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum BinaryOperator {
     Add,
@@ -37,34 +129,88 @@ pub enum BinaryOperator {
 
 impl BinaryOperator {
     pub fn requires_pointer(&self) -> bool {
-        match self {
-            _ => false,
-        }
+        false
     }
 }
 
-#[derive(Debug, PartialEq)]
+// Where a statement starts in the source; line and column are 1-based, the column
+// counted in characters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new(line: usize, column: usize) -> Self {
+        Location { line, column }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     Expression(Expression),
     Declaration(String, Expression),
+    // `T& name = value`: the name refers to the object the value denotes instead of
+    // holding a copy of it
+    Reference(String, Expression),
     If(Expression, Box<Statement>, Box<Statement>),
     While(Expression, Box<Statement>),
+    // for (element : range) body
+    RangeFor(String, Expression, Box<Statement>),
     Return(Expression),
+    Block(Vec<Statement>),
+    Throw(Expression),
+    // A statement as written in the source. The parser wraps every statement it reads;
+    // statements it adds itself, e.g. the step of a `for`, take the enclosing location
+    Located(Location, Box<Statement>),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Declaration {
     Function(String, Vec<String>, Box<Statement>),
     Variable(String, Expression),
+    Class(ClassDeclaration),
 }
 
-pub struct Parser<'a> {
-    lexer: Lexer<'a>,
+// A class or struct definition. Members defined out of line are separate functions
+// named `Class::member`, e.g. `Buffer::~Buffer`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDeclaration {
+    pub name: String,
+    // Where the class name is written, if it came from source
+    pub location: Option<Location>,
+    pub bases: Vec<String>,
+    // Data members as (type, name)
+    pub fields: Vec<(String, String)>,
+    pub methods: Vec<Method>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method {
+    // `Class` for constructors, `~Class` for the destructor, `operator=` for assignment
+    pub name: String,
+    // Full parameter declarations, e.g. `const Buffer& other`
+    pub params: Vec<String>,
+    // Member-initializer list of a constructor, e.g. `data(new int[n])` as `data` and
+    // `new int[n]`
+    pub initializers: Vec<(String, Expression)>,
+    pub is_virtual: bool,
+    pub body: MethodBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MethodBody {
+    // Declared only; the definition, if any, is a separate `Class::name` function
+    Declared,
+    Defined(Box<Statement>),
+    Defaulted,
+    Deleted,
 }
 
 #[derive(Debug)]
 pub struct ParserError {
-    details: String,
+    pub(crate) details: String,
 }
 
 impl fmt::Display for ParserError {
@@ -79,111 +225,1956 @@ impl Error for ParserError {
     }
 }
 
+// The name a parameter declaration introduces, e.g. `other` for `const Buffer& other`
+// or `values` for `int values[]`. A bare name is returned as is
+pub fn parameter_name(param: &str) -> &str {
+    let param = param.trim();
+    let param = match param.find('[') {
+        Some(index) => param[..index].trim_end(),
+        None => param,
+    };
+    let prefix = param.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+    &param[prefix.len()..]
+}
+
+// The array size and initializer arguments of a `new` expression, in evaluation order
+pub fn new_operands<'e>(
+    size: &'e Option<Box<Expression>>,
+    initializer: &'e Option<Vec<Expression>>,
+) -> impl Iterator<Item = &'e Expression> {
+    size.as_deref()
+        .into_iter()
+        .chain(initializer.iter().flatten())
+}
+
+// Words that may start a declaration without being part of the type it declares
+const DECLARATION_SPECIFIERS: &[&str] = &[
+    "static",
+    "inline",
+    "extern",
+    "constexpr",
+    "consteval",
+    "constinit",
+    "virtual",
+    "explicit",
+    "friend",
+    "mutable",
+    "thread_local",
+    "register",
+    "typename",
+    "struct",
+    "class",
+    "union",
+    "enum",
+];
+
+const BUILTIN_TYPES: &[&str] = &[
+    "void", "bool", "char", "char8_t", "char16_t", "char32_t", "wchar_t", "short", "int", "long",
+    "float", "double", "signed", "unsigned", "auto",
+];
+
+const CASTS: &[&str] = &[
+    "static_cast",
+    "dynamic_cast",
+    "reinterpret_cast",
+    "const_cast",
+];
+
+// Binding strength of a binary operator; higher binds tighter
+fn binary_precedence(op: &str) -> Option<u8> {
+    Some(match op {
+        "||" => 1,
+        "&&" => 2,
+        "|" => 3,
+        "^" => 4,
+        "&" => 5,
+        "==" | "!=" => 6,
+        "<" | ">" | "<=" | ">=" => 7,
+        "<<" | ">>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        _ => return None,
+    })
+}
+
+// Built-in arithmetic types, pointers and references, and the `_t` typedefs of the
+// standard library: `T x{}` zero-initializes them and `T x(v)` simply sets them
+fn is_scalar_type(type_name: &str) -> bool {
+    let type_name = type_name
+        .trim_start_matches("const ")
+        .trim_start_matches("std::");
+    type_name.ends_with(['*', '&'])
+        || type_name.ends_with("_t")
+        || type_name
+            .split_whitespace()
+            .next()
+            .is_some_and(|word| BUILTIN_TYPES.contains(&word))
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Keyword(text) | Token::Identifier(text) | Token::Operator(text) => text.clone(),
+        Token::Symbol(c) => c.to_string(),
+        Token::Integer(value) => value.to_string(),
+        Token::Float(value) => value.to_string(),
+        Token::StringLiteral(text) => format!("\"{}\"", text),
+        Token::Whitespace => String::from(" "),
+        Token::Newline => String::from("\n"),
+    }
+}
+
+fn is_word(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Keyword(_)
+            | Token::Identifier(_)
+            | Token::Integer(_)
+            | Token::Float(_)
+            | Token::StringLiteral(_)
+    )
+}
+
+// Source text for a run of tokens, spaced the way declarations are usually written,
+// e.g. `const std::map<int, int>& m`
+fn render(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut previous: Option<&Token> = None;
+    for token in tokens {
+        if let Some(previous) = previous {
+            let after_declarator = matches!(previous, Token::Symbol('*' | '&' | '>'))
+                || matches!(previous, Token::Operator(op) if op == "&&" || op == ">>");
+            if *previous == Token::Symbol(',')
+                || (is_word(token) && (is_word(previous) || after_declarator))
+            {
+                text.push(' ');
+            }
+        }
+        text.push_str(&token_text(token));
+        previous = Some(token);
+    }
+    text
+}
+
+// How a declarator is initialized
+enum Initializer {
+    None,
+    // `T x = value`
+    Value(Expression),
+    // `T x(args)`, `T x{args}` or `T x = {args}`
+    Arguments(Vec<Expression>),
+}
+
+// The value a declaration of `type_name` starts with
+fn initial_value(type_name: &str, initializer: Initializer) -> Expression {
+    match initializer {
+        Initializer::None => Expression::Uninitialized(type_name.to_string()),
+        Initializer::Value(value) => value,
+        Initializer::Arguments(mut args) if is_scalar_type(type_name) => match args.len() {
+            0 if type_name.ends_with('*') => Expression::Null,
+            0 => Expression::Integer(0),
+            1 => args.remove(0),
+            _ => Expression::FunctionCall(type_name.to_string(), args),
+        },
+        Initializer::Arguments(args) => Expression::FunctionCall(type_name.to_string(), args),
+    }
+}
+
+// The element count of an array declared as `T name[bounds] = initializer`
+fn array_size(
+    type_name: &str,
+    bounds: Vec<Option<Expression>>,
+    initializer: &Initializer,
+) -> Expression {
+    match (bounds.into_iter().next().flatten(), initializer) {
+        (Some(size), _) => size,
+        (None, Initializer::Arguments(args)) => Expression::Integer(args.len() as i64),
+        // `char text[] = "abc"` has room for the terminating zero
+        (None, Initializer::Value(Expression::Identifier(text))) if text.starts_with('"') => {
+            Expression::Integer(text.len() as i64 - 1)
+        }
+        (None, _) => Expression::Uninitialized(type_name.to_string()),
+    }
+}
+
+// `int&` or `const T&`, but not the forwarding `auto&&`
+fn is_lvalue_reference(type_name: &str) -> bool {
+    type_name.ends_with('&') && !type_name.ends_with("&&")
+}
+
+// The type of the declarators after the first in `int* p, q;`, which share only the
+// part before the `*`
+fn base_type(type_name: &str) -> String {
+    type_name.trim_end_matches(['*', '&', ' ']).to_string()
+}
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    // The input without whitespace and preprocessor lines, each token with where it starts
+    tokens: Vec<(Token, Location)>,
+    position: usize,
+    // Positions of `>>` tokens split to close template arguments, undone on backtracking
+    splits: Vec<usize>,
+}
 
 impl<'a> Parser<'a> {
     pub fn new(lexer: Lexer<'a>) -> Self {
-        Parser { lexer }
+        Parser {
+            lexer,
+            tokens: Vec::new(),
+            position: 0,
+            splits: Vec::new(),
+        }
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, ParserError> {
-        if let Some(token) = self.lexer.next_token()? {
+    fn tokenize(&mut self) -> Result<(), ParserError> {
+        let mut line_start = true;
+        let mut directive = false;
+        let mut continued = false;
+        loop {
+            let location = Location::new(self.lexer.line(), self.lexer.column());
+            let token = match self.lexer.next_token()? {
+                Some(token) => token,
+                None => return Ok(()),
+            };
             match token {
-                Token::Identifier(identifier) => Ok(Expression::Identifier(identifier)),
-                Token::Integer(value) => Ok(Expression::Integer(value)),
-                _ => Err(ParserError {
-                    details: String::from("Unexpected token in expression"),
-                }),
+                Token::Newline => {
+                    directive &= continued;
+                    line_start = true;
+                    continued = false;
+                }
+                Token::Whitespace => {}
+                Token::Symbol('#') if line_start => {
+                    directive = true;
+                    line_start = false;
+                }
+                token => {
+                    line_start = false;
+                    continued = directive && token == Token::Symbol('\\');
+                    if !directive {
+                        self.tokens.push((token, location));
+                    }
+                }
             }
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    // Goes back to `position`, e.g. after a speculative parse failed
+    fn restore(&mut self, position: usize) {
+        while let Some(&split) = self.splits.last() {
+            if split < position {
+                break;
+            }
+            self.tokens[split].0 = Token::Operator(String::from(">>"));
+            self.splits.pop();
+        }
+        self.position = position;
+    }
+
+    // Whether the token `offset` ahead is the punctuation or word `text`
+    fn at_offset(&self, offset: usize, text: &str) -> bool {
+        match self.peek_at(offset) {
+            Some(Token::Symbol(c)) => text.chars().eq(std::iter::once(*c)),
+            Some(Token::Operator(word) | Token::Keyword(word) | Token::Identifier(word)) => {
+                word == text
+            }
+            _ => false,
+        }
+    }
+
+    fn at(&self, text: &str) -> bool {
+        self.at_offset(0, text)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.at(text);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), ParserError> {
+        if self.eat(text) {
+            Ok(())
         } else {
-            Err(ParserError {
-                details: String::from("Unexpected end of input"),
-            })
+            Err(self.error(&format!("Expected '{}'", text)))
+        }
+    }
+
+    fn error(&self, message: &str) -> ParserError {
+        let found = match self.peek() {
+            Some(token) => format!("'{}'", token_text(token)),
+            None => String::from("end of input"),
+        };
+        let line = self
+            .tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(_, location)| location.line);
+        ParserError {
+            details: format!("line {}: {}, found {}", line, message, found),
         }
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, ParserError> {
-        if let Some(token) = self.lexer.next_token()? {
+    // Where the next token starts, or the end of the input
+    fn location(&self) -> Location {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(Location::new(1, 1), |(_, location)| *location)
+    }
+
+    fn word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Keyword(word) | Token::Identifier(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, ParserError> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("Expected a name")),
+        }
+    }
+
+    // Skips from an opening bracket to just past the bracket that closes it
+    fn skip_balanced(&mut self) -> Result<(), ParserError> {
+        let mut depth = 0;
+        while let Some(token) = self.advance() {
             match token {
-                Token::Keyword(keyword) if keyword == "return" => {
-                    let expr = self.parse_expression()?;
-                    Ok(Statement::Return(expr))
+                Token::Symbol('(' | '[' | '{') => depth += 1,
+                Token::Symbol(')' | ']' | '}') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
                 }
-                _ => Err(ParserError {
-                    details: String::from("Unexpected token in statement"),
-                }),
+                _ => {}
+            }
+        }
+        Err(self.error("Unbalanced brackets"))
+    }
+
+    // Skips to just past the next `;` outside brackets, e.g. for `using` or `enum`
+    fn skip_to_semicolon(&mut self) -> Result<(), ParserError> {
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Symbol(';') => {
+                    self.position += 1;
+                    break;
+                }
+                Token::Symbol('(' | '[' | '{') => self.skip_balanced()?,
+                Token::Symbol(')' | ']' | '}') => break,
+                _ => self.position += 1,
+            }
+        }
+        Ok(())
+    }
+
+    // `[[...]]`, `alignas(...)` and compiler-specific attributes
+    fn skip_attributes(&mut self) -> Result<(), ParserError> {
+        loop {
+            if self.at("[") && self.at_offset(1, "[") {
+                self.skip_balanced()?;
+            } else if matches!(
+                self.word(),
+                Some("alignas" | "__attribute__" | "__declspec")
+            ) && self.at_offset(1, "(")
+            {
+                self.position += 1;
+                self.skip_balanced()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // Whether a `;` comes before the `)` that closes the current parentheses, as in
+    // `if (auto it = m.find(k); it != m.end())`
+    fn semicolon_before_close(&self) -> bool {
+        let mut depth = 0;
+        for (token, _) in &self.tokens[self.position..] {
+            match token {
+                Token::Symbol('(' | '[' | '{') => depth += 1,
+                Token::Symbol(')' | ']' | '}') if depth == 0 => return false,
+                Token::Symbol(')' | ']' | '}') => depth -= 1,
+                Token::Symbol(';') if depth == 0 => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    // `<...>` after a template name, as written. None, with the position unchanged, when
+    // the brackets do not balance, e.g. because the `<` is a comparison
+    fn template_arguments(&mut self) -> Option<String> {
+        let start = self.position;
+        let mut tokens = Vec::new();
+        let mut depth = 0;
+        let mut nesting = 0;
+        while let Some(token) = self.peek().cloned() {
+            match &token {
+                Token::Symbol('<') if nesting == 0 => depth += 1,
+                Token::Symbol('>') if nesting == 0 => depth -= 1,
+                Token::Operator(op) if op == ">>" && nesting == 0 => {
+                    if depth == 1 {
+                        // The first `>` closes these arguments, the second an outer list
+                        self.tokens[self.position].0 = Token::Symbol('>');
+                        self.splits.push(self.position);
+                        tokens.push(Token::Symbol('>'));
+                        return Some(render(&tokens));
+                    }
+                    depth -= 2;
+                }
+                Token::Symbol('(' | '[') => nesting += 1,
+                Token::Symbol(')' | ']') if nesting == 0 => break,
+                Token::Symbol(')' | ']') => nesting -= 1,
+                Token::Symbol(';' | '{' | '}') => break,
+                Token::Operator(op) if nesting == 0 && (op == "&&" || op == "||") => break,
+                _ => {}
+            }
+            tokens.push(token);
+            self.position += 1;
+            if depth == 0 {
+                return Some(render(&tokens));
+            }
+        }
+        self.restore(start);
+        None
+    }
+
+    // What follows the `operator` keyword in a name, e.g. `=`, `()` or ` bool`
+    fn operator_name(&mut self) -> Option<String> {
+        if self.eat("(") {
+            return self.eat(")").then(|| String::from("()"));
+        }
+        if self.eat("[") {
+            return self.eat("]").then(|| String::from("[]"));
+        }
+        match self.peek()?.clone() {
+            Token::Symbol(c) => {
+                self.position += 1;
+                Some(c.to_string())
+            }
+            Token::Operator(op) => {
+                self.position += 1;
+                Some(op)
+            }
+            Token::Keyword(word) if word == "new" || word == "delete" => {
+                self.position += 1;
+                let array = self.at("[") && self.at_offset(1, "]");
+                if array {
+                    self.position += 2;
+                }
+                Some(format!(" {}{}", word, if array { "[]" } else { "" }))
+            }
+            _ => self.parse_type().map(|type_name| format!(" {}", type_name)),
+        }
+    }
+
+    // A possibly qualified name such as `std::vector<int>::iterator`, `Buffer::~Buffer`
+    // or `Buffer::operator=`. Template arguments are taken whenever they balance
+    fn name(&mut self) -> Option<String> {
+        let start = self.position;
+        let mut name = String::new();
+        if self.eat("::") {
+            name.push_str("::");
+        }
+        loop {
+            if self.at("~") {
+                match self.peek_at(1) {
+                    Some(Token::Identifier(class)) => {
+                        name.push('~');
+                        name.push_str(class);
+                        self.position += 2;
+                    }
+                    _ => break,
+                }
+            } else if self.eat("operator") {
+                match self.operator_name() {
+                    Some(op) => name.push_str(&format!("operator{}", op)),
+                    None => break,
+                }
+            } else {
+                match self.peek() {
+                    Some(Token::Identifier(part)) => {
+                        name.push_str(part);
+                        self.position += 1;
+                    }
+                    _ => break,
+                }
+                if self.at("<") {
+                    if let Some(args) = self.template_arguments() {
+                        name.push_str(&args);
+                    }
+                }
+            }
+            if !self.at("::") || self.at_offset(1, "*") {
+                return Some(name);
+            }
+            self.position += 1;
+            name.push_str("::");
+            self.eat("template");
+        }
+        self.restore(start);
+        None
+    }
+
+    // A type such as `const std::vector<int>&` or `unsigned long*`, without storage
+    // class and similar specifiers. None, with the position unchanged, when there is none
+    fn parse_type(&mut self) -> Option<String> {
+        let start = self.position;
+        let mut text = String::new();
+        loop {
+            if self.skip_attributes().is_err() {
+                self.restore(start);
+                return None;
             }
+            match self.word() {
+                Some(word) if DECLARATION_SPECIFIERS.contains(&word) => self.position += 1,
+                Some(word @ ("const" | "volatile")) => {
+                    text.push_str(word);
+                    text.push(' ');
+                    self.position += 1;
+                }
+                _ => break,
+            }
+        }
+        if self
+            .word()
+            .is_some_and(|word| BUILTIN_TYPES.contains(&word))
+        {
+            let mut words = Vec::new();
+            while let Some(word) = self.word().filter(|word| BUILTIN_TYPES.contains(word)) {
+                words.push(word.to_string());
+                self.position += 1;
+            }
+            text.push_str(&words.join(" "));
+        } else if self.at("decltype") && self.at_offset(1, "(") {
+            let open = self.position;
+            self.position += 1;
+            if self.skip_balanced().is_err() {
+                self.restore(start);
+                return None;
+            }
+            let tokens: Vec<Token> = self.tokens[open..self.position]
+                .iter()
+                .map(|(token, _)| token.clone())
+                .collect();
+            text.push_str(&render(&tokens));
         } else {
-            Err(ParserError {
-                details: String::from("Unexpected end of input"),
-            })
+            match self.name() {
+                Some(name) => text.push_str(&name),
+                None => {
+                    self.restore(start);
+                    return None;
+                }
+            }
+        }
+        loop {
+            // Attributes may follow `*`, as in `int* [[scpp::lifetime(a)]] p`
+            if self.skip_attributes().is_err() {
+                self.restore(start);
+                return None;
+            }
+            match self.peek() {
+                Some(Token::Symbol(c @ ('*' | '&'))) => text.push(*c),
+                Some(Token::Operator(op)) if op == "&&" => text.push_str("&&"),
+                _ if self.at("const") || self.at("volatile") => text.push_str(" const"),
+                _ => break,
+            }
+            self.position += 1;
+        }
+        Some(text)
+    }
+
+    // The rest of a declarator after its name: array bounds and the initializer
+    fn declarator_rest(&mut self) -> Result<(Vec<Option<Expression>>, Initializer), ParserError> {
+        let mut bounds = Vec::new();
+        while self.eat("[") {
+            if self.eat("]") {
+                bounds.push(None);
+            } else {
+                bounds.push(Some(self.parse_expression()?));
+                self.expect("]")?;
+            }
+        }
+        self.skip_attributes()?;
+        let initializer = if self.eat("=") {
+            if self.at("{") {
+                Initializer::Arguments(self.braced_list()?)
+            } else {
+                Initializer::Value(self.parse_assignment()?)
+            }
+        } else if self.eat("(") {
+            Initializer::Arguments(self.arguments(")")?)
+        } else if self.at("{") {
+            Initializer::Arguments(self.braced_list()?)
+        } else {
+            Initializer::None
+        };
+        Ok((bounds, initializer))
+    }
+
+    // Parses a declaration statement such as `int* p = new int, q;` into `out`. Returns
+    // false, with the position unchanged, when the statement is not a declaration
+    fn try_declaration(&mut self, out: &mut Vec<Statement>) -> Result<bool, ParserError> {
+        let start = self.position;
+        let type_name = match self.parse_type() {
+            Some(type_name) => type_name,
+            None => return Ok(false),
+        };
+        // Structured bindings: `auto [key, value] = entry;`
+        if type_name.contains("auto") && self.eat("[") {
+            let mut names = vec![self.identifier()?];
+            while self.eat(",") {
+                names.push(self.identifier()?);
+            }
+            self.expect("]")?;
+            let value = match self.declarator_rest()?.1 {
+                Initializer::Value(value) => value,
+                initializer => initial_value(&type_name, initializer),
+            };
+            for name in names {
+                let element = Expression::MemberAccess(Box::new(value.clone()), name.clone());
+                out.push(Statement::Declaration(name, element));
+            }
+            self.expect(";")?;
+            return Ok(true);
+        }
+        let declares = matches!(self.peek(), Some(Token::Identifier(_)))
+            && ["=", ";", "(", "{", "[", ","]
+                .iter()
+                .any(|follower| self.at_offset(1, follower));
+        if !declares {
+            self.restore(start);
+            return Ok(false);
+        }
+
+        let mut declarator_type = type_name.clone();
+        loop {
+            let name = self.identifier()?;
+            let prototype = self.at("(");
+            let (bounds, initializer) = match self.declarator_rest() {
+                Ok(declarator) => declarator,
+                // `int helper(int);` declares a function, whose parameters are no expressions
+                Err(_) if prototype => {
+                    self.restore(start);
+                    self.skip_to_semicolon()?;
+                    return Ok(true);
+                }
+                Err(error) => return Err(error),
+            };
+            out.push(if bounds.is_empty() {
+                let value = initial_value(&declarator_type, initializer);
+                if is_lvalue_reference(&declarator_type) {
+                    Statement::Reference(name, value)
+                } else {
+                    Statement::Declaration(name, value)
+                }
+            } else {
+                let size = array_size(&declarator_type, bounds, &initializer);
+                Statement::Expression(Expression::ArrayDeclaration(name, Box::new(size)))
+            });
+            if !self.eat(",") {
+                break;
+            }
+            declarator_type = base_type(&type_name);
+            while let Some(Token::Symbol(c @ ('*' | '&'))) = self.peek() {
+                declarator_type.push(*c);
+                self.position += 1;
+            }
+        }
+        self.expect(";")?;
+        Ok(true)
+    }
+
+    fn parse_block_statements(&mut self) -> Result<Vec<Statement>, ParserError> {
+        self.expect("{")?;
+        let mut statements = Vec::new();
+        while !self.eat("}") {
+            if self.at_end() {
+                return Err(self.error("Expected '}'"));
+            }
+            self.parse_statement(&mut statements)?;
+        }
+        Ok(statements)
+    }
+
+    fn parse_block(&mut self) -> Result<Statement, ParserError> {
+        Ok(Statement::Block(self.parse_block_statements()?))
+    }
+
+    // A statement where the grammar allows exactly one, e.g. an `if` branch
+    fn parse_substatement(&mut self) -> Result<Statement, ParserError> {
+        let mut statements = Vec::new();
+        self.parse_statement(&mut statements)?;
+        Ok(match statements.len() {
+            1 => statements.remove(0),
+            _ => Statement::Block(statements),
+        })
+    }
+
+    // The condition of an `if`, `while` or `switch` and its closing `)`. Declarations in
+    // it, as in `if (int* p = find())` or `if (auto it = m.find(k); it != m.end())`, are
+    // returned separately, and a declared variable then is the condition
+    fn condition(&mut self) -> Result<(Vec<Statement>, Expression), ParserError> {
+        let mut setup = Vec::new();
+        if self.semicolon_before_close() && !self.try_declaration(&mut setup)? {
+            setup.push(Statement::Expression(self.parse_expression()?));
+            self.expect(";")?;
+        }
+        let start = self.position;
+        if let Some(type_name) = self.parse_type() {
+            if let Some(Token::Identifier(name)) = self.peek().cloned() {
+                if self.at_offset(1, "=") || self.at_offset(1, "{") {
+                    self.position += 1;
+                    let (_, initializer) = self.declarator_rest()?;
+                    let value = initial_value(&type_name, initializer);
+                    setup.push(Statement::Declaration(name.clone(), value));
+                    self.expect(")")?;
+                    return Ok((setup, Expression::Identifier(name)));
+                }
+            }
+            self.restore(start);
+        }
+        let condition = self.parse_expression()?;
+        self.expect(")")?;
+        Ok((setup, condition))
+    }
+
+    // Statements declared in a condition go in a block around the statement using it
+    fn with_setup(mut setup: Vec<Statement>, statement: Statement) -> Statement {
+        if setup.is_empty() {
+            statement
+        } else {
+            setup.push(statement);
+            Statement::Block(setup)
+        }
+    }
+
+    // Whether the `for` parentheses hold `element : range`
+    fn is_range_for(&self) -> bool {
+        let mut depth = 0;
+        for (token, _) in &self.tokens[self.position..] {
+            match token {
+                Token::Symbol('(' | '[' | '{') => depth += 1,
+                Token::Symbol(')' | ']' | '}') if depth == 0 => return false,
+                Token::Symbol(')' | ']' | '}') => depth -= 1,
+                Token::Symbol(';') if depth == 0 => return false,
+                Token::Symbol(':') if depth == 0 => return true,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn parse_range_for(&mut self) -> Result<Statement, ParserError> {
+        self.parse_type()
+            .ok_or_else(|| self.error("Expected the element type"))?;
+        // Of a structured binding only the first name is kept
+        let element = if self.eat("[") {
+            let first = self.identifier()?;
+            while !self.eat("]") {
+                self.advance().ok_or_else(|| self.error("Expected ']'"))?;
+            }
+            first
+        } else {
+            self.identifier()?
+        };
+        self.expect(":")?;
+        let range = self.parse_expression()?;
+        self.expect(")")?;
+        let body = self.parse_substatement()?;
+        Ok(Statement::RangeFor(element, range, Box::new(body)))
+    }
+
+    // `for (init; condition; step) body` is `init; while (condition) { body step }`
+    fn parse_for(&mut self) -> Result<Statement, ParserError> {
+        let mut statements = Vec::new();
+        if !self.eat(";") && !self.try_declaration(&mut statements)? {
+            statements.extend(self.expression_statement()?);
+        }
+        let condition = if self.at(";") {
+            Expression::Integer(1)
+        } else {
+            self.parse_expression()?
+        };
+        self.expect(";")?;
+        let mut step = Vec::new();
+        while !self.eat(")") {
+            step.push(Statement::Expression(self.parse_assignment()?));
+            if !self.eat(",") {
+                self.expect(")")?;
+                break;
+            }
         }
+        let mut iteration = vec![self.parse_substatement()?];
+        iteration.extend(step);
+        statements.push(Statement::While(
+            condition,
+            Box::new(Statement::Block(iteration)),
+        ));
+        Ok(Statement::Block(statements))
     }
 
-    fn parse_declaration(&mut self) -> Result<Declaration, ParserError> {
-        if let Some(token) = self.lexer.next_token()? {
+    // `case` labels split the body into groups, each a branch of an `if` chain testing
+    // the subject, with the `default` group as the final else. Fall-through is not
+    // modelled and `break` is dropped
+    fn parse_switch_body(&mut self, subject: &Expression) -> Result<Statement, ParserError> {
+        self.expect("{")?;
+        let mut groups: Vec<(Vec<Expression>, bool, Vec<Statement>)> = Vec::new();
+        while !self.eat("}") {
+            if self.at_end() {
+                return Err(self.error("Expected '}'"));
+            }
+            if self.eat("case") {
+                let label = self.parse_conditional()?;
+                self.expect(":")?;
+                match groups.last_mut() {
+                    Some((labels, _, statements)) if statements.is_empty() => labels.push(label),
+                    _ => groups.push((vec![label], false, Vec::new())),
+                }
+            } else if self.at("default") && self.at_offset(1, ":") {
+                self.position += 2;
+                match groups.last_mut() {
+                    Some((_, default, statements)) if statements.is_empty() => *default = true,
+                    _ => groups.push((Vec::new(), true, Vec::new())),
+                }
+            } else if let Some((_, _, statements)) = groups.last_mut() {
+                self.parse_statement(statements)?;
+            } else {
+                // Nothing before the first label runs
+                self.parse_statement(&mut Vec::new())?;
+            }
+        }
+
+        let mut otherwise = Statement::Block(Vec::new());
+        let mut cases = Vec::new();
+        for (labels, default, statements) in groups {
+            if default {
+                otherwise = Statement::Block(statements);
+            } else {
+                cases.push((labels, statements));
+            }
+        }
+        for (labels, statements) in cases.into_iter().rev() {
+            let condition = labels
+                .into_iter()
+                .map(|label| {
+                    Expression::BinaryOperation(
+                        String::from("=="),
+                        Box::new(subject.clone()),
+                        Box::new(label),
+                    )
+                })
+                .reduce(|left, right| {
+                    Expression::BinaryOperation(String::from("||"), Box::new(left), Box::new(right))
+                })
+                .unwrap_or(Expression::Integer(0));
+            otherwise = Statement::If(
+                condition,
+                Box::new(Statement::Block(statements)),
+                Box::new(otherwise),
+            );
+        }
+        Ok(otherwise)
+    }
+
+    // Whether a `class`, `struct` or `union` keyword starts a definition rather than a
+    // forward declaration or a variable of that type
+    fn is_class_definition(&self) -> bool {
+        let mut offset = 1;
+        while let Some(token) = self.peek_at(offset) {
             match token {
-                Token::Keyword(keyword) => match keyword.as_str() {
-                    "int" => {
-                        if let Some(Token::Identifier(identifier)) = self.lexer.next_token()? {
-                            if let Some(Token::Symbol('(')) = self.lexer.next_token()? {
-                                let mut params = Vec::new();
-                                while let Some(Token::Identifier(param)) = self.lexer.next_token()? {
-                                    params.push(param);
-                                    if let Some(Token::Symbol(',')) = self.lexer.next_token()? {
-                                        continue;
-                                    } else {
-                                        break;
-                                    }
-                                }
-                                if let Some(Token::Symbol(')')) = self.lexer.next_token()? {
-                                    let stmt = self.parse_statement()?;
-                                    Ok(Declaration::Function(identifier, params, Box::new(stmt)))
-                                } else {
-                                    Err(ParserError {
-                                        details: String::from("Expected ')'"),
-                                    })
-                                }
-                            } else {
-                                let value = self.parse_expression()?;
-                                Ok(Declaration::Variable(identifier, value))
-                            }
-                        } else {
-                            Err(ParserError {
-                                details: String::from("Expected identifier"),
-                            })
+                Token::Symbol('{') => return true,
+                Token::Symbol(';' | '=' | '*' | '&' | '(' | ')') => return false,
+                Token::Operator(op) if op == "&&" => return false,
+                _ => offset += 1,
+            }
+        }
+        false
+    }
+
+    // Skips a class definition and any declarators after it
+    fn skip_class(&mut self) -> Result<(), ParserError> {
+        while !self.at("{") {
+            self.advance().ok_or_else(|| self.error("Expected '{'"))?;
+        }
+        self.skip_balanced()?;
+        self.skip_to_semicolon()
+    }
+
+    // A class definition, and any declarators after it, which are dropped. Nested
+    // classes become declarations of their own
+    fn parse_class(&mut self, out: &mut Vec<Declaration>) -> Result<(), ParserError> {
+        self.position += 1;
+        self.skip_attributes()?;
+        let location = self.location();
+        let name = match self.name() {
+            Some(name) => name,
+            // An anonymous class has no members to check
+            None => return self.skip_class(),
+        };
+        self.eat("final");
+        let mut bases = Vec::new();
+        if self.eat(":") {
+            loop {
+                while matches!(
+                    self.word(),
+                    Some("public" | "protected" | "private" | "virtual")
+                ) {
+                    self.position += 1;
+                }
+                let base = self
+                    .name()
+                    .ok_or_else(|| self.error("Expected a base class"))?;
+                bases.push(base);
+                self.eat("...");
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        self.expect("{")?;
+        let mut class = ClassDeclaration {
+            name,
+            location: Some(location),
+            bases,
+            fields: Vec::new(),
+            methods: Vec::new(),
+        };
+        while !self.eat("}") {
+            if self.at_end() {
+                return Err(self.error("Expected '}'"));
+            }
+            self.parse_member(&mut class, out)?;
+        }
+        out.push(Declaration::Class(class));
+        self.skip_to_semicolon()
+    }
+
+    // Whether `specifier`, e.g. `virtual`, is among the words that start the declaration
+    // at the current position
+    fn has_specifier(&self, specifier: &str) -> bool {
+        self.tokens[self.position..]
+            .iter()
+            .map_while(|(token, _)| match token {
+                Token::Keyword(word) | Token::Identifier(word)
+                    if DECLARATION_SPECIFIERS.contains(&word.as_str()) =>
+                {
+                    Some(word)
+                }
+                _ => None,
+            })
+            .any(|word| word == specifier)
+    }
+
+    // One member declaration: fields, a method, or something that declares neither,
+    // such as an access specifier or a type alias
+    fn parse_member(
+        &mut self,
+        class: &mut ClassDeclaration,
+        out: &mut Vec<Declaration>,
+    ) -> Result<(), ParserError> {
+        self.skip_attributes()?;
+        if self.eat(";") {
+            return Ok(());
+        }
+        match self.word() {
+            Some("public" | "protected" | "private") if self.at_offset(1, ":") => {
+                self.position += 2;
+                return Ok(());
+            }
+            Some("using" | "typedef" | "static_assert" | "enum") => {
+                return self.skip_to_semicolon()
+            }
+            // The member after the template parameters is parsed like any other
+            Some("template") => {
+                self.position += 1;
+                if self.at("<") && self.template_arguments().is_none() {
+                    return Err(self.error("Expected '>'"));
+                }
+                return Ok(());
+            }
+            Some("class" | "struct" | "union") if self.is_class_definition() => {
+                return self.parse_class(out)
+            }
+            Some("friend") if self.at_offset(1, "class") || self.at_offset(1, "struct") => {
+                return self.skip_to_semicolon()
+            }
+            _ => {}
+        }
+        // Friends are not members, and static data members are not part of each object
+        let friend = self.has_specifier("friend");
+        let is_static = self.has_specifier("static");
+        let is_virtual = self.has_specifier("virtual");
+        let type_name = self
+            .parse_type()
+            .ok_or_else(|| self.error("Expected a member declaration"))?;
+        // Constructors, destructors and conversion operators have no return type
+        let mut name = if self.at("(") {
+            type_name.clone()
+        } else {
+            self.name()
+                .ok_or_else(|| self.error("Expected a member name"))?
+        };
+        if self.at("(") {
+            let params = self.parameters()?;
+            let method = self.parse_method(name, params, is_virtual)?;
+            if !friend {
+                class.methods.push(method);
+            }
+            return Ok(());
+        }
+        let mut declarator_type = type_name.clone();
+        loop {
+            // The width of a bit-field
+            if self.eat(":") {
+                self.parse_conditional()?;
+            }
+            self.declarator_rest()?;
+            if !friend && !is_static {
+                class.fields.push((declarator_type, name));
+            }
+            if !self.eat(",") {
+                return self.expect(";");
+            }
+            declarator_type = base_type(&type_name);
+            while let Some(Token::Symbol(c @ ('*' | '&'))) = self.peek() {
+                declarator_type.push(*c);
+                self.position += 1;
+            }
+            name = self.identifier()?;
+        }
+    }
+
+    // A method after its parameters: declared only, defaulted, deleted, pure, or defined
+    // in the class with its member initializers
+    fn parse_method(
+        &mut self,
+        name: String,
+        params: Vec<String>,
+        is_virtual: bool,
+    ) -> Result<Method, ParserError> {
+        self.skip_function_qualifiers()?;
+        let mut initializers = Vec::new();
+        let body = if self.eat("=") {
+            let body = match self.word() {
+                Some("default") => MethodBody::Defaulted,
+                Some("delete") => MethodBody::Deleted,
+                // `= 0`
+                _ => MethodBody::Declared,
+            };
+            self.skip_to_semicolon()?;
+            body
+        } else if self.eat(";") {
+            MethodBody::Declared
+        } else {
+            if self.at(":") {
+                initializers = self.member_initializers()?;
+            }
+            MethodBody::Defined(Box::new(Statement::Block(self.function_body()?)))
+        };
+        Ok(Method {
+            name,
+            params,
+            initializers,
+            is_virtual,
+            body,
+        })
+    }
+
+    // The block of a function definition. Handlers of a function try block are dropped
+    fn function_body(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let try_block = self.eat("try");
+        let body = self.parse_block_statements()?;
+        while try_block && self.eat("catch") {
+            self.skip_balanced()?;
+            self.parse_block()?;
+        }
+        Ok(body)
+    }
+
+    // Expressions separated by commas up to a `;`, each a statement
+    fn expression_statement(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let mut statements = vec![Statement::Expression(self.parse_expression()?)];
+        while self.eat(",") {
+            statements.push(Statement::Expression(self.parse_expression()?));
+        }
+        self.expect(";")?;
+        Ok(statements)
+    }
+
+    // Parses one statement into `out`, each resulting statement wrapped with where it
+    // starts
+    fn parse_statement(&mut self, out: &mut Vec<Statement>) -> Result<(), ParserError> {
+        self.skip_attributes()?;
+        let location = self.location();
+        let start = out.len();
+        self.parse_bare_statement(out)?;
+        for statement in &mut out[start..] {
+            if !matches!(statement, Statement::Located(..)) {
+                let bare = std::mem::replace(statement, Statement::Block(Vec::new()));
+                *statement = Statement::Located(location, Box::new(bare));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_bare_statement(&mut self, out: &mut Vec<Statement>) -> Result<(), ParserError> {
+        // Labels, e.g. for `goto`
+        if matches!(self.peek(), Some(Token::Identifier(_))) && self.at_offset(1, ":") {
+            self.position += 2;
+            return self.parse_statement(out);
+        }
+        let keyword = match self.peek() {
+            Some(Token::Keyword(keyword)) => keyword.clone(),
+            _ => String::new(),
+        };
+        match keyword.as_str() {
+            "if" => {
+                self.position += 1;
+                self.eat("constexpr");
+                self.expect("(")?;
+                let (setup, condition) = self.condition()?;
+                let then_branch = self.parse_substatement()?;
+                let else_branch = if self.eat("else") {
+                    self.parse_substatement()?
+                } else {
+                    Statement::Block(Vec::new())
+                };
+                let statement =
+                    Statement::If(condition, Box::new(then_branch), Box::new(else_branch));
+                out.push(Parser::with_setup(setup, statement));
+            }
+            "while" => {
+                self.position += 1;
+                self.expect("(")?;
+                let (setup, condition) = self.condition()?;
+                let body = self.parse_substatement()?;
+                let statement = Statement::While(condition, Box::new(body));
+                out.push(Parser::with_setup(setup, statement));
+            }
+            "do" => {
+                // The body runs once before the condition is first tested; it is parsed
+                // twice rather than cloned
+                self.position += 1;
+                let body_start = self.position;
+                out.push(self.parse_substatement()?);
+                self.restore(body_start);
+                let body = self.parse_substatement()?;
+                self.expect("while")?;
+                self.expect("(")?;
+                let (_, condition) = self.condition()?;
+                self.expect(";")?;
+                out.push(Statement::While(condition, Box::new(body)));
+            }
+            "for" => {
+                self.position += 1;
+                self.expect("(")?;
+                out.push(if self.is_range_for() {
+                    self.parse_range_for()?
+                } else {
+                    self.parse_for()?
+                });
+            }
+            "switch" => {
+                self.position += 1;
+                self.expect("(")?;
+                let (setup, subject) = self.condition()?;
+                let statement = self.parse_switch_body(&subject)?;
+                out.push(Parser::with_setup(setup, statement));
+            }
+            "return" | "co_return" | "throw" => {
+                self.position += 1;
+                // `return;` and the rethrowing `throw;` have no value
+                let value = if self.at(";") {
+                    Expression::Uninitialized(String::from("void"))
+                } else {
+                    self.parse_expression()?
+                };
+                self.expect(";")?;
+                out.push(if keyword == "throw" {
+                    Statement::Throw(value)
+                } else {
+                    Statement::Return(value)
+                });
+            }
+            "try" => {
+                self.position += 1;
+                out.push(self.parse_block()?);
+                // A handler runs only when the body throws, which is not known here
+                while self.eat("catch") {
+                    if !self.at("(") {
+                        return Err(self.error("Expected '('"));
+                    }
+                    self.skip_balanced()?;
+                    let handler = self.parse_block()?;
+                    out.push(Statement::If(
+                        Expression::FunctionCall(String::from("catch"), Vec::new()),
+                        Box::new(handler),
+                        Box::new(Statement::Block(Vec::new())),
+                    ));
+                }
+            }
+            "case" => {
+                self.position += 1;
+                self.parse_conditional()?;
+                self.expect(":")?;
+                self.parse_statement(out)?;
+            }
+            "default" if self.at_offset(1, ":") => {
+                self.position += 2;
+                self.parse_statement(out)?;
+            }
+            // Control flow that is not modelled, and declarations that declare no
+            // variables
+            "break" | "continue" | "goto" | "using" | "typedef" | "static_assert" | "asm"
+            | "namespace" | "enum" => self.skip_to_semicolon()?,
+            "class" | "struct" | "union" if self.is_class_definition() => self.skip_class()?,
+            _ if self.at("{") => out.push(self.parse_block()?),
+            _ if self.eat(";") => {}
+            _ => {
+                if !self.try_declaration(out)? {
+                    out.extend(self.expression_statement()?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Comma separated expressions up to and including `close`; the opening bracket has
+    // been read already
+    fn arguments(&mut self, close: &str) -> Result<Vec<Expression>, ParserError> {
+        let mut args = Vec::new();
+        while !self.eat(close) {
+            // Designated initializers: `{.x = 1}`
+            if self.at(".")
+                && matches!(self.peek_at(1), Some(Token::Identifier(_)))
+                && self.at_offset(2, "=")
+            {
+                self.position += 3;
+            }
+            args.push(self.parse_assignment()?);
+            self.eat("...");
+            if !self.eat(",") {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn braced_list(&mut self) -> Result<Vec<Expression>, ParserError> {
+        self.expect("{")?;
+        self.arguments("}")
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, ParserError> {
+        self.parse_assignment()
+    }
+
+    fn parse_assignment(&mut self) -> Result<Expression, ParserError> {
+        let target = self.parse_conditional()?;
+        if self.eat("=") {
+            let value = self.parse_assignment()?;
+            return Ok(Expression::Assignment(Box::new(target), Box::new(value)));
+        }
+        let compound = match self.peek() {
+            Some(Token::Operator(op))
+                if op.ends_with('=') && !matches!(op.as_str(), "==" | "!=" | "<=" | ">=") =>
+            {
+                op[..op.len() - 1].to_string()
+            }
+            _ => return Ok(target),
+        };
+        // `a += b` is `a = a + b`
+        self.position += 1;
+        let value = self.parse_assignment()?;
+        let result =
+            Expression::BinaryOperation(compound, Box::new(target.clone()), Box::new(value));
+        Ok(Expression::Assignment(Box::new(target), Box::new(result)))
+    }
+
+    fn parse_conditional(&mut self) -> Result<Expression, ParserError> {
+        let condition = self.parse_binary(1)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then_value = self.parse_assignment()?;
+        self.expect(":")?;
+        let else_value = self.parse_assignment()?;
+        // There is no conditional expression node; the operands become arguments
+        Ok(Expression::FunctionCall(
+            String::from("operator?:"),
+            vec![condition, then_value, else_value],
+        ))
+    }
+
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, ParserError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(c)) => c.to_string(),
+                Some(Token::Operator(op)) => op.clone(),
+                _ => break,
+            };
+            match binary_precedence(&op) {
+                Some(precedence) if precedence >= min_precedence => {
+                    self.position += 1;
+                    let right = self.parse_binary(precedence + 1)?;
+                    left = Expression::BinaryOperation(op, Box::new(left), Box::new(right));
+                }
+                _ => break,
+            }
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ParserError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("Expected an expression"))?;
+        match token {
+            Token::Symbol('*') => {
+                self.position += 1;
+                Ok(Expression::Dereference(Box::new(self.parse_unary()?)))
+            }
+            Token::Symbol('&') => {
+                self.position += 1;
+                Ok(Expression::AddressOf(Box::new(self.parse_unary()?)))
+            }
+            Token::Symbol('-') if matches!(self.peek_at(1), Some(Token::Integer(_))) => {
+                self.position += 1;
+                match self.parse_postfix()? {
+                    Expression::Integer(value) => Ok(Expression::Integer(value.wrapping_neg())),
+                    operand => Ok(Expression::UnaryOperation(
+                        String::from("-"),
+                        Box::new(operand),
+                    )),
+                }
+            }
+            Token::Symbol(c @ ('!' | '-' | '+' | '~')) => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                Ok(Expression::UnaryOperation(c.to_string(), Box::new(operand)))
+            }
+            Token::Operator(op) if op == "++" || op == "--" => {
+                self.position += 1;
+                let operand = self.parse_unary()?;
+                Ok(Expression::UnaryOperation(op, Box::new(operand)))
+            }
+            Token::Symbol('(') => match self.try_cast()? {
+                Some(operand) => Ok(operand),
+                None => self.parse_postfix(),
+            },
+            Token::Keyword(word) if word == "sizeof" || word == "alignof" => {
+                self.position += 1;
+                self.eat("...");
+                let start = self.position;
+                if self.eat("(") {
+                    if let Some(type_name) = self.parse_type() {
+                        if self.eat(")") {
+                            let operand = Expression::Identifier(type_name);
+                            return Ok(Expression::FunctionCall(word, vec![operand]));
                         }
                     }
-                    
-                    _ => Err(ParserError {
-                        details: String::from("Unexpected keyword in declaration"),
-                    }),
-                },
-                _ => Err(ParserError {
-                    details: String::from("Unexpected token in declaration"),
-                }),
+                    self.restore(start);
+                }
+                let operand = self.parse_unary()?;
+                Ok(Expression::FunctionCall(word, vec![operand]))
             }
+            Token::Keyword(word) if word == "new" => self.parse_new(),
+            Token::Keyword(word) if word == "delete" => {
+                self.position += 1;
+                let array = self.at("[") && self.at_offset(1, "]");
+                if array {
+                    self.position += 2;
+                }
+                let target = self.parse_unary()?;
+                Ok(Expression::Delete(Box::new(target), array))
+            }
+            Token::Operator(op)
+                if op == "::" && (self.at_offset(1, "new") || self.at_offset(1, "delete")) =>
+            {
+                self.position += 1;
+                self.parse_unary()
+            }
+            Token::Keyword(word) if word == "co_await" || word == "throw" => {
+                self.position += 1;
+                self.parse_unary()
+            }
+            _ => self.parse_postfix(),
+        }
+    }
+
+    // `(T)operand`, which is just the operand. None, with the position unchanged, when
+    // the parentheses hold an expression instead
+    fn try_cast(&mut self) -> Result<Option<Expression>, ParserError> {
+        let start = self.position;
+        self.position += 1;
+        if let Some(type_name) = self.parse_type() {
+            if self.eat(")") {
+                // `(T)` before an operator is a cast only for types no variable can be
+                // named like, e.g. in `(int)-x`, but not `(size) - 1`
+                let certain = type_name.ends_with(['*', '&'])
+                    || type_name
+                        .split_whitespace()
+                        .next()
+                        .is_some_and(|word| word == "const" || BUILTIN_TYPES.contains(&word));
+                let operand_follows = match self.peek() {
+                    Some(
+                        Token::Identifier(_)
+                        | Token::Integer(_)
+                        | Token::Float(_)
+                        | Token::StringLiteral(_)
+                        | Token::Symbol('('),
+                    ) => true,
+                    Some(Token::Keyword(word)) => {
+                        matches!(
+                            word.as_str(),
+                            "new" | "sizeof" | "nullptr" | "true" | "false"
+                        ) || CASTS.contains(&word.as_str())
+                    }
+                    Some(Token::Symbol(')' | ';' | ',' | ']' | '}')) | None => false,
+                    Some(_) => certain,
+                };
+                if operand_follows {
+                    return Ok(Some(self.parse_unary()?));
+                }
+            }
+        }
+        self.restore(start);
+        Ok(None)
+    }
+
+    // `new T`, `new T[n]`, `new T(args)` or `new (placement) T`
+    fn parse_new(&mut self) -> Result<Expression, ParserError> {
+        self.position += 1;
+        let mut placement = None;
+        let mut parenthesized = false;
+        if self.at("(") {
+            let open = self.position;
+            self.skip_balanced()?;
+            if self.word().is_some() || self.at("::") {
+                let tokens: Vec<Token> = self.tokens[open + 1..self.position - 1]
+                    .iter()
+                    .map(|(token, _)| token.clone())
+                    .collect();
+                placement = Some(render(&tokens));
+            } else {
+                // `new (T)` only parenthesizes the type
+                self.restore(open + 1);
+                parenthesized = true;
+            }
+        }
+        let type_name = self
+            .parse_type()
+            .ok_or_else(|| self.error("Expected a type after 'new'"))?;
+        if parenthesized {
+            self.expect(")")?;
+        }
+        let mut size = None;
+        while self.eat("[") {
+            let bound = self.parse_expression()?;
+            self.expect("]")?;
+            size.get_or_insert(Box::new(bound));
+        }
+        let initializer = if self.eat("(") {
+            Some(self.arguments(")")?)
+        } else if self.at("{") {
+            Some(self.braced_list()?)
         } else {
-            Err(ParserError {
-                details: String::from("Unexpected end of input"),
-            })
+            None
+        };
+        let type_name = match placement {
+            Some(placement) => format!("({}) {}", placement, type_name),
+            None => type_name,
+        };
+        Ok(Expression::New(type_name, size, initializer))
+    }
+
+    // The member after `.` or `->`, with template arguments when a call follows
+    fn member_name(&mut self) -> Result<String, ParserError> {
+        if self.at("~") {
+            self.position += 1;
+            return Ok(format!("~{}", self.identifier()?));
+        }
+        let mut name = self.identifier()?;
+        if self.at("<") {
+            let start = self.position;
+            match self.template_arguments() {
+                Some(args) if self.at("(") => name.push_str(&args),
+                _ => self.restore(start),
+            }
         }
+        Ok(name)
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Declaration>, ParserError> {
-        let mut declarations = Vec::new();
+    fn parse_postfix(&mut self) -> Result<Expression, ParserError> {
+        let mut expression = self.parse_primary()?;
+        loop {
+            if self.eat("(") {
+                let args = self.arguments(")")?;
+                expression = match expression {
+                    Expression::Identifier(name) => Expression::FunctionCall(name, args),
+                    Expression::MemberAccess(object, method) => {
+                        Expression::MethodCall(object, method, args)
+                    }
+                    callee => Expression::FunctionCall(callee.to_string(), args),
+                };
+            } else if self.eat("[") {
+                let index = self.parse_expression()?;
+                self.expect("]")?;
+                expression = Expression::ArrayAccess(expression.to_string(), Box::new(index));
+            } else if self.at(".") || self.at("->") {
+                let arrow = self.at("->");
+                self.position += 1;
+                self.eat("template");
+                let member = self.member_name()?;
+                let object = if arrow {
+                    Expression::Dereference(Box::new(expression))
+                } else {
+                    expression
+                };
+                expression = Expression::MemberAccess(Box::new(object), member);
+            } else if self.at("++") || self.at("--") {
+                let op = self
+                    .advance()
+                    .map(|token| token_text(&token))
+                    .unwrap_or_default();
+                expression = Expression::UnaryOperation(op, Box::new(expression));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
 
-        while let Ok(declaration) = self.parse_declaration() {
-            declarations.push(declaration);
+    // A name in an expression. Unlike in a type, `<` starts template arguments only when
+    // a call, a braced initializer or `::` follows them, as in `std::make_unique<T>()`
+    fn expression_name(&mut self) -> Result<String, ParserError> {
+        let mut name = String::new();
+        if self.eat("::") {
+            name.push_str("::");
         }
+        loop {
+            if self.eat("operator") {
+                let op = self
+                    .operator_name()
+                    .ok_or_else(|| self.error("Expected an operator"))?;
+                name.push_str(&format!("operator{}", op));
+            } else {
+                name.push_str(&self.identifier()?);
+            }
+            if self.at("<") {
+                let start = self.position;
+                match self.template_arguments() {
+                    Some(args) if self.at("(") || self.at("{") || self.at("::") => {
+                        name.push_str(&args)
+                    }
+                    _ => self.restore(start),
+                }
+            }
+            if !self.eat("::") {
+                return Ok(name);
+            }
+            name.push_str("::");
+            self.eat("template");
+        }
+    }
 
-        if let Some(token) = self.lexer.next_token()? {
-            Err(ParserError {
-                details: format!("Unexpected token {:?}", token),
-            })
+    // `[captures](params) { body }`, skipped
+    fn skip_lambda(&mut self) -> Result<(), ParserError> {
+        self.skip_balanced()?;
+        while !self.at("{") {
+            if self.at("(") {
+                self.skip_balanced()?;
+            } else {
+                self.advance()
+                    .ok_or_else(|| self.error("Expected the lambda body"))?;
+            }
+        }
+        self.skip_balanced()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParserError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error("Expected an expression"))?;
+        match token {
+            Token::Integer(value) => {
+                self.position += 1;
+                Ok(Expression::Integer(value))
+            }
+            // Only integers are modelled
+            Token::Float(value) => {
+                self.position += 1;
+                Ok(Expression::Integer(value as i64))
+            }
+            Token::StringLiteral(_) => {
+                // Adjacent literals are one string
+                let mut text = String::new();
+                while let Some(Token::StringLiteral(part)) = self.peek() {
+                    text.push_str(part);
+                    self.position += 1;
+                }
+                Ok(Expression::Identifier(format!("\"{}\"", text)))
+            }
+            Token::Symbol('(') => {
+                self.position += 1;
+                let mut expression = self.parse_expression()?;
+                // The comma operator yields its last operand
+                while self.eat(",") {
+                    expression = self.parse_assignment()?;
+                }
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Token::Symbol('[') => {
+                self.skip_lambda()?;
+                Ok(Expression::Identifier(String::from("<lambda>")))
+            }
+            Token::Symbol('{') => Ok(Expression::FunctionCall(String::new(), self.braced_list()?)),
+            Token::Identifier(name) if name == "NULL" => {
+                self.position += 1;
+                Ok(Expression::Null)
+            }
+            Token::Identifier(_) | Token::Operator(_) if self.word().is_some() || self.at("::") => {
+                let name = self.expression_name()?;
+                if self.at("{") {
+                    return Ok(Expression::FunctionCall(name, self.braced_list()?));
+                }
+                Ok(Expression::Identifier(name))
+            }
+            Token::Keyword(word) => match word.as_str() {
+                "true" | "false" => {
+                    self.position += 1;
+                    Ok(Expression::Integer((word == "true") as i64))
+                }
+                "nullptr" => {
+                    self.position += 1;
+                    Ok(Expression::Null)
+                }
+                "operator" => Ok(Expression::Identifier(self.expression_name()?)),
+                "typeid" => {
+                    self.position += 1;
+                    self.skip_balanced()?;
+                    Ok(Expression::FunctionCall(word, Vec::new()))
+                }
+                word if CASTS.contains(&word) => {
+                    self.position += 1;
+                    if !self.at("<") || self.template_arguments().is_none() {
+                        return Err(self.error("Expected the target type"));
+                    }
+                    self.expect("(")?;
+                    let operand = self.parse_expression()?;
+                    self.expect(")")?;
+                    Ok(operand)
+                }
+                // Functional casts such as `int(x)` or `unsigned{}`
+                _ => {
+                    let type_name = self
+                        .parse_type()
+                        .ok_or_else(|| self.error("Expected an expression"))?;
+                    let args = if self.eat("(") {
+                        self.arguments(")")?
+                    } else if self.at("{") {
+                        self.braced_list()?
+                    } else {
+                        return Err(self.error("Expected '('"));
+                    };
+                    Ok(initial_value(&type_name, Initializer::Arguments(args)))
+                }
+            },
+            _ => Err(self.error("Expected an expression")),
+        }
+    }
+
+    // Parameter declarations as written, e.g. `const Buffer& other`, without default
+    // arguments; `(void)` has none
+    fn parameters(&mut self) -> Result<Vec<String>, ParserError> {
+        self.expect("(")?;
+        if self.at("void") && self.at_offset(1, ")") {
+            self.position += 2;
+            return Ok(Vec::new());
+        }
+        let mut params = Vec::new();
+        let mut tokens = Vec::new();
+        let mut default = false;
+        let mut depth = 0;
+        let mut angles = 0;
+        loop {
+            self.skip_attributes()?;
+            let token = self.advance().ok_or_else(|| self.error("Expected ')'"))?;
+            let end = match &token {
+                Token::Symbol(')') if depth == 0 => true,
+                Token::Symbol(',') if depth == 0 && angles <= 0 => true,
+                Token::Symbol('(' | '[' | '{') => {
+                    depth += 1;
+                    false
+                }
+                Token::Symbol(')' | ']' | '}') => {
+                    depth -= 1;
+                    false
+                }
+                Token::Symbol('=') if depth == 0 && angles <= 0 => {
+                    default = true;
+                    false
+                }
+                Token::Symbol('<') if !default => {
+                    angles += 1;
+                    false
+                }
+                Token::Symbol('>') if !default => {
+                    angles -= 1;
+                    false
+                }
+                Token::Operator(op) if op == ">>" && !default => {
+                    angles -= 2;
+                    false
+                }
+                _ => false,
+            };
+            if end {
+                if !tokens.is_empty() {
+                    params.push(render(&tokens));
+                }
+                if token == Token::Symbol(')') {
+                    return Ok(params);
+                }
+                tokens.clear();
+                default = false;
+                angles = 0;
+            } else if !default {
+                tokens.push(token);
+            }
+        }
+    }
+
+    // `: data(new int[n]), size{n}` before a constructor body, as (member, value)
+    fn member_initializers(&mut self) -> Result<Vec<(String, Expression)>, ParserError> {
+        self.expect(":")?;
+        let mut initializers = Vec::new();
+        loop {
+            let member = self
+                .name()
+                .ok_or_else(|| self.error("Expected a member name"))?;
+            let mut args = if self.at("{") {
+                self.braced_list()?
+            } else {
+                self.expect("(")?;
+                self.arguments(")")?
+            };
+            let value = match args.len() {
+                1 => args.remove(0),
+                _ => Expression::FunctionCall(member.clone(), args),
+            };
+            initializers.push((member, value));
+            self.eat("...");
+            if !self.eat(",") {
+                return Ok(initializers);
+            }
+        }
+    }
+
+    // Qualifiers, exception specifications and trailing return types after the
+    // parameters of a function
+    fn skip_function_qualifiers(&mut self) -> Result<(), ParserError> {
+        loop {
+            self.skip_attributes()?;
+            if self.eat("->") {
+                self.parse_type()
+                    .ok_or_else(|| self.error("Expected the return type"))?;
+            } else if matches!(
+                self.word(),
+                Some("const" | "volatile" | "override" | "final" | "noexcept" | "throw")
+            ) {
+                self.position += 1;
+                if self.at("(") {
+                    self.skip_balanced()?;
+                }
+            } else if self.at("&") || self.at("&&") {
+                self.position += 1;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // The function after its parameters: nothing for a prototype, else the body, with
+    // member initializers as assignments in front
+    fn parse_function_rest(
+        &mut self,
+        name: String,
+        params: Vec<String>,
+        out: &mut Vec<Declaration>,
+    ) -> Result<(), ParserError> {
+        self.skip_function_qualifiers()?;
+        // `= default`, `= delete` or `= 0`
+        if self.eat("=") {
+            return self.skip_to_semicolon();
+        }
+        if self.eat(";") {
+            return Ok(());
+        }
+        let mut body: Vec<Statement> = if self.at(":") {
+            self.member_initializers()?
+                .into_iter()
+                .map(|(member, value)| {
+                    Statement::Expression(Expression::Assignment(
+                        Box::new(Expression::Identifier(member)),
+                        Box::new(value),
+                    ))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        body.extend(self.function_body()?);
+        out.push(Declaration::Function(
+            name,
+            params,
+            Box::new(Statement::Block(body)),
+        ));
+        Ok(())
+    }
+
+    fn parse_function_or_variables(
+        &mut self,
+        out: &mut Vec<Declaration>,
+    ) -> Result<(), ParserError> {
+        let type_name = self
+            .parse_type()
+            .ok_or_else(|| self.error("Expected a declaration"))?;
+        // Constructors and destructors have no return type, so what was read as the type
+        // is their name
+        let mut name = if self.at("(") {
+            type_name.clone()
         } else {
-            Ok(declarations)
+            self.name()
+                .ok_or_else(|| self.error("Expected a declarator name"))?
+        };
+        if self.at("(") {
+            let params = self.parameters()?;
+            return self.parse_function_rest(name, params, out);
+        }
+        let mut declarator_type = type_name.clone();
+        loop {
+            let (bounds, initializer) = self.declarator_rest()?;
+            let value = if bounds.is_empty() {
+                initial_value(&declarator_type, initializer)
+            } else {
+                let size = array_size(&declarator_type, bounds, &initializer);
+                Expression::ArrayDeclaration(name.clone(), Box::new(size))
+            };
+            out.push(Declaration::Variable(name, value));
+            if !self.eat(",") {
+                return self.expect(";");
+            }
+            declarator_type = base_type(&type_name);
+            while let Some(Token::Symbol(c @ ('*' | '&'))) = self.peek() {
+                declarator_type.push(*c);
+                self.position += 1;
+            }
+            name = self.identifier()?;
+        }
+    }
+
+    // The declarations of a namespace or `extern "C"` block, which are flattened
+    fn parse_scope(&mut self, out: &mut Vec<Declaration>) -> Result<(), ParserError> {
+        self.expect("{")?;
+        while !self.eat("}") {
+            if self.at_end() {
+                return Err(self.error("Expected '}'"));
+            }
+            self.parse_top_level(out)?;
+        }
+        Ok(())
+    }
+
+    fn parse_top_level(&mut self, out: &mut Vec<Declaration>) -> Result<(), ParserError> {
+        self.skip_attributes()?;
+        if self.at("inline") && self.at_offset(1, "namespace") {
+            self.position += 1;
+        }
+        if self.eat(";") {
+            return Ok(());
+        }
+        match self.word().map(str::to_string).as_deref() {
+            Some("namespace") => {
+                while !self.at("{") && !self.at("=") {
+                    self.advance().ok_or_else(|| self.error("Expected '{'"))?;
+                }
+                if self.at("=") {
+                    return self.skip_to_semicolon();
+                }
+                self.parse_scope(out)
+            }
+            Some("extern") if matches!(self.peek_at(1), Some(Token::StringLiteral(_))) => {
+                self.position += 2;
+                if self.at("{") {
+                    self.parse_scope(out)
+                } else {
+                    self.parse_top_level(out)
+                }
+            }
+            // The declaration after the template parameters is parsed like any other
+            Some("template") => {
+                self.position += 1;
+                if self.at("<") && self.template_arguments().is_none() {
+                    return Err(self.error("Expected '>'"));
+                }
+                Ok(())
+            }
+            Some("using" | "typedef" | "static_assert" | "enum") => self.skip_to_semicolon(),
+            Some("class" | "struct" | "union") if self.is_class_definition() => {
+                self.parse_class(out)
+            }
+            Some("class" | "struct" | "union") if self.at_offset(2, ";") => {
+                self.skip_to_semicolon()
+            }
+            _ => self.parse_function_or_variables(out),
         }
     }
+
+    pub fn parse(&mut self) -> Result<Vec<Declaration>, ParserError> {
+        self.tokenize()?;
+        let mut declarations = Vec::new();
+        while !self.at_end() {
+            self.parse_top_level(&mut declarations)?;
+        }
+        Ok(declarations)
+    }
 }
 
 pub fn parse_cpp_code(source_code: &str) -> Result<Vec<Declaration>, ParserError> {
@@ -191,4 +2182,3 @@ pub fn parse_cpp_code(source_code: &str) -> Result<Vec<Declaration>, ParserError
     let mut parser = Parser::new(lexer);
     parser.parse()
 }
-
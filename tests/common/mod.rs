@@ -0,0 +1,34 @@
+// tests/common/mod.rs
+// Builders for the AST shared by the tests; each test uses only some of them
+#![allow(dead_code)]
+
+use safecpp::parser::cpp_parser::{Declaration, Expression, Location, Statement};
+
+pub fn var(name: &str) -> Expression {
+    Expression::Identifier(name.to_string())
+}
+
+pub fn call(name: &str, args: Vec<Expression>) -> Expression {
+    Expression::FunctionCall(name.to_string(), args)
+}
+
+pub fn method(object: &str, name: &str, args: Vec<Expression>) -> Expression {
+    Expression::MethodCall(Box::new(var(object)), name.to_string(), args)
+}
+
+pub fn declare(name: &str, value: Expression) -> Statement {
+    Statement::Declaration(name.to_string(), value)
+}
+
+// A statement as the parser returns it, starting at the given line and column
+pub fn at(line: usize, column: usize, statement: Statement) -> Statement {
+    Statement::Located(Location::new(line, column), Box::new(statement))
+}
+
+pub fn function(name: &str, params: &[&str], body: Vec<Statement>) -> Declaration {
+    Declaration::Function(
+        name.to_string(),
+        params.iter().map(|param| param.to_string()).collect(),
+        Box::new(Statement::Block(body)),
+    )
+}
@@ -1,53 +1,62 @@
 // tests/test_analyzer.rs
+use safecpp::analyzer::memory_analyzer::{MemoryAnalyzer, MemoryErrorType};
 use safecpp::analyzer::pointer_analyzer::PointerAnalyzer;
-use safecpp::parser::cpp_lexer::{Lexer};
+use safecpp::parser::cpp_lexer::Lexer;
 use safecpp::parser::cpp_parser::{Declaration, Parser};
-use safecpp::error::error::MemoryError;
 
-#[test]
-fn test_analyze_pointer_declaration() {
-    let input = "int *x;";
+fn parse(input: &str) -> Vec<Declaration> {
     let lexer = Lexer::new(input);
     let mut parser = Parser::new(lexer);
-    let declarations = parser.parse().expect("Failed to parse");
+    parser.parse().expect("Failed to parse")
+}
 
-    let mut pointer_analyzer = PointerAnalyzer::new();
-    let result = pointer_analyzer.analyze(&declarations);
+#[test]
+fn test_analyze_pointer_declaration() {
+    let declarations = parse("int *x;");
+
+    let mut pointer_analyzer = PointerAnalyzer::new(declarations);
+    let result = pointer_analyzer.analyze();
 
     assert!(result.is_ok());
 }
 
+// Runs the memory analyzer on the body of the only function in `input`
+fn analyze_function(input: &str) -> Result<(), MemoryErrorType> {
+    let declarations = parse(input);
+    let Declaration::Function(_, _, body) = &declarations[0] else {
+        panic!("Expected a function");
+    };
+    let mut memory_analyzer = MemoryAnalyzer::new(&declarations);
+    memory_analyzer
+        .analyze(std::slice::from_ref(&**body))
+        .map_err(|error| error.error_type())
+}
+
 #[test]
 fn test_analyze_null_pointer_dereference() {
-    let input = r#"
-    int *x = nullptr;
-    int y = *x;
-    "#;
-
-    let lexer = Lexer::new(input);
-    let mut parser = Parser::new(lexer);
-    let declarations = parser.parse().expect("Failed to parse");
-
-    let mut pointer_analyzer = PointerAnalyzer::new();
-    let result = pointer_analyzer.analyze(&declarations);
-
-    assert!(matches!(result, Err(MemoryError::NullPointerDereference {..})));
+    let result = analyze_function(
+        r#"
+    void f() {
+        int *x = nullptr;
+        int y = *x;
+    }
+    "#,
+    );
+
+    assert_eq!(result, Err(MemoryErrorType::NullPointerDereference));
 }
 
 #[test]
 fn test_analyze_double_free() {
-    let input = r#"
-    int *x = new int;
-    delete x;
-    delete x;
-    "#;
-
-    let lexer = Lexer::new(input);
-    let mut parser = Parser::new(lexer);
-    let declarations = parser.parse().expect("Failed to parse");
-
-    let mut pointer_analyzer = PointerAnalyzer::new();
-    let result = pointer_analyzer.analyze(&declarations);
-
-    assert!(matches!(result, Err(MemoryError::DoubleFree {..})));
+    let result = analyze_function(
+        r#"
+    void f() {
+        int *x = new int;
+        delete x;
+        delete x;
+    }
+    "#,
+    );
+
+    assert_eq!(result, Err(MemoryErrorType::DoubleFree));
 }
@@ -0,0 +1,315 @@
+// tests/test_array_analyzer.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::array_analyzer::ArrayAnalyzer;
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration, Expression, Statement};
+
+fn binary(op: &str, left: Expression, right: Expression) -> Expression {
+    Expression::BinaryOperation(op.to_string(), Box::new(left), Box::new(right))
+}
+
+// int a[5]; for (int i = 0; i <op> 5; ++i) a[i] = 0;
+fn counting_loop(op: &str) -> Vec<Statement> {
+    vec![
+        Statement::Expression(Expression::ArrayDeclaration(
+            "a".to_string(),
+            Box::new(Expression::Integer(5)),
+        )),
+        Statement::Declaration("i".to_string(), Expression::Integer(0)),
+        Statement::While(
+            binary(op, var("i"), Expression::Integer(5)),
+            Box::new(Statement::Block(vec![
+                Statement::Expression(Expression::Assignment(
+                    Box::new(Expression::ArrayAccess("a".to_string(), Box::new(var("i")))),
+                    Box::new(Expression::Integer(0)),
+                )),
+                Statement::Expression(Expression::UnaryOperation(
+                    "++".to_string(),
+                    Box::new(var("i")),
+                )),
+            ])),
+        ),
+    ]
+}
+
+#[test]
+fn test_loop_within_bounds() {
+    let program = counting_loop("<");
+    let mut analyzer = ArrayAnalyzer::new(&program);
+
+    assert!(analyzer.analyze().is_ok());
+}
+
+#[test]
+fn test_off_by_one_loop() {
+    let program = counting_loop("<=");
+    let mut analyzer = ArrayAnalyzer::new(&program);
+
+    assert!(analyzer.analyze().is_err());
+}
+
+#[test]
+fn test_index_refined_by_condition() {
+    // int a[5]; if (n < 5 && n >= 0) a[n]; else {} a[n - 5];
+    let program = vec![
+        Statement::Expression(Expression::ArrayDeclaration(
+            "a".to_string(),
+            Box::new(Expression::Integer(5)),
+        )),
+        Statement::If(
+            binary(
+                "&&",
+                binary("<", var("n"), Expression::Integer(5)),
+                binary(">=", var("n"), Expression::Integer(0)),
+            ),
+            Box::new(Statement::Expression(Expression::ArrayAccess(
+                "a".to_string(),
+                Box::new(var("n")),
+            ))),
+            Box::new(Statement::Return(Expression::Integer(0))),
+        ),
+        Statement::Expression(Expression::ArrayAccess(
+            "a".to_string(),
+            Box::new(binary("-", var("n"), Expression::Integer(5))),
+        )),
+    ];
+    let mut analyzer = ArrayAnalyzer::new(&program);
+
+    assert!(analyzer.analyze().is_err());
+}
+
+fn heap_loop(allocation: Expression, op: &str) -> Vec<Statement> {
+    // int *p = <allocation>; int *q = p; for (int i = 0; i <op> 5; ++i) q[i] = 0;
+    vec![
+        Statement::Declaration("p".to_string(), allocation),
+        Statement::Declaration("q".to_string(), var("p")),
+        Statement::Declaration("i".to_string(), Expression::Integer(0)),
+        Statement::While(
+            binary(op, var("i"), Expression::Integer(5)),
+            Box::new(Statement::Block(vec![
+                Statement::Expression(Expression::Assignment(
+                    Box::new(Expression::ArrayAccess("q".to_string(), Box::new(var("i")))),
+                    Box::new(Expression::Integer(0)),
+                )),
+                Statement::Expression(Expression::UnaryOperation(
+                    "++".to_string(),
+                    Box::new(var("i")),
+                )),
+            ])),
+        ),
+    ]
+}
+
+fn sizeof_int() -> Expression {
+    Expression::FunctionCall("sizeof".to_string(), vec![var("int")])
+}
+
+#[test]
+fn test_new_array_buffer() {
+    let allocation = || {
+        Expression::New(
+            "int".to_string(),
+            Some(Box::new(Expression::Integer(5))),
+            None,
+        )
+    };
+
+    let program = heap_loop(allocation(), "<");
+    assert!(ArrayAnalyzer::new(&program).analyze().is_ok());
+
+    let program = heap_loop(allocation(), "<=");
+    assert!(ArrayAnalyzer::new(&program).analyze().is_err());
+}
+
+#[test]
+fn test_malloc_and_calloc_buffers() {
+    let program = heap_loop(
+        Expression::FunctionCall(
+            "malloc".to_string(),
+            vec![binary("*", Expression::Integer(4), sizeof_int())],
+        ),
+        "<",
+    );
+    assert!(ArrayAnalyzer::new(&program).analyze().is_err());
+
+    let program = heap_loop(
+        Expression::FunctionCall(
+            "calloc".to_string(),
+            vec![Expression::Integer(5), sizeof_int()],
+        ),
+        "<",
+    );
+    assert!(ArrayAnalyzer::new(&program).analyze().is_ok());
+}
+
+#[test]
+fn test_pointer_arithmetic_past_end() {
+    // int *p = new int[5]; int *q = p + 4; *(q + 1) = 0;
+    let program = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New(
+                "int".to_string(),
+                Some(Box::new(Expression::Integer(5))),
+                None,
+            ),
+        ),
+        Statement::Declaration(
+            "q".to_string(),
+            binary("+", var("p"), Expression::Integer(4)),
+        ),
+        Statement::Expression(Expression::Dereference(Box::new(var("q")))),
+        Statement::Expression(Expression::Assignment(
+            Box::new(Expression::Dereference(Box::new(binary(
+                "+",
+                var("q"),
+                Expression::Integer(1),
+            )))),
+            Box::new(Expression::Integer(0)),
+        )),
+    ];
+    let mut analyzer = ArrayAnalyzer::new(&program);
+
+    assert!(analyzer.analyze().is_err());
+}
+
+#[test]
+fn test_compound_assignment_updates_index() {
+    // int a[5]; int i = 0; i += 5; a[i] = 0;
+    let program = vec![
+        Statement::Expression(Expression::ArrayDeclaration(
+            "a".to_string(),
+            Box::new(Expression::Integer(5)),
+        )),
+        Statement::Declaration("i".to_string(), Expression::Integer(0)),
+        Statement::Expression(binary("+=", var("i"), Expression::Integer(5))),
+        Statement::Expression(Expression::Assignment(
+            Box::new(Expression::ArrayAccess("a".to_string(), Box::new(var("i")))),
+            Box::new(Expression::Integer(0)),
+        )),
+    ];
+
+    assert!(ArrayAnalyzer::new(&program).analyze().is_err());
+}
+
+#[test]
+fn test_pointer_increment_past_end() {
+    // int *p = new int[2]; p++; ++p; *p = 0;
+    let increment = || {
+        Statement::Expression(Expression::UnaryOperation(
+            "++".to_string(),
+            Box::new(var("p")),
+        ))
+    };
+    let program = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New(
+                "int".to_string(),
+                Some(Box::new(Expression::Integer(2))),
+                None,
+            ),
+        ),
+        increment(),
+        increment(),
+        Statement::Expression(Expression::Assignment(
+            Box::new(Expression::Dereference(Box::new(var("p")))),
+            Box::new(Expression::Integer(0)),
+        )),
+    ];
+
+    assert!(ArrayAnalyzer::new(&program).analyze().is_err());
+}
+
+#[test]
+fn test_division_overflow_is_unbounded() {
+    // int a[5]; a[INT64_MIN / -1] = 0; must not panic
+    let program = vec![
+        Statement::Expression(Expression::ArrayDeclaration(
+            "a".to_string(),
+            Box::new(Expression::Integer(5)),
+        )),
+        Statement::Expression(Expression::Assignment(
+            Box::new(Expression::ArrayAccess(
+                "a".to_string(),
+                Box::new(binary(
+                    "/",
+                    Expression::Integer(i64::MIN),
+                    Expression::Integer(-1),
+                )),
+            )),
+            Box::new(Expression::Integer(0)),
+        )),
+    ];
+
+    assert!(ArrayAnalyzer::new(&program).analyze().is_ok());
+}
+
+#[test]
+fn test_realloc_buffer() {
+    let reallocation = |count| {
+        Expression::FunctionCall(
+            "realloc".to_string(),
+            vec![
+                Expression::Null,
+                binary("*", Expression::Integer(count), sizeof_int()),
+            ],
+        )
+    };
+
+    let program = heap_loop(reallocation(4), "<");
+    assert!(ArrayAnalyzer::new(&program).analyze().is_err());
+
+    let program = heap_loop(reallocation(5), "<");
+    assert!(ArrayAnalyzer::new(&program).analyze().is_ok());
+}
+
+#[test]
+fn test_std_array_off_by_one() {
+    let analyze = |declaration: &str, op: &str| {
+        let source = format!(
+            "void f() {{ {} for (int i = 0; i {} 5; ++i) a[i] = 0; }}",
+            declaration, op
+        );
+        let body = match parse_cpp_code(&source).unwrap().remove(0) {
+            Declaration::Function(_, _, body) => *body,
+            declaration => panic!("Expected a function, found {:?}", declaration),
+        };
+        ArrayAnalyzer::new(std::slice::from_ref(&body)).analyze()
+    };
+
+    for declaration in ["std::array<int, 5> a;", "std::array<int, 5> a{};"] {
+        assert!(analyze(declaration, "<").is_ok(), "{}", declaration);
+        assert!(analyze(declaration, "<=").is_err(), "{}", declaration);
+    }
+}
+
+#[test]
+fn test_half_bounded_index_is_not_reported() {
+    let analyze = |source: &str| {
+        let body = match parse_cpp_code(source).unwrap().remove(0) {
+            Declaration::Function(_, _, body) => *body,
+            declaration => panic!("Expected a function, found {:?}", declaration),
+        };
+        ArrayAnalyzer::new(std::slice::from_ref(&body)).analyze()
+    };
+
+    // i is in [0, +inf]: nothing says it reaches 5
+    assert!(analyze("void g(int n) { int a[5]; for (int i = 0; i < n; ++i) a[i] = 0; }").is_ok());
+    // ...but a known bound past the end still is
+    assert!(analyze("void g(int n) { int a[5]; if (n <= 5) a[n] = 0; }").is_err());
+}
+
+#[test]
+fn test_most_negative_extent_does_not_panic() {
+    let source = "void f() { int *p = new int[-9223372036854775807 - 1]; p[0] = 1; }";
+    let body = match parse_cpp_code(source).unwrap().remove(0) {
+        Declaration::Function(_, _, body) => *body,
+        declaration => panic!("Expected a function, found {:?}", declaration),
+    };
+
+    assert!(ArrayAnalyzer::new(std::slice::from_ref(&body))
+        .analyze()
+        .is_err());
+}
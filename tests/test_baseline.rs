@@ -0,0 +1,99 @@
+// tests/test_baseline.rs
+use safecpp::diagnostics::baseline::{Baseline, BaselineError};
+use safecpp::diagnostics::{Diagnostic, SourceFile};
+
+const SOURCE: &str = "\
+void load() {
+    int* buffer = new int[16];
+    int* spare = new int;
+}
+";
+
+// The same function after code was added above it and the leak was reindented
+const EDITED: &str = "\
+#include <cstdio>
+
+void load() {
+    std::puts(\"loading\");
+        int* buffer = new int[16];
+    int* spare = new int;
+    int* extra = new int;
+}
+";
+
+fn leak(name: &str) -> Diagnostic {
+    Diagnostic::new(
+        "memory-leak",
+        format!(
+            "Memory leak: memory allocated with new for '{}' is never freed",
+            name
+        ),
+    )
+    .in_function("load")
+}
+
+fn findings(source: &SourceFile, names: &[&str]) -> Vec<Diagnostic> {
+    names.iter().map(|name| source.locate(leak(name))).collect()
+}
+
+#[test]
+fn test_baseline_survives_line_shifts() {
+    let source = SourceFile::new("load.cpp", SOURCE);
+    let mut baseline = Baseline::new();
+    baseline.record(&source, &findings(&source, &["buffer", "spare"]));
+    assert_eq!(baseline.entries().len(), 2);
+
+    let edited = SourceFile::new("load.cpp", EDITED);
+    let (new, known) = baseline.filter(&edited, findings(&edited, &["buffer", "spare", "extra"]));
+    assert_eq!(known, 2);
+    assert_eq!(new.len(), 1);
+    assert!(new[0].message.contains("'extra'"));
+
+    // Another file with the same code is not covered
+    let other = SourceFile::new("other.cpp", SOURCE);
+    let (new, known) = baseline.filter(&other, findings(&other, &["buffer"]));
+    assert_eq!((new.len(), known), (1, 0));
+
+    // The same file named with a leading `./`
+    let dotted = SourceFile::new("./load.cpp", SOURCE);
+    let (new, known) = baseline.filter(&dotted, findings(&dotted, &["buffer", "spare"]));
+    assert_eq!((new.len(), known), (0, 2));
+}
+
+#[test]
+fn test_baseline_round_trip() {
+    let source = SourceFile::new("load.cpp", SOURCE);
+    let other = SourceFile::new("other.cpp", SOURCE);
+    let mut baseline = Baseline::new();
+    baseline.record(&source, &findings(&source, &["buffer", "spare"]));
+    baseline.record(&other, &findings(&other, &["buffer"]));
+    // Recording a file again replaces its entries only
+    baseline.record(&source, &findings(&source, &["spare"]));
+
+    let parsed = Baseline::parse(&baseline.to_json().pretty()).unwrap();
+    assert_eq!(parsed, baseline);
+    let files: Vec<&str> = parsed
+        .entries()
+        .iter()
+        .map(|entry| entry.file.as_str())
+        .collect();
+    assert_eq!(files, vec!["load.cpp", "other.cpp"]);
+    assert_eq!(parsed.entries()[0].function.as_deref(), Some("load"));
+    assert_eq!(parsed.entries()[0].fingerprint.len(), 16);
+}
+
+#[test]
+fn test_invalid_baseline() {
+    assert_eq!(
+        Baseline::parse("{\"version\": 2, \"findings\": []}"),
+        Err(BaselineError {
+            details: "version 2 is not supported, expected 1".to_string(),
+        })
+    );
+    assert_eq!(
+        Baseline::parse("{\"version\": 1, \"findings\": [{\"file\": \"a.cpp\"}]}"),
+        Err(BaselineError {
+            details: "every finding needs a string \"fingerprint\"".to_string(),
+        })
+    );
+}
@@ -0,0 +1,197 @@
+// tests/test_borrow_checker.rs
+mod common;
+
+use common::{call, function, var};
+use safecpp::analyzer::borrow_checker::{FunctionLifetimes, Lifetime};
+use safecpp::analyzer::pointer_analyzer::{PointerAnalyzer, PointerErrorKind};
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+fn address_of(name: &str) -> Expression {
+    Expression::AddressOf(Box::new(var(name)))
+}
+
+fn local(name: &str) -> Statement {
+    Statement::Declaration(
+        name.to_string(),
+        Expression::Uninitialized("int".to_string()),
+    )
+}
+
+fn analyze(declarations: Vec<Declaration>, signatures: &[&str]) -> Result<(), String> {
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    for signature in signatures {
+        analyzer.add_lifetime_annotations(FunctionLifetimes::parse(signature).unwrap());
+    }
+    analyzer.analyze().map_err(|error| {
+        let kind = match error.kind {
+            PointerErrorKind::LifetimeMismatch => "lifetime",
+            PointerErrorKind::BorrowConflict => "borrow",
+            _ => "other",
+        };
+        format!("{}: {}", kind, error.details)
+    })
+}
+
+const PICK: &str = "int* [[scpp::lifetime(a)]] pick(int* [[scpp::lifetime(a)]] x, \
+                    int* [[scpp::lifetime(b)]] y)";
+
+#[test]
+fn test_parse_signature() {
+    let lifetime = |name: &str, mutable| {
+        Some(Lifetime {
+            name: name.to_string(),
+            mutable,
+        })
+    };
+
+    let signature = FunctionLifetimes::parse(PICK).unwrap();
+    assert_eq!(signature.name, "pick");
+    assert_eq!(signature.returns, lifetime("a", false));
+    assert_eq!(
+        signature.params,
+        vec![lifetime("a", false), lifetime("b", false)]
+    );
+
+    let signature =
+        FunctionLifetimes::parse("int* first(Vec* v /* scpp::lifetime(mut a) */, int n)").unwrap();
+    assert_eq!(signature.returns, None);
+    assert_eq!(signature.params, vec![lifetime("a", true), None]);
+}
+
+#[test]
+fn test_collect_signatures_from_source() {
+    let source = "\
+#include <vector>
+int* [[scpp::lifetime(a)]] pick(int* [[scpp::lifetime(a)]] x, int* y);
+int* first(Vec* v /* scpp::lifetime(mut a) */) {
+    int* [[scpp::lifetime(a)]] q = get(v);
+    return q;
+}
+int plain(int n) { return n; }
+";
+
+    let signatures = FunctionLifetimes::collect(source);
+    let names: Vec<&str> = signatures
+        .iter()
+        .map(|lifetimes| lifetimes.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["pick", "first"]);
+    assert_eq!(
+        signatures[0],
+        FunctionLifetimes::parse(PICK_PROTOTYPE).unwrap()
+    );
+    assert_eq!(
+        signatures[1].params,
+        vec![Some(Lifetime {
+            name: "a".to_string(),
+            mutable: true,
+        })]
+    );
+}
+
+const PICK_PROTOTYPE: &str =
+    "int* [[scpp::lifetime(a)]] pick(int* [[scpp::lifetime(a)]] x, int* y)";
+
+#[test]
+fn test_returned_reference_must_have_return_lifetime() {
+    let pick = |returned: Expression| {
+        vec![function(
+            "pick",
+            &["x", "y"],
+            vec![local("z"), Statement::Return(returned)],
+        )]
+    };
+
+    assert!(analyze(pick(var("x")), &[PICK]).is_ok());
+
+    let error = analyze(pick(var("y")), &[PICK]).unwrap_err();
+    assert!(error.starts_with("lifetime"), "{}", error);
+    assert!(
+        error.contains("parameter 'y' with lifetime 'b"),
+        "{}",
+        error
+    );
+
+    let error = analyze(pick(address_of("z")), &[PICK]).unwrap_err();
+    // Caught by the stack lifetime check before the annotations are consulted
+    assert!(error.contains("local 'z'"), "{}", error);
+}
+
+#[test]
+fn test_call_result_outliving_argument() {
+    // int *r; { int x; int y; r = pick(&x, &y); } *r = 1;
+    let declarations = vec![function(
+        "main",
+        &[],
+        vec![
+            local("r"),
+            Statement::Block(vec![
+                local("x"),
+                local("y"),
+                Statement::Expression(Expression::Assignment(
+                    Box::new(var("r")),
+                    Box::new(call("pick", vec![address_of("x"), address_of("y")])),
+                )),
+            ]),
+            Statement::Expression(Expression::Assignment(
+                Box::new(Expression::Dereference(Box::new(var("r")))),
+                Box::new(Expression::Integer(1)),
+            )),
+        ],
+    )];
+
+    let error = analyze(declarations, &[PICK]).unwrap_err();
+    assert!(
+        error.contains("'r' borrows 'x', which does not live long enough"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_use_while_mutably_borrowed() {
+    const FIRST: &str = "int* [[scpp::lifetime(a)]] first(Vec* [[scpp::lifetime(mut a)]] v)";
+    let main = |use_borrow_later: bool| {
+        let mut body = vec![
+            local("v"),
+            Statement::Declaration("r".to_string(), call("first", vec![address_of("v")])),
+            Statement::Expression(call("print", vec![address_of("v")])),
+        ];
+        if use_borrow_later {
+            body.push(Statement::Expression(call("print", vec![var("r")])));
+        }
+        vec![function("main", &[], body)]
+    };
+
+    let error = analyze(main(true), &[FIRST]).unwrap_err();
+    assert!(error.starts_with("borrow"), "{}", error);
+    assert!(
+        error.contains("while 'v' is mutably borrowed by 'r'"),
+        "{}",
+        error
+    );
+
+    // The borrow ends after its last use
+    assert!(analyze(main(false), &[FIRST]).is_ok());
+}
+
+#[test]
+fn test_mutable_argument_aliasing_another_argument() {
+    const APPEND: &str =
+        "void append(Vec* [[scpp::lifetime(mut a)]] v, int* [[scpp::lifetime(b)]] x)";
+    let declarations = vec![function(
+        "main",
+        &[],
+        vec![
+            local("v"),
+            Statement::Expression(call("append", vec![address_of("v"), address_of("v")])),
+        ],
+    )];
+
+    let error = analyze(declarations, &[APPEND]).unwrap_err();
+    assert!(
+        error.contains("'v' is mutably borrowed by argument 1 of 'append'"),
+        "{}",
+        error
+    );
+}
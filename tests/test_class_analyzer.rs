@@ -0,0 +1,230 @@
+// tests/test_class_analyzer.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::class_analyzer::{ClassAnalyzer, ClassErrorKind};
+use safecpp::parser::cpp_parser::{
+    ClassDeclaration, Declaration, Expression, Method, MethodBody, Statement,
+};
+
+fn assign(target: Expression, value: Expression) -> Statement {
+    Statement::Expression(Expression::Assignment(Box::new(target), Box::new(value)))
+}
+
+fn new_array() -> Expression {
+    Expression::New("int".to_string(), Some(Box::new(var("n"))), None)
+}
+
+fn delete_data() -> Statement {
+    Statement::Expression(Expression::Delete(Box::new(var("data")), true))
+}
+
+fn method(name: &str, params: &[&str], body: MethodBody) -> Method {
+    Method {
+        name: name.to_string(),
+        params: params.iter().map(|param| param.to_string()).collect(),
+        initializers: vec![],
+        is_virtual: false,
+        body,
+    }
+}
+
+fn defined(statements: Vec<Statement>) -> MethodBody {
+    MethodBody::Defined(Box::new(Statement::Block(statements)))
+}
+
+// class Buffer { int *data; Buffer(int n) { data = new int[n]; } ... };
+fn buffer(mut methods: Vec<Method>) -> Declaration {
+    methods.insert(
+        0,
+        method(
+            "Buffer",
+            &["int n"],
+            defined(vec![assign(var("data"), new_array())]),
+        ),
+    );
+    Declaration::Class(ClassDeclaration {
+        name: "Buffer".to_string(),
+        location: None,
+        bases: vec![],
+        fields: vec![("int*".to_string(), "data".to_string())],
+        methods,
+    })
+}
+
+fn destructor() -> Method {
+    method("~Buffer", &[], defined(vec![delete_data()]))
+}
+
+fn analyze(declarations: &[Declaration]) -> Result<(), (ClassErrorKind, String)> {
+    ClassAnalyzer::new(declarations)
+        .analyze()
+        .map_err(|error| (error.kind, error.details))
+}
+
+fn assert_error(declarations: &[Declaration], kind: ClassErrorKind, message: &str) {
+    let (actual, details) = analyze(declarations).unwrap_err();
+    assert_eq!(actual, kind, "{}", details);
+    assert!(details.contains(message), "{}", details);
+}
+
+#[test]
+fn test_owning_class_without_destructor() {
+    assert_error(
+        &[buffer(vec![])],
+        ClassErrorKind::RuleOfThree,
+        "'Buffer' allocates 'data' in its constructor but has no destructor",
+    );
+}
+
+#[test]
+fn test_allocation_in_member_initializer() {
+    // class Buffer { int *data; Buffer(int n) : data(new int[n]) {} };
+    let mut constructor = method("Buffer", &["int n"], defined(vec![]));
+    constructor.initializers = vec![("data".to_string(), new_array())];
+    let class = Declaration::Class(ClassDeclaration {
+        name: "Buffer".to_string(),
+        location: None,
+        bases: vec![],
+        fields: vec![("int*".to_string(), "data".to_string())],
+        methods: vec![constructor],
+    });
+
+    assert_error(
+        &[class],
+        ClassErrorKind::RuleOfThree,
+        "'Buffer' allocates 'data' in its constructor but has no destructor",
+    );
+}
+
+#[test]
+fn test_rule_of_three() {
+    assert_error(
+        &[buffer(vec![destructor()])],
+        ClassErrorKind::RuleOfThree,
+        "no user-defined copy constructor",
+    );
+
+    // A defaulted copy is as shallow as the implicit one
+    assert_error(
+        &[buffer(vec![
+            destructor(),
+            method("Buffer", &["const Buffer& other"], MethodBody::Deleted),
+            method("operator=", &["const Buffer&"], MethodBody::Defaulted),
+        ])],
+        ClassErrorKind::RuleOfThree,
+        "no user-defined copy assignment operator",
+    );
+
+    // Non-copyable owners are fine; the destructor may also be defined out of line
+    let declarations = [
+        buffer(vec![
+            method("~Buffer", &[], MethodBody::Declared),
+            method("Buffer", &["const Buffer& other"], MethodBody::Deleted),
+            method("operator=", &["const Buffer& other"], MethodBody::Deleted),
+        ]),
+        Declaration::Function(
+            "Buffer::~Buffer".to_string(),
+            vec![],
+            Box::new(Statement::Block(vec![delete_data()])),
+        ),
+    ];
+    assert!(analyze(&declarations).is_ok());
+}
+
+#[test]
+fn test_rule_of_five() {
+    let declarations = [buffer(vec![
+        destructor(),
+        method("Buffer", &["const Buffer& other"], MethodBody::Deleted),
+        method("operator=", &["const Buffer& other"], MethodBody::Deleted),
+        method("Buffer", &["Buffer&& other"], defined(vec![])),
+    ])];
+
+    assert_error(
+        &declarations,
+        ClassErrorKind::RuleOfFive,
+        "declares move operations but no move assignment operator",
+    );
+}
+
+#[test]
+fn test_self_assignment_hazard() {
+    // Buffer& operator=(const Buffer& other) { delete[] data; data = new int[other.n]; ... }
+    let copy_assignment = |check: bool| {
+        let mut body = vec![
+            delete_data(),
+            assign(
+                var("data"),
+                Expression::New(
+                    "int".to_string(),
+                    Some(Box::new(Expression::MemberAccess(
+                        Box::new(var("other")),
+                        "n".to_string(),
+                    ))),
+                    None,
+                ),
+            ),
+        ];
+        if check {
+            // if (this != &other) { ... }
+            body = vec![Statement::If(
+                Expression::BinaryOperation(
+                    "!=".to_string(),
+                    Box::new(var("this")),
+                    Box::new(Expression::AddressOf(Box::new(var("other")))),
+                ),
+                Box::new(Statement::Block(body)),
+                Box::new(Statement::Block(vec![])),
+            )];
+        }
+        vec![buffer(vec![
+            destructor(),
+            method("Buffer", &["const Buffer& other"], MethodBody::Deleted),
+            method("operator=", &["const Buffer& other"], defined(body)),
+        ])]
+    };
+
+    assert_error(
+        &copy_assignment(false),
+        ClassErrorKind::SelfAssignment,
+        "frees 'data' before reading from 'other'",
+    );
+    assert!(analyze(&copy_assignment(true)).is_ok());
+}
+
+#[test]
+fn test_polymorphic_base_needs_virtual_destructor() {
+    let animal = |destructor: Option<Method>| {
+        let mut speak = method("speak", &[], MethodBody::Declared);
+        speak.is_virtual = true;
+        let mut methods = vec![speak];
+        methods.extend(destructor);
+        vec![
+            Declaration::Class(ClassDeclaration {
+                name: "Animal".to_string(),
+                location: None,
+                bases: vec![],
+                fields: vec![],
+                methods,
+            }),
+            Declaration::Class(ClassDeclaration {
+                name: "Dog".to_string(),
+                location: None,
+                bases: vec!["Animal".to_string()],
+                fields: vec![],
+                methods: vec![],
+            }),
+        ]
+    };
+
+    assert_error(
+        &animal(None),
+        ClassErrorKind::NonVirtualDestructor,
+        "'Animal' has virtual functions but a non-virtual destructor",
+    );
+
+    let mut destructor = method("~Animal", &[], MethodBody::Defaulted);
+    destructor.is_virtual = true;
+    assert!(analyze(&animal(Some(destructor))).is_ok());
+}
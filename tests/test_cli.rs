@@ -0,0 +1,358 @@
+// tests/test_cli.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// Frees `p` twice only when `n > 3`, so the problem needs a path
+const BRANCHY: &str = "\
+void f(int n) {
+    int* p = new int(1);
+    if (n > 3) {
+        delete p;
+    }
+    delete p;
+}
+";
+
+// A class that owns memory without copy operations, and a leak in a function
+const OWNER: &str = "\
+class Buffer {
+public:
+    Buffer() : data(new char[16]) {}
+    ~Buffer() { delete[] data; }
+private:
+    char* data;
+};
+
+void leak() {
+    int* p = new int(1);
+}
+";
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("safecpp-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, source: &str, args: &[&str]) -> Output {
+    let input = dir.join("input.cpp");
+    fs::write(&input, source).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_SafeCpp"))
+        .args(args)
+        .arg(&input)
+        .output()
+        .expect("Failed to run the binary")
+}
+
+#[test]
+fn test_symbolic_path_reaches_sarif_code_flows() {
+    let dir = scratch("sarif-path");
+    let output = run(&dir, BRANCHY, &["--format", "sarif"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("\"double-free\""), "{}", stdout);
+    assert!(stdout.contains("\"codeFlows\""), "{}", stdout);
+    assert!(stdout.contains("condition `n > 3` is true"), "{}", stdout);
+}
+
+#[test]
+fn test_symbolic_path_goes_on_the_finding_at_the_same_statement() {
+    let dir = scratch("path-match");
+    let source = "\
+void f(int c) {
+    int *a = new int(1);
+    int *b = new int(2);
+    if (c) {
+        delete b;
+    }
+}
+";
+    let output = run(&dir, source, &["--color=never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let leak_of_b = stdout
+        .split("\n\n")
+        .find(|finding| finding.contains("for 'b'"))
+        .unwrap_or_else(|| panic!("{}", stdout));
+    assert!(leak_of_b.contains("condition `c` is false"), "{}", stdout);
+}
+
+#[test]
+fn test_symbolic_path_reaches_html_report() {
+    let dir = scratch("html-path");
+    let report = dir.join("report");
+    run(&dir, BRANCHY, &["--html-report", report.to_str().unwrap()]);
+
+    let pages: Vec<String> = fs::read_dir(&report)
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    assert!(pages
+        .iter()
+        .any(|page| page.contains("condition `n &gt; 3` is true")));
+}
+
+#[test]
+fn test_lifetime_annotations_are_checked() {
+    let dir = scratch("lifetimes");
+    let source = "\
+int* [[scpp::lifetime(a)]] pick(int* [[scpp::lifetime(a)]] x, int* [[scpp::lifetime(b)]] y) {
+    return y;
+}
+";
+    let output = run(&dir, source, &["--color=never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("error[lifetime-mismatch]"), "{}", stdout);
+    assert!(!output.status.success());
+}
+
+fn run_format(name: &str, format: &str) -> String {
+    let dir = scratch(name);
+    let output = run(&dir, OWNER, &["--color=never", "--format", format]);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_text_output() {
+    let stdout = run_format("text", "text");
+    assert!(stdout.contains("warning[rule-of-three]"), "{}", stdout);
+    assert!(stdout.contains("error[memory-leak]"), "{}", stdout);
+    assert!(stdout.contains("input.cpp:10:"), "{}", stdout);
+}
+
+#[test]
+fn test_sarif_output() {
+    let stdout = run_format("sarif", "sarif");
+    assert!(stdout.contains("\"version\": \"2.1.0\""), "{}", stdout);
+    assert!(
+        stdout.contains("\"ruleId\": \"rule-of-three\""),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("\"ruleId\": \"memory-leak\""), "{}", stdout);
+}
+
+#[test]
+fn test_json_output() {
+    let stdout = run_format("json", "json");
+    assert!(stdout.contains("\"rule\": \"rule-of-three\""), "{}", stdout);
+    assert!(stdout.contains("\"function\": \"Buffer\""), "{}", stdout);
+    assert!(stdout.contains("\"function\": \"leak\""), "{}", stdout);
+}
+
+#[test]
+fn test_jsonl_output() {
+    let stdout = run_format("jsonl", "jsonl");
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines.len() >= 3, "{}", stdout);
+    assert!(lines.iter().all(|line| line.starts_with('{')), "{}", stdout);
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("\"rule\":\"memory-leak\"")));
+}
+
+#[test]
+fn test_jsonl_is_written_one_function_at_a_time() {
+    let dir = scratch("jsonl-order");
+    let source = "\
+#include <memory>
+#include <utility>
+void moves() {
+    std::unique_ptr<int> a = std::make_unique<int>(1);
+    auto b = std::move(a);
+    *a = 2;
+}
+void leaks() {
+    int* p = new int(1);
+}
+";
+    let output = run(&dir, source, &["--format", "jsonl"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let functions: Vec<&str> = stdout
+        .lines()
+        .map(|line| {
+            let start = line.find("\"function\":\"").unwrap() + "\"function\":\"".len();
+            &line[start..start + line[start..].find('"').unwrap()]
+        })
+        .collect();
+    // In source order, even though the leak is found by an analyzer that runs first
+    assert_eq!(functions, ["moves", "leaks"], "{}", stdout);
+}
+
+#[test]
+fn test_html_output() {
+    let dir = scratch("html");
+    let report = dir.join("report");
+    run(&dir, OWNER, &["--html-report", report.to_str().unwrap()]);
+
+    let index = fs::read_to_string(report.join("index.html")).unwrap();
+    assert!(index.contains("rule-of-three"), "{}", index);
+    assert!(index.contains("memory-leak"), "{}", index);
+}
+
+#[test]
+fn test_same_name_in_two_functions_has_two_fingerprints() {
+    let one = "\
+void f() {
+    std::string s = \"a\";
+    std::string t = std::move(s);
+    use(s);
+}
+";
+    let two = "\
+void f() {
+    std::string s = \"a\";
+    std::string t = std::move(s);
+    use(s);
+}
+
+void g() {
+    std::string s = \"a\";
+    std::string t = std::move(s);
+    use(s);
+}
+";
+    let dir = scratch("fingerprints");
+    let output = run(&dir, two, &["--format", "jsonl"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let fingerprints: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split("\"fingerprint\":\"").nth(1).unwrap())
+        .map(|rest| &rest[..16])
+        .collect();
+    assert_eq!(fingerprints.len(), 2, "{}", stdout);
+    assert_ne!(fingerprints[0], fingerprints[1]);
+    // Each use points into its own function
+    assert!(stdout.contains("\"line\":4"), "{}", stdout);
+    assert!(stdout.contains("\"line\":10"), "{}", stdout);
+
+    // A baseline taken before `g` was added does not hide the problem in `g`
+    let baseline = dir.join("baseline.json");
+    let baseline = baseline.to_str().unwrap();
+    run(&dir, one, &["--write-baseline", baseline]);
+    let output = run(&dir, two, &["--format", "jsonl", "--baseline", baseline]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    assert!(stdout.contains("\"function\":\"g\""), "{}", stdout);
+}
+
+#[test]
+fn test_baseline_matches_however_the_file_is_named() {
+    let dir = scratch("baseline-paths");
+    fs::write(dir.join("a.cpp"), OWNER).unwrap();
+    let safecpp = |cwd: &Path, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_SafeCpp"))
+            .current_dir(cwd)
+            .args(args)
+            .output()
+            .expect("Failed to run the binary")
+    };
+
+    safecpp(&dir, &["--write-baseline", "baseline.json", "./a.cpp"]);
+    let recorded = fs::read_to_string(dir.join("baseline.json")).unwrap();
+    assert!(recorded.contains("\"file\": \"a.cpp\""), "{}", recorded);
+
+    // Named differently, from another directory
+    let baseline = dir.join("baseline.json");
+    let input = dir.join("a.cpp");
+    let output = safecpp(
+        &std::env::temp_dir(),
+        &[
+            "--format",
+            "jsonl",
+            "--baseline",
+            baseline.to_str().unwrap(),
+            input.to_str().unwrap(),
+        ],
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "", "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_findings_point_at_their_statements() {
+    let dir = scratch("locations");
+    let source = "\
+#include <memory>
+#include <utility>
+void bounds() {
+    int a[4];
+    int i = 4;
+    a[i] = 0;
+}
+void moved() {
+    std::unique_ptr<int> a = std::make_unique<int>(1);
+    auto b = std::move(a);
+    *a = 2;
+}
+int* dangle() {
+    int x = 1;
+    return &x;
+}
+void freed() {
+    int *p = new int(1);
+    int *q = p;
+    delete p;
+    *q = 1;
+}
+class Buffer {
+public:
+    Buffer() : data(new int[4]) {}
+    ~Buffer() { delete[] data; }
+private:
+    int* data;
+};
+";
+    let output = run(&dir, source, &["--color=never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    for (rule, location) in [
+        ("out-of-bounds", "input.cpp:6:5"),
+        ("use-after-move", "input.cpp:11:6"),
+        ("dangling-reference", "input.cpp:15:5"),
+        ("use-after-free", "input.cpp:21:6"),
+        ("rule-of-three", "input.cpp:23:7"),
+    ] {
+        let found = stdout
+            .split("\n\n")
+            .any(|finding| finding.contains(&format!("[{}]", rule)) && finding.contains(location));
+        assert!(found, "{} at {}:\n{}", rule, location, stdout);
+    }
+    assert!(stdout.contains("- moved here"), "{}", stdout);
+    assert!(stdout.contains("-------- freed here"), "{}", stdout);
+}
+
+#[test]
+fn test_suppressions_on_the_line_of_the_use() {
+    let dir = scratch("suppress-use");
+    let source = "\
+void bounds() {
+    int a[4];
+    int i = 4;
+    a[i] = 0; // scpp-ignore[out-of-bounds]: known
+}
+int* dangle() {
+    int x = 1;
+    // scpp-ignore[dangling-reference]
+    return &x;
+}
+";
+    let output = run(&dir, source, &["--color=never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "No memory issues detected.\n");
+    assert!(output.status.success());
+
+    // Without the comments both are reported
+    let plain: String = source
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .map(|line| line.split(" //").next().unwrap().to_string() + "\n")
+        .collect();
+    let output = run(&dir, &plain, &["--color=never"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("error[out-of-bounds]"), "{}", stdout);
+    assert!(stdout.contains("error[dangling-reference]"), "{}", stdout);
+}
@@ -0,0 +1,226 @@
+// tests/test_container_analyzer.rs
+mod common;
+
+use common::{declare, method, var};
+use safecpp::analyzer::container_analyzer::ContainerAnalyzer;
+use safecpp::analyzer::memory_analyzer::MemoryErrorType;
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration, Expression, Statement};
+
+fn deref(name: &str) -> Expression {
+    Expression::Dereference(Box::new(var(name)))
+}
+
+fn container(name: &str, type_name: &str) -> Statement {
+    declare(name, Expression::Uninitialized(type_name.to_string()))
+}
+
+fn analyze(body: Vec<Statement>) -> Result<(), String> {
+    let declarations = vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(body)),
+    )];
+    let mut analyzer = ContainerAnalyzer::new(&declarations);
+    analyzer.analyze().map_err(|error| {
+        assert_eq!(error.error_type(), MemoryErrorType::InvalidatedIterator);
+        error.details().to_string()
+    })
+}
+
+#[test]
+fn test_iterator_used_after_push_back() {
+    // std::vector<int> v; auto it = v.begin(); v.push_back(1); int x = *it;
+    let program = |type_name: &str| {
+        vec![
+            container("v", type_name),
+            declare("it", method("v", "begin", vec![])),
+            Statement::Expression(method("v", "push_back", vec![Expression::Integer(1)])),
+            declare("x", deref("it")),
+        ]
+    };
+
+    let error = analyze(program("std::vector<int>")).unwrap_err();
+    assert!(
+        error
+            .contains("iterator 'it' into 'v' is used after it was invalidated by 'v.push_back()'"),
+        "{}",
+        error
+    );
+
+    // Growing a list leaves its iterators valid
+    assert!(analyze(program("std::list<int>")).is_ok());
+}
+
+#[test]
+fn test_element_reference_and_data_pointer() {
+    // int& first = v[0]; v.reserve(100); first = 2;
+    let element = Expression::AddressOf(Box::new(Expression::ArrayAccess(
+        "v".to_string(),
+        Box::new(Expression::Integer(0)),
+    )));
+    let program = vec![
+        container("v", "std::vector<int>"),
+        declare("first", element),
+        Statement::Expression(method("v", "reserve", vec![Expression::Integer(100)])),
+        Statement::Expression(Expression::Assignment(
+            Box::new(deref("first")),
+            Box::new(Expression::Integer(2)),
+        )),
+    ];
+    let error = analyze(program).unwrap_err();
+    assert!(error.contains("element reference 'first'"), "{}", error);
+
+    // int *p = s.data(); s.resize(10); p[0] = 'a';
+    let program = vec![
+        container("s", "std::string"),
+        declare("p", method("s", "data", vec![])),
+        Statement::Expression(method("s", "resize", vec![Expression::Integer(10)])),
+        Statement::Expression(Expression::Assignment(
+            Box::new(Expression::ArrayAccess(
+                "p".to_string(),
+                Box::new(Expression::Integer(0)),
+            )),
+            Box::new(Expression::Integer(97)),
+        )),
+    ];
+    let error = analyze(program).unwrap_err();
+    assert!(error.contains("data() pointer 'p'"), "{}", error);
+}
+
+#[test]
+fn test_erase_in_iterator_loop() {
+    // for (auto it = m.begin(); it != m.end(); ++it) if (*it == 0) m.erase(it);
+    let program = |erase: Statement| {
+        vec![
+            container("m", "std::map<int, int>"),
+            declare("it", method("m", "begin", vec![])),
+            Statement::While(
+                Expression::BinaryOperation(
+                    "!=".to_string(),
+                    Box::new(var("it")),
+                    Box::new(method("m", "end", vec![])),
+                ),
+                Box::new(Statement::Block(vec![
+                    Statement::If(
+                        var("done"),
+                        Box::new(erase),
+                        Box::new(Statement::Block(vec![])),
+                    ),
+                    Statement::Expression(Expression::UnaryOperation(
+                        "++".to_string(),
+                        Box::new(var("it")),
+                    )),
+                ])),
+            ),
+        ]
+    };
+
+    let error = analyze(program(Statement::Expression(method(
+        "m",
+        "erase",
+        vec![var("it")],
+    ))))
+    .unwrap_err();
+    assert!(
+        error.contains("may have been invalidated by 'm.erase()'"),
+        "{}",
+        error
+    );
+
+    // it = m.erase(it);
+    assert!(
+        analyze(program(Statement::Expression(Expression::Assignment(
+            Box::new(var("it")),
+            Box::new(method("m", "erase", vec![var("it")])),
+        ))))
+        .is_ok()
+    );
+}
+
+#[test]
+fn test_modifying_container_in_range_for() {
+    // for (int x : v) if (x == 0) v.erase(v.begin());
+    let program = |modify: &str| {
+        vec![
+            container("v", "std::vector<int>"),
+            Statement::RangeFor(
+                "x".to_string(),
+                var("v"),
+                Box::new(Statement::If(
+                    var("x"),
+                    Box::new(Statement::Expression(method(
+                        "v",
+                        modify,
+                        vec![method("v", "begin", vec![])],
+                    ))),
+                    Box::new(Statement::Block(vec![])),
+                )),
+            ),
+        ]
+    };
+
+    let error = analyze(program("erase")).unwrap_err();
+    assert!(
+        error.contains("'v' is modified by 'v.erase()' while a range-based for loop iterates"),
+        "{}",
+        error
+    );
+    assert!(analyze(program("count")).is_ok());
+}
+
+fn analyze_source(source: &str) -> Result<(), String> {
+    let declarations = parse_cpp_code(source).unwrap();
+    ContainerAnalyzer::new(&declarations)
+        .analyze()
+        .map_err(|error| error.details().to_string())
+}
+
+#[test]
+fn test_erase_by_key_in_range_for() {
+    for type_name in ["std::map<int, int>", "std::unordered_map<int, int>"] {
+        let source = format!(
+            "void f() {{ {} m; for (auto& kv : m) m.erase(kv.first); }}",
+            type_name
+        );
+        let error = analyze_source(&source).unwrap_err();
+        assert!(
+            error.contains("'m' is modified by 'm.erase()' while a range-based for loop"),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn test_parameter_types() {
+    let source = |type_name: &str| {
+        format!(
+            "void f({}& items) {{ auto it = items.begin(); items.push_back(1); int x = *it; }}",
+            type_name
+        )
+    };
+
+    // Growing a list keeps its iterators valid; growing a vector does not
+    assert!(analyze_source(&source("std::list<int>")).is_ok());
+    assert!(analyze_source(&source("std::vector<int>")).is_err());
+}
+
+#[test]
+fn test_reference_declarations() {
+    let source = |statement: &str| {
+        format!(
+            "void f() {{ std::vector<int> v(10); {} v.reserve(100); r = 3; }}",
+            statement
+        )
+    };
+
+    let error = analyze_source(&source("int &r = v[0];")).unwrap_err();
+    assert!(
+        error.contains("element reference 'r' into 'v' is used after it was invalidated"),
+        "{}",
+        error
+    );
+    assert!(analyze_source(&source("const int& r = v.front();")).is_err());
+    // A copy of the element stays valid, and assigning to it rebinds nothing
+    assert!(analyze_source(&source("int r = v[0];")).is_ok());
+}
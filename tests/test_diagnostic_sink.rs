@@ -0,0 +1,247 @@
+// tests/test_diagnostic_sink.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::class_analyzer::ClassAnalyzer;
+use safecpp::analyzer::container_analyzer::ContainerAnalyzer;
+use safecpp::analyzer::memory_analyzer::{MemoryAnalyzer, MemoryErrorType};
+use safecpp::analyzer::move_analyzer::MoveAnalyzer;
+use safecpp::analyzer::pointer_analyzer::PointerAnalyzer;
+use safecpp::analyzer::smart_pointer_analyzer::SmartPointerAnalyzer;
+use safecpp::diagnostics::{Diagnostic, DiagnosticSink, Limits, Severity};
+use safecpp::parser::cpp_parser::{
+    parse_cpp_code, ClassDeclaration, Declaration, Expression, Method, MethodBody, Statement,
+};
+
+fn new_int() -> Expression {
+    Expression::New("int".to_string(), None, None)
+}
+
+fn delete(name: &str) -> Statement {
+    Statement::Expression(Expression::Delete(Box::new(var(name)), false))
+}
+
+fn diagnostic(rule: &str, message: &str) -> Diagnostic {
+    Diagnostic::new(rule, message.to_string())
+}
+
+fn functions(sink: &DiagnosticSink) -> Vec<Option<&str>> {
+    sink.diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.function.as_deref())
+        .collect()
+}
+
+fn rules(sink: &DiagnosticSink) -> Vec<&str> {
+    sink.diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.rule.as_str())
+        .collect()
+}
+
+// int* p = new int; delete p; delete p; int* q = new int;
+fn double_free_and_leak() -> Vec<Statement> {
+    vec![Statement::Block(vec![
+        Statement::Declaration("p".to_string(), new_int()),
+        delete("p"),
+        delete("p"),
+        Statement::Declaration("q".to_string(), new_int()),
+    ])]
+}
+
+#[test]
+fn test_analyzer_reports_every_error() {
+    let program = double_free_and_leak();
+
+    let error = MemoryAnalyzer::new(&[]).analyze(&program).unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::DoubleFree);
+
+    let mut sink = DiagnosticSink::new();
+    MemoryAnalyzer::new(&[]).analyze_into(&program, &mut sink);
+    assert_eq!(rules(&sink), ["double-free", "memory-leak"]);
+    assert!(sink.has_errors());
+}
+
+#[test]
+fn test_class_analyzer_reports_every_missing_member() {
+    // class Buffer { int* data; Buffer() { data = new int; } };
+    let constructor = Method {
+        name: "Buffer".to_string(),
+        params: vec![],
+        initializers: vec![],
+        is_virtual: false,
+        body: MethodBody::Defined(Box::new(Statement::Expression(Expression::Assignment(
+            Box::new(var("data")),
+            Box::new(new_int()),
+        )))),
+    };
+    let declarations = [Declaration::Class(ClassDeclaration {
+        name: "Buffer".to_string(),
+        location: None,
+        bases: vec![],
+        fields: vec![("int*".to_string(), "data".to_string())],
+        methods: vec![constructor],
+    })];
+
+    let mut sink = DiagnosticSink::new();
+    ClassAnalyzer::new(&declarations).analyze_into(&mut sink);
+    assert_eq!(sink.diagnostics().len(), 3);
+    assert_eq!(sink.count(Severity::Warning), 3);
+    assert!(!sink.has_errors());
+    assert_eq!(functions(&sink), [Some("Buffer"); 3]);
+}
+
+#[test]
+fn test_analyzers_name_the_function() {
+    let source = r#"
+        void clean() {
+            int x = 1;
+        }
+
+        void moves() {
+            std::string s = "a";
+            std::string t = std::move(s);
+            use(s);
+        }
+
+        void grows() {
+            std::vector<int> v;
+            auto it = v.begin();
+            v.push_back(1);
+            use(*it);
+        }
+
+        void owns() {
+            std::unique_ptr<int> p = std::make_unique<int>(1);
+            std::unique_ptr<int> q = std::move(p);
+            use(*p);
+        }
+
+        int* dangles() {
+            int local = 1;
+            return &local;
+        }
+    "#;
+    let declarations = parse_cpp_code(source).unwrap();
+
+    let mut sink = DiagnosticSink::new();
+    MoveAnalyzer::new(&declarations).analyze_into(&mut sink);
+    assert_eq!(functions(&sink), [Some("moves"), Some("owns")]);
+
+    let mut sink = DiagnosticSink::new();
+    ContainerAnalyzer::new(&declarations).analyze_into(&mut sink);
+    assert_eq!(functions(&sink), [Some("grows")]);
+
+    let mut sink = DiagnosticSink::new();
+    SmartPointerAnalyzer::new(&declarations).analyze_into(&mut sink);
+    assert_eq!(functions(&sink), [Some("owns")]);
+
+    let mut sink = DiagnosticSink::new();
+    PointerAnalyzer::new(declarations).analyze_into(&mut sink);
+    assert_eq!(functions(&sink), [Some("dangles")]);
+}
+
+#[test]
+fn test_limits() {
+    let mut sink = DiagnosticSink::with_limits(Limits {
+        max_per_file: Some(3),
+        max_per_rule: Some(2),
+    });
+    assert!(sink.push(diagnostic("double-free", "first")));
+    assert!(sink.push(diagnostic("double-free", "second")));
+    assert!(!sink.push(diagnostic("double-free", "third")));
+    assert!(sink.push(diagnostic("memory-leak", "fourth")));
+    assert!(sink.is_full());
+    assert!(!sink.push(diagnostic("use-after-free", "fifth")));
+
+    assert_eq!(rules(&sink), ["double-free", "double-free", "memory-leak"]);
+    assert_eq!(sink.dropped(), 2);
+}
+
+#[test]
+fn test_duplicates_are_kept_once() {
+    let mut sink = DiagnosticSink::new();
+    sink.extend([
+        diagnostic("memory-leak", "'p' is never freed"),
+        diagnostic("memory-leak", "'p' is never freed"),
+        diagnostic("memory-leak", "'p' is never freed").in_function("f"),
+    ]);
+    assert_eq!(sink.diagnostics().len(), 2);
+    assert_eq!(sink.dropped(), 0);
+}
+
+#[test]
+fn test_dereference_after_move_is_reported_once() {
+    let source = "
+        void owns() {
+            std::unique_ptr<int> a = std::make_unique<int>(1);
+            auto b = std::move(a);
+            *a = 2;
+        }
+    ";
+    let declarations = parse_cpp_code(source).unwrap();
+
+    let mut sink = DiagnosticSink::new();
+    MoveAnalyzer::new(&declarations).analyze_into(&mut sink);
+    SmartPointerAnalyzer::new(&declarations).analyze_into(&mut sink);
+    let diagnostics = sink.into_diagnostics();
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+    assert_eq!(diagnostics[0].rule, "use-after-move");
+    // The null dereference it stands for is an error
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+}
+
+#[test]
+fn test_one_function_at_a_time_finds_the_same() {
+    let source = r#"
+        int* global = new int(1);
+
+        void escape() {
+            int x = 1;
+            global = &x;
+        }
+
+        void moves() {
+            std::unique_ptr<int> a = std::make_unique<int>(1);
+            auto b = std::move(a);
+            *a = 2;
+        }
+
+        void grows(std::vector<int>& v) {
+            auto it = v.begin();
+            v.push_back(1);
+            use(*it);
+        }
+    "#;
+    let declarations = parse_cpp_code(source).unwrap();
+    let functions: Vec<usize> = (0..declarations.len())
+        .filter(|&index| matches!(declarations[index], Declaration::Function(..)))
+        .collect();
+
+    let mut whole = DiagnosticSink::new();
+    MoveAnalyzer::new(&declarations).analyze_into(&mut whole);
+    ContainerAnalyzer::new(&declarations).analyze_into(&mut whole);
+    SmartPointerAnalyzer::new(&declarations).analyze_into(&mut whole);
+    PointerAnalyzer::new(declarations.clone()).analyze_into(&mut whole);
+
+    let mut by_function = DiagnosticSink::new();
+    let mut move_analyzer = MoveAnalyzer::new(&declarations);
+    let mut container_analyzer = ContainerAnalyzer::new(&declarations);
+    let mut smart_pointer_analyzer = SmartPointerAnalyzer::new(&declarations);
+    let mut pointer_analyzer = PointerAnalyzer::new(declarations.clone());
+    for &index in &functions {
+        move_analyzer.analyze_function_into(index, &mut by_function);
+    }
+    for &index in &functions {
+        container_analyzer.analyze_function_into(index, &mut by_function);
+    }
+    for &index in &functions {
+        smart_pointer_analyzer.analyze_function_into(index, &mut by_function);
+    }
+    for &index in &functions {
+        pointer_analyzer.analyze_function_into(index, &mut by_function);
+    }
+
+    assert!(whole.diagnostics().len() >= 3, "{:?}", whole.diagnostics());
+    assert_eq!(by_function.diagnostics(), whole.diagnostics());
+}
@@ -0,0 +1,118 @@
+// tests/test_diagnostics.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::array_analyzer::ArrayAnalyzer;
+use safecpp::analyzer::class_analyzer::{ClassError, ClassErrorKind};
+use safecpp::analyzer::memory_analyzer::{MemoryError, MemoryErrorType};
+use safecpp::analyzer::pointer_analyzer::{PointerError, PointerErrorKind};
+use safecpp::analyzer::symbolic_executor::SymbolicExecutor;
+use safecpp::diagnostics::{rules, Diagnostic, Severity, Span};
+use safecpp::error::error::Error;
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+#[test]
+fn test_conversions_assign_rules() {
+    let memory = Diagnostic::from(MemoryError::new(
+        MemoryErrorType::DoubleFree,
+        "Double free attempt on variable: p".to_string(),
+    ));
+    assert_eq!(memory.rule, "double-free");
+    assert_eq!(memory.severity, Severity::Error);
+    assert_eq!(memory.message, "Double free attempt on variable: p");
+
+    // Both analyzers report use after free under the same rule
+    let pointer = Diagnostic::from(PointerError::new(
+        PointerErrorKind::UseAfterFree,
+        "Use after free: 'q' dereferenced".to_string(),
+    ));
+    assert_eq!(pointer.rule, "use-after-free");
+    let wrapped = Diagnostic::from(Error::from(MemoryError::new(
+        MemoryErrorType::UseAfterFree,
+        String::new(),
+    )));
+    assert_eq!(wrapped.rule, pointer.rule);
+
+    let class = Diagnostic::from(ClassError::new(
+        ClassErrorKind::NonVirtualDestructor,
+        "Animal",
+        "'Animal' has virtual functions but a non-virtual destructor".to_string(),
+    ));
+    assert_eq!(class.severity, Severity::Warning);
+    assert_eq!(
+        class.help.as_deref(),
+        Some("declare the destructor virtual")
+    );
+
+    let program = vec![
+        Statement::Expression(Expression::ArrayDeclaration(
+            "a".to_string(),
+            Box::new(Expression::Integer(2)),
+        )),
+        Statement::Expression(Expression::ArrayAccess(
+            "a".to_string(),
+            Box::new(Expression::Integer(2)),
+        )),
+    ];
+    let array = Diagnostic::from(ArrayAnalyzer::new(&program).analyze().unwrap_err());
+    assert_eq!(array.rule, "out-of-bounds");
+}
+
+#[test]
+fn test_every_rule_is_unique() {
+    for (i, rule) in rules::RULES.iter().enumerate() {
+        assert!(!rule.description.is_empty());
+        assert!(rules::RULES[..i].iter().all(|other| other.id != rule.id));
+    }
+}
+
+#[test]
+fn test_display() {
+    let diagnostic = Diagnostic::new("null-dereference", "'p' may be null here".to_string())
+        .in_function("main")
+        .with_primary(Span::new(3, 5, 2), "dereferenced here")
+        .with_note("'p' comes from malloc".to_string());
+
+    assert_eq!(
+        diagnostic.to_string(),
+        "error[null-dereference]: 'p' may be null here at 3:5\n  \
+         = note: 'p' comes from malloc\n  \
+         = help: compare the pointer against nullptr before using it"
+    );
+}
+
+#[test]
+fn test_symbolic_path_becomes_notes() {
+    // void f(int n) { int* p = new int; if (n > 0) { return; } delete p; }
+    let declarations = [Declaration::Function(
+        "f".to_string(),
+        vec!["n".to_string()],
+        Box::new(Statement::Block(vec![
+            Statement::Declaration(
+                "p".to_string(),
+                Expression::New("int".to_string(), None, None),
+            ),
+            Statement::If(
+                Expression::BinaryOperation(
+                    ">".to_string(),
+                    Box::new(var("n")),
+                    Box::new(Expression::Integer(0)),
+                ),
+                Box::new(Statement::Return(Expression::Integer(0))),
+                Box::new(Statement::Block(vec![])),
+            ),
+            Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+        ])),
+    )];
+
+    let mut executor = SymbolicExecutor::new(&declarations);
+    let finding = executor.analyze()[0].clone();
+    let diagnostic = Diagnostic::from(finding);
+
+    assert_eq!(diagnostic.rule, "memory-leak");
+    assert_eq!(diagnostic.function.as_deref(), Some("f"));
+    assert_eq!(
+        diagnostic.notes,
+        vec!["condition `n > 0` is true".to_string()]
+    );
+}
@@ -0,0 +1,250 @@
+// tests/test_function_summaries.rs
+mod common;
+
+use common::{call, function, var};
+use std::collections::BTreeSet;
+
+use safecpp::analyzer::memory_analyzer::{MemoryAnalyzer, MemoryErrorType};
+use safecpp::analyzer::pointer_analyzer::{PointerAnalyzer, PointerErrorKind};
+use safecpp::analyzer::summaries::FunctionSummaries;
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+fn deref(name: &str) -> Expression {
+    Expression::Dereference(Box::new(var(name)))
+}
+
+fn indices(indices: &[usize]) -> BTreeSet<usize> {
+    indices.iter().copied().collect()
+}
+
+// void destroy(int* p) { *p = 0; delete p; }
+fn destroy() -> Declaration {
+    function(
+        "destroy",
+        &["int* p"],
+        vec![
+            Statement::Expression(Expression::Assignment(
+                Box::new(deref("p")),
+                Box::new(Expression::Integer(0)),
+            )),
+            Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+        ],
+    )
+}
+
+// int* make() { return new int; }
+fn make() -> Declaration {
+    function(
+        "make",
+        &[],
+        vec![Statement::Return(Expression::New(
+            "int".to_string(),
+            None,
+            None,
+        ))],
+    )
+}
+
+#[test]
+fn test_summary_effects() {
+    let declarations = [
+        destroy(),
+        make(),
+        // int* pick(int* a, int* b) { if (a) return a; return nullptr; }
+        function(
+            "pick",
+            &["int* a", "int* b"],
+            vec![
+                Statement::If(
+                    var("a"),
+                    Box::new(Statement::Return(var("a"))),
+                    Box::new(Statement::Block(vec![])),
+                ),
+                Statement::Return(Expression::Null),
+            ],
+        ),
+        // void remember(int* p) { cache = p; }
+        function(
+            "remember",
+            &["int* p"],
+            vec![Statement::Expression(Expression::Assignment(
+                Box::new(var("cache")),
+                Box::new(var("p")),
+            ))],
+        ),
+        // int* make_shared() { int* p = make(); remember(p); return p; }
+        function(
+            "make_shared",
+            &[],
+            vec![
+                Statement::Declaration("p".to_string(), call("make", vec![])),
+                Statement::Expression(call("remember", vec![var("p")])),
+                Statement::Return(var("p")),
+            ],
+        ),
+    ];
+    let summaries = FunctionSummaries::compute(&declarations);
+
+    let destroy = summaries.get("destroy").unwrap();
+    assert_eq!(destroy.frees, indices(&[0]));
+    assert_eq!(destroy.dereferences, indices(&[0]));
+
+    let make = summaries.get("make").unwrap();
+    assert!(make.returns_fresh && !make.may_return_null);
+
+    let pick = summaries.get("pick").unwrap();
+    assert_eq!(pick.returned_params, indices(&[0]));
+    assert!(pick.may_return_null);
+
+    assert_eq!(summaries.get("remember").unwrap().stores, indices(&[0]));
+    // The allocation is also kept in `cache`, so the caller does not own it
+    assert!(!summaries.get("make_shared").unwrap().returns_fresh);
+}
+
+#[test]
+fn test_recursive_summaries_reach_a_fixpoint() {
+    // void release(int* p, int n) { if (n) drop(p, n - 1); else free(p); }
+    // void drop(int* p, int n) { release(p, n); }
+    let declarations = [
+        function(
+            "release",
+            &["int* p", "int n"],
+            vec![Statement::If(
+                var("n"),
+                Box::new(Statement::Expression(call(
+                    "drop",
+                    vec![
+                        var("p"),
+                        Expression::BinaryOperation(
+                            "-".to_string(),
+                            Box::new(var("n")),
+                            Box::new(Expression::Integer(1)),
+                        ),
+                    ],
+                ))),
+                Box::new(Statement::Expression(call("free", vec![var("p")]))),
+            )],
+        ),
+        function(
+            "drop",
+            &["int* p", "int n"],
+            vec![Statement::Expression(call(
+                "release",
+                vec![var("p"), var("n")],
+            ))],
+        ),
+    ];
+    let summaries = FunctionSummaries::compute(&declarations);
+
+    assert_eq!(summaries.get("release").unwrap().frees, indices(&[0]));
+    assert_eq!(summaries.get("drop").unwrap().frees, indices(&[0]));
+}
+
+#[test]
+fn test_memory_checks_at_call_sites() {
+    let declarations = [destroy(), make()];
+    let analyze = |program: Vec<Statement>| MemoryAnalyzer::new(&declarations).analyze(&program);
+
+    // int* p = new int; destroy(p); destroy(p);
+    let error = analyze(vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::Expression(call("destroy", vec![var("p")])),
+        Statement::Expression(call("destroy", vec![var("p")])),
+    ])
+    .unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::DoubleFree);
+    assert!(
+        error.details().contains("passed to 'destroy'"),
+        "{}",
+        error.details()
+    );
+
+    // int* p = make(); return;
+    let error = analyze(vec![
+        Statement::Declaration("p".to_string(), call("make", vec![])),
+        Statement::Return(Expression::Integer(0)),
+    ])
+    .unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::MemoryLeak);
+    assert!(
+        error.details().contains("returned by make()"),
+        "{}",
+        error.details()
+    );
+
+    // int* p = make(); destroy(p);
+    assert!(analyze(vec![
+        Statement::Declaration("p".to_string(), call("make", vec![])),
+        Statement::Expression(call("destroy", vec![var("p")])),
+    ])
+    .is_ok());
+
+    // int* p = nullptr; destroy(p);
+    let error = analyze(vec![
+        Statement::Declaration("p".to_string(), Expression::Null),
+        Statement::Expression(call("destroy", vec![var("p")])),
+    ])
+    .unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::NullPointerDereference);
+}
+
+#[test]
+fn test_may_return_null_result() {
+    // int* find(int* p) { if (p) return p; return nullptr; }
+    let declarations = [function(
+        "find",
+        &["int* p"],
+        vec![
+            Statement::If(
+                var("p"),
+                Box::new(Statement::Return(var("p"))),
+                Box::new(Statement::Block(vec![])),
+            ),
+            Statement::Return(Expression::Null),
+        ],
+    )];
+    // int* q = find(p); int v = *q;
+    let program = vec![
+        Statement::Declaration("q".to_string(), call("find", vec![var("p")])),
+        Statement::Declaration("v".to_string(), deref("q")),
+    ];
+
+    let error = MemoryAnalyzer::new(&declarations)
+        .analyze(&program)
+        .unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::NullPointerDereference);
+}
+
+#[test]
+fn test_use_after_free_through_callee() {
+    // int* p = new int; destroy(p); *p = 1;
+    let declarations = vec![
+        destroy(),
+        function(
+            "main",
+            &[],
+            vec![
+                Statement::Declaration(
+                    "p".to_string(),
+                    Expression::New("int".to_string(), None, None),
+                ),
+                Statement::Expression(call("destroy", vec![var("p")])),
+                Statement::Expression(Expression::Assignment(
+                    Box::new(deref("p")),
+                    Box::new(Expression::Integer(1)),
+                )),
+            ],
+        ),
+    ];
+
+    let error = PointerAnalyzer::new(declarations).analyze().unwrap_err();
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+    assert!(
+        error.details.contains("'p' dereferenced"),
+        "{}",
+        error.details
+    );
+}
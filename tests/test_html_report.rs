@@ -0,0 +1,107 @@
+// tests/test_html_report.rs
+use safecpp::diagnostics::html::write_html_report;
+use safecpp::diagnostics::{Diagnostic, SourceFile};
+use std::fs;
+use std::path::PathBuf;
+
+const SOURCE: &str = "\
+void release(bool done) {
+    int* p = new int;
+    if (done) {
+        delete p;
+    }
+    if (*p < 1) {
+        return;
+    }
+}
+";
+
+fn report_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("safecpp-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn findings(source: &SourceFile) -> Vec<Diagnostic> {
+    let use_after_free = Diagnostic::new(
+        "use-after-free",
+        "Use after free: 'p' is dereferenced after delete".to_string(),
+    )
+    .in_function("release")
+    .with_step(
+        "if (done)".to_string(),
+        "taking the true branch".to_string(),
+    );
+    let leak = Diagnostic::new(
+        "memory-leak",
+        "Memory leak: memory allocated with new for 'p' is never freed".to_string(),
+    )
+    .in_function("release");
+    vec![source.locate(use_after_free), source.locate(leak)]
+}
+
+#[test]
+fn test_index_groups_findings_by_rule() {
+    let source = SourceFile::new("release.cpp", SOURCE);
+    let dir = report_dir("index");
+    write_html_report(&dir, &source, &findings(&source)).unwrap();
+
+    let index = fs::read_to_string(dir.join("index.html")).unwrap();
+    assert!(index.contains("<code>memory-leak</code> (1)"));
+    assert!(index.contains("<code>use-after-free</code> (1)"));
+    assert!(index.contains("href=\"finding-1.html\""));
+    assert!(index.contains("href=\"finding-2.html\""));
+    assert!(index.contains("<td>release.cpp</td><td>release</td><td>6:10</td>"));
+    // Memory leaks come before use after free in the rule order
+    assert!(index.find("memory-leak").unwrap() < index.find("use-after-free").unwrap());
+    assert!(dir.join("finding-1.html").exists());
+    assert!(dir.join("finding-2.html").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_finding_page_highlights_path() {
+    let source = SourceFile::new("release.cpp", SOURCE);
+    let dir = report_dir("finding");
+    write_html_report(&dir, &source, &findings(&source)).unwrap();
+
+    let page = fs::read_to_string(dir.join("finding-1.html")).unwrap();
+    // Allocation, branch, free and use, numbered in source order
+    let steps = [
+        "<span class=\"badge\">1</span>allocated here",
+        "<span class=\"badge\">2</span>taking the true branch",
+        "<span class=\"badge\">3</span>freed here",
+        "<span class=\"badge\">4</span>used here",
+    ];
+    let positions: Vec<usize> = steps
+        .iter()
+        .map(|step| {
+            page.find(step)
+                .unwrap_or_else(|| panic!("missing {}", step))
+        })
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(page.contains("<tr class=\"primary\"><td class=\"number\" id=\"L6\">6</td>"));
+    assert!(page.contains("<tr class=\"marked\"><td class=\"number\" id=\"L4\">4</td>"));
+    // Source text is escaped, and the whole function is shown
+    assert!(page.contains("if (*p &lt; 1) {"));
+    assert!(page.contains("id=\"L1\""));
+    assert!(page.contains("id=\"L9\""));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_report_is_self_contained() {
+    let source = SourceFile::new("release.cpp", SOURCE);
+    let dir = report_dir("offline");
+    write_html_report(&dir, &source, &findings(&source)).unwrap();
+
+    for entry in fs::read_dir(&dir).unwrap() {
+        let page = fs::read_to_string(entry.unwrap().path()).unwrap();
+        assert!(page.contains("<style>"));
+        assert!(!page.contains("http"));
+        assert!(!page.contains("<script"));
+        assert!(!page.contains("<link"));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,53 +1,68 @@
 // tests/test_integration.rs
-use safecpp::analyzer::{pointer_analyzer::PointerAnalyzer, memory_analyzer::MemoryAnalyzer};
-use safecpp::parser::cpp_lexer::{Lexer};
-use safecpp::parser::cpp_parser::{Declaration, Parser};
-use safecpp::error::error::MemoryError;
+use safecpp::analyzer::{memory_analyzer::MemoryAnalyzer, pointer_analyzer::PointerAnalyzer};
+use safecpp::analyzer::memory_analyzer::MemoryErrorType;
+use safecpp::parser::cpp_lexer::Lexer;
+use safecpp::parser::cpp_parser::{Declaration, Parser, Statement};
+
+fn parse(input: &str) -> Vec<Declaration> {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse().expect("Failed to parse")
+}
+
+fn body(declarations: &[Declaration]) -> &Statement {
+    match &declarations[0] {
+        Declaration::Function(_, _, body) => body,
+        _ => panic!("Expected a function"),
+    }
+}
 
 #[test]
 fn test_integration_pointer_and_memory_analyzer() {
     let input = r#"
-    int *x = new int;
-    *x = 42;
-    int y = *x;
-    delete x;
+    void f() {
+        int *x = new int;
+        *x = 42;
+        int y = *x;
+        delete x;
+    }
     "#;
+    let declarations = parse(input);
 
-    let lexer = Lexer::new(input);
-    let mut parser = Parser::new(lexer);
-    let declarations = parser.parse().expect("Failed to parse");
-
-    let mut pointer_analyzer = PointerAnalyzer::new();
-    let pointer_result = pointer_analyzer.analyze(&declarations);
+    let mut memory_analyzer = MemoryAnalyzer::new(&declarations);
+    let memory_result = memory_analyzer.analyze(std::slice::from_ref(body(&declarations)));
 
-    assert!(pointer_result.is_ok());
+    assert!(memory_result.is_ok());
 
-    let mut memory_analyzer = MemoryAnalyzer::new();
-    let memory_result = memory_analyzer.analyze(&declarations);
+    let mut pointer_analyzer = PointerAnalyzer::new(parse(input));
+    let pointer_result = pointer_analyzer.analyze();
 
-    assert!(memory_result.is_ok());
+    assert!(pointer_result.is_ok());
 }
 
 #[test]
 fn test_integration_pointer_and_memory_analyzer_with_error() {
     let input = r#"
-    int *x = new int;
-    int y = *x;
-    delete x;
-    delete x;
+    void f() {
+        int *x = new int;
+        *x = 1;
+        int y = *x;
+        delete x;
+        delete x;
+    }
     "#;
+    let declarations = parse(input);
 
-    let lexer = Lexer::new(input);
-    let mut parser = Parser::new(lexer);
-    let declarations = parser.parse().expect("Failed to parse");
-
-    let mut pointer_analyzer = PointerAnalyzer::new();
-    let pointer_result = pointer_analyzer.analyze(&declarations);
+    let mut memory_analyzer = MemoryAnalyzer::new(&declarations);
+    let memory_result = memory_analyzer.analyze(std::slice::from_ref(body(&declarations)));
 
-    assert!(pointer_result.is_ok());
+    assert!(matches!(
+        memory_result,
+        Err(error) if error.error_type() == MemoryErrorType::DoubleFree
+    ));
 
-    let mut memory_analyzer = MemoryAnalyzer::new();
-    let memory_result = memory_analyzer.analyze(&declarations);
+    let mut pointer_analyzer = PointerAnalyzer::new(parse(input));
+    let pointer_result = pointer_analyzer.analyze();
 
-    assert!(matches!(memory_result, Err(MemoryError::DoubleFree {..})));
+    assert!(pointer_result.is_ok());
 }
@@ -0,0 +1,30 @@
+// tests/test_json.rs
+use safecpp::json::Json;
+
+#[test]
+fn test_round_trip() {
+    let source = r#"{"name": "a\"b\n", "values": [1, -2.5, true, null], "empty": {}}"#;
+    let value = Json::parse(source).unwrap();
+
+    assert_eq!(value.get("name").and_then(Json::as_str), Some("a\"b\n"));
+    assert_eq!(
+        value
+            .get("values")
+            .and_then(Json::as_array)
+            .map(<[Json]>::len),
+        Some(4)
+    );
+    assert_eq!(
+        value.to_string(),
+        r#"{"name":"a\"b\n","values":[1,-2.5,true,null],"empty":{}}"#
+    );
+    assert_eq!(Json::parse(&value.pretty()).unwrap(), value);
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = Json::parse("[1,\n  2,,]").unwrap_err();
+    assert_eq!((error.line, error.column), (2, 5));
+    assert!(Json::parse("{} {}").is_err());
+    assert!(Json::parse("\"unterminated").is_err());
+}
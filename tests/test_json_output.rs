@@ -0,0 +1,94 @@
+// tests/test_json_output.rs
+use safecpp::diagnostics::export::{json_line, json_report, SCHEMA_VERSION};
+use safecpp::diagnostics::{Diagnostic, Fix, SourceFile, Span};
+use safecpp::json::Json;
+
+const SOURCE: &str = "\
+void f() {
+    int* p = new int;
+    delete p;
+    delete p;
+}
+";
+
+fn double_free(source: &SourceFile) -> Diagnostic {
+    let diagnostic = Diagnostic::new(
+        "double-free",
+        "Double free attempt on variable: p".to_string(),
+    )
+    .in_function("f")
+    .with_fix(Fix {
+        message: "remove the second delete".to_string(),
+        span: Span::new(4, 5, 9),
+        replacement: String::new(),
+    });
+    source.locate(diagnostic)
+}
+
+#[test]
+fn test_json_line() {
+    let source = SourceFile::new("f.cpp", SOURCE);
+    let line = json_line(&source, &double_free(&source));
+    assert!(!line.contains('\n'));
+
+    let diagnostic = Json::parse(&line).unwrap();
+    assert_eq!(
+        diagnostic.get("schema_version").and_then(Json::as_usize),
+        Some(SCHEMA_VERSION)
+    );
+    assert_eq!(
+        diagnostic.get("rule").and_then(Json::as_str),
+        Some("double-free")
+    );
+    assert_eq!(
+        diagnostic.get("severity").and_then(Json::as_str),
+        Some("error")
+    );
+    assert_eq!(diagnostic.get("file").and_then(Json::as_str), Some("f.cpp"));
+    assert_eq!(diagnostic.get("function").and_then(Json::as_str), Some("f"));
+
+    let span = diagnostic.get("span").unwrap();
+    assert_eq!(span.get("line").and_then(Json::as_usize), Some(4));
+    assert_eq!(span.get("column").and_then(Json::as_usize), Some(12));
+    assert_eq!(
+        span.get("label").and_then(Json::as_str),
+        Some("freed again here")
+    );
+    let related = diagnostic.get("related").and_then(Json::as_array).unwrap();
+    assert_eq!(related[0].get("line").and_then(Json::as_usize), Some(3));
+
+    let fix = &diagnostic.get("fixes").and_then(Json::as_array).unwrap()[0];
+    assert_eq!(fix.get("replacement").and_then(Json::as_str), Some(""));
+    assert_eq!(
+        fix.get("span")
+            .and_then(|span| span.get("length"))
+            .and_then(Json::as_usize),
+        Some(9)
+    );
+    assert!(diagnostic
+        .get("fingerprint")
+        .and_then(Json::as_str)
+        .is_some());
+}
+
+#[test]
+fn test_json_report() {
+    let source = SourceFile::new("f.cpp", SOURCE);
+    let unlocated = Diagnostic::new("rule-of-three", "'Buffer' has no destructor".to_string());
+    let diagnostics = [double_free(&source), source.locate(unlocated)];
+    let report = Json::parse(&json_report(&source, &diagnostics, 2).pretty()).unwrap();
+
+    assert_eq!(
+        report.get("schema_version").and_then(Json::as_usize),
+        Some(SCHEMA_VERSION)
+    );
+    let entries = report.get("diagnostics").and_then(Json::as_array).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].get("span"), Some(&Json::Null));
+    assert_eq!(entries[1].get("function"), Some(&Json::Null));
+
+    let summary = report.get("summary").unwrap();
+    assert_eq!(summary.get("errors").and_then(Json::as_usize), Some(1));
+    assert_eq!(summary.get("warnings").and_then(Json::as_usize), Some(1));
+    assert_eq!(summary.get("dropped").and_then(Json::as_usize), Some(2));
+}
@@ -0,0 +1,445 @@
+// tests/test_memory_analyzer.rs
+mod common;
+
+use common::{call, var};
+use safecpp::analyzer::memory_analyzer::{MemoryAnalyzer, MemoryErrorType};
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration, Expression, Statement};
+
+fn new_array() -> Expression {
+    Expression::New(
+        "int".to_string(),
+        Some(Box::new(Expression::Integer(5))),
+        None,
+    )
+}
+
+#[test]
+fn test_matching_deallocation() {
+    let program = vec![
+        Statement::Declaration("p".to_string(), new_array()),
+        Statement::Expression(Expression::Delete(Box::new(var("p")), true)),
+        Statement::Declaration(
+            "q".to_string(),
+            call("malloc", vec![Expression::Integer(4)]),
+        ),
+        Statement::Expression(call("free", vec![var("q")])),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    assert!(analyzer.analyze(&program).is_ok());
+}
+
+#[test]
+fn test_delete_on_new_array() {
+    let program = vec![
+        Statement::Declaration("p".to_string(), new_array()),
+        Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let error = analyzer.analyze(&program).unwrap_err();
+
+    assert_eq!(error.error_type(), MemoryErrorType::MismatchedDeallocation);
+}
+
+#[test]
+fn test_free_on_new() {
+    let program = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::Expression(call("free", vec![var("p")])),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let error = analyzer.analyze(&program).unwrap_err();
+
+    assert_eq!(error.error_type(), MemoryErrorType::MismatchedDeallocation);
+}
+
+#[test]
+fn test_registered_allocation_pair() {
+    let program = vec![
+        Statement::Declaration("f".to_string(), call("fopen", vec![var("path")])),
+        Statement::Expression(call("free", vec![var("f")])),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    analyzer.register_allocation_pair("fopen", "fclose");
+    let error = analyzer.analyze(&program).unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::MismatchedDeallocation);
+
+    let program = vec![
+        Statement::Declaration("f".to_string(), call("fopen", vec![var("path")])),
+        Statement::Expression(call("fclose", vec![var("f")])),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    analyzer.register_allocation_pair("fopen", "fclose");
+    assert!(analyzer.analyze(&program).is_ok());
+}
+
+fn new_int() -> Expression {
+    Expression::New("int".to_string(), None, None)
+}
+
+fn assert_leak(program: &[Statement], reason: &str) {
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let error = analyzer.analyze(program).unwrap_err();
+
+    assert_eq!(error.error_type(), MemoryErrorType::MemoryLeak);
+    assert!(error.details().contains(reason), "{}", error.details());
+}
+
+#[test]
+fn test_leak_at_scope_exit() {
+    let program = vec![Statement::Block(vec![Statement::Declaration(
+        "p".to_string(),
+        new_int(),
+    )])];
+
+    assert_leak(&program, "'p' goes out of scope");
+}
+
+#[test]
+fn test_leak_on_overwrite() {
+    let program = vec![
+        Statement::Declaration("p".to_string(), new_int()),
+        Statement::Expression(Expression::Assignment(
+            Box::new(var("p")),
+            Box::new(new_int()),
+        )),
+        Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+    ];
+
+    assert_leak(&program, "'p' is overwritten");
+
+    // int *p = new int; p = nullptr;
+    let program = vec![
+        Statement::Declaration("p".to_string(), new_int()),
+        Statement::Expression(Expression::Assignment(
+            Box::new(var("p")),
+            Box::new(Expression::Null),
+        )),
+    ];
+
+    assert_leak(&program, "'p' is overwritten");
+}
+
+#[test]
+fn test_leak_on_early_return_and_throw() {
+    let early_exit = |exit: Statement| {
+        vec![
+            Statement::Declaration(
+                "p".to_string(),
+                call("malloc", vec![Expression::Integer(4)]),
+            ),
+            Statement::If(
+                var("error"),
+                Box::new(exit),
+                Box::new(Statement::Block(vec![])),
+            ),
+            Statement::Expression(call("free", vec![var("p")])),
+        ]
+    };
+
+    assert_leak(
+        &early_exit(Statement::Return(Expression::Integer(1))),
+        "(the function returns without freeing it)",
+    );
+    assert_leak(
+        &early_exit(Statement::Throw(Expression::Integer(1))),
+        "an exception is thrown",
+    );
+}
+
+#[test]
+fn test_ownership_transfer_is_not_a_leak() {
+    let program = vec![
+        Statement::Declaration("p".to_string(), new_int()),
+        Statement::Declaration("q".to_string(), var("p")),
+        Statement::If(
+            var("flag"),
+            Box::new(Statement::Return(var("q"))),
+            Box::new(Statement::Block(vec![])),
+        ),
+        Statement::Expression(call("take_ownership", vec![var("p")])),
+    ];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    assert!(analyzer.analyze(&program).is_ok());
+}
+
+fn uninitialized(type_name: &str) -> Expression {
+    Expression::Uninitialized(type_name.to_string())
+}
+
+fn assign(target: Expression, value: Expression) -> Statement {
+    Statement::Expression(Expression::Assignment(Box::new(target), Box::new(value)))
+}
+
+fn element(array: &str, index: Expression) -> Expression {
+    Expression::ArrayAccess(array.to_string(), Box::new(index))
+}
+
+fn field(object: Expression, name: &str) -> Expression {
+    Expression::MemberAccess(Box::new(object), name.to_string())
+}
+
+fn assert_uninitialized_read(declarations: &[Declaration], program: &[Statement], path: &str) {
+    let mut analyzer = MemoryAnalyzer::new(declarations);
+    let error = analyzer.analyze(program).unwrap_err();
+
+    assert_eq!(
+        error.error_type(),
+        MemoryErrorType::UninitializedMemoryAccess
+    );
+    assert!(
+        error.details().contains(&format!("'{}'", path)),
+        "{}",
+        error.details()
+    );
+}
+
+#[test]
+fn test_uninitialized_read_on_some_path() {
+    let program = |else_branch: Statement| {
+        vec![
+            Statement::Declaration("x".to_string(), uninitialized("int")),
+            Statement::If(
+                var("flag"),
+                Box::new(assign(var("x"), Expression::Integer(1))),
+                Box::new(else_branch),
+            ),
+            Statement::Return(var("x")),
+        ]
+    };
+
+    assert_uninitialized_read(&[], &program(Statement::Block(vec![])), "x");
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let both_paths = program(assign(var("x"), Expression::Integer(2)));
+    assert!(analyzer.analyze(&both_paths).is_ok());
+}
+
+#[test]
+fn test_partially_initialized_array() {
+    let program = |read: i64| {
+        vec![
+            Statement::Expression(Expression::ArrayDeclaration(
+                "a".to_string(),
+                Box::new(Expression::Integer(3)),
+            )),
+            assign(element("a", Expression::Integer(0)), Expression::Integer(1)),
+            Statement::Declaration("v".to_string(), element("a", Expression::Integer(read))),
+        ]
+    };
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    assert!(analyzer.analyze(&program(0)).is_ok());
+    assert_uninitialized_read(&[], &program(1), "a[1]");
+}
+
+#[test]
+fn test_field_not_set_by_constructor() {
+    let declarations = vec![Declaration::Function(
+        "Point::Point".to_string(),
+        vec![],
+        Box::new(Statement::Block(vec![assign(
+            var("x"),
+            Expression::Integer(0),
+        )])),
+    )];
+    let program = |name: &str| {
+        vec![
+            Statement::Declaration("p".to_string(), uninitialized("Point")),
+            Statement::Declaration("v".to_string(), field(var("p"), name)),
+        ]
+    };
+
+    let mut analyzer = MemoryAnalyzer::new(&declarations);
+    assert!(analyzer.analyze(&program("x")).is_ok());
+    assert_uninitialized_read(&declarations, &program("y"), "p.y");
+}
+
+#[test]
+fn test_uninitialized_heap_memory() {
+    let deref = |name: &str| Expression::Dereference(Box::new(var(name)));
+    let program = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            call("malloc", vec![Expression::Integer(4)]),
+        ),
+        Statement::Expression(call("assert", vec![var("p")])),
+        Statement::Declaration(
+            "q".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        assign(deref("q"), Expression::Integer(1)),
+        Statement::Declaration("w".to_string(), deref("q")),
+        Statement::Declaration("v".to_string(), deref("p")),
+    ];
+
+    assert_uninitialized_read(&[], &program, "*p[0]");
+}
+
+#[test]
+fn test_initialized_new_is_not_indeterminate() {
+    // int *p = new int<initializer>; int v = *p; delete p;
+    let program = |initializer| {
+        vec![
+            Statement::Declaration(
+                "p".to_string(),
+                Expression::New("int".to_string(), None, initializer),
+            ),
+            Statement::Declaration("v".to_string(), deref("p")),
+            Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+        ]
+    };
+
+    for initializer in [vec![Expression::Integer(5)], vec![]] {
+        let program = program(Some(initializer));
+        assert!(MemoryAnalyzer::new(&[]).analyze(&program).is_ok());
+    }
+    assert_uninitialized_read(&[], &program(None), "*p[0]");
+}
+
+fn deref(name: &str) -> Expression {
+    Expression::Dereference(Box::new(var(name)))
+}
+
+fn compare(op: &str, left: Expression, right: Expression) -> Expression {
+    Expression::BinaryOperation(op.to_string(), Box::new(left), Box::new(right))
+}
+
+fn assert_null_dereference(program: &[Statement], message: &str) {
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let error = analyzer.analyze(program).unwrap_err();
+
+    assert_eq!(error.error_type(), MemoryErrorType::NullPointerDereference);
+    assert!(error.details().contains(message), "{}", error.details());
+}
+
+#[test]
+fn test_dereference_of_null_constant() {
+    for null in [Expression::Null, var("NULL"), Expression::Integer(0)] {
+        let program = vec![
+            Statement::Declaration("p".to_string(), null),
+            assign(deref("p"), Expression::Integer(1)),
+        ];
+
+        assert_null_dereference(&program, "'p' is null here");
+    }
+}
+
+#[test]
+fn test_unchecked_allocation_may_be_null() {
+    let program = |check: Vec<Statement>| {
+        let mut program = vec![Statement::Declaration(
+            "p".to_string(),
+            call("malloc", vec![Expression::Integer(4)]),
+        )];
+        program.extend(check);
+        program.push(assign(deref("p"), Expression::Integer(1)));
+        program.push(Statement::Expression(call("free", vec![var("p")])));
+        program
+    };
+
+    assert_null_dereference(&program(vec![]), "'p' may be null here");
+
+    let guards = vec![
+        Statement::If(
+            Expression::UnaryOperation("!".to_string(), Box::new(var("p"))),
+            Box::new(Statement::Return(Expression::Integer(1))),
+            Box::new(Statement::Block(vec![])),
+        ),
+        Statement::If(
+            compare("==", var("p"), Expression::Null),
+            Box::new(Statement::Throw(Expression::Integer(1))),
+            Box::new(Statement::Block(vec![])),
+        ),
+        Statement::Expression(call("assert", vec![var("p")])),
+    ];
+    for guard in guards {
+        let mut analyzer = MemoryAnalyzer::new(&[]);
+        assert!(analyzer.analyze(&program(vec![guard])).is_ok());
+    }
+
+    let nothrow = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("(std::nothrow) int".to_string(), None, None),
+        ),
+        Statement::Declaration("v".to_string(), deref("p")),
+    ];
+    assert_null_dereference(&nothrow, "'p' may be null here");
+}
+
+#[test]
+fn test_branch_refinement() {
+    // if (p == nullptr) *p = 0;
+    let null_branch = |init: Expression| {
+        vec![
+            Statement::Declaration("p".to_string(), init),
+            Statement::If(
+                compare("==", var("p"), Expression::Null),
+                Box::new(assign(deref("p"), Expression::Integer(0))),
+                Box::new(Statement::Block(vec![])),
+            ),
+            Statement::Expression(call("consume", vec![var("p")])),
+        ]
+    };
+
+    assert_null_dereference(&null_branch(call("lookup", vec![])), "'p' is null here");
+
+    // The branch is infeasible when p is known to be non-null
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let program = null_branch(Expression::New("int".to_string(), None, None));
+    assert!(analyzer.analyze(&program).is_ok());
+}
+
+#[test]
+fn test_analyzer_reused_across_functions() {
+    let frees = vec![
+        Statement::Declaration(
+            "p".to_string(),
+            call("malloc", vec![Expression::Integer(4)]),
+        ),
+        Statement::Expression(call("free", vec![var("p")])),
+    ];
+    // A later function freeing its own `p` is not a double free
+    let frees_parameter = vec![Statement::Expression(call("free", vec![var("p")]))];
+
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    assert!(analyzer.analyze(&frees).is_ok());
+    assert!(analyzer.analyze(&frees_parameter).is_ok());
+}
+
+fn function_body(source: &str) -> Vec<Statement> {
+    match parse_cpp_code(source).unwrap().remove(0) {
+        Declaration::Function(_, _, body) => vec![*body],
+        declaration => panic!("Expected a function, found {:?}", declaration),
+    }
+}
+
+#[test]
+fn test_frees_on_separate_paths_are_not_double_frees() {
+    let sources = [
+        "void f(int c) { int *p = new int; if (c) { delete p; } else { delete p; } }",
+        "void f(int c) { int *p = new int; if (c) { delete p; return; } delete p; }",
+        "void f(int c) { int *p = new int; while (c) { delete p; return; } delete p; }",
+    ];
+    for source in sources {
+        let program = function_body(source);
+        let mut analyzer = MemoryAnalyzer::new(&[]);
+        assert!(analyzer.analyze(&program).is_ok(), "{}", source);
+    }
+
+    // Freed on one branch, freed again after the join
+    let program =
+        function_body("void f(int c) { int *p = new int; if (c) { delete p; } delete p; }");
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    let error = analyzer.analyze(&program).unwrap_err();
+    assert_eq!(error.error_type(), MemoryErrorType::DoubleFree);
+}
@@ -0,0 +1,153 @@
+// tests/test_models.rs
+mod common;
+
+use common::{call, var};
+use safecpp::analyzer::array_analyzer::ArrayAnalyzer;
+use safecpp::analyzer::memory_analyzer::{MemoryAnalyzer, MemoryErrorType};
+use safecpp::analyzer::models::Models;
+use safecpp::analyzer::pointer_analyzer::{PointerAnalyzer, PointerErrorKind};
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+const POOL_MODELS: &str = r#"{
+  "functions": {
+    "pool_alloc": { "allocates": "pool", "nullable_return": true },
+    "pool_release": { "deallocates": { "family": "pool", "param": 1 } },
+    "log_pointer": {}
+  }
+}"#;
+
+fn analyze_memory(models: &Models, program: &[Statement]) -> Result<(), MemoryErrorType> {
+    let mut analyzer = MemoryAnalyzer::new(&[]);
+    analyzer.add_models(models);
+    analyzer
+        .analyze(program)
+        .map_err(|error| error.error_type())
+}
+
+// p = pool_alloc(); <release>
+fn allocation(release: Statement) -> Vec<Statement> {
+    vec![
+        Statement::Declaration("p".to_string(), call("pool_alloc", vec![])),
+        release,
+    ]
+}
+
+#[test]
+fn test_modelled_allocator_family() {
+    let models = Models::parse(POOL_MODELS).unwrap();
+    let release = |name: &str, args: Vec<Expression>| Statement::Expression(call(name, args));
+
+    let program = allocation(release("pool_release", vec![var("ctx"), var("p")]));
+    assert!(analyze_memory(&models, &program).is_ok());
+
+    let program = allocation(release("free", vec![var("p")]));
+    assert_eq!(
+        analyze_memory(&models, &program),
+        Err(MemoryErrorType::MismatchedDeallocation)
+    );
+
+    // Modelled functions only take ownership when their model says so
+    let program = allocation(release("log_pointer", vec![var("p")]));
+    assert_eq!(
+        analyze_memory(&models, &program),
+        Err(MemoryErrorType::MemoryLeak)
+    );
+}
+
+#[test]
+fn test_nullable_return() {
+    let models = Models::parse(POOL_MODELS).unwrap();
+    let program = vec![
+        Statement::Declaration("p".to_string(), call("pool_alloc", vec![])),
+        Statement::Declaration("v".to_string(), Expression::Dereference(Box::new(var("p")))),
+    ];
+
+    assert_eq!(
+        analyze_memory(&models, &program),
+        Err(MemoryErrorType::NullPointerDereference)
+    );
+}
+
+#[test]
+fn test_bundled_buffer_sizes() {
+    // char* buf = new char[8]; memcpy(buf, src, n);
+    let program = |n: i64| {
+        vec![
+            Statement::Declaration(
+                "buf".to_string(),
+                Expression::New(
+                    "char".to_string(),
+                    Some(Box::new(Expression::Integer(8))),
+                    None,
+                ),
+            ),
+            Statement::Expression(call(
+                "memcpy",
+                vec![var("buf"), var("src"), Expression::Integer(n)],
+            )),
+        ]
+    };
+    let models = Models::bundled();
+    let analyze = |program: &[Statement]| {
+        let mut analyzer = ArrayAnalyzer::new(program);
+        analyzer.add_models(&models);
+        analyzer.analyze()
+    };
+
+    assert!(analyze(&program(8)).is_ok());
+    let error = analyze(&program(16)).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Call to 'memcpy' accesses 'buf' out of bounds"));
+}
+
+#[test]
+fn test_modelled_deallocator_in_pointer_analyzer() {
+    // FILE* f = fopen(path, mode); fclose(f); fprintf(f, fmt);
+    let declarations = vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(vec![
+            Statement::Declaration(
+                "f".to_string(),
+                call("fopen", vec![var("path"), var("mode")]),
+            ),
+            Statement::Expression(call("fclose", vec![var("f")])),
+            Statement::Expression(call("fprintf", vec![var("f"), var("fmt")])),
+        ])),
+    )];
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    analyzer.add_models(&Models::bundled());
+    let error = analyzer.analyze().unwrap_err();
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+    assert!(
+        error.details.contains("passed to 'fprintf'"),
+        "{}",
+        error.details
+    );
+}
+
+#[test]
+fn test_invalid_models() {
+    let error = Models::parse(r#"{"functions": {"f": {"alocates": "pool"}}}"#).unwrap_err();
+    assert!(
+        error.details.contains("unknown key 'alocates'"),
+        "{}",
+        error.details
+    );
+
+    let error = Models::parse(r#"{"functions": {"f": {"dereferences": [-1]}}}"#).unwrap_err();
+    assert!(
+        error.details.contains("array of parameter indices"),
+        "{}",
+        error.details
+    );
+
+    let error = Models::parse("{\"functions\": {\n  \"f\": {,}\n}}").unwrap_err();
+    assert!(error.details.starts_with("2:"), "{}", error.details);
+
+    let bundled = Models::bundled();
+    assert!(bundled.get("getenv").unwrap().taints_return);
+    assert_eq!(bundled.get("system").unwrap().taint_sinks, vec![0]);
+}
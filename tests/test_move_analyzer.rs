@@ -0,0 +1,187 @@
+// tests/test_move_analyzer.rs
+mod common;
+
+use common::{call, declare, function, method, var};
+use safecpp::analyzer::memory_analyzer::MemoryErrorType;
+use safecpp::analyzer::move_analyzer::MoveAnalyzer;
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+fn moved(name: &str) -> Expression {
+    call("std::move", vec![var(name)])
+}
+
+fn call_method(object: &str, name: &str) -> Statement {
+    Statement::Expression(method(object, name, vec![]))
+}
+
+fn analyze(declarations: &[Declaration]) -> Result<(), String> {
+    let mut analyzer = MoveAnalyzer::new(declarations);
+    analyzer.analyze().map_err(|error| {
+        assert_eq!(error.error_type(), MemoryErrorType::UseAfterMove);
+        error.details().to_string()
+    })
+}
+
+#[test]
+fn test_use_after_move() {
+    // std::string s = make(); std::string t = std::move(s); print(s);
+    let declarations = [function(
+        "main",
+        &[],
+        vec![
+            declare("s", call("make", vec![])),
+            declare("t", moved("s")),
+            Statement::Expression(call("print", vec![var("s")])),
+        ],
+    )];
+
+    let error = analyze(&declarations).unwrap_err();
+    assert!(
+        error.contains("'s' is used after it was moved from (moved into 't')"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_reinitialization_after_move() {
+    // consume(std::move(v)); v.clear(); v.push_back(1);
+    let program = |reset: Statement| {
+        vec![function(
+            "main",
+            &[],
+            vec![
+                declare("v", call("make", vec![])),
+                Statement::Expression(call("consume", vec![moved("v")])),
+                reset,
+                Statement::Expression(Expression::MethodCall(
+                    Box::new(var("v")),
+                    "push_back".to_string(),
+                    vec![Expression::Integer(1)],
+                )),
+            ],
+        )]
+    };
+
+    assert!(analyze(&program(call_method("v", "clear"))).is_ok());
+    assert!(
+        analyze(&program(Statement::Expression(Expression::Assignment(
+            Box::new(var("v")),
+            Box::new(call("make", vec![])),
+        ))))
+        .is_ok()
+    );
+
+    let error = analyze(&program(call_method("v", "size"))).unwrap_err();
+    assert!(error.contains("passed to std::move"), "{}", error);
+}
+
+#[test]
+fn test_move_on_some_paths_and_in_loops() {
+    // if (flag) sink(std::move(s)); print(s);
+    let declarations = [function(
+        "main",
+        &[],
+        vec![
+            declare("s", call("make", vec![])),
+            Statement::If(
+                var("flag"),
+                Box::new(Statement::Expression(call("sink", vec![moved("s")]))),
+                Box::new(Statement::Block(vec![])),
+            ),
+            Statement::Expression(call("print", vec![var("s")])),
+        ],
+    )];
+    let error = analyze(&declarations).unwrap_err();
+    assert!(error.contains("may have been moved"), "{}", error);
+
+    // while (more()) sink(std::move(s));
+    let declarations = [function(
+        "main",
+        &[],
+        vec![
+            declare("s", call("make", vec![])),
+            Statement::While(
+                call("more", vec![]),
+                Box::new(Statement::Expression(call("sink", vec![moved("s")]))),
+            ),
+        ],
+    )];
+    assert!(analyze(&declarations).is_err());
+}
+
+#[test]
+fn test_rvalue_reference_parameters() {
+    // void take(Widget&& w) { store(std::forward<Widget>(w)); w.draw(); }
+    let declarations = [function(
+        "take",
+        &["Widget&& w"],
+        vec![
+            Statement::Expression(call(
+                "store",
+                vec![call("std::forward<Widget>", vec![var("w")])],
+            )),
+            call_method("w", "draw"),
+        ],
+    )];
+    assert!(analyze(&declarations).is_err());
+
+    // take(std::move(w)); auto n = w.name;
+    let declarations = [
+        function("take", &["Widget&& w"], vec![]),
+        function(
+            "main",
+            &[],
+            vec![
+                declare("w", Expression::Uninitialized("Widget".to_string())),
+                Statement::Expression(call("take", vec![moved("w")])),
+                declare(
+                    "n",
+                    Expression::MemberAccess(Box::new(var("w")), "name".to_string()),
+                ),
+            ],
+        ),
+    ];
+    let error = analyze(&declarations).unwrap_err();
+    assert!(
+        error.contains("'w.name' is used after it was moved from"),
+        "{}",
+        error
+    );
+    assert!(
+        error.contains("rvalue-reference parameter of 'take'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_moved_from_smart_pointer_is_null() {
+    // auto p = std::make_unique<Dog>(); auto q = std::move(p); if (p) ...; p->bark();
+    let program = |last: Statement| {
+        vec![function(
+            "main",
+            &[],
+            vec![
+                declare("p", call("std::make_unique<Dog>", vec![])),
+                declare("q", moved("p")),
+                Statement::If(
+                    var("p"),
+                    Box::new(Statement::Block(vec![])),
+                    Box::new(Statement::Block(vec![])),
+                ),
+                last,
+            ],
+        )]
+    };
+
+    assert!(analyze(&program(Statement::Block(vec![]))).is_ok());
+
+    let bark = Statement::Expression(Expression::MethodCall(
+        Box::new(Expression::Dereference(Box::new(var("p")))),
+        "bark".to_string(),
+        vec![],
+    ));
+    let error = analyze(&program(bark)).unwrap_err();
+    assert!(error.contains("it is null"), "{}", error);
+}
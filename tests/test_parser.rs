@@ -1,6 +1,12 @@
 // tests/test_parser.rs
-use safecpp::parser::cpp_lexer::{Lexer, Token};
-use safecpp::parser::cpp_parser::{Declaration, Expression, Parser, Statement};
+mod common;
+
+use common::{at, var};
+use safecpp::parser::cpp_lexer::Lexer;
+use safecpp::parser::cpp_parser::{
+    parse_cpp_code, ClassDeclaration, Declaration, Expression, Location, Method, MethodBody,
+    Parser, Statement,
+};
 
 #[test]
 fn test_parse_variable_declaration() {
@@ -29,8 +35,16 @@ fn test_parse_function_declaration() {
         result,
         vec![Declaration::Function(
             "sum".to_string(),
-            vec!["a".to_string(), "b".to_string()],
-            Box::new(Statement::Return(Expression::Identifier("a + b".to_string())))
+            vec!["int a".to_string(), "int b".to_string()],
+            Box::new(Statement::Block(vec![at(
+                1,
+                25,
+                Statement::Return(Expression::BinaryOperation(
+                    "+".to_string(),
+                    Box::new(Expression::Identifier("a".to_string())),
+                    Box::new(Expression::Identifier("b".to_string())),
+                ))
+            )]))
         )]
     );
 }
@@ -44,3 +58,214 @@ fn test_parse_invalid_input() {
 
     assert!(result.is_err());
 }
+
+fn function_body(input: &str) -> Vec<Statement> {
+    match Parser::new(Lexer::new(input))
+        .parse()
+        .expect("Failed to parse")
+        .remove(0)
+    {
+        Declaration::Function(_, _, body) => match *body {
+            Statement::Block(statements) => statements,
+            body => panic!("Expected a block, found {:?}", body),
+        },
+        declaration => panic!("Expected a function, found {:?}", declaration),
+    }
+}
+
+#[test]
+fn test_parse_pointers_and_calls() {
+    let body = function_body(
+        r#"
+    #include <memory>
+    void f(Node* node) {
+        int* p = new int[4], q;
+        int* r = new int(5);
+        node->next = std::make_unique<Node>(p[0]);
+        v.push_back(*p);
+        delete[] p;
+    }
+    "#,
+    );
+
+    assert_eq!(
+        body,
+        vec![
+            at(
+                4,
+                9,
+                Statement::Declaration(
+                    "p".to_string(),
+                    Expression::New(
+                        "int".to_string(),
+                        Some(Box::new(Expression::Integer(4))),
+                        None
+                    ),
+                )
+            ),
+            at(
+                4,
+                9,
+                Statement::Declaration(
+                    "q".to_string(),
+                    Expression::Uninitialized("int".to_string())
+                )
+            ),
+            at(
+                5,
+                9,
+                Statement::Declaration(
+                    "r".to_string(),
+                    Expression::New("int".to_string(), None, Some(vec![Expression::Integer(5)])),
+                )
+            ),
+            at(
+                6,
+                9,
+                Statement::Expression(Expression::Assignment(
+                    Box::new(Expression::MemberAccess(
+                        Box::new(Expression::Dereference(Box::new(var("node")))),
+                        "next".to_string(),
+                    )),
+                    Box::new(Expression::FunctionCall(
+                        "std::make_unique<Node>".to_string(),
+                        vec![Expression::ArrayAccess(
+                            "p".to_string(),
+                            Box::new(Expression::Integer(0))
+                        )],
+                    )),
+                ))
+            ),
+            at(
+                7,
+                9,
+                Statement::Expression(Expression::MethodCall(
+                    Box::new(var("v")),
+                    "push_back".to_string(),
+                    vec![Expression::Dereference(Box::new(var("p")))],
+                ))
+            ),
+            at(
+                8,
+                9,
+                Statement::Expression(Expression::Delete(Box::new(var("p")), true))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_loops_and_compound_assignment() {
+    let body = function_body("void f() { for (int i = 0; i < 10; ++i) total += i; }");
+
+    let increment = Expression::UnaryOperation("++".to_string(), Box::new(var("i")));
+    let add = Expression::Assignment(
+        Box::new(var("total")),
+        Box::new(Expression::BinaryOperation(
+            "+".to_string(),
+            Box::new(var("total")),
+            Box::new(var("i")),
+        )),
+    );
+    assert_eq!(
+        body,
+        vec![at(
+            1,
+            12,
+            Statement::Block(vec![
+                Statement::Declaration("i".to_string(), Expression::Integer(0)),
+                Statement::While(
+                    Expression::BinaryOperation(
+                        "<".to_string(),
+                        Box::new(var("i")),
+                        Box::new(Expression::Integer(10)),
+                    ),
+                    Box::new(Statement::Block(vec![
+                        at(1, 41, Statement::Expression(add)),
+                        Statement::Expression(increment),
+                    ])),
+                ),
+            ])
+        )]
+    );
+}
+
+#[test]
+fn test_parse_class() {
+    let source = "
+        class Buffer : public Base {
+        public:
+            explicit Buffer(int n) : data(new char[n]) {}
+            Buffer(const Buffer&) = delete;
+            virtual ~Buffer();
+            virtual int size() const = 0;
+            static int count;
+        private:
+            char* data;
+        };
+        Buffer::~Buffer() { delete[] data; }
+    ";
+    let declarations = parse_cpp_code(source).expect("Failed to parse");
+
+    let method = |name: &str, params: &[&str], is_virtual, body| Method {
+        name: name.to_string(),
+        params: params.iter().map(|param| param.to_string()).collect(),
+        initializers: vec![],
+        is_virtual,
+        body,
+    };
+    let constructor = Method {
+        initializers: vec![(
+            "data".to_string(),
+            Expression::New("char".to_string(), Some(Box::new(var("n"))), None),
+        )],
+        ..method(
+            "Buffer",
+            &["int n"],
+            false,
+            MethodBody::Defined(Box::new(Statement::Block(vec![]))),
+        )
+    };
+    assert_eq!(
+        declarations[0],
+        Declaration::Class(ClassDeclaration {
+            name: "Buffer".to_string(),
+            bases: vec!["Base".to_string()],
+            fields: vec![("char*".to_string(), "data".to_string())],
+            methods: vec![
+                constructor,
+                method("Buffer", &["const Buffer&"], false, MethodBody::Deleted),
+                method("~Buffer", &[], true, MethodBody::Declared),
+                method("size", &[], true, MethodBody::Declared),
+            ],
+            location: Some(Location::new(2, 15)),
+        })
+    );
+    assert!(matches!(
+        &declarations[1],
+        Declaration::Function(name, _, _) if name == "Buffer::~Buffer"
+    ));
+}
+
+#[test]
+fn test_parse_reference_declaration() {
+    let body = function_body("void f() { int &r = v[0], c = v[1]; auto&& t = g(); }");
+
+    let element =
+        |index| Expression::ArrayAccess("v".to_string(), Box::new(Expression::Integer(index)));
+    assert_eq!(
+        body,
+        vec![
+            at(1, 12, Statement::Reference("r".to_string(), element(0))),
+            at(1, 12, Statement::Declaration("c".to_string(), element(1))),
+            at(
+                1,
+                37,
+                Statement::Declaration(
+                    "t".to_string(),
+                    Expression::FunctionCall("g".to_string(), vec![])
+                )
+            ),
+        ]
+    );
+}
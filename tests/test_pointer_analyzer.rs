@@ -0,0 +1,310 @@
+// tests/test_pointer_analyzer.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::pointer_analyzer::{PointerAnalyzer, PointerErrorKind};
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration, Expression, Statement};
+
+fn main_function(body: Vec<Statement>) -> Vec<Declaration> {
+    vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(body)),
+    )]
+}
+
+fn delete(expr: Expression) -> Statement {
+    Statement::Expression(Expression::Delete(Box::new(expr), false))
+}
+
+fn store(target: Expression) -> Statement {
+    Statement::Expression(Expression::Assignment(
+        Box::new(target),
+        Box::new(Expression::Integer(1)),
+    ))
+}
+
+#[test]
+fn test_use_after_free_through_copy() {
+    // int *p = new int; int *q = p; delete p; *q = 1;
+    let declarations = main_function(vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::Declaration("q".to_string(), var("p")),
+        delete(var("p")),
+        store(Expression::Dereference(Box::new(var("q")))),
+    ]);
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+    assert!(error.details.contains("'q'"));
+    assert!(error.details.contains("freed through 'p'"));
+}
+
+#[test]
+fn test_use_after_free_through_struct_field() {
+    // s.ptr = new int; int *q = s.ptr; free(q); use(s.ptr);
+    let field = || Expression::MemberAccess(Box::new(var("s")), "ptr".to_string());
+    let declarations = main_function(vec![
+        Statement::Expression(Expression::Assignment(
+            Box::new(field()),
+            Box::new(Expression::New("int".to_string(), None, None)),
+        )),
+        Statement::Declaration("q".to_string(), field()),
+        Statement::Expression(Expression::FunctionCall("free".to_string(), vec![var("q")])),
+        Statement::Expression(Expression::FunctionCall("use".to_string(), vec![field()])),
+    ]);
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+    assert!(error.details.contains("passed to 'use'"));
+}
+
+#[test]
+fn test_use_after_free_through_container_element() {
+    // v.push_back(p); delete p; v.front()->x = 1;
+    let declarations = main_function(vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("Node".to_string(), None, None),
+        ),
+        Statement::Expression(Expression::MethodCall(
+            Box::new(var("v")),
+            "push_back".to_string(),
+            vec![var("p")],
+        )),
+        delete(var("p")),
+        store(Expression::MemberAccess(
+            Box::new(Expression::Dereference(Box::new(Expression::MethodCall(
+                Box::new(var("v")),
+                "front".to_string(),
+                vec![],
+            )))),
+            "x".to_string(),
+        )),
+    ]);
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+}
+
+#[test]
+fn test_reassigned_alias_is_not_dangling() {
+    // int *p = new int; int *q = p; delete p; q = new int; *q = 1;
+    let declarations = main_function(vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::Declaration("q".to_string(), var("p")),
+        delete(var("p")),
+        Statement::Expression(Expression::Assignment(
+            Box::new(var("q")),
+            Box::new(Expression::New("int".to_string(), None, None)),
+        )),
+        store(Expression::Dereference(Box::new(var("q")))),
+    ]);
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    assert!(analyzer.analyze().is_ok());
+}
+
+#[test]
+fn test_free_on_returning_branch_does_not_reach_join() {
+    // int *p = new int; if (err) { delete p; return; } *p = 1;
+    let early_return = |exit: Statement| {
+        main_function(vec![
+            Statement::Declaration(
+                "p".to_string(),
+                Expression::New("int".to_string(), None, None),
+            ),
+            Statement::If(
+                var("err"),
+                Box::new(Statement::Block(vec![delete(var("p")), exit])),
+                Box::new(Statement::Block(vec![])),
+            ),
+            store(Expression::Dereference(Box::new(var("p")))),
+        ])
+    };
+
+    let returned = early_return(Statement::Return(Expression::Uninitialized(
+        "void".to_string(),
+    )));
+    assert!(PointerAnalyzer::new(returned).analyze().is_ok());
+
+    let thrown = early_return(Statement::Throw(Expression::Integer(1)));
+    assert!(PointerAnalyzer::new(thrown).analyze().is_ok());
+
+    // Without the return, the freed pointer reaches the store
+    let fell_through = main_function(vec![
+        Statement::Declaration(
+            "p".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::If(
+            var("err"),
+            Box::new(Statement::Block(vec![delete(var("p"))])),
+            Box::new(Statement::Block(vec![])),
+        ),
+        store(Expression::Dereference(Box::new(var("p")))),
+    ]);
+    let error = PointerAnalyzer::new(fell_through).analyze().unwrap_err();
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+}
+
+#[test]
+fn test_free_in_returning_loop_body_does_not_reach_exit() {
+    let analyze = |source: &str| PointerAnalyzer::new(parse_cpp_code(source).unwrap()).analyze();
+
+    let returning =
+        "void f(int c) { int *p = new int(1); while (c) { delete p; return; } *p = 1; delete p; }";
+    assert!(analyze(returning).is_ok());
+
+    let returning_range = "void f(std::vector<int> v) { int *p = new int(1); for (int x : v) { delete p; return; } *p = 1; delete p; }";
+    assert!(analyze(returning_range).is_ok());
+
+    // A body that falls through reaches the code after the loop
+    let falling_through = "void f(int c) { int *p = new int(1); while (c) { delete p; } *p = 1; }";
+    let error = analyze(falling_through).unwrap_err();
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+}
+
+fn address_of(name: &str) -> Expression {
+    Expression::AddressOf(Box::new(var(name)))
+}
+
+fn assign(target: Expression, value: Expression) -> Statement {
+    Statement::Expression(Expression::Assignment(Box::new(target), Box::new(value)))
+}
+
+fn local(name: &str) -> Statement {
+    Statement::Declaration(
+        name.to_string(),
+        Expression::Uninitialized("int".to_string()),
+    )
+}
+
+fn assert_dangling(declarations: Vec<Declaration>, message: &str) {
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+
+    assert!(matches!(
+        error.kind,
+        PointerErrorKind::DanglingStackReference
+    ));
+    assert!(error.details.contains(message), "{}", error.details);
+}
+
+#[test]
+fn test_returning_address_of_local() {
+    // int x; return &x;
+    assert_dangling(
+        main_function(vec![local("x"), Statement::Return(address_of("x"))]),
+        "'main' returns the address of its local 'x'",
+    );
+
+    // int buf[4]; char *p = buf + 1; return p;
+    assert_dangling(
+        main_function(vec![
+            Statement::Expression(Expression::ArrayDeclaration(
+                "buf".to_string(),
+                Box::new(Expression::Integer(4)),
+            )),
+            Statement::Declaration(
+                "p".to_string(),
+                Expression::BinaryOperation(
+                    "+".to_string(),
+                    Box::new(var("buf")),
+                    Box::new(Expression::Integer(1)),
+                ),
+            ),
+            Statement::Return(var("p")),
+        ]),
+        "local 'buf'",
+    );
+}
+
+#[test]
+fn test_local_address_stored_in_longer_lived_storage() {
+    // int *g; void main() { int x; g = &x; }
+    let mut declarations = vec![Declaration::Variable("g".to_string(), Expression::Null)];
+    declarations.extend(main_function(vec![
+        local("x"),
+        assign(var("g"), address_of("x")),
+    ]));
+    assert_dangling(declarations, "stored in global 'g'");
+
+    // void link(Node *node) { int x; node->value = &x; }
+    let declarations = vec![Declaration::Function(
+        "link".to_string(),
+        vec!["node".to_string()],
+        Box::new(Statement::Block(vec![
+            local("x"),
+            assign(
+                Expression::MemberAccess(
+                    Box::new(Expression::Dereference(Box::new(var("node")))),
+                    "value".to_string(),
+                ),
+                address_of("x"),
+            ),
+        ])),
+    )];
+    assert_dangling(declarations, "stored in memory pointed to by 'node'");
+
+    // int x; results.push_back(&x); with `results` a member
+    assert_dangling(
+        main_function(vec![
+            local("x"),
+            Statement::Expression(Expression::MethodCall(
+                Box::new(var("results")),
+                "push_back".to_string(),
+                vec![address_of("x")],
+            )),
+        ]),
+        "stored in member 'results'",
+    );
+}
+
+#[test]
+fn test_pointer_outliving_inner_scope() {
+    // int *p; { int x; p = &x; } *p = 1;
+    assert_dangling(
+        main_function(vec![
+            local("p"),
+            Statement::Block(vec![local("x"), assign(var("p"), address_of("x"))]),
+            store(Expression::Dereference(Box::new(var("p")))),
+        ]),
+        "it points to 'x', which is out of scope",
+    );
+}
+
+#[test]
+fn test_local_addresses_within_their_lifetime() {
+    // Node n; Node *q = &n; int y; q->next = &y; int *p = &y; *p = 1;
+    let declarations = main_function(vec![
+        local("n"),
+        Statement::Declaration("q".to_string(), address_of("n")),
+        local("y"),
+        assign(
+            Expression::MemberAccess(
+                Box::new(Expression::Dereference(Box::new(var("q")))),
+                "next".to_string(),
+            ),
+            address_of("y"),
+        ),
+        Statement::Declaration("p".to_string(), address_of("y")),
+        store(Expression::Dereference(Box::new(var("p")))),
+        Statement::Return(Expression::Integer(0)),
+    ]);
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    assert!(analyzer.analyze().is_ok());
+}
@@ -0,0 +1,156 @@
+// tests/test_points_to.rs
+mod common;
+
+use common::var;
+use safecpp::analyzer::pointer_analyzer::{PointerAnalyzer, PointerErrorKind};
+use safecpp::analyzer::points_to::{AbstractLocation, PointsToAnalysis};
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+fn address_of(expr: Expression) -> Expression {
+    Expression::AddressOf(Box::new(expr))
+}
+
+fn deref(expr: Expression) -> Expression {
+    Expression::Dereference(Box::new(expr))
+}
+
+fn local(function: &str, name: &str) -> AbstractLocation {
+    AbstractLocation::Variable {
+        function: Some(function.to_string()),
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_points_to_through_pointer_to_pointer() {
+    // int x; int *p = &x; int **pp = &p; int *q = *pp;
+    let declarations = vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(vec![
+            Statement::Declaration("x".to_string(), Expression::Integer(0)),
+            Statement::Declaration("p".to_string(), address_of(var("x"))),
+            Statement::Declaration("pp".to_string(), address_of(var("p"))),
+            Statement::Declaration("q".to_string(), deref(var("pp"))),
+        ])),
+    )];
+
+    let mut analysis = PointsToAnalysis::new();
+    analysis.analyze(&declarations);
+
+    assert_eq!(
+        analysis
+            .points_to("main", &var("q"))
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![local("main", "x")]
+    );
+    assert!(analysis.may_alias("main", &var("p"), &var("q")));
+}
+
+#[test]
+fn test_field_sensitivity_and_interprocedural_flow() {
+    // int *id(int *a) { return a; }
+    // main: s.f = new int; s.g = new int; int *r = id(s.f);
+    let field = |name: &str| Expression::MemberAccess(Box::new(var("s")), name.to_string());
+    let declarations = vec![
+        Declaration::Function(
+            "id".to_string(),
+            vec!["a".to_string()],
+            Box::new(Statement::Return(var("a"))),
+        ),
+        Declaration::Function(
+            "main".to_string(),
+            vec![],
+            Box::new(Statement::Block(vec![
+                Statement::Expression(Expression::Assignment(
+                    Box::new(field("f")),
+                    Box::new(Expression::New("int".to_string(), None, None)),
+                )),
+                Statement::Expression(Expression::Assignment(
+                    Box::new(field("g")),
+                    Box::new(Expression::New("int".to_string(), None, None)),
+                )),
+                Statement::Declaration(
+                    "r".to_string(),
+                    Expression::FunctionCall("id".to_string(), vec![field("f")]),
+                ),
+            ])),
+        ),
+    ];
+
+    let mut analysis = PointsToAnalysis::new();
+    analysis.analyze(&declarations);
+
+    assert_eq!(analysis.points_to("main", &var("r")).len(), 1);
+    assert!(analysis.may_alias("main", &var("r"), &field("f")));
+    assert!(!analysis.may_alias("main", &var("r"), &field("g")));
+}
+
+#[test]
+fn test_indirect_call_resolution() {
+    // int one(int a) { return a; } main: fp = one; fp(1, 2);
+    let declarations = vec![
+        Declaration::Function(
+            "one".to_string(),
+            vec!["a".to_string()],
+            Box::new(Statement::Return(var("a"))),
+        ),
+        Declaration::Function(
+            "main".to_string(),
+            vec![],
+            Box::new(Statement::Block(vec![
+                Statement::Declaration("fp".to_string(), var("one")),
+                Statement::Expression(Expression::FunctionCall(
+                    "fp".to_string(),
+                    vec![Expression::Integer(1), Expression::Integer(2)],
+                )),
+            ])),
+        ),
+    ];
+
+    let mut analysis = PointsToAnalysis::new();
+    analysis.analyze(&declarations);
+    assert_eq!(
+        analysis
+            .resolve_call("main", "fp")
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["one".to_string()]
+    );
+    assert!(analysis.callees("main").contains("one"));
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+    assert!(matches!(
+        error.kind,
+        PointerErrorKind::IncorrectNumberOfArguments
+    ));
+}
+
+#[test]
+fn test_use_after_free_through_pointer_to_pointer() {
+    // int *p = new int; int **pp = &p; int *q = *pp; delete p; *q = 1;
+    let declarations = vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(vec![
+            Statement::Declaration(
+                "p".to_string(),
+                Expression::New("int".to_string(), None, None),
+            ),
+            Statement::Declaration("pp".to_string(), address_of(var("p"))),
+            Statement::Declaration("q".to_string(), deref(var("pp"))),
+            Statement::Expression(Expression::Delete(Box::new(var("p")), false)),
+            Statement::Expression(Expression::Assignment(
+                Box::new(deref(var("q"))),
+                Box::new(Expression::Integer(1)),
+            )),
+        ])),
+    )];
+
+    let mut analyzer = PointerAnalyzer::new(declarations);
+    let error = analyzer.analyze().unwrap_err();
+
+    assert!(matches!(error.kind, PointerErrorKind::UseAfterFree));
+}
@@ -0,0 +1,113 @@
+// tests/test_render.rs
+use safecpp::analyzer::pointer_analyzer::PointerAnalyzer;
+use safecpp::diagnostics::{ColorChoice, Diagnostic, DiagnosticSink, Renderer, SourceFile, Span};
+use safecpp::parser::cpp_parser::parse_cpp_code;
+
+const SOURCE: &str = "\
+int main() {
+    int* p = new int;
+    delete p;
+    // p is gone
+    delete p;
+    return 0;
+}
+";
+
+fn double_free() -> Diagnostic {
+    Diagnostic::new(
+        "double-free",
+        "Double free attempt on variable: p".to_string(),
+    )
+    .in_function("main")
+}
+
+#[test]
+fn test_locate_double_free() {
+    let source = SourceFile::new("main.cpp", SOURCE);
+    let diagnostic = source.locate(double_free());
+
+    let primary = diagnostic.primary.unwrap();
+    assert_eq!(primary.span, Span::new(5, 12, 1));
+    assert_eq!(primary.message, "freed again here");
+    assert_eq!(diagnostic.secondary[0].span, Span::new(3, 12, 1));
+    assert_eq!(diagnostic.secondary[0].message, "first freed here");
+}
+
+#[test]
+fn test_locate_leak() {
+    let source = SourceFile::new("main.cpp", SOURCE);
+    let leak = Diagnostic::new(
+        "memory-leak",
+        "Memory leak: memory allocated with new for 'p' is never freed".to_string(),
+    );
+    let primary = source.locate(leak).primary.unwrap();
+    assert_eq!(primary.span, Span::new(2, 10, 1));
+    assert_eq!(primary.message, "allocated here");
+}
+
+#[test]
+fn test_render() {
+    let source = SourceFile::new("main.cpp", SOURCE);
+    let rendered = Renderer::new(false).render(&source, &source.locate(double_free()));
+    assert_eq!(
+        rendered,
+        "\
+error[double-free]: Double free attempt on variable: p
+ --> main.cpp:5:12
+  |
+3 |     delete p;
+  |            - first freed here
+...
+5 |     delete p;
+  |            ^ freed again here
+  |
+  = help: set the pointer to nullptr after freeing it, or use a smart pointer
+"
+    );
+}
+
+#[test]
+fn test_render_use_through_alias() {
+    let text = "\
+void f() {
+    int *p = new int(1);
+    int *q = p;
+    delete p;
+    *q = 1;
+}
+";
+    let mut sink = DiagnosticSink::new();
+    PointerAnalyzer::new(parse_cpp_code(text).unwrap()).analyze_into(&mut sink);
+    let diagnostic = sink.into_diagnostics().remove(0);
+
+    let source = SourceFile::new("alias.cpp", text);
+    let rendered = Renderer::new(false).render(&source, &source.locate(diagnostic));
+    assert!(rendered.contains(" --> alias.cpp:5:6\n"), "{}", rendered);
+    assert!(
+        rendered.contains("4 |     delete p;\n  |     -------- freed here\n"),
+        "{}",
+        rendered
+    );
+    assert!(
+        rendered.contains("5 |     *q = 1;\n  |      ^ used here\n"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn test_render_without_location() {
+    let source = SourceFile::new("main.cpp", SOURCE);
+    let diagnostic = Diagnostic::new("rule-of-three", "'Buffer' has no destructor".to_string());
+    let rendered = Renderer::new(true).render(&source, &source.locate(diagnostic));
+    assert!(rendered.starts_with("\x1b[1;33mwarning[rule-of-three]\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;34m-->\x1b[0m main.cpp\n"));
+}
+
+#[test]
+fn test_color_choice() {
+    assert_eq!(ColorChoice::parse("always"), Some(ColorChoice::Always));
+    assert_eq!(ColorChoice::parse("sometimes"), None);
+    assert!(ColorChoice::Always.enabled());
+    assert!(!ColorChoice::Never.enabled());
+}
@@ -0,0 +1,222 @@
+// tests/test_sarif.rs
+use safecpp::diagnostics::sarif::sarif_log;
+use safecpp::diagnostics::{Diagnostic, Severity, SourceFile};
+use safecpp::json::Json;
+
+const SOURCE: &str = "\
+void f(int n) {
+    int* p = new int;
+    if (n > 0) {
+        return;
+    }
+    delete p;
+    delete p;
+}
+";
+
+// Hand-picked constraints of the SARIF 2.1.0 format on the objects the analyzer emits:
+// required properties, value ranges and cross references. This is not a validation
+// against the published JSON schema, which the tests do not have
+fn check_message(message: &Json) {
+    assert!(
+        message.get("text").and_then(Json::as_str).is_some(),
+        "{}",
+        message
+    );
+}
+
+// Every location names its file; the region is there when the span is known
+fn check_location(location: &Json) {
+    let physical = location.get("physicalLocation").unwrap();
+    let artifact = physical.get("artifactLocation").unwrap();
+    assert!(artifact.get("uri").and_then(Json::as_str).is_some());
+    if let Some(region) = physical.get("region") {
+        for key in ["startLine", "startColumn", "endColumn"] {
+            assert!(
+                region.get(key).and_then(Json::as_usize).unwrap() >= 1,
+                "{}",
+                key
+            );
+        }
+    }
+    for logical in location
+        .get("logicalLocations")
+        .and_then(Json::as_array)
+        .unwrap_or_default()
+    {
+        assert!(logical.get("name").and_then(Json::as_str).is_some());
+    }
+    if let Some(message) = location.get("message") {
+        check_message(message);
+    }
+}
+
+fn check_sarif_subset(log: &Json) {
+    assert_eq!(log.get("version").and_then(Json::as_str), Some("2.1.0"));
+    let runs = log.get("runs").and_then(Json::as_array).unwrap();
+    for run in runs {
+        let driver = run.get("tool").and_then(|tool| tool.get("driver")).unwrap();
+        assert!(driver.get("name").and_then(Json::as_str).is_some());
+        let rules = driver.get("rules").and_then(Json::as_array).unwrap();
+        for rule in rules {
+            assert!(rule.get("id").and_then(Json::as_str).is_some());
+            check_message(rule.get("shortDescription").unwrap());
+        }
+        for result in run.get("results").and_then(Json::as_array).unwrap() {
+            check_message(result.get("message").unwrap());
+            let level = result.get("level").and_then(Json::as_str).unwrap();
+            assert!(["none", "note", "warning", "error"].contains(&level));
+            // ruleIndex must point at the rule named by ruleId
+            let rule_id = result.get("ruleId").and_then(Json::as_str).unwrap();
+            if let Some(index) = result.get("ruleIndex").and_then(Json::as_usize) {
+                assert_eq!(rules[index].get("id").and_then(Json::as_str), Some(rule_id));
+            }
+            for location in result.get("locations").and_then(Json::as_array).unwrap() {
+                check_location(location);
+            }
+            for related in result
+                .get("relatedLocations")
+                .and_then(Json::as_array)
+                .unwrap_or_default()
+            {
+                assert!(related.get("id").and_then(Json::as_usize).is_some());
+                check_location(related);
+            }
+            for flow in result
+                .get("codeFlows")
+                .and_then(Json::as_array)
+                .unwrap_or_default()
+            {
+                let threads = flow.get("threadFlows").and_then(Json::as_array).unwrap();
+                assert!(!threads.is_empty());
+                for thread in threads {
+                    let locations = thread.get("locations").and_then(Json::as_array).unwrap();
+                    assert!(!locations.is_empty());
+                    for step in locations {
+                        check_location(step.get("location").unwrap());
+                    }
+                }
+            }
+            let fingerprints = result.get("partialFingerprints").unwrap();
+            for (_, value) in fingerprints.as_object().unwrap() {
+                assert!(value.as_str().is_some());
+            }
+        }
+    }
+}
+
+fn diagnostics(source: &SourceFile) -> Vec<Diagnostic> {
+    let double_free = Diagnostic::new(
+        "double-free",
+        "Double free attempt on variable: p".to_string(),
+    )
+    .in_function("f");
+    let leak = Diagnostic::new(
+        "memory-leak",
+        "Memory leak: memory allocated with new for 'p' is never freed".to_string(),
+    )
+    .in_function("f")
+    .with_step("n > 0".to_string(), "condition `n > 0` is true".to_string());
+    let class = Diagnostic::new("rule-of-three", "'Buffer' has no destructor".to_string());
+    [double_free, leak, class]
+        .into_iter()
+        .map(|diagnostic| source.locate(diagnostic))
+        .collect()
+}
+
+#[test]
+fn test_sarif_log_is_well_formed() {
+    let source = SourceFile::new("src/f.cpp", SOURCE);
+    let log = sarif_log(&source, &diagnostics(&source));
+    check_sarif_subset(&log);
+    // What is printed parses back to the same log
+    assert_eq!(Json::parse(&log.pretty()).unwrap(), log);
+}
+
+#[test]
+fn test_sarif_results() {
+    let source = SourceFile::new("src/f.cpp", SOURCE);
+    let log = sarif_log(&source, &diagnostics(&source));
+    let run = &log.get("runs").and_then(Json::as_array).unwrap()[0];
+    let results = run.get("results").and_then(Json::as_array).unwrap();
+    assert_eq!(results.len(), 3);
+
+    let double_free = &results[0];
+    assert_eq!(
+        double_free.get("level").and_then(Json::as_str),
+        Some("error")
+    );
+    let region = |location: &Json| {
+        let region = location
+            .get("physicalLocation")
+            .and_then(|physical| physical.get("region"))
+            .unwrap();
+        (
+            region.get("startLine").and_then(Json::as_usize).unwrap(),
+            region.get("startColumn").and_then(Json::as_usize).unwrap(),
+        )
+    };
+    let primary = &double_free
+        .get("locations")
+        .and_then(Json::as_array)
+        .unwrap()[0];
+    assert_eq!(region(primary), (7, 12));
+    let related = &double_free
+        .get("relatedLocations")
+        .and_then(Json::as_array)
+        .unwrap()[0];
+    assert_eq!(region(related), (6, 12));
+
+    // The branch of a path-sensitive finding is located in the source
+    let flow = results[1]
+        .get("codeFlows")
+        .and_then(Json::as_array)
+        .unwrap()[0]
+        .get("threadFlows")
+        .and_then(Json::as_array)
+        .unwrap()[0]
+        .get("locations")
+        .and_then(Json::as_array)
+        .unwrap()[0]
+        .get("location")
+        .unwrap()
+        .clone();
+    assert_eq!(region(&flow), (3, 9));
+
+    assert_eq!(
+        results[2].get("level").and_then(Json::as_str),
+        Some(Severity::Warning.as_str())
+    );
+    // Without a span the result still names its file, just without a region
+    let physical = results[2]
+        .get("locations")
+        .and_then(Json::as_array)
+        .unwrap()[0]
+        .get("physicalLocation")
+        .unwrap();
+    assert_eq!(
+        physical
+            .get("artifactLocation")
+            .and_then(|artifact| artifact.get("uri"))
+            .and_then(Json::as_str),
+        Some("src/f.cpp")
+    );
+    assert!(physical.get("region").is_none());
+}
+
+#[test]
+fn test_fingerprints_ignore_line_numbers() {
+    let source = SourceFile::new("src/f.cpp", SOURCE);
+    let moved = SourceFile::new("src/f.cpp", &format!("// header\n\n{}", SOURCE));
+    let fingerprints = |source: &SourceFile| {
+        let log = sarif_log(source, &diagnostics(source));
+        let run = &log.get("runs").and_then(Json::as_array).unwrap()[0];
+        run.get("results")
+            .and_then(Json::as_array)
+            .unwrap()
+            .iter()
+            .map(|result| result.get("partialFingerprints").unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(fingerprints(&source), fingerprints(&moved));
+}
@@ -0,0 +1,208 @@
+// tests/test_smart_pointer_analyzer.rs
+mod common;
+
+use common::{call, declare, method, var};
+use safecpp::analyzer::memory_analyzer::MemoryErrorType;
+use safecpp::analyzer::smart_pointer_analyzer::SmartPointerAnalyzer;
+use safecpp::parser::cpp_parser::{Declaration, Expression, Statement};
+
+fn arrow(pointer: Expression, field: &str) -> Expression {
+    Expression::MemberAccess(
+        Box::new(Expression::Dereference(Box::new(pointer))),
+        field.to_string(),
+    )
+}
+
+fn assign(target: Expression, value: Expression) -> Statement {
+    Statement::Expression(Expression::Assignment(Box::new(target), Box::new(value)))
+}
+
+fn analyze(body: Vec<Statement>) -> Result<(), (MemoryErrorType, String)> {
+    let declarations = vec![Declaration::Function(
+        "main".to_string(),
+        vec![],
+        Box::new(Statement::Block(body)),
+    )];
+    let mut analyzer = SmartPointerAnalyzer::new(&declarations);
+    analyzer
+        .analyze()
+        .map_err(|error| (error.error_type(), error.details().to_string()))
+}
+
+fn assert_error(body: Vec<Statement>, error_type: MemoryErrorType, message: &str) {
+    let (actual, details) = analyze(body).unwrap_err();
+    assert_eq!(actual, error_type, "{}", details);
+    assert!(details.contains(message), "{}", details);
+}
+
+#[test]
+fn test_moved_from_unique_ptr_is_null() {
+    // auto a = std::make_unique<Dog>(); auto b = std::move(a); a->bark();
+    let program = |used: &str| {
+        vec![
+            declare("a", call("std::make_unique<Dog>", vec![])),
+            declare("b", call("std::move", vec![var("a")])),
+            Statement::Expression(Expression::MethodCall(
+                Box::new(Expression::Dereference(Box::new(var(used)))),
+                "bark".to_string(),
+                vec![],
+            )),
+        ]
+    };
+
+    assert!(analyze(program("b")).is_ok());
+    assert_error(
+        program("a"),
+        MemoryErrorType::NullPointerDereference,
+        "'a' was moved into 'b'",
+    );
+}
+
+#[test]
+fn test_released_unique_ptr_is_null() {
+    // auto a = std::make_unique<int>(); int *raw = a.release(); *a = 1;
+    let program = vec![
+        declare("a", call("std::make_unique<int>", vec![])),
+        declare("raw", method("a", "release", vec![])),
+        assign(
+            Expression::Dereference(Box::new(var("a"))),
+            Expression::Integer(1),
+        ),
+    ];
+
+    assert_error(
+        program,
+        MemoryErrorType::NullPointerDereference,
+        "'a' was released",
+    );
+}
+
+#[test]
+fn test_deleting_get_alias() {
+    // auto a = std::make_unique<int>(); int *raw = a.get(); delete raw;
+    let program = vec![
+        declare("a", call("std::make_unique<int>", vec![])),
+        declare("raw", method("a", "get", vec![])),
+        Statement::Expression(Expression::Delete(Box::new(var("raw")), false)),
+    ];
+
+    assert_error(program, MemoryErrorType::DoubleFree, "'a.get()'");
+}
+
+#[test]
+fn test_get_alias_used_after_reset() {
+    // auto a = std::make_unique<int>(); int *raw = a.get(); a.reset(); *raw = 1;
+    let program = vec![
+        declare("a", call("std::make_unique<int>", vec![])),
+        declare("raw", method("a", "get", vec![])),
+        Statement::Expression(method("a", "reset", vec![])),
+        assign(
+            Expression::Dereference(Box::new(var("raw"))),
+            Expression::Integer(1),
+        ),
+    ];
+
+    assert_error(program, MemoryErrorType::UseAfterFree, "'a' was reset");
+}
+
+#[test]
+fn test_two_owners_from_one_raw_pointer() {
+    // int *raw = new int; std::shared_ptr<int> a(raw); std::shared_ptr<int> b(raw);
+    let program = vec![
+        declare("raw", Expression::New("int".to_string(), None, None)),
+        declare("a", call("std::shared_ptr<int>", vec![var("raw")])),
+        declare("b", call("std::shared_ptr<int>", vec![var("raw")])),
+    ];
+
+    assert_error(
+        program,
+        MemoryErrorType::DoubleFree,
+        "'b' takes ownership of memory already owned by 'a'",
+    );
+}
+
+#[test]
+fn test_shared_ptr_cycle() {
+    // auto a = std::make_shared<Node>(); auto b = std::make_shared<Node>();
+    // a->next = b; b->prev = a;
+    let program = |back_link: Expression| {
+        vec![
+            declare("a", call("std::make_shared<Node>", vec![])),
+            declare("b", call("std::make_shared<Node>", vec![])),
+            assign(arrow(var("a"), "next"), var("b")),
+            assign(arrow(var("b"), "prev"), back_link),
+        ]
+    };
+
+    assert_error(
+        program(var("a")),
+        MemoryErrorType::MemoryLeak,
+        "make one of the links a weak_ptr",
+    );
+
+    // b->prev = std::weak_ptr<Node>(a);
+    assert!(analyze(program(call("std::weak_ptr<Node>", vec![var("a")]))).is_ok());
+}
+
+#[test]
+fn test_unchecked_weak_ptr_lock() {
+    // std::weak_ptr<Node> w = owner; auto s = w.lock(); s->value = 1;
+    let program = |check: bool| {
+        let store = assign(arrow(var("s"), "value"), Expression::Integer(1));
+        vec![
+            declare("owner", call("std::make_shared<Node>", vec![])),
+            declare("w", call("std::weak_ptr<Node>", vec![var("owner")])),
+            declare("s", method("w", "lock", vec![])),
+            if check {
+                Statement::If(
+                    var("s"),
+                    Box::new(store),
+                    Box::new(Statement::Block(vec![])),
+                )
+            } else {
+                store
+            },
+        ]
+    };
+
+    assert!(analyze(program(true)).is_ok());
+    assert_error(
+        program(false),
+        MemoryErrorType::NullPointerDereference,
+        "without checking for null",
+    );
+
+    // w.lock()->value = 1;
+    let direct = vec![
+        declare(
+            "w",
+            Expression::Uninitialized("std::weak_ptr<Node>".to_string()),
+        ),
+        assign(
+            arrow(method("w", "lock", vec![]), "value"),
+            Expression::Integer(1),
+        ),
+    ];
+    assert_error(
+        direct,
+        MemoryErrorType::NullPointerDereference,
+        "'w.lock()'",
+    );
+}
+
+#[test]
+fn test_plain_new_and_delete_are_left_to_the_pointer_analyzer() {
+    // int *p = new int; int *q = p; delete p; *q = 1; delete q;
+    let delete = |name: &str| Statement::Expression(Expression::Delete(Box::new(var(name)), false));
+    let program = vec![
+        declare("p", Expression::New("int".to_string(), None, None)),
+        declare("q", var("p")),
+        delete("p"),
+        assign(
+            Expression::Dereference(Box::new(var("q"))),
+            Expression::Integer(1),
+        ),
+        delete("q"),
+    ];
+    assert!(analyze(program).is_ok());
+}
@@ -0,0 +1,168 @@
+// tests/test_suppression.rs
+use safecpp::diagnostics::{Diagnostic, Span, Suppressions};
+use safecpp::parser::cpp_lexer::{collect_comments, Comment};
+
+fn at_line(rule: &str, line: usize) -> Diagnostic {
+    Diagnostic::new(rule, format!("{} on line {}", rule, line))
+        .with_primary(Span::new(line, 5, 1), "")
+}
+
+fn suppressions(source: &str, require_reason: bool) -> Suppressions {
+    Suppressions::new(&collect_comments(source), require_reason)
+}
+
+#[test]
+fn test_lexer_collects_comments() {
+    let source = "int x; // first\n/* second\n   line */ int y;\nchar* s = \"// not a comment\";\n";
+    let comments = collect_comments(source);
+    assert_eq!(
+        comments,
+        vec![
+            Comment {
+                line: 1,
+                column: 8,
+                text: "// first".to_string(),
+                trailing: true,
+            },
+            Comment {
+                line: 2,
+                column: 1,
+                text: "/* second\n   line */".to_string(),
+                trailing: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_line_suppression() {
+    let source = "\
+void f() {
+    // scpp-ignore[memory-leak]: owned by the pool
+    int* p = new int;
+    int* q = new int; // scpp-ignore[memory-leak]: owned by the pool
+    int* r = new int;
+}
+";
+    let mut suppressions = suppressions(source, false);
+    assert!(suppressions.suppresses(&at_line("memory-leak", 3)));
+    assert!(suppressions.suppresses(&at_line("memory-leak", 4)));
+    // The trailing comment on line 4 is about that line only
+    assert!(!suppressions.suppresses(&at_line("memory-leak", 5)));
+    assert!(!suppressions.suppresses(&at_line("memory-leak", 6)));
+    assert!(!suppressions.suppresses(&at_line("double-free", 3)));
+    assert!(suppressions.finish().is_empty());
+}
+
+#[test]
+fn test_comments_after_literals_and_lexer_errors() {
+    // Single-digit numbers, empty strings and tokens the lexer rejects do not hide the
+    // suppressions after them
+    let source = "\
+void f() {
+    int a = 5;
+    const char* s = \"\";
+    int b = 09;
+    char c = '';
+    // scpp-ignore[memory-leak]: owned by the pool
+    int* p = new int;
+}
+";
+    let comments = collect_comments(source);
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].line, 6);
+
+    let mut suppressions = suppressions(source, false);
+    assert!(suppressions.suppresses(&at_line("memory-leak", 7)));
+    assert!(suppressions.finish().is_empty());
+}
+
+#[test]
+fn test_file_and_region_suppressions() {
+    let source = "\
+// scpp-ignore-file[use-after-move]: moved-from strings are reset
+void f() {
+    /* scpp-ignore-begin[double-free, use-after-free]: legacy allocator */
+    release(p);
+    release(p);
+    // scpp-ignore-end
+    release(q);
+}
+";
+    let mut suppressions = suppressions(source, false);
+    assert!(suppressions.suppresses(&at_line("use-after-move", 7)));
+    assert!(suppressions.suppresses(&Diagnostic::new(
+        "use-after-move",
+        "Use after move of 's'".to_string()
+    )));
+    assert!(suppressions.suppresses(&at_line("double-free", 5)));
+    assert!(!suppressions.suppresses(&at_line("double-free", 7)));
+
+    // A suppression is used once any of its rules matched
+    let problems = suppressions.finish();
+    assert!(problems.is_empty(), "{:?}", problems);
+}
+
+#[test]
+fn test_unused_and_invalid_suppressions() {
+    let source = "\
+// scpp-ignore[memory-leak]: never triggers
+// scpp-ignore[no-such-rule]: typo
+// scpp-ignore memory-leak
+// scpp-ignore-end
+// scpp-ignored is an ordinary comment
+";
+    let problems = suppressions(source, false).finish();
+    let summary: Vec<(&str, usize)> = problems
+        .iter()
+        .map(|problem| {
+            (
+                problem.rule.as_str(),
+                problem.primary.as_ref().unwrap().span.line,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("unused-suppression", 1),
+            ("invalid-suppression", 2),
+            ("unused-suppression", 2),
+            ("invalid-suppression", 3),
+            ("invalid-suppression", 4),
+        ]
+    );
+    assert_eq!(
+        problems[0].message,
+        "Unused suppression: no 'memory-leak' diagnostic is silenced by it"
+    );
+    assert_eq!(
+        problems[0].primary.as_ref().unwrap().span,
+        Span::new(1, 1, 43)
+    );
+    assert_eq!(
+        problems[1].message,
+        "Suppression names an unknown rule 'no-such-rule'"
+    );
+}
+
+#[test]
+fn test_required_reason() {
+    let source = "\
+int* p = new int; // scpp-ignore[memory-leak]
+int* q = new int; // scpp-ignore[memory-leak]: freed by the caller
+";
+    let mut lenient = suppressions(source, false);
+    assert!(lenient.suppresses(&at_line("memory-leak", 1)));
+
+    let mut strict = suppressions(source, true);
+    assert!(!strict.suppresses(&at_line("memory-leak", 1)));
+    assert!(strict.suppresses(&at_line("memory-leak", 2)));
+    let problems = strict.finish();
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].rule, "invalid-suppression");
+    assert_eq!(
+        problems[0].message,
+        "Suppression has no reason; write it after a ':'"
+    );
+}
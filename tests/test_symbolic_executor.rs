@@ -0,0 +1,178 @@
+// tests/test_symbolic_executor.rs
+mod common;
+
+use common::{function, var};
+use safecpp::analyzer::constraint_solver::{Constraint, ConstraintSolver, LinearExpr, Relation};
+use safecpp::analyzer::memory_analyzer::MemoryErrorType;
+use safecpp::analyzer::symbolic_executor::SymbolicExecutor;
+use safecpp::parser::cpp_parser::{parse_cpp_code, Declaration, Expression, Statement};
+
+fn delete(name: &str) -> Statement {
+    Statement::Expression(Expression::Delete(Box::new(var(name)), false))
+}
+
+// `void f(int* p) { <body> }`
+fn function_of_p(body: Vec<Statement>) -> Vec<Declaration> {
+    vec![function("f", &["p"], body)]
+}
+
+#[test]
+fn test_conditional_delete_is_not_double_free() {
+    // if (p) delete p; else {}
+    let declarations = function_of_p(vec![Statement::If(
+        var("p"),
+        Box::new(delete("p")),
+        Box::new(Statement::Block(vec![])),
+    )]);
+
+    let mut executor = SymbolicExecutor::new(&declarations);
+    assert!(executor.analyze().is_empty());
+}
+
+#[test]
+fn test_double_free_reports_path() {
+    // if (p) delete p; delete p;
+    let declarations = function_of_p(vec![
+        Statement::If(
+            var("p"),
+            Box::new(delete("p")),
+            Box::new(Statement::Block(vec![])),
+        ),
+        delete("p"),
+    ]);
+
+    let mut executor = SymbolicExecutor::new(&declarations);
+    let findings = executor.analyze();
+
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].error.error_type(), MemoryErrorType::DoubleFree);
+    assert_eq!(findings[0].path.len(), 1);
+    assert!(findings[0].path[0].taken);
+}
+
+#[test]
+fn test_infeasible_path_is_pruned() {
+    // int *q = nullptr; if (p > 5) { if (p < 3) { *q = 1; } }
+    let declarations = function_of_p(vec![
+        Statement::Declaration("q".to_string(), Expression::Null),
+        Statement::If(
+            Expression::BinaryOperation(
+                ">".to_string(),
+                Box::new(var("p")),
+                Box::new(Expression::Integer(5)),
+            ),
+            Box::new(Statement::If(
+                Expression::BinaryOperation(
+                    "<".to_string(),
+                    Box::new(var("p")),
+                    Box::new(Expression::Integer(3)),
+                ),
+                Box::new(Statement::Expression(Expression::Assignment(
+                    Box::new(Expression::Dereference(Box::new(var("q")))),
+                    Box::new(Expression::Integer(1)),
+                ))),
+                Box::new(Statement::Block(vec![])),
+            )),
+            Box::new(Statement::Block(vec![])),
+        ),
+    ]);
+
+    let mut executor = SymbolicExecutor::new(&declarations);
+    assert!(executor.analyze().is_empty());
+}
+
+#[test]
+fn test_leak_on_early_return() {
+    // int *q = new int; if (p) return 0; delete q;
+    let declarations = function_of_p(vec![
+        Statement::Declaration(
+            "q".to_string(),
+            Expression::New("int".to_string(), None, None),
+        ),
+        Statement::If(
+            var("p"),
+            Box::new(Statement::Return(Expression::Integer(0))),
+            Box::new(Statement::Block(vec![])),
+        ),
+        delete("q"),
+    ]);
+
+    let mut executor = SymbolicExecutor::new(&declarations);
+    let findings = executor.analyze();
+
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].error.error_type(), MemoryErrorType::MemoryLeak);
+    assert!(findings[0].path[0].taken);
+}
+
+#[test]
+fn test_solver_detects_contradiction() {
+    let solver = ConstraintSolver::new();
+    let x = LinearExpr::variable("x");
+    let constraints =
+        vec![
+            Constraint::compare(&x.scale(2).unwrap(), Relation::Eq, &LinearExpr::constant(3))
+                .unwrap(),
+        ];
+
+    assert!(!solver.is_satisfiable(&constraints));
+    assert!(solver.is_satisfiable(&[Constraint::compare(
+        &x,
+        Relation::Lt,
+        &LinearExpr::constant(3)
+    )
+    .unwrap()]));
+}
+
+#[test]
+fn test_overflowing_arithmetic_is_unknown() {
+    let x = LinearExpr::variable("x");
+    assert_eq!(
+        LinearExpr::constant(i64::MAX).add(&LinearExpr::constant(1)),
+        None
+    );
+    assert_eq!(x.scale(i64::MAX).unwrap().scale(2), None);
+    assert_eq!(LinearExpr::constant(i64::MIN).scale(-1), None);
+
+    let source = "
+        void f(int* p, int x) {
+            int i = 9223372036854775807;
+            int a[4];
+            a[i + 1] = 0;
+            int y = x * 9223372036854775807;
+            if (y * 2 > 0 && -i < 0) {
+                delete p;
+            }
+            delete p;
+        }
+    ";
+    let declarations = parse_cpp_code(source).unwrap();
+    let mut executor = SymbolicExecutor::new(&declarations);
+    let findings = executor.analyze();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].error.error_type(), MemoryErrorType::DoubleFree);
+}
+
+#[test]
+fn test_leaks_of_alike_allocations_are_told_apart() {
+    let source = "
+        void f(int c) {
+            int *a = new int(1);
+            int *b = new int(2);
+            if (c) {
+                delete b;
+            }
+        }
+    ";
+    let declarations = parse_cpp_code(source).unwrap();
+    let mut executor = SymbolicExecutor::new(&declarations);
+    let findings = executor.analyze();
+
+    let lines: Vec<_> = findings
+        .iter()
+        .map(|finding| finding.location.map(|location| location.line))
+        .collect();
+    assert_eq!(lines, vec![Some(3), Some(4)], "{:?}", findings);
+    // `b` leaks only when the delete is skipped
+    assert!(!findings[1].path[0].taken);
+}
@@ -1,6 +1,6 @@
-// tests/test_parser.rs
-use safecpp::parser::cpp_lexer::{Lexer, Token};
-use safecpp::parser::cpp_parser::{Declaration, Expression, Parser, Statement};
+// tests/tests_parser.rs
+use safecpp::parser::cpp_lexer::Lexer;
+use safecpp::parser::cpp_parser::{Declaration, Expression, Location, Parser, Statement};
 
 #[test]
 fn test_parse_variable_declaration() {
@@ -29,8 +29,15 @@ fn test_parse_function_declaration() {
         result,
         vec![Declaration::Function(
             "sum".to_string(),
-            vec!["a".to_string(), "b".to_string()],
-            Box::new(Statement::Return(Expression::Identifier("a + b".to_string())))
+            vec!["int a".to_string(), "int b".to_string()],
+            Box::new(Statement::Block(vec![Statement::Located(
+                Location::new(1, 25),
+                Box::new(Statement::Return(Expression::BinaryOperation(
+                    "+".to_string(),
+                    Box::new(Expression::Identifier("a".to_string())),
+                    Box::new(Expression::Identifier("b".to_string())),
+                )))
+            )]))
         )]
     );
 }